@@ -0,0 +1,634 @@
+//! Builds modules in code so engine and expression behavior can be tested
+//! without module files.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use failure::Error;
+
+use crate::expression::Expression;
+use crate::module::{
+    AssignmentInfo, AssignmentItem, AutoTrackPolicy, DisplayViewInfo, DisplayViewInfoView,
+    EntranceInfo, GridColumns, Manifest, Module, ObjectiveCheck, ObjectiveInfo, ObjectiveList,
+    ObjectiveListSpecial, ObjectiveStage, RegionExit, RegionInfo, SplitInfo, Tooltip,
+};
+
+// Objectives are given a type of `ty` unless `objective_type()` is used.
+struct BuilderObjective {
+    ty: String,
+    info: ObjectiveInfo,
+}
+
+// Builds a `Module` one objective at a time:
+//
+//     ModuleBuilder::new()
+//         .objective("key")
+//         .objective("door")
+//         .unlocked_by("key")
+//         .build()?
+//
+// Methods other than `objective()` apply to the most recently added
+// objective.  Expression errors are reported by `build()`.
+pub struct ModuleBuilder {
+    objectives: Vec<BuilderObjective>,
+    groups: HashMap<String, Vec<String>>,
+    layouts: HashMap<String, DisplayViewInfo>,
+    entrances: Vec<EntranceInfo>,
+    regions: Vec<RegionInfo>,
+    aliases: HashMap<String, String>,
+    splits: Vec<SplitInfo>,
+    go_mode: Expression,
+    assignments: Vec<AssignmentInfo>,
+    auto_track_policy: AutoTrackPolicy,
+    error: Option<Error>,
+}
+
+fn layout(view: DisplayViewInfoView) -> DisplayViewInfo {
+    DisplayViewInfo {
+        layout_params: Default::default(),
+        view,
+    }
+}
+
+impl Default for ModuleBuilder {
+    fn default() -> Self {
+        ModuleBuilder::new()
+    }
+}
+
+impl ModuleBuilder {
+    pub fn new() -> ModuleBuilder {
+        let mut layouts = HashMap::new();
+        layouts.insert("main".to_string(), layout(DisplayViewInfoView::Spacer {}));
+        layouts.insert(
+            "checks".to_string(),
+            layout(DisplayViewInfoView::Grid {
+                columns: GridColumns::Count(1),
+                objectives: ObjectiveList::Special(ObjectiveListSpecial::Checks),
+                disabled: Default::default(),
+            }),
+        );
+        ModuleBuilder {
+            objectives: Vec::new(),
+            groups: HashMap::new(),
+            layouts,
+            entrances: Vec::new(),
+            regions: Vec::new(),
+            aliases: HashMap::new(),
+            splits: Vec::new(),
+            go_mode: Expression::Default,
+            assignments: Vec::new(),
+            auto_track_policy: Default::default(),
+            error: None,
+        }
+    }
+
+    // Add an objective named after its `id`.
+    pub fn objective(mut self, id: &str) -> Self {
+        self.objectives.push(BuilderObjective {
+            ty: "objective".to_string(),
+            info: ObjectiveInfo::new(id, id),
+        });
+        self
+    }
+
+    pub fn objective_type(self, ty: &str) -> Self {
+        self.update(|o| {
+            o.ty = ty.to_string();
+            Ok(())
+        })
+    }
+
+    pub fn enabled_by(self, expr: &str) -> Self {
+        self.update(|o| {
+            o.info.enabled_by = Expression::parse(expr)?;
+            Ok(())
+        })
+    }
+
+    pub fn unlocked_by(self, expr: &str) -> Self {
+        self.update(|o| {
+            o.info.unlocked_by = Expression::parse(expr)?;
+            Ok(())
+        })
+    }
+
+    // Add a check with `unlocked_by`.  An empty expression leaves the check
+    // always unlocked.
+    pub fn check(self, id: &str, unlocked_by: &str) -> Self {
+        self.gated_check(id, "", unlocked_by)
+    }
+
+    // Add a check that is only enabled when `enabled_by` is.
+    pub fn gated_check(self, id: &str, enabled_by: &str, unlocked_by: &str) -> Self {
+        let parse = |expr: &str| {
+            if expr.is_empty() {
+                Ok(Expression::Default)
+            } else {
+                Expression::parse(expr)
+            }
+        };
+        self.update(|o| {
+            o.info.checks.push(ObjectiveCheck {
+                ty: "check".to_string(),
+                id: id.to_string(),
+                name: id.to_string(),
+                enabled_by: parse(enabled_by)?,
+                unlocked_by: parse(unlocked_by)?,
+                region: String::new(),
+                description: String::new(),
+                tooltip: None,
+            });
+            Ok(())
+        })
+    }
+
+    // Add a stage for each of `unlocked_by`.  Empty expressions leave the
+    // stage unlocked once the one before it is reached.
+    pub fn stages(self, unlocked_by: &[&str]) -> Self {
+        self.update(|o| {
+            for expr in unlocked_by {
+                o.info.stages.push(ObjectiveStage {
+                    name: String::new(),
+                    unlocked_by: if expr.is_empty() {
+                        Expression::Default
+                    } else {
+                        Expression::parse(expr)?
+                    },
+                });
+            }
+            Ok(())
+        })
+    }
+
+    pub fn tag(self, tag: &str) -> Self {
+        self.update(|o| {
+            o.info.tags.push(tag.to_string());
+            Ok(())
+        })
+    }
+
+    pub fn group(mut self, name: &str, ids: &[&str]) -> Self {
+        let ids = ids.iter().map(|id| id.to_string()).collect();
+        self.groups.insert(name.to_string(), ids);
+        self
+    }
+
+    // Add an entrance reached by `unlocked_by`.  An empty expression leaves
+    // it only reachable through its connection.
+    pub fn entrance(mut self, id: &str, unlocked_by: &str) -> Self {
+        let unlocked_by = if unlocked_by.is_empty() {
+            Ok(Expression::Default)
+        } else {
+            Expression::parse(unlocked_by)
+        };
+        match unlocked_by {
+            Ok(unlocked_by) => self.entrances.push(EntranceInfo {
+                id: id.to_string(),
+                name: id.to_string(),
+                unlocked_by,
+            }),
+            Err(e) => self.error = self.error.or(Some(e)),
+        }
+        self
+    }
+
+    // Set the objective's long description.
+    pub fn description(self, description: &str) -> Self {
+        self.update(|o| {
+            o.info.description = description.to_string();
+            Ok(())
+        })
+    }
+
+    pub fn tooltip(self, text: &str, image: &str) -> Self {
+        self.update(|o| {
+            o.info.tooltip = Some(Tooltip {
+                text: text.to_string(),
+                image: image.to_string(),
+            });
+            Ok(())
+        })
+    }
+
+    // Place the objective in `region`.
+    pub fn in_region(self, region: &str) -> Self {
+        self.update(|o| {
+            o.info.region = region.to_string();
+            Ok(())
+        })
+    }
+
+    // Add a region reached by `unlocked_by`.  An empty expression leaves it
+    // only reachable through exits.
+    pub fn region(mut self, id: &str, unlocked_by: &str) -> Self {
+        let unlocked_by = if unlocked_by.is_empty() {
+            Ok(Expression::Default)
+        } else {
+            Expression::parse(unlocked_by)
+        };
+        match unlocked_by {
+            Ok(unlocked_by) => self.regions.push(RegionInfo {
+                id: id.to_string(),
+                name: id.to_string(),
+                unlocked_by,
+                exits: Vec::new(),
+            }),
+            Err(e) => self.error = self.error.or(Some(e)),
+        }
+        self
+    }
+
+    // Add an exit from the most recently added region to `to`.
+    pub fn exit(mut self, to: &str, requires: &str) -> Self {
+        let requires = if requires.is_empty() {
+            Ok(Expression::Default)
+        } else {
+            Expression::parse(requires)
+        };
+        let result = requires.and_then(|requires| match self.regions.last_mut() {
+            Some(region) => {
+                region.exits.push(RegionExit {
+                    to: to.to_string(),
+                    requires,
+                });
+                Ok(())
+            }
+            None => Err(failure::format_err!("no region added yet")),
+        });
+        if let Err(e) = result {
+            self.error = self.error.or(Some(e));
+        }
+        self
+    }
+
+    // Let expressions refer to objective `id` as `alias`.
+    pub fn alias(mut self, alias: &str, id: &str) -> Self {
+        self.aliases.insert(alias.to_string(), id.to_string());
+        self
+    }
+
+    // Record a split named `name` when `objective` is first completed.
+    pub fn split(mut self, name: &str, objective: &str) -> Self {
+        self.splits.push(SplitInfo {
+            name: name.to_string(),
+            objective: objective.to_string(),
+        });
+        self
+    }
+
+    // Track go mode as complete while `expr` holds.
+    pub fn go_mode(mut self, expr: &str) -> Self {
+        match Expression::parse(expr) {
+            Ok(expr) => self.go_mode = expr,
+            Err(e) => self.error = self.error.or(Some(e)),
+        }
+        self
+    }
+
+    // Add an assignment of `pool` values to `slots`, each named after its
+    // id.
+    pub fn assignment(mut self, id: &str, slots: &[&str], pool: &[&str], unique: bool) -> Self {
+        let items = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| AssignmentItem {
+                    id: id.to_string(),
+                    name: id.to_string(),
+                })
+                .collect()
+        };
+        self.assignments.push(AssignmentInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            slots: items(slots),
+            pool: items(pool),
+            unique,
+        });
+        self
+    }
+
+    pub fn layout(mut self, name: &str, info: DisplayViewInfo) -> Self {
+        self.layouts.insert(name.to_string(), info);
+        self
+    }
+
+    pub fn auto_track_policy(mut self, policy: AutoTrackPolicy) -> Self {
+        self.auto_track_policy = policy;
+        self
+    }
+
+    fn update(mut self, f: impl FnOnce(&mut BuilderObjective) -> Result<(), Error>) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        self.error = match self.objectives.last_mut() {
+            Some(o) => f(o).err(),
+            None => Some(failure::format_err!("no objective added yet")),
+        };
+        self
+    }
+
+    pub fn build(self) -> Result<Module, Error> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        let mut module = Module::new(Manifest {
+            name: "Builder".to_string(),
+            authors: Vec::new(),
+            game_url: String::new(),
+            auto_track: None,
+            auto_track_scripts: Vec::new(),
+            auto_track_libs: Vec::new(),
+            auto_track_policy: self.auto_track_policy,
+            auto_track_backend: Default::default(),
+            process: None,
+            params: Vec::new(),
+            objectives: Vec::new(),
+            maps: Vec::new(),
+            groups: self.groups,
+            layouts: self.layouts,
+            watches: Vec::new(),
+            overlays: Vec::new(),
+            entrances: self.entrances,
+            regions: self.regions,
+            aliases: self.aliases,
+            splits: self.splits,
+            go_mode: self.go_mode,
+            assignments: self.assignments,
+            marker_shapes: HashMap::new(),
+        });
+        for o in self.objectives {
+            module.add_objective(o.info, &o.ty, Path::new("<builder>"))?;
+        }
+        module.add_entrances()?;
+        module.add_regions()?;
+        module.add_go_mode()?;
+        module.add_assignments()?;
+        module.resolve_aliases();
+        module.resolve_sets()?;
+        module.replace_unknown_ids();
+        Ok(module)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::{ASSIGNMENT_TYPE, GO_MODE_ID};
+
+    #[test]
+    fn build() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("a")
+            .tag("t")
+            .objective("b")
+            .unlocked_by("all(t)")
+            .objective("loc")
+            .objective_type("location")
+            .description("Up the ladder")
+            .tooltip("Left of the door", "screenshot:loc")
+            .check("loc:0", "")
+            .check("loc:1", "a")
+            .build()?;
+
+        assert_eq!(module.objectives["a"].unlocked_by, Expression::Manual);
+        assert_eq!(
+            module.objectives["b"].unlocked_by,
+            Expression::Objective("a".into())
+        );
+        assert_eq!(module.objectives["loc"].ty, "location");
+        assert_eq!(module.objectives["loc"].description, "Up the ladder");
+        assert_eq!(
+            module.objectives["loc"]
+                .tooltip
+                .as_ref()
+                .map(|t| t.image.as_str()),
+            Some("screenshot:loc")
+        );
+        assert_eq!(module.objectives["loc:0"].unlocked_by, Expression::True);
+        assert!(module.manifest.layouts.contains_key("checks"));
+
+        let module = ModuleBuilder::new()
+            .objective("a")
+            .objective("sword")
+            .stages(&["", "a"])
+            .build()?;
+        assert_eq!(
+            module.objectives["sword"].unlocked_by,
+            Expression::ObjectiveComplete("sword:stage1".into())
+        );
+        assert_eq!(
+            module.objectives["sword:stage1"].unlocked_by,
+            Expression::True
+        );
+        assert_eq!(module.objectives["sword:stage2"].name, "sword 2");
+        assert_eq!(
+            module.objectives["sword:stage2"].unlocked_by,
+            Expression::And(
+                Box::new(Expression::Objective("a".into())),
+                Box::new(Expression::ObjectiveComplete("sword:stage1".into()))
+            )
+        );
+
+        let module = ModuleBuilder::new()
+            .objective("lantern")
+            .objective("cave")
+            .unlocked_by("lamp")
+            .objective("torch")
+            .alias("lamp", "lantern")
+            .alias("torch", "lantern")
+            .alias("candle", "wick")
+            .build()?;
+        assert_eq!(
+            module.objectives["cave"].unlocked_by,
+            Expression::Objective("lantern".into())
+        );
+        assert_eq!(module.canonical_id(&"lamp".to_string()), "lantern");
+        assert_eq!(module.canonical_id(&"torch".to_string()), "torch");
+        let problems: Vec<String> = module
+            .validate()
+            .into_iter()
+            .filter(|p| p.starts_with("alias"))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                "alias candle names unknown objective wick",
+                "alias torch is ignored, it is an objective id"
+            ]
+        );
+
+        assert!(ModuleBuilder::new().unlocked_by("a").build().is_err());
+        assert!(ModuleBuilder::new().exit("a", "").build().is_err());
+        assert!(ModuleBuilder::new()
+            .objective("a")
+            .unlocked_by("a &&")
+            .build()
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn regions() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .region("town", "true")
+            .exit("cave", "lamp")
+            .region("cave", "")
+            .objective("lamp")
+            .objective("cave-chest")
+            .in_region("cave")
+            .check("cave-chest:0", "")
+            .check("cave-chest:1", "lamp")
+            .build()?;
+
+        assert_eq!(module.objectives["town"].ty, "region");
+        assert_eq!(module.objectives["cave"].unlocked_by, Expression::False);
+        assert_eq!(
+            module.objectives["cave-chest:0"].unlocked_by,
+            Expression::Objective("cave".into())
+        );
+        assert_eq!(
+            module.objectives["cave-chest:1"].unlocked_by,
+            Expression::And(
+                Box::new(Expression::Objective("cave".into())),
+                Box::new(Expression::Objective("lamp".into()))
+            )
+        );
+        assert_eq!(
+            module.manifest.regions[0].exits[0].requires,
+            Expression::Objective("lamp".into())
+        );
+
+        assert!(ModuleBuilder::new()
+            .region("town", "true")
+            .exit("nowhere", "")
+            .build()
+            .is_err());
+        assert!(ModuleBuilder::new()
+            .objective("chest")
+            .in_region("nowhere")
+            .build()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_expression() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("hook")
+            .tag("t")
+            .objective("bomb")
+            .tag("t")
+            .alias("grapple", "hook")
+            .build()?;
+
+        let resolve = |s: &str| module.resolve_expression(Expression::parse(s)?);
+        assert_eq!(resolve("grapple")?, Expression::Objective("hook".into()));
+        assert_eq!(resolve("all(t)")?, Expression::parse("hook && bomb")?);
+        assert!(resolve("any(nothing)").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn splits() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("sword")
+            .alias("blade", "sword")
+            .split("Sword", "blade")
+            .split("Nothing", "nope")
+            .build()?;
+        assert_eq!(module.manifest.splits[0].objective, "sword");
+        assert_eq!(
+            module.validate(),
+            vec!["split Nothing refers to unknown objective nope".to_string()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn go_mode() -> Result<(), Error> {
+        let module = ModuleBuilder::new().objective("sword").build()?;
+        assert!(!module.objectives.contains_key(GO_MODE_ID));
+
+        let module = ModuleBuilder::new()
+            .objective("sword")
+            .alias("blade", "sword")
+            .go_mode("blade")
+            .build()?;
+        let info = &module.objectives[GO_MODE_ID];
+        assert_eq!(info.ty, GO_MODE_ID);
+        assert_eq!(info.completed_by, Expression::parse("sword")?);
+
+        assert!(ModuleBuilder::new()
+            .objective(GO_MODE_ID)
+            .go_mode("true")
+            .build()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn assignments() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .assignment("bosses", &["door-a", "door-b"], &["dragon", "golem"], true)
+            .objective("prize")
+            .unlocked_by("door-a-is-dragon")
+            .build()?;
+        let info = &module.objectives["door-b-is-golem"];
+        assert_eq!(info.ty, ASSIGNMENT_TYPE);
+        assert_eq!(info.completed_by, Expression::Manual);
+        assert_eq!(
+            module.objectives["prize"].unlocked_by,
+            Expression::Objective("door-a-is-dragon".into())
+        );
+        assert!(module.assignment("bosses").is_some());
+
+        assert!(ModuleBuilder::new()
+            .assignment("bosses", &["door", "door"], &["dragon"], false)
+            .build()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_ids() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("sword")
+            .stages(&["", ""])
+            .check("chest", "sword && hookshot")
+            .check("tower", "stage(sword) >= 2 || stage(sword) >= 3")
+            .region("town", "true")
+            .exit("town", "complete(boots)")
+            .build()?;
+
+        assert_eq!(
+            module.objectives["chest"].unlocked_by,
+            Expression::parse("sword && false")?
+        );
+        assert_eq!(
+            module.objectives["tower"].unlocked_by,
+            Expression::parse("stage(sword) >= 2 || false")?
+        );
+        assert_eq!(
+            module.manifest.regions[0].exits[0].requires,
+            Expression::False
+        );
+        assert_eq!(
+            module.unknown_ids,
+            vec![
+                ("objective chest".to_string(), "hookshot".to_string()),
+                ("objective tower".to_string(), "sword:stage3".to_string()),
+                (
+                    "exit of region town to town".to_string(),
+                    "boots".to_string()
+                ),
+            ]
+        );
+        assert!(module
+            .validate()
+            .contains(&"objective chest refers to unknown id hookshot".to_string()));
+        Ok(())
+    }
+}
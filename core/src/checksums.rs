@@ -0,0 +1,135 @@
+// Verifies a module against the SHA-256 checksums its author shipped with it.
+//
+// The checksums live in a `SHA256SUMS` file next to the manifest in the
+// format written by `sha256sum`:
+//
+//     9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08  manifest.json
+//     60303ae22b998861bce3b28f33eec1be758a213c86c93c076dbe9f558c11c752  assets/icon.png
+//
+// Every file of the module must be listed, so a script can't be slipped in
+// next to the ones the author checked.
+
+use failure::{format_err, Error};
+use path_slash::PathExt;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+// Checks the files in `base_path` against its checksums file.  Returns
+// `false` if there is no checksums file and an error if any file is
+// missing, unlisted, or doesn't match.
+pub fn verify(base_path: &Path) -> Result<bool, Error> {
+    let sums_path = base_path.join(CHECKSUMS_FILE);
+    if !sums_path.exists() {
+        return Ok(false);
+    }
+    let sums_str = fs::read_to_string(&sums_path)
+        .map_err(|e| format_err!("Failed to open {}: {}", sums_path.display(), e))?;
+    let mut sums = parse(&sums_str)?;
+
+    let mut files = Vec::new();
+    visit_dir(base_path, base_path, &mut files)?;
+    for file in files {
+        if file == CHECKSUMS_FILE {
+            continue;
+        }
+        let expected = sums.remove(&file).ok_or(format_err!(
+            "{} is not listed in {}",
+            file,
+            CHECKSUMS_FILE
+        ))?;
+        let data = fs::read(base_path.join(&file))?;
+        if sha256_hex(&data) != expected {
+            return Err(format_err!("{} does not match its checksum", file));
+        }
+    }
+
+    if let Some(file) = sums.keys().next() {
+        return Err(format_err!(
+            "{} is listed in {} but missing",
+            file,
+            CHECKSUMS_FILE
+        ));
+    }
+    Ok(true)
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Checksums by '/' separated path.  Also reads the checksums published with
+// releases.
+pub fn parse(sums: &str) -> Result<HashMap<String, String>, Error> {
+    let mut map = HashMap::new();
+    for line in sums.lines().filter(|l| !l.trim().is_empty()) {
+        let mut parts = line.splitn(2, ' ');
+        let hash = parts.next().unwrap_or_default().to_lowercase();
+        let path = parts.next().unwrap_or_default();
+        // `sha256sum` marks files read in binary mode with '*'.
+        let path = path.trim_start_matches(' ').trim_start_matches('*');
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) || path.is_empty() {
+            return Err(format_err!("Bad line in {}: {}", CHECKSUMS_FILE, line));
+        }
+        map.insert(path.trim_start_matches("./").to_string(), hash);
+    }
+    Ok(map)
+}
+
+fn visit_dir(base_dir: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            visit_dir(base_dir, &path, files)?;
+        } else if let Some(relative) = path.strip_prefix(base_dir)?.to_slash() {
+            files.push(relative);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_module_files() -> Result<(), Error> {
+        let dir = std::env::temp_dir().join(format!("pollendina-sums-{}", std::process::id()));
+        fs::create_dir_all(dir.join("scripts"))?;
+        fs::write(dir.join("manifest.json"), "{}")?;
+        fs::write(dir.join("scripts/auto.lua"), "return 1")?;
+        assert!(!verify(&dir)?);
+
+        let sums = format!(
+            "{}  manifest.json\n{} *scripts/auto.lua\n",
+            sha256_hex(b"{}"),
+            sha256_hex(b"return 1")
+        );
+        fs::write(dir.join(CHECKSUMS_FILE), &sums)?;
+        assert!(verify(&dir)?);
+
+        fs::write(dir.join("scripts/auto.lua"), "return 2")?;
+        assert!(verify(&dir).is_err());
+        fs::write(dir.join("scripts/auto.lua"), "return 1")?;
+
+        fs::write(dir.join("scripts/extra.lua"), "")?;
+        assert!(verify(&dir).is_err());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn known_hash() {
+        assert_eq!(
+            sha256_hex(b"test"),
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+        );
+    }
+}
@@ -0,0 +1,1204 @@
+//! Objective logic evaluation.
+//!
+//! `Engine` keeps the state of every objective of a module and evaluates
+//! their expressions as updates are applied.  Apps that derive more state
+//! from the objectives, such as the tracker's display, implement
+//! `EngineObserver` and hand it to each call that evaluates.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use failure::{format_err, Error};
+use petgraph::{algo::toposort, graph::DiGraph};
+
+use crate::event_log::{Clock, EventLog, SessionEvent};
+use crate::expression::Expression;
+use crate::module::{AutoTrackPolicy, Module, RegionInfo, GO_MODE_ID};
+use crate::{ObjectiveState, StateChange, StateSource};
+
+// The objective state changes made by one update and what made them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateEvent {
+    pub changes: Vec<StateChange>,
+    pub source: StateSource,
+}
+
+// What applying updates to the objective states changed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChangeSet {
+    // The updates that changed an objective, as they were applied.
+    pub changes: Vec<StateChange>,
+    // Every objective whose state ended up different, including the ones
+    // re-evaluated because of the updates.
+    pub changed: HashSet<String>,
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+    }
+
+    pub fn contains(&self, id: &String) -> bool {
+        self.changed.contains(id)
+    }
+}
+
+// Told what an engine does so state derived from the objectives can be kept
+// up to date.
+pub trait EngineObserver {
+    // The objectives were evaluated.  `before` has the earlier state of each
+    // objective that changed.
+    fn evaluated(&mut self, engine: &Engine, before: &HashMap<String, ObjectiveState>);
+
+    // Updates changed objective states.  Not called for updates that change
+    // nothing.
+    fn state_changed(&mut self, engine: &Engine, event: &StateEvent);
+}
+
+// For evaluating without deriving anything.
+impl EngineObserver for () {
+    fn evaluated(&mut self, _engine: &Engine, _before: &HashMap<String, ObjectiveState>) {}
+
+    fn state_changed(&mut self, _engine: &Engine, _event: &StateEvent) {}
+}
+
+// Outcome of one of a module's tests.  Passed when `failures` is empty.
+#[derive(Clone, Debug)]
+pub struct ModuleTestResult {
+    pub name: String,
+    pub failures: Vec<String>,
+}
+
+pub struct Engine {
+    module: Arc<Module>,
+    objectives: HashMap<String, ObjectiveState>,
+    // Where each objective's state was last set from.  Objectives whose
+    // state has only been derived from other objectives are absent.
+    sources: HashMap<String, StateSource>,
+    eval_order: Vec<String>,
+
+    // Objectives whose state the user pinned.  Neither evaluation nor
+    // updates change them until they are unlocked.
+    locked: HashSet<String>,
+
+    // Entrances the user found to lead to each other.  Stored both ways.
+    connections: HashMap<String, String>,
+
+    // Objectives each locked objective is missing to become unlocked.
+    // Recomputed whenever objective state is evaluated.
+    logic_hints: HashMap<String, Vec<String>>,
+
+    event_log: EventLog,
+}
+
+impl Engine {
+    // Creates an engine for `module` and evaluates its objectives.
+    pub fn new(module: Arc<Module>, observer: &mut dyn EngineObserver) -> Result<Engine, Error> {
+        let objectives = module
+            .objectives
+            .keys()
+            .map(|id| (id.clone(), ObjectiveState::Disabled))
+            .collect();
+        let eval_order = Self::calc_eval_order(&module)?;
+        let mut engine = Engine {
+            module,
+            objectives,
+            sources: HashMap::new(),
+            eval_order,
+            locked: HashSet::new(),
+            connections: HashMap::new(),
+            logic_hints: HashMap::new(),
+            event_log: EventLog::new(),
+        };
+        engine.eval_objectives(observer)?;
+        Ok(engine)
+    }
+
+    pub fn calc_eval_order(module: &Module) -> Result<Vec<String>, Error> {
+        // `petgraph` requires indexes to be integers so we first enumerate our
+        // objectives and assign the integer indexes.  We keep maps from
+        // id -> index and index -> id so we can create the graph then
+        // return the topological sort order by id.
+        //
+        // We expect the node count to be fairly low so this conversion
+        // happening once at module load and updates requiring several
+        // HashMap lookups.  If this becomes a performance bottleneck,
+        // we can switch to storing everything in a Vec and converting
+        // ids to indexes at module load and keeping the that way.
+        let mut id_map = HashMap::new();
+        let mut index_map = HashMap::new();
+        let mut index = 0;
+
+        for id in module.objectives.keys() {
+            id_map.insert(index, id.clone());
+            index_map.insert(id.clone(), index);
+            index += 1;
+        }
+
+        // Generate a list of edges a tuples of (node, dependant node).
+        // Dependencies come from the objective unlocked_by and
+        // enabled_by expressions.
+        let mut edges = Vec::new();
+        for (id, info) in &module.objectives {
+            let idx = index_map.get(id).unwrap();
+            let mut deps = info.enabled_by.deps();
+            deps.append(&mut info.unlocked_by.deps());
+            deps.append(&mut info.completed_by.deps());
+
+            // TODO(konkers): we could de-dup these for a performance gain.
+            for dep in deps {
+                // Unknown ids were replaced with `False` when the module
+                // was loaded.
+                if let Some(dep_idx) = index_map.get(&dep) {
+                    edges.push((*dep_idx, *idx));
+                }
+            }
+        }
+
+        // Every objective is added, in module order, so ones without
+        // dependencies are evaluated and ties are broken the same way on
+        // every run.
+        let mut graph = DiGraph::<u32, ()>::with_capacity(index as usize, edges.len());
+        for i in 0..index {
+            graph.add_node(i);
+        }
+        graph.extend_with_edges(&edges);
+
+        // A topological sort gives us a static traversal order allowing
+        // os to propagate objective state changes in a single pass.
+        let nodes = toposort(&graph, None)
+            .map_err(|e| format_err!("cycle detected in objective dependencies: {:?}", e))?;
+
+        // Convert the eval_order back into a Vec of String ids.
+        let mut eval_order = Vec::new();
+        for node_index in nodes {
+            eval_order.push(id_map.get(&(node_index.index() as u32)).unwrap().clone());
+        }
+
+        Ok(eval_order)
+    }
+
+    pub fn module(&self) -> &Arc<Module> {
+        &self.module
+    }
+
+    pub fn objectives(&self) -> &HashMap<String, ObjectiveState> {
+        &self.objectives
+    }
+
+    // Objective ids in the order they are evaluated: each after the ones
+    // its expressions depend on.
+    pub fn eval_order(&self) -> &[String] {
+        &self.eval_order
+    }
+
+    pub fn event_log(&self) -> &EventLog {
+        &self.event_log
+    }
+
+    // Starts the session clock again from `clock`, dropping the log.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.event_log = EventLog::with_clock(clock);
+    }
+
+    // Time played this session.
+    pub fn elapsed(&self) -> Duration {
+        self.event_log.elapsed()
+    }
+
+    pub fn pause_clock(&mut self) {
+        self.event_log.pause();
+    }
+
+    pub fn resume_clock(&mut self) {
+        self.event_log.resume();
+    }
+
+    pub fn record_event(&mut self, event: SessionEvent) {
+        self.event_log.record_event(event);
+    }
+
+    pub fn eval_objectives(&mut self, observer: &mut dyn EngineObserver) -> Result<(), Error> {
+        self.eval_changes(&mut HashMap::new(), observer)
+    }
+
+    // Evaluates the objectives, adding the state of each one that changes to
+    // `before` unless it's already there.
+    fn eval_changes(
+        &mut self,
+        before: &mut HashMap<String, ObjectiveState>,
+        observer: &mut dyn EngineObserver,
+    ) -> Result<(), Error> {
+        // Reaching an entrance or region reaches the ones it leads to, which
+        // can unlock objectives that reach more.  Passes repeat until no more
+        // are reached.
+        let mut reached = HashSet::new();
+        let manifest = &self.module.manifest;
+        let max_passes = manifest.entrances.len() + manifest.regions.len() + 1;
+        for _ in 0..max_passes {
+            self.eval_pass(&reached, before)?;
+            let next = self.reached()?;
+            if next == reached {
+                break;
+            }
+            reached = next;
+        }
+        self.update_logic_hints()?;
+        observer.evaluated(self, before);
+        Ok(())
+    }
+
+    // Entrances connected to an unlocked or complete entrance and regions
+    // with a way in from one.
+    fn reached(&self) -> Result<HashSet<String>, Error> {
+        let is_reached = |id: &String| {
+            self.objectives
+                .get(id)
+                .map_or(false, |s| s.at_least(&ObjectiveState::Unlocked))
+        };
+        let mut reached: HashSet<String> = self
+            .connections
+            .iter()
+            .filter(|(_, to)| is_reached(to))
+            .map(|(from, _)| from.clone())
+            .collect();
+
+        // Breadth first through the exits of regions reached so far.
+        let regions: HashMap<&String, &RegionInfo> = self
+            .module
+            .manifest
+            .regions
+            .iter()
+            .map(|r| (&r.id, r))
+            .collect();
+        let mut visited: HashSet<&String> = regions
+            .keys()
+            .copied()
+            .filter(|id| is_reached(id))
+            .collect();
+        let mut queue: VecDeque<&String> = visited.iter().copied().collect();
+        while let Some(id) = queue.pop_front() {
+            for exit in &regions[id].exits {
+                if !exit.requires.evaluate_unlocked(&self.objectives)? {
+                    continue;
+                }
+                reached.insert(exit.to.clone());
+                if visited.insert(&exit.to) {
+                    queue.push_back(&exit.to);
+                }
+            }
+        }
+        Ok(reached)
+    }
+
+    // Evaluates every objective once in dependency order.  Entrances in
+    // `reached` are unlocked regardless of their expressions.
+    fn eval_pass(
+        &mut self,
+        reached: &HashSet<String>,
+        before: &mut HashMap<String, ObjectiveState>,
+    ) -> Result<(), Error> {
+        for id in &self.eval_order {
+            if self.locked.contains(id) {
+                continue;
+            }
+            let info = self
+                .module
+                .objectives
+                .get(id)
+                .ok_or(format_err!("Can't get info for objective '{}'", id))?;
+
+            let old_state = *self
+                .objectives
+                .get(id)
+                .ok_or(format_err!("can't get objective state for '{}`", id))?;
+            let mut state = old_state;
+
+            if info.enabled_by != Expression::Manual {
+                let enabled = info.enabled_by.evaluate_enabled(&self.objectives)?;
+                if state == ObjectiveState::Disabled && enabled {
+                    state = ObjectiveState::Locked;
+                }
+            }
+
+            if info.unlocked_by != Expression::Manual {
+                let unlocked =
+                    info.unlocked_by.evaluate_unlocked(&self.objectives)? || reached.contains(id);
+                // Objectives marked out of logic are in logic once unlocked.
+                if (state == ObjectiveState::Locked || state == ObjectiveState::GlitchLocked)
+                    && unlocked
+                {
+                    state = ObjectiveState::Unlocked;
+                }
+            }
+
+            if info.completed_by != Expression::Manual {
+                let completed = info.completed_by.evaluate_unlocked(&self.objectives)?;
+                if completed {
+                    state = ObjectiveState::Complete;
+                }
+                if state == ObjectiveState::Complete && !completed {
+                    state = ObjectiveState::Unlocked;
+                }
+            }
+
+            if info.unlocked_by != Expression::Manual {
+                let unlocked =
+                    info.unlocked_by.evaluate_unlocked(&self.objectives)? || reached.contains(id);
+                // Re-lock if a dependencies become locked.
+                if state == ObjectiveState::Unlocked && !unlocked {
+                    state = ObjectiveState::Locked;
+                }
+            }
+
+            if info.enabled_by != Expression::Manual {
+                let enabled = info.enabled_by.evaluate_enabled(&self.objectives)?;
+                if !enabled {
+                    state = ObjectiveState::Disabled;
+                }
+            }
+            if state != old_state {
+                before.entry(id.clone()).or_insert(old_state);
+            }
+            *self
+                .objectives
+                .get_mut(id)
+                .ok_or(format_err!("can't get objective state for '{}`", id))? = state;
+        }
+        Ok(())
+    }
+
+    // Connects entrances `a` and `b` so reaching either reaches the other.
+    // Earlier connections of either are removed.
+    pub fn connect_entrances(
+        &mut self,
+        a: &String,
+        b: &String,
+        observer: &mut dyn EngineObserver,
+    ) -> Result<(), Error> {
+        for id in &[a, b] {
+            if !self.module.is_entrance(id) {
+                return Err(format_err!("{} is not an entrance", id));
+            }
+        }
+        if a == b {
+            return Err(format_err!("can't connect {} to itself", a));
+        }
+        self.connect(a, b);
+        self.event_log
+            .record_event(SessionEvent::Connect(a.clone(), b.clone()));
+        self.eval_objectives(observer)
+    }
+
+    pub fn disconnect_entrance(
+        &mut self,
+        id: &String,
+        observer: &mut dyn EngineObserver,
+    ) -> Result<(), Error> {
+        self.disconnect(id);
+        self.event_log
+            .record_event(SessionEvent::Disconnect(id.clone()));
+        self.eval_objectives(observer)
+    }
+
+    // The entrance `id` is connected to, if any.
+    pub fn connection(&self, id: &String) -> Option<&String> {
+        self.connections.get(id)
+    }
+
+    fn connect(&mut self, a: &String, b: &String) {
+        self.disconnect(a);
+        self.disconnect(b);
+        self.connections.insert(a.clone(), b.clone());
+        self.connections.insert(b.clone(), a.clone());
+    }
+
+    fn disconnect(&mut self, id: &String) {
+        if let Some(other) = self.connections.remove(id) {
+            self.connections.remove(&other);
+        }
+    }
+
+    pub fn is_locked(&self, id: &String) -> bool {
+        self.locked.contains(id)
+    }
+
+    // Pin `id` at its current state, or release it if it already is.
+    // Released objectives are evaluated again right away.
+    pub fn toggle_lock(
+        &mut self,
+        id: &String,
+        observer: &mut dyn EngineObserver,
+    ) -> Result<(), Error> {
+        if !self.objectives.contains_key(id) {
+            return Err(format_err!("toggle_lock: id {} not found", id));
+        }
+        if !self.locked.remove(id) {
+            self.locked.insert(id.clone());
+        }
+        self.eval_objectives(observer)
+    }
+
+    // Re-checks which objectives locked ones are missing.
+    fn update_logic_hints(&mut self) -> Result<(), Error> {
+        self.logic_hints.clear();
+        for (id, info) in &self.module.objectives {
+            if self.objectives.get(id) != Some(&ObjectiveState::Locked)
+                || info.unlocked_by == Expression::Manual
+            {
+                continue;
+            }
+            if let Some(missing) = info.unlocked_by.missing_unlocked(&self.objectives)? {
+                self.logic_hints.insert(id.clone(), missing);
+            }
+        }
+
+        // Locations unlock with their first check so they need whatever the
+        // closest check needs.
+        for (id, info) in &self.module.objectives {
+            if info.checks.is_empty()
+                || self.logic_hints.contains_key(id)
+                || self.objectives.get(id) != Some(&ObjectiveState::Locked)
+            {
+                continue;
+            }
+            let closest = info
+                .checks
+                .iter()
+                .filter_map(|check| self.logic_hints.get(&check.id))
+                .min_by_key(|missing| missing.len())
+                .cloned();
+            if let Some(missing) = closest {
+                self.logic_hints.insert(id.clone(), missing);
+            }
+        }
+        Ok(())
+    }
+
+    // Objectives `id` is missing to become unlocked.  `None` unless `id` is
+    // locked and can be unlocked by finding objectives.
+    pub fn logic_hint(&self, id: &String) -> Option<&Vec<String>> {
+        self.logic_hints.get(id)
+    }
+
+    // Sets `id` to `state` without logging or evaluating it, for objectives
+    // set up before playing such as params.  Call `eval_objectives()` after.
+    pub fn set_param_state(&mut self, id: &String, state: ObjectiveState) -> Result<(), Error> {
+        *self
+            .objectives
+            .get_mut(id)
+            .ok_or(format_err!("objective {} not found", id))? = state;
+        Ok(())
+    }
+
+    // Returns the objectives whose state would change if `updates` were
+    // applied.
+    pub fn state_changes(&self, updates: &HashMap<String, ObjectiveState>) -> Vec<StateChange> {
+        let mut changes = Vec::new();
+        for (id, new_state) in updates {
+            let old_state = match self.objectives.get(id) {
+                Some(state) => *state,
+                None => continue,
+            };
+            if old_state == *new_state {
+                continue;
+            }
+            let ty = match self.module.objectives.get(id) {
+                Some(info) => info.ty.clone(),
+                None => "".into(),
+            };
+            changes.push(StateChange {
+                id: id.clone(),
+                ty,
+                old_state,
+                new_state: *new_state,
+            });
+        }
+        changes
+    }
+
+    pub fn state_source(&self, id: &String) -> Option<StateSource> {
+        self.sources.get(id).copied()
+    }
+
+    // Returns the subset of `updates` from `source` that the module's
+    // auto track policy allows.
+    pub fn allowed_updates(
+        &self,
+        updates: &HashMap<String, ObjectiveState>,
+        source: StateSource,
+    ) -> HashMap<String, ObjectiveState> {
+        let policy = self.module.manifest.auto_track_policy;
+        // Auto trackers may still use ids that have since been renamed.
+        updates
+            .iter()
+            .map(|(id, state)| (self.module.canonical_id(id), state))
+            .filter(|(id, _)| !self.locked.contains(*id))
+            .filter(|(id, new_state)| {
+                let old_state = match self.objectives.get(*id) {
+                    Some(state) => state,
+                    None => return true,
+                };
+                if source != StateSource::AutoTracker || new_state.at_least(old_state) {
+                    return true;
+                }
+                match policy {
+                    AutoTrackPolicy::Any => true,
+                    AutoTrackPolicy::ProtectManual => {
+                        self.state_source(id) != Some(StateSource::Manual)
+                    }
+                    AutoTrackPolicy::UpgradeOnly => false,
+                }
+            })
+            .map(|(id, state)| (id.clone(), *state))
+            .collect()
+    }
+
+    // Applies `updates` and re-evaluates the objectives.  Returns the
+    // objectives whose state changed so only they need to be redrawn.
+    pub fn update_state(
+        &mut self,
+        updates: &HashMap<String, ObjectiveState>,
+        source: StateSource,
+        observer: &mut dyn EngineObserver,
+    ) -> Result<ChangeSet, Error> {
+        self.apply_updates(updates, source, true, observer)
+    }
+
+    // Like `update_state()` but the objectives are only evaluated once all of
+    // `updates` are applied, which is much faster for many updates at once.
+    pub fn batch_update(
+        &mut self,
+        updates: &HashMap<String, ObjectiveState>,
+        source: StateSource,
+        observer: &mut dyn EngineObserver,
+    ) -> Result<ChangeSet, Error> {
+        self.apply_updates(updates, source, false, observer)
+    }
+
+    fn apply_updates(
+        &mut self,
+        updates: &HashMap<String, ObjectiveState>,
+        source: StateSource,
+        eval_each: bool,
+        observer: &mut dyn EngineObserver,
+    ) -> Result<ChangeSet, Error> {
+        let updates = self.allowed_updates(updates, source);
+        let changes = self.state_changes(&updates);
+        for change in &changes {
+            self.event_log.record(change, source);
+        }
+        let mut before = HashMap::new();
+        for (id, state) in &updates {
+            let old_state = self.objectives.insert(id.clone(), *state);
+            if old_state != Some(*state) {
+                self.sources.insert(id.clone(), source);
+                if let Some(old_state) = old_state {
+                    before.entry(id.clone()).or_insert(old_state);
+                }
+            }
+            if eval_each {
+                self.eval_changes(&mut before, observer)?;
+            }
+        }
+        if !eval_each {
+            self.eval_changes(&mut before, observer)?;
+        }
+        // Go mode is only ever evaluated but observers are told about it
+        // like any other change.
+        let mut event_changes = changes.clone();
+        if let Some(old_state) = before.get(GO_MODE_ID) {
+            let new_state = self.objectives[GO_MODE_ID];
+            if new_state != *old_state {
+                event_changes.push(StateChange {
+                    id: GO_MODE_ID.to_string(),
+                    ty: GO_MODE_ID.to_string(),
+                    old_state: *old_state,
+                    new_state,
+                });
+            }
+        }
+        if !event_changes.is_empty() {
+            let event = StateEvent {
+                changes: event_changes,
+                source,
+            };
+            observer.state_changed(self, &event);
+        }
+        // Objectives can change and change back while the updates are
+        // applied one at a time.
+        let changed = before
+            .into_iter()
+            .filter(|(id, old_state)| self.objectives.get(id) != Some(old_state))
+            .map(|(id, _)| id)
+            .collect();
+        Ok(ChangeSet { changes, changed })
+    }
+
+    // A new engine with the objectives as they were `time` into the
+    // session.  The updates and connections logged up to then are replayed
+    // and the objectives evaluated.  Nothing is pinned in it.
+    pub fn at_time(
+        &self,
+        time: Duration,
+        observer: &mut dyn EngineObserver,
+    ) -> Result<Engine, Error> {
+        let mut engine = Engine::new(self.module.clone(), &mut ())?;
+        for entry in self.event_log.until(time) {
+            engine.objectives.insert(entry.id.clone(), entry.new_state);
+            engine.sources.insert(entry.id.clone(), entry.source);
+        }
+        for entry in self.event_log.events_until(time) {
+            match &entry.event {
+                SessionEvent::Connect(a, b) => engine.connect(a, b),
+                SessionEvent::Disconnect(id) => engine.disconnect(id),
+                SessionEvent::Hint { .. } => (),
+            }
+        }
+        engine.eval_objectives(observer)?;
+        Ok(engine)
+    }
+}
+
+// Runs the module's `tests.json`.  Each test starts from a fresh engine.
+pub fn run_module_tests(module: &Arc<Module>) -> Result<Vec<ModuleTestResult>, Error> {
+    let mut results = Vec::new();
+    for test in &module.tests {
+        let mut engine = Engine::new(module.clone(), &mut ())?;
+        let mut failures = Vec::new();
+        for (i, step) in test.steps.iter().enumerate() {
+            engine.update_state(&step.set, StateSource::Manual, &mut ())?;
+            let mut expected: Vec<_> = step.expect.iter().collect();
+            expected.sort_by_key(|(id, _)| *id);
+            for (id, state) in expected {
+                let actual = engine.objectives.get(id).copied();
+                if actual != Some(*state) {
+                    failures.push(format!(
+                        "step {}: {} is {:?}, expected {:?}",
+                        i + 1,
+                        id,
+                        actual.unwrap_or(ObjectiveState::Disabled),
+                        state
+                    ));
+                }
+            }
+        }
+        results.push(ModuleTestResult {
+            name: test.name.clone(),
+            failures,
+        });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ModuleBuilder;
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    fn build_engine(builder: ModuleBuilder) -> Result<Engine, Error> {
+        Engine::new(Arc::new(builder.build()?), &mut ())
+    }
+
+    fn assert_state(engine: &Engine, id: &str, state: ObjectiveState) {
+        assert_eq!(engine.objectives()[id], state);
+    }
+
+    fn update_state(
+        engine: &mut Engine,
+        updates: &[(&str, ObjectiveState)],
+    ) -> Result<ChangeSet, Error> {
+        let updates = updates.iter().map(|x| (x.0.to_string(), x.1)).collect();
+        engine.update_state(&updates, StateSource::Manual, &mut ())
+    }
+
+    // Gives `engine` a session clock that only moves forward when the
+    // returned function is called with a number of seconds.
+    fn manual_clock(engine: &mut Engine) -> impl Fn(u64) {
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let clock_now = now.clone();
+        engine.set_clock(Arc::new(move || *clock_now.lock().unwrap()));
+        move |secs| *now.lock().unwrap() += Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn eval_order_is_stable() -> Result<(), Error> {
+        let build = || {
+            ModuleBuilder::new()
+                .objective("b")
+                .unlocked_by("a")
+                .objective("a")
+                .objective("c")
+                .build()
+        };
+        let module = build()?;
+        let ids: Vec<&String> = module.objectives.keys().collect();
+        assert_eq!(ids, vec!["b", "a", "c"]);
+
+        // Objectives no others depend on are still evaluated.
+        let order = Engine::calc_eval_order(&module)?;
+        assert_eq!(order.len(), 3);
+        let pos = |id: &str| order.iter().position(|o| o == id).unwrap();
+        assert!(pos("a") < pos("b"));
+
+        for _ in 0..4 {
+            assert_eq!(Engine::calc_eval_order(&build()?)?, order);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unlocked_by() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("castle")
+                .check("castle:0", "complete(key)"),
+        )?;
+        assert_state(&engine, "castle", ObjectiveState::Locked);
+
+        // Finding the key isn't enough.
+        update_state(&mut engine, &[("key", ObjectiveState::Unlocked)])?;
+        assert_state(&engine, "castle", ObjectiveState::Locked);
+
+        update_state(&mut engine, &[("key", ObjectiveState::Complete)])?;
+        assert_state(&engine, "castle", ObjectiveState::Unlocked);
+
+        // Un-completing the key re-locks the castle.
+        update_state(&mut engine, &[("key", ObjectiveState::Unlocked)])?;
+        assert_state(&engine, "castle", ObjectiveState::Locked);
+
+        Ok(())
+    }
+
+    #[test]
+    fn gated_checks() -> Result<(), Error> {
+        // The castle starts Unlocked due to its ungated checks, locks once
+        // those are complete, unlocks again with the key and completes with
+        // the gated checks.
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("castle")
+                .check("castle:0", "")
+                .check("castle:1", "")
+                .check("castle:2", "key"),
+        )?;
+        assert_state(&engine, "castle", ObjectiveState::Unlocked);
+
+        update_state(
+            &mut engine,
+            &[
+                ("castle:0", ObjectiveState::Complete),
+                ("castle:1", ObjectiveState::Complete),
+            ],
+        )?;
+        assert_state(&engine, "castle", ObjectiveState::Locked);
+
+        update_state(&mut engine, &[("key", ObjectiveState::Unlocked)])?;
+        assert_state(&engine, "castle", ObjectiveState::Unlocked);
+
+        update_state(&mut engine, &[("castle:2", ObjectiveState::Complete)])?;
+        assert_state(&engine, "castle", ObjectiveState::Complete);
+
+        Ok(())
+    }
+
+    #[test]
+    fn disabled_checks() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("flag")
+                .enabled_by("param")
+                .objective("mountain")
+                .gated_check("mountain:0", "!flag", "")
+                .check("mountain:1", "")
+                .objective("town")
+                .gated_check("town:0", "!flag", ""),
+        )?;
+        assert_state(&engine, "town:0", ObjectiveState::Unlocked);
+        assert_state(&engine, "town", ObjectiveState::Unlocked);
+
+        // Turning on the flag disables the gated checks.  Locations with
+        // only disabled checks are disabled.
+        update_state(&mut engine, &[("flag", ObjectiveState::Unlocked)])?;
+        assert_state(&engine, "mountain:0", ObjectiveState::Disabled);
+        assert_state(&engine, "mountain", ObjectiveState::Unlocked);
+        assert_state(&engine, "town:0", ObjectiveState::Disabled);
+        assert_state(&engine, "town", ObjectiveState::Disabled);
+
+        // Completing all non-disabled checks completes the location.
+        update_state(&mut engine, &[("mountain:1", ObjectiveState::Complete)])?;
+        assert_state(&engine, "mountain", ObjectiveState::Complete);
+
+        update_state(&mut engine, &[("flag", ObjectiveState::Disabled)])?;
+        assert_state(&engine, "town:0", ObjectiveState::Unlocked);
+        assert_state(&engine, "town", ObjectiveState::Unlocked);
+
+        Ok(())
+    }
+
+    #[test]
+    fn state_changes() -> Result<(), Error> {
+        let engine = build_engine(
+            ModuleBuilder::new()
+                .objective("magma-key")
+                .objective_type("key-item")
+                .objective("baron")
+                .unlocked_by("true"),
+        )?;
+
+        // Only objectives that actually change state should be reported.
+        let updates = vec![
+            ("magma-key".to_string(), ObjectiveState::Complete),
+            ("baron".to_string(), ObjectiveState::Unlocked),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            engine.state_changes(&updates),
+            vec![StateChange {
+                id: "magma-key".to_string(),
+                ty: "key-item".to_string(),
+                old_state: ObjectiveState::Locked,
+                new_state: ObjectiveState::Complete,
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn change_sets() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("door")
+                .unlocked_by("complete(key)")
+                .objective("chest"),
+        )?;
+        let key = "key".to_string();
+        let door = "door".to_string();
+
+        // The door is re-evaluated but only the key was updated.
+        let changes = update_state(&mut engine, &[("key", ObjectiveState::Complete)])?;
+        assert_eq!(
+            changes.changes.iter().map(|c| &c.id).collect::<Vec<_>>(),
+            vec![&key]
+        );
+        assert_eq!(changes.changed, vec![key, door].into_iter().collect());
+
+        // Setting a state it already has changes nothing.
+        assert!(update_state(&mut engine, &[("key", ObjectiveState::Complete)])?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn observers() -> Result<(), Error> {
+        #[derive(Default)]
+        struct Recorder {
+            before: Vec<HashMap<String, ObjectiveState>>,
+            events: Vec<StateEvent>,
+        }
+        impl EngineObserver for Recorder {
+            fn evaluated(&mut self, _engine: &Engine, before: &HashMap<String, ObjectiveState>) {
+                self.before.push(before.clone());
+            }
+
+            fn state_changed(&mut self, _engine: &Engine, event: &StateEvent) {
+                self.events.push(event.clone());
+            }
+        }
+
+        let mut recorder = Recorder::default();
+        let module = ModuleBuilder::new()
+            .objective("key")
+            .objective("door")
+            .unlocked_by("complete(key)")
+            .build()?;
+        let mut engine = Engine::new(Arc::new(module), &mut recorder)?;
+        assert_eq!(recorder.before[0]["door"], ObjectiveState::Disabled);
+
+        let mut updates = HashMap::new();
+        updates.insert("key".to_string(), ObjectiveState::Complete);
+        engine.update_state(&updates, StateSource::AutoTracker, &mut recorder)?;
+        let before = recorder.before.last().unwrap();
+        assert_eq!(before["key"], ObjectiveState::Locked);
+        assert_eq!(before["door"], ObjectiveState::Locked);
+        assert_eq!(recorder.events.len(), 1);
+        assert_eq!(recorder.events[0].source, StateSource::AutoTracker);
+
+        // Updates that change nothing aren't events.
+        engine.update_state(&updates, StateSource::Manual, &mut recorder)?;
+        assert_eq!(recorder.events.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn batch_updates() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("cave")
+                .check("cave:0", "")
+                .check("cave:1", "complete(key)"),
+        )?;
+        let updates = |state| {
+            vec![("cave:0".to_string(), state), ("cave:1".to_string(), state)]
+                .into_iter()
+                .collect()
+        };
+
+        let changes = engine.batch_update(
+            &updates(ObjectiveState::Complete),
+            StateSource::Manual,
+            &mut (),
+        )?;
+        assert_eq!(changes.changes.len(), 2);
+        assert_state(&engine, "cave", ObjectiveState::Complete);
+
+        // Reset checks are evaluated again.
+        engine.batch_update(
+            &updates(ObjectiveState::Locked),
+            StateSource::Manual,
+            &mut (),
+        )?;
+        assert_state(&engine, "cave:0", ObjectiveState::Unlocked);
+        assert_state(&engine, "cave:1", ObjectiveState::Locked);
+
+        Ok(())
+    }
+
+    #[test]
+    fn locks() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("door")
+                .unlocked_by("complete(key)"),
+        )?;
+        let door = "door".to_string();
+        assert_state(&engine, "door", ObjectiveState::Locked);
+
+        engine.toggle_lock(&door, &mut ())?;
+        assert!(engine.is_locked(&door));
+        update_state(&mut engine, &[("key", ObjectiveState::Complete)])?;
+        assert_state(&engine, "door", ObjectiveState::Locked);
+        let mut updates = HashMap::new();
+        updates.insert(door.clone(), ObjectiveState::Complete);
+        assert!(engine
+            .update_state(&updates, StateSource::AutoTracker, &mut ())?
+            .is_empty());
+        assert_state(&engine, "door", ObjectiveState::Locked);
+
+        // Unlocking catches up with the evaluation it missed.
+        engine.toggle_lock(&door, &mut ())?;
+        assert!(!engine.is_locked(&door));
+        assert_state(&engine, "door", ObjectiveState::Unlocked);
+        assert!(engine.toggle_lock(&"nope".to_string(), &mut ()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn module_tests() -> Result<(), Error> {
+        let mut module = ModuleBuilder::new()
+            .objective("key")
+            .objective("door")
+            .unlocked_by("complete(key)")
+            .build()?;
+        module.tests = serde_json::from_str(
+            r#"[
+                {
+                    "name": "key opens door",
+                    "steps": [
+                        {"expect": {"door": "locked"}},
+                        {"set": {"key": "complete"}, "expect": {"door": "unlocked"}}
+                    ]
+                },
+                {
+                    "name": "starts fresh",
+                    "steps": [{"expect": {"key": "complete"}}]
+                }
+            ]"#,
+        )?;
+
+        let results = run_module_tests(&Arc::new(module))?;
+        assert_eq!(results.len(), 2);
+        assert!(results[0].failures.is_empty());
+        assert_eq!(
+            results[1].failures,
+            vec!["step 1: key is Locked, expected Complete".to_string()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn entrances() -> Result<(), Error> {
+        // The house door is on the overworld.  The cave is behind a shuffled
+        // door and its back exit leads out again.
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .entrance("house-door", "true")
+                .entrance("cave-front", "")
+                .entrance("cave-back", "cave-front")
+                .entrance("tower-door", "")
+                .objective("cave-chest")
+                .check("cave-chest:0", "cave-front"),
+        )?;
+        let house = "house-door".to_string();
+        let cave = "cave-front".to_string();
+        let tower = "tower-door".to_string();
+        assert_state(&engine, "house-door", ObjectiveState::Unlocked);
+        assert_state(&engine, "cave-front", ObjectiveState::Locked);
+        assert_state(&engine, "cave-chest", ObjectiveState::Locked);
+
+        engine.connect_entrances(&house, &cave, &mut ())?;
+        assert_state(&engine, "cave-front", ObjectiveState::Unlocked);
+        assert_state(&engine, "cave-chest", ObjectiveState::Unlocked);
+
+        // Reached through the cave.
+        engine.connect_entrances(&"cave-back".to_string(), &tower, &mut ())?;
+        assert_state(&engine, "tower-door", ObjectiveState::Unlocked);
+
+        // Connecting the house door elsewhere drops its connection to the cave.
+        engine.connect_entrances(&house, &tower, &mut ())?;
+        assert_state(&engine, "cave-chest", ObjectiveState::Locked);
+        assert_eq!(engine.connection(&house), Some(&tower));
+        assert_eq!(engine.connection(&cave), None);
+
+        engine.disconnect_entrance(&tower, &mut ())?;
+        assert_state(&engine, "tower-door", ObjectiveState::Locked);
+        assert!(engine
+            .connect_entrances(&house, &"cave-chest".to_string(), &mut ())
+            .is_err());
+        assert!(engine.connect_entrances(&house, &house, &mut ()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn regions() -> Result<(), Error> {
+        // The lake is only reachable through the cave, which needs the lamp.
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .region("town", "true")
+                .exit("cave", "complete(lamp)")
+                .region("cave", "")
+                .exit("town", "")
+                .exit("lake", "")
+                .region("lake", "")
+                .objective("lamp")
+                .objective("cave-chest")
+                .in_region("cave")
+                .check("cave-chest:0", "")
+                .objective("lake-chest")
+                .in_region("lake")
+                .check("lake-chest:0", ""),
+        )?;
+        assert_state(&engine, "town", ObjectiveState::Unlocked);
+        assert_state(&engine, "cave", ObjectiveState::Locked);
+        assert_state(&engine, "lake-chest", ObjectiveState::Locked);
+
+        update_state(&mut engine, &[("lamp", ObjectiveState::Complete)])?;
+        assert_state(&engine, "cave", ObjectiveState::Unlocked);
+        assert_state(&engine, "cave-chest", ObjectiveState::Unlocked);
+        assert_state(&engine, "lake-chest", ObjectiveState::Unlocked);
+
+        update_state(&mut engine, &[("lamp", ObjectiveState::Unlocked)])?;
+        assert_state(&engine, "lake", ObjectiveState::Locked);
+        assert_state(&engine, "cave-chest", ObjectiveState::Locked);
+        Ok(())
+    }
+
+    #[test]
+    fn logic_hints() -> Result<(), Error> {
+        let module = Module::open("src/test_data/mod/manifest.json")?;
+        let mut engine = Engine::new(Arc::new(module), &mut ())?;
+        let gate = "dragon-gate".to_string();
+        assert_eq!(
+            engine.logic_hint(&gate),
+            Some(&vec!["cave-key".to_string(), "sword-of-might".to_string()])
+        );
+
+        update_state(&mut engine, &[("cave-key", ObjectiveState::Complete)])?;
+        assert_eq!(
+            engine.logic_hint(&gate),
+            Some(&vec!["sword-of-might".to_string()])
+        );
+
+        // Unlocked objectives have no hints.
+        update_state(&mut engine, &[("sword-of-might", ObjectiveState::Complete)])?;
+        assert_eq!(engine.logic_hint(&gate), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn auto_track_policy() -> Result<(), Error> {
+        let module = Module::open("src/test_data/mod/manifest.json")?;
+        let mut engine = Engine::new(Arc::new(module), &mut ())?;
+        let auto_update = |engine: &mut Engine, id: &str, state| {
+            let updates = vec![(id.to_string(), state)].into_iter().collect();
+            engine.update_state(&updates, StateSource::AutoTracker, &mut ())
+        };
+        let cave_key = "cave-key".to_string();
+        let sword = "sword-of-might".to_string();
+
+        // The test module protects states the user set.
+        update_state(&mut engine, &[("cave-key", ObjectiveState::Complete)])?;
+        assert_eq!(engine.state_source(&cave_key), Some(StateSource::Manual));
+        auto_update(&mut engine, "cave-key", ObjectiveState::Locked)?;
+        assert_eq!(engine.objectives()[&cave_key], ObjectiveState::Complete);
+
+        // The auto tracker may still correct its own states.
+        auto_update(&mut engine, "sword-of-might", ObjectiveState::Complete)?;
+        assert_eq!(engine.state_source(&sword), Some(StateSource::AutoTracker));
+        auto_update(&mut engine, "sword-of-might", ObjectiveState::Locked)?;
+        assert_eq!(engine.objectives()[&sword], ObjectiveState::Locked);
+
+        Ok(())
+    }
+
+    #[test]
+    fn at_time() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .entrance("house-door", "true")
+                .entrance("cave-front", "")
+                .objective("cave-chest")
+                .check("cave-chest:0", "cave-front")
+                .objective("key"),
+        )?;
+        let advance = manual_clock(&mut engine);
+        let cave = "cave-front".to_string();
+        let start = engine.elapsed();
+        advance(1);
+        engine.connect_entrances(&"house-door".to_string(), &cave, &mut ())?;
+        update_state(&mut engine, &[("key", ObjectiveState::Complete)])?;
+        let connected = engine.elapsed();
+        advance(1);
+        engine.disconnect_entrance(&cave, &mut ())?;
+
+        // Connections and updates made later aren't known yet, and ones
+        // removed later are still there.
+        let past = engine.at_time(start, &mut ())?;
+        assert_state(&past, "cave-chest", ObjectiveState::Locked);
+        assert_state(&past, "key", ObjectiveState::Locked);
+        let past = engine.at_time(connected, &mut ())?;
+        assert_state(&past, "cave-chest", ObjectiveState::Unlocked);
+        assert_state(&past, "key", ObjectiveState::Complete);
+        assert_eq!(
+            past.state_source(&"key".to_string()),
+            Some(StateSource::Manual)
+        );
+
+        assert_state(&engine, "cave-chest", ObjectiveState::Locked);
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{ObjectiveState, StateChange, StateSource};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventLogEntry {
+    // Time since the start of the session.
+    pub time: Duration,
+    pub id: String,
+    pub ty: String,
+    pub old_state: ObjectiveState,
+    pub new_state: ObjectiveState,
+    pub source: StateSource,
+}
+
+// Changes to the tracker other than objective states that logic depends on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SessionEvent {
+    // The two entrances were found to lead to each other.
+    Connect(String, String),
+    // The entrance's connection was removed.
+    Disconnect(String),
+    // The item was seen at the location.
+    Hint { location: String, item: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionEventEntry {
+    // Time since the start of the session.
+    pub time: Duration,
+    pub event: SessionEvent,
+}
+
+// Returns the current time.  Tests use one they can move forward.
+pub type Clock = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+// A record of objective state changes and session events made during a
+// session.
+pub struct EventLog {
+    clock: Clock,
+    start: Instant,
+    // When the clock was paused.  `None` while it runs.
+    paused: Option<Instant>,
+    entries: Vec<EventLogEntry>,
+    events: Vec<SessionEventEntry>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        EventLog::new()
+    }
+}
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        EventLog::with_clock(Arc::new(Instant::now))
+    }
+
+    pub fn with_clock(clock: Clock) -> EventLog {
+        EventLog {
+            start: clock(),
+            clock,
+            paused: None,
+            entries: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, change: &StateChange, source: StateSource) {
+        self.entries.push(EventLogEntry {
+            time: self.elapsed(),
+            id: change.id.clone(),
+            ty: change.ty.clone(),
+            old_state: change.old_state,
+            new_state: change.new_state,
+            source,
+        });
+    }
+
+    pub fn record_event(&mut self, event: SessionEvent) {
+        self.events.push(SessionEventEntry {
+            time: self.elapsed(),
+            event,
+        });
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.paused.unwrap_or_else(|| (self.clock)()) - self.start
+    }
+
+    // Stops the session clock.  Time spent paused doesn't count towards
+    // `elapsed`.
+    pub fn pause(&mut self) {
+        if self.paused.is_none() {
+            self.paused = Some((self.clock)());
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(paused) = self.paused.take() {
+            self.start += (self.clock)() - paused;
+        }
+    }
+
+    // Entries recorded after `time`, oldest first.
+    pub fn since(&self, time: Duration) -> &[EventLogEntry] {
+        let start = self.entries.partition_point(|e| e.time <= time);
+        &self.entries[start..]
+    }
+
+    // Entries recorded up to and including `time`, oldest first.
+    pub fn until(&self, time: Duration) -> &[EventLogEntry] {
+        let end = self.entries.partition_point(|e| e.time <= time);
+        &self.entries[..end]
+    }
+
+    // Session events recorded up to and including `time`, oldest first.
+    pub fn events_until(&self, time: Duration) -> &[SessionEventEntry] {
+        let end = self.events.partition_point(|e| e.time <= time);
+        &self.events[..end]
+    }
+
+    // Returns the first entry for each objective that reached at least
+    // `threshold`, in the order they happened.
+    pub fn first_reached(&self, threshold: &ObjectiveState) -> Vec<&EventLogEntry> {
+        let mut seen = Vec::new();
+        let mut entries = Vec::new();
+        for entry in &self.entries {
+            if entry.new_state.at_least(threshold) && !seen.contains(&&entry.id) {
+                seen.push(&entry.id);
+                entries.push(entry);
+            }
+        }
+        entries
+    }
+}
+
+pub fn format_duration(d: &Duration) -> String {
+    let secs = d.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // A log whose clock only moves when the returned time is moved.
+    fn manual_log() -> (EventLog, Arc<Mutex<Instant>>) {
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let clock_now = now.clone();
+        let log = EventLog::with_clock(Arc::new(move || *clock_now.lock().unwrap()));
+        (log, now)
+    }
+
+    fn change(id: &str, new_state: ObjectiveState) -> StateChange {
+        StateChange {
+            id: id.to_string(),
+            ty: "key-item".to_string(),
+            old_state: ObjectiveState::Locked,
+            new_state,
+        }
+    }
+
+    #[test]
+    fn first_reached() {
+        let mut log = EventLog::new();
+        let manual = StateSource::Manual;
+        log.record(&change("hook", ObjectiveState::Unlocked), manual);
+        log.record(&change("pass", ObjectiveState::Locked), manual);
+        log.record(&change("magma-key", ObjectiveState::Complete), manual);
+        log.record(&change("hook", ObjectiveState::Complete), manual);
+
+        let ids: Vec<&String> = log
+            .first_reached(&ObjectiveState::Unlocked)
+            .iter()
+            .map(|e| &e.id)
+            .collect();
+        assert_eq!(ids, vec!["hook", "magma-key"]);
+        assert_eq!(log.entries.len(), 4);
+    }
+
+    #[test]
+    fn since() {
+        let (mut log, now) = manual_log();
+        log.record(
+            &change("hook", ObjectiveState::Unlocked),
+            StateSource::Manual,
+        );
+        let time = log.elapsed();
+        *now.lock().unwrap() += Duration::from_secs(1);
+        log.record(
+            &change("pass", ObjectiveState::Complete),
+            StateSource::AutoTracker,
+        );
+        log.record_event(SessionEvent::Disconnect("cave".to_string()));
+
+        let ids: Vec<&String> = log.since(time).iter().map(|e| &e.id).collect();
+        assert_eq!(ids, vec!["pass"]);
+        assert!(log.since(log.elapsed()).is_empty());
+        let ids: Vec<&String> = log.until(time).iter().map(|e| &e.id).collect();
+        assert_eq!(ids, vec!["hook"]);
+        assert!(log.events_until(time).is_empty());
+        assert_eq!(log.events_until(log.elapsed()).len(), 1);
+    }
+
+    #[test]
+    fn pause() {
+        let (mut log, now) = manual_log();
+        *now.lock().unwrap() += Duration::from_secs(1);
+        log.pause();
+        *now.lock().unwrap() += Duration::from_secs(5);
+        assert_eq!(log.elapsed(), Duration::from_secs(1));
+
+        log.resume();
+        assert_eq!(log.elapsed(), Duration::from_secs(1));
+        *now.lock().unwrap() += Duration::from_secs(1);
+        assert_eq!(log.elapsed(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn duration() {
+        assert_eq!(format_duration(&Duration::from_secs(0)), "0:00:00");
+        assert_eq!(format_duration(&Duration::from_secs(3723)), "1:02:03");
+    }
+}
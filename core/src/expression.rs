@@ -0,0 +1,952 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use failure::{format_err, Error};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while, take_while1, take_while_m_n},
+    character::complete::digit1,
+    combinator::{map, map_res, opt, recognize},
+    error::ErrorKind,
+    multi::many0,
+    sequence::{pair, preceded},
+    IResult,
+};
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{stage_id, ObjectiveState};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Default,
+    Manual,
+    True,
+    False,
+    Objective(String),
+    ObjectiveComplete(String),
+    ObjectiveDisabled(String),
+    ObjectiveUnlocked(String),
+    // A staged objective has reached at least the given stage.
+    Stage(String, u32),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    // Any or all objectives in a tag or group.  These are replaced by `Or`
+    // and `And` expressions when a module is loaded.
+    Any(String),
+    All(String),
+}
+
+impl Default for Expression {
+    fn default() -> Self {
+        Expression::Default
+    }
+}
+
+fn is_lower_alphanum(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit()
+}
+
+fn is_lower_alpha(c: char) -> bool {
+    c.is_ascii_lowercase()
+}
+
+fn whitespace(input: &str) -> IResult<&str, &str> {
+    let chars = " \t\r\n";
+    take_while(move |c| chars.contains(c))(input)
+}
+
+// Recognizes the first part of an objective id.
+//   Matches regexp `[a-z][a-z0-9]*`
+fn objective_first_part(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        take_while_m_n(1, 1, is_lower_alpha),
+        opt(take_while(is_lower_alphanum)),
+    ))(input)
+}
+
+// Recognizes objectice id parts following the first.
+//   Matches regexp `-[a-z0-9]+`
+fn objective_part(input: &str) -> IResult<&str, &str> {
+    recognize(pair(tag("-"), take_while1(is_lower_alphanum)))(input)
+}
+
+fn objective_id(input: &str) -> IResult<&str, &str> {
+    preceded(
+        whitespace,
+        recognize(pair(objective_first_part, many0(objective_part))),
+    )(input)
+}
+
+fn objective(input: &str) -> IResult<&str, Expression> {
+    map(objective_id, |s: &str| match s {
+        "true" => Expression::True,
+        "false" => Expression::False,
+        _ => Expression::Objective(s.into()),
+    })(input)
+}
+
+// Parses `<name>(<objective id>)`.
+fn objective_fn<'a>(
+    input: &'a str,
+    name: &'static str,
+    f: impl Fn(String) -> Expression,
+) -> IResult<&'a str, Expression> {
+    let (input, _) = preceded(whitespace, tag(name))(input)?;
+    let (input, _) = preceded(whitespace, tag("("))(input)?;
+    let (input, expr) = map(preceded(whitespace, objective_id), |s: &str| f(s.into()))(input)?;
+    let (input, _) = preceded(whitespace, tag(")"))(input)?;
+
+    Ok((input, expr))
+}
+
+fn objective_complete(input: &str) -> IResult<&str, Expression> {
+    objective_fn(input, "complete", Expression::ObjectiveComplete)
+}
+
+fn objective_disabled(input: &str) -> IResult<&str, Expression> {
+    objective_fn(input, "disabled", Expression::ObjectiveDisabled)
+}
+
+fn objective_unlocked(input: &str) -> IResult<&str, Expression> {
+    objective_fn(input, "unlocked", Expression::ObjectiveUnlocked)
+}
+
+// Parses `stage(<objective id>) >= <stage>`.
+fn objective_stage(input: &str) -> IResult<&str, Expression> {
+    let (input, _) = preceded(whitespace, tag("stage"))(input)?;
+    let (input, _) = preceded(whitespace, tag("("))(input)?;
+    let (input, id) = preceded(whitespace, objective_id)(input)?;
+    let (input, _) = preceded(whitespace, tag(")"))(input)?;
+    let (input, _) = preceded(whitespace, tag(">="))(input)?;
+    let (input, stage) = preceded(whitespace, map_res(digit1, |s: &str| s.parse::<u32>()))(input)?;
+
+    Ok((input, Expression::Stage(id.into(), stage)))
+}
+
+fn any(input: &str) -> IResult<&str, Expression> {
+    objective_fn(input, "any", Expression::Any)
+}
+
+fn all(input: &str) -> IResult<&str, Expression> {
+    objective_fn(input, "all", Expression::All)
+}
+
+fn parenthetical(input: &str) -> IResult<&str, Expression> {
+    let (input, _) = preceded(whitespace, tag("("))(input)?;
+    let (input, expr) = preceded(whitespace, parse_expression)(input)?;
+    let (input, _) = preceded(whitespace, tag(")"))(input)?;
+    Ok((input, expr))
+}
+
+fn not(input: &str) -> IResult<&str, Expression> {
+    let (input, _) = preceded(whitespace, tag("!"))(input)?;
+    map(preceded(whitespace, parse_expression), |e: Expression| {
+        Expression::Not(Box::new(e))
+    })(input)
+}
+
+fn operand(input: &str) -> IResult<&str, Expression> {
+    alt((
+        parenthetical,
+        not,
+        objective_complete,
+        objective_disabled,
+        objective_unlocked,
+        objective_stage,
+        any,
+        all,
+        objective,
+    ))(input)
+}
+
+fn and_expr(input: &str) -> IResult<&str, Expression> {
+    let (input, a) = preceded(whitespace, operand)(input)?;
+    let (input, _) = preceded(whitespace, tag("&&"))(input)?;
+    let (input, b) = preceded(whitespace, and)(input)?;
+
+    Ok((input, Expression::And(Box::new(a), Box::new(b))))
+}
+
+fn and(input: &str) -> IResult<&str, Expression> {
+    alt((and_expr, operand))(input)
+}
+
+fn or_expr(input: &str) -> IResult<&str, Expression> {
+    let (input, a) = preceded(whitespace, and)(input)?;
+    let (input, _) = preceded(whitespace, tag("||"))(input)?;
+    let (input, b) = preceded(whitespace, or)(input)?;
+
+    Ok((input, Expression::Or(Box::new(a), Box::new(b))))
+}
+
+fn or(input: &str) -> IResult<&str, Expression> {
+    alt((or_expr, and))(input)
+}
+
+// Param is a special case for parameter objectives.  It is way of making
+// an objective enabled manually instead of defaulting to true.
+fn param(input: &str) -> IResult<&str, Expression> {
+    let (input, _) = preceded(whitespace, tag("param"))(input)?;
+    let (input, _) = whitespace(input)?;
+    // `param` must be the whole expression.
+    if !input.is_empty() {
+        return Err(nom::Err::Error((input, ErrorKind::Eof)));
+    }
+    Ok((input, Expression::Manual))
+}
+
+fn parse_expression(input: &str) -> IResult<&str, Expression> {
+    alt((param, or))(input)
+}
+
+impl Expression {
+    pub fn parse(input: &str) -> Result<Expression, Error> {
+        // Reports the unparsed `rest` of the input along with its offset.
+        let error = |rest: &str| {
+            let rest = rest.trim_start();
+            let offset = input.len() - rest.len();
+            if rest.is_empty() {
+                format_err!(
+                    "error parsing expression `{}`: unexpected end of input at offset {}",
+                    input,
+                    offset
+                )
+            } else {
+                format_err!(
+                    "error parsing expression `{}`: unexpected `{}` at offset {}",
+                    input,
+                    rest,
+                    offset
+                )
+            }
+        };
+
+        match parse_expression(input) {
+            Ok((rest, expr)) if rest.trim_start().is_empty() => Ok(expr),
+            Ok((rest, _)) => Err(error(rest)),
+            Err(nom::Err::Error((rest, _))) | Err(nom::Err::Failure((rest, _))) => Err(error(rest)),
+            Err(nom::Err::Incomplete(_)) => Err(error("")),
+        }
+    }
+
+    pub fn eval_default(self, default_value: Expression) -> Expression {
+        if self == Expression::Default {
+            default_value
+        } else {
+            self
+        }
+    }
+
+    pub fn or(self, other: Self) -> Expression {
+        // short circut constants
+        if self == Expression::False {
+            other
+        } else if other == Expression::False {
+            self
+        } else if other == Expression::True || self == Expression::True {
+            Expression::True
+        } else {
+            Expression::Or(Box::new(self), Box::new(other))
+        }
+    }
+
+    pub fn and(self, other: Self) -> Expression {
+        // short circut constants
+        if self == Expression::True {
+            other
+        } else if other == Expression::True {
+            self
+        } else if other == Expression::False || self == Expression::False {
+            Expression::False
+        } else {
+            Expression::And(Box::new(self), Box::new(other))
+        }
+    }
+
+    // Replace `any()` and `all()` with the objectives of the set they name.
+    // `lookup` returns the objective ids of a tag or group.
+    pub fn resolve_sets<'a>(
+        self,
+        lookup: &impl Fn(&str) -> Option<&'a Vec<String>>,
+    ) -> Result<Expression, Error> {
+        let ids =
+            |name: &String| lookup(name).ok_or(format_err!("unknown tag or group '{}'", name));
+        Ok(match self {
+            Expression::Any(name) => ids(&name)?.iter().fold(Expression::False, |e, id| {
+                e.or(Expression::Objective(id.clone()))
+            }),
+            Expression::All(name) => ids(&name)?.iter().fold(Expression::True, |e, id| {
+                e.and(Expression::Objective(id.clone()))
+            }),
+            Expression::Not(e) => Expression::Not(Box::new(e.resolve_sets(lookup)?)),
+            Expression::And(a, b) => Expression::And(
+                Box::new(a.resolve_sets(lookup)?),
+                Box::new(b.resolve_sets(lookup)?),
+            ),
+            Expression::Or(a, b) => Expression::Or(
+                Box::new(a.resolve_sets(lookup)?),
+                Box::new(b.resolve_sets(lookup)?),
+            ),
+            e => e,
+        })
+    }
+
+    // Replace objective ids that `lookup` knows an alias for with the id
+    // they stand for.
+    pub fn resolve_aliases<'a>(self, lookup: &impl Fn(&str) -> Option<&'a String>) -> Expression {
+        let id = |id: String| lookup(&id).cloned().unwrap_or(id);
+        match self {
+            Expression::Objective(i) => Expression::Objective(id(i)),
+            Expression::ObjectiveComplete(i) => Expression::ObjectiveComplete(id(i)),
+            Expression::ObjectiveDisabled(i) => Expression::ObjectiveDisabled(id(i)),
+            Expression::ObjectiveUnlocked(i) => Expression::ObjectiveUnlocked(id(i)),
+            Expression::Stage(i, stage) => Expression::Stage(id(i), stage),
+            Expression::Not(e) => Expression::Not(Box::new(e.resolve_aliases(lookup))),
+            Expression::And(a, b) => Expression::And(
+                Box::new(a.resolve_aliases(lookup)),
+                Box::new(b.resolve_aliases(lookup)),
+            ),
+            Expression::Or(a, b) => Expression::Or(
+                Box::new(a.resolve_aliases(lookup)),
+                Box::new(b.resolve_aliases(lookup)),
+            ),
+            e => e,
+        }
+    }
+
+    // Replace references to objectives `known` doesn't accept with `False`,
+    // adding their ids to `unknown`.
+    pub fn replace_unknown(
+        self,
+        known: &impl Fn(&str) -> bool,
+        unknown: &mut Vec<String>,
+    ) -> Expression {
+        let dep = match &self {
+            Expression::Objective(id)
+            | Expression::ObjectiveComplete(id)
+            | Expression::ObjectiveDisabled(id)
+            | Expression::ObjectiveUnlocked(id) => Some(id.clone()),
+            Expression::Stage(id, stage) if *stage > 0 => Some(stage_id(id, *stage)),
+            _ => None,
+        };
+        if let Some(id) = dep {
+            if known(&id) {
+                return self;
+            }
+            unknown.push(id);
+            return Expression::False;
+        }
+        match self {
+            Expression::Not(e) => Expression::Not(Box::new(e.replace_unknown(known, unknown))),
+            Expression::And(a, b) => Expression::And(
+                Box::new(a.replace_unknown(known, unknown)),
+                Box::new(b.replace_unknown(known, unknown)),
+            ),
+            Expression::Or(a, b) => Expression::Or(
+                Box::new(a.replace_unknown(known, unknown)),
+                Box::new(b.replace_unknown(known, unknown)),
+            ),
+            e => e,
+        }
+    }
+
+    // Return a `Vec` of objective ids upon which this expression depends.
+    pub fn deps(&self) -> Vec<String> {
+        match self {
+            Expression::Default
+            | Expression::Manual
+            | Expression::False
+            | Expression::True
+            | Expression::Any(_)
+            | Expression::All(_) => vec![],
+            Expression::Objective(id)
+            | Expression::ObjectiveComplete(id)
+            | Expression::ObjectiveDisabled(id)
+            | Expression::ObjectiveUnlocked(id) => vec![id.clone()],
+            Expression::Stage(_, 0) => vec![],
+            Expression::Stage(id, stage) => vec![stage_id(id, *stage)],
+            Expression::Not(obj) => obj.deps(),
+            Expression::And(a, b) => {
+                let mut d = a.deps();
+                d.append(&mut b.deps());
+                d
+            }
+            Expression::Or(a, b) => {
+                let mut d = a.deps();
+                d.append(&mut b.deps());
+                d
+            }
+        }
+    }
+
+    fn find_state<'a>(
+        id: &String,
+        state: &'a HashMap<String, ObjectiveState>,
+    ) -> Result<&'a ObjectiveState, Error> {
+        state.get(id).ok_or(format_err!("can't find id {}", id))
+    }
+
+    // Evaluate this expression based on `state`.
+    pub fn evaluate_by(
+        &self,
+        state: &HashMap<String, ObjectiveState>,
+        threshold: &ObjectiveState,
+    ) -> Result<bool, Error> {
+        match self {
+            Expression::Default => Err(format_err!("evaluate called on default expression")),
+            Expression::Manual => Err(format_err!("evaluate called on manual expression")),
+            Expression::Any(name) | Expression::All(name) => {
+                Err(format_err!("evaluate called on unresolved set '{}'", name))
+            }
+            Expression::False => Ok(false),
+            Expression::True => Ok(true),
+            Expression::Objective(id) => Self::find_state(id, state).map(|o| o.at_least(threshold)),
+            Expression::ObjectiveComplete(id) => {
+                Self::find_state(id, state).map(|o| o.is(&ObjectiveState::Complete))
+            }
+            Expression::ObjectiveDisabled(id) => {
+                Self::find_state(id, state).map(|o| o.is(&ObjectiveState::Disabled))
+            }
+            Expression::ObjectiveUnlocked(id) => {
+                Self::find_state(id, state).map(|o| o.is(&ObjectiveState::Unlocked))
+            }
+            // Every objective has reached stage 0.
+            Expression::Stage(_, 0) => Ok(true),
+            Expression::Stage(id, stage) => Self::find_state(&stage_id(id, *stage), state)
+                .map(|o| o.is(&ObjectiveState::Complete)),
+            Expression::Not(obj) => obj.evaluate_by(state, threshold).map(|v| !v),
+            Expression::And(a, b) => {
+                Ok(a.evaluate_by(state, threshold)? && b.evaluate_by(state, threshold)?)
+            }
+            Expression::Or(a, b) => {
+                Ok(a.evaluate_by(state, threshold)? || b.evaluate_by(state, threshold)?)
+            }
+        }
+    }
+
+    pub fn evaluate_unlocked(
+        &self,
+        state: &HashMap<String, ObjectiveState>,
+    ) -> Result<bool, Error> {
+        self.evaluate_by(state, &ObjectiveState::Unlocked)
+    }
+
+    pub fn evaluate_enabled(&self, state: &HashMap<String, ObjectiveState>) -> Result<bool, Error> {
+        self.evaluate_by(state, &ObjectiveState::Locked)
+    }
+
+    // Return the smallest set of objective ids found that, if raised to
+    // `threshold`, would make this expression true.  Returns `None` when no
+    // such set exists, e.g. when a `!` or `disabled()` term is false.  `Or`
+    // picks its smaller side so the result is not guaranteed minimal when
+    // sides share objectives.
+    pub fn missing_by(
+        &self,
+        state: &HashMap<String, ObjectiveState>,
+        threshold: &ObjectiveState,
+    ) -> Result<Option<Vec<String>>, Error> {
+        Ok(match self {
+            Expression::Objective(id) => {
+                if Self::find_state(id, state)?.at_least(threshold) {
+                    Some(vec![])
+                } else {
+                    Some(vec![id.clone()])
+                }
+            }
+            Expression::ObjectiveComplete(id) => {
+                if Self::find_state(id, state)?.is(&ObjectiveState::Complete) {
+                    Some(vec![])
+                } else {
+                    Some(vec![id.clone()])
+                }
+            }
+            Expression::And(a, b) => match (
+                a.missing_by(state, threshold)?,
+                b.missing_by(state, threshold)?,
+            ) {
+                (Some(mut a), Some(b)) => {
+                    for id in b {
+                        if !a.contains(&id) {
+                            a.push(id);
+                        }
+                    }
+                    Some(a)
+                }
+                _ => None,
+            },
+            Expression::Or(a, b) => match (
+                a.missing_by(state, threshold)?,
+                b.missing_by(state, threshold)?,
+            ) {
+                (Some(a), Some(b)) => Some(if b.len() < a.len() { b } else { a }),
+                (a, None) => a,
+                (None, b) => b,
+            },
+            // Anything else can't be satisfied by collecting objectives.
+            e => {
+                if e.evaluate_by(state, threshold)? {
+                    Some(vec![])
+                } else {
+                    None
+                }
+            }
+        })
+    }
+
+    pub fn missing_unlocked(
+        &self,
+        state: &HashMap<String, ObjectiveState>,
+    ) -> Result<Option<Vec<String>>, Error> {
+        self.missing_by(state, &ObjectiveState::Unlocked)
+    }
+
+    // Writes this expression with as few parentheses as will parse back to
+    // the same expression.  `followed` is set when more of the enclosing
+    // expression is written after this one.
+    fn write(&self, f: &mut fmt::Formatter, followed: bool) -> fmt::Result {
+        match self {
+            Expression::Default => Ok(()),
+            Expression::Manual => write!(f, "param"),
+            Expression::True => write!(f, "true"),
+            Expression::False => write!(f, "false"),
+            Expression::Objective(id) => write!(f, "{}", id),
+            Expression::ObjectiveComplete(id) => write!(f, "complete({})", id),
+            Expression::ObjectiveDisabled(id) => write!(f, "disabled({})", id),
+            Expression::ObjectiveUnlocked(id) => write!(f, "unlocked({})", id),
+            Expression::Stage(id, stage) => write!(f, "stage({}) >= {}", id, stage),
+            Expression::Any(name) => write!(f, "any({})", name),
+            Expression::All(name) => write!(f, "all({})", name),
+            // `!` applies to everything after it so it needs parentheses
+            // when anything follows.
+            Expression::Not(_) if followed => self.write_parenthesized(f),
+            Expression::Not(e) => {
+                write!(f, "!")?;
+                match **e {
+                    // `!a && b` parses the same but reads as `(!a) && b`.
+                    Expression::And(..) | Expression::Or(..) => e.write_parenthesized(f),
+                    _ => e.write(f, false),
+                }
+            }
+            // Both operators are right associative and `&&` binds tighter
+            // than `||`.
+            Expression::And(a, b) => {
+                match **a {
+                    Expression::And(..) | Expression::Or(..) => a.write_parenthesized(f)?,
+                    _ => a.write(f, true)?,
+                }
+                write!(f, " && ")?;
+                match **b {
+                    Expression::Or(..) => b.write_parenthesized(f),
+                    _ => b.write(f, followed),
+                }
+            }
+            Expression::Or(a, b) => {
+                match **a {
+                    Expression::Or(..) => a.write_parenthesized(f)?,
+                    _ => a.write(f, true)?,
+                }
+                write!(f, " || ")?;
+                b.write(f, followed)
+            }
+        }
+    }
+
+    fn write_parenthesized(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        self.write(f, false)?;
+        write!(f, ")")
+    }
+}
+
+// Formats the expression in the same syntax it is parsed from.  `Default`
+// has no syntax and formats as an empty string.
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, false)
+    }
+}
+
+impl Serialize for Expression {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expression {
+    fn deserialize<D>(deserializer: D) -> Result<Expression, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <String>::deserialize(deserializer)?;
+        Expression::parse(&s).map_err(de::Error::custom)
+    }
+}
+
+// Module files hold expressions as strings.
+impl JsonSchema for Expression {
+    fn schema_name() -> String {
+        "Expression".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let mut schema = String::json_schema(gen).into_object();
+        schema.metadata().description = Some(
+            "Objective logic such as `complete(key) && (boots || stage(sword) >= 2)`.".to_string(),
+        );
+        schema.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_expression(s: &str, e: Expression) {
+        println!("TEST == {}", s);
+        assert_eq!(parse_expression(s), Ok(("", e)));
+    }
+
+    fn test_expressions(exprs: &Vec<&str>, e: Expression) {
+        for s in exprs {
+            println!("TEST == {}", s);
+            assert_eq!(parse_expression(s), Ok(("", e.clone())));
+        }
+    }
+
+    #[test]
+    fn objective_names() {
+        test_expression("a", Expression::Objective("a".into()));
+        test_expression("aa", Expression::Objective("aa".into()));
+        test_expression("a0", Expression::Objective("a0".into()));
+        test_expression("a0-b1-2", Expression::Objective("a0-b1-2".into()));
+        test_expression("a0-b1-c2", Expression::Objective("a0-b1-c2".into()));
+        test_expression(" a0-b1-c2", Expression::Objective("a0-b1-c2".into()));
+
+        assert_eq!(
+            parse_expression("0"),
+            Err(nom::Err::Error(("0", nom::error::ErrorKind::TakeWhileMN)))
+        );
+
+        assert_eq!(
+            parse_expression("-"),
+            Err(nom::Err::Error(("-", nom::error::ErrorKind::TakeWhileMN)))
+        );
+
+        assert_eq!(
+            parse_expression("a-"),
+            Ok(("-", Expression::Objective("a".into())))
+        );
+
+        assert_eq!(
+            parse_expression("0a"),
+            Err(nom::Err::Error(("0a", nom::error::ErrorKind::TakeWhileMN)))
+        );
+
+        assert_eq!(
+            parse_expression("-a"),
+            Err(nom::Err::Error(("-a", nom::error::ErrorKind::TakeWhileMN)))
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        test_expressions(&vec!["param"], Expression::Manual);
+
+        test_expressions(
+            &vec!["complete(tower-key)", " complete ( tower-key )"],
+            Expression::ObjectiveComplete("tower-key".into()),
+        );
+        test_expressions(
+            &vec![
+                "!tower-key",
+                " !tower-key",
+                "! tower-key",
+                " ! tower-key",
+                "( ! tower-key)",
+                "!(tower-key)",
+            ],
+            Expression::Not(Box::new(Expression::Objective("tower-key".into()))),
+        );
+
+        test_expressions(
+            &vec![
+                "tower-key && luca-key",
+                "(tower-key && luca-key)",
+                "(tower-key) && (luca-key)",
+            ],
+            Expression::And(
+                Box::new(Expression::Objective("tower-key".into())),
+                Box::new(Expression::Objective("luca-key".into())),
+            ),
+        );
+
+        test_expressions(
+            &vec![
+                "tower-key && !luca-key",
+                "(tower-key && !luca-key)",
+                "(tower-key) && !(luca-key)",
+            ],
+            Expression::And(
+                Box::new(Expression::Objective("tower-key".into())),
+                Box::new(Expression::Not(Box::new(Expression::Objective(
+                    "luca-key".into(),
+                )))),
+            ),
+        );
+
+        test_expressions(
+            &vec![
+                "tower-key || luca-key",
+                "(tower-key || luca-key)",
+                "(tower-key) || (luca-key)",
+            ],
+            Expression::Or(
+                Box::new(Expression::Objective("tower-key".into())),
+                Box::new(Expression::Objective("luca-key".into())),
+            ),
+        );
+
+        test_expressions(
+            &vec!["tower-key && magma-key && luca-key"],
+            Expression::And(
+                Box::new(Expression::Objective("tower-key".into())),
+                Box::new(Expression::And(
+                    Box::new(Expression::Objective("magma-key".into())),
+                    Box::new(Expression::Objective("luca-key".into())),
+                )),
+            ),
+        );
+
+        test_expressions(
+            &vec!["tower-key && magma-key || luca-key"],
+            Expression::Or(
+                Box::new(Expression::And(
+                    Box::new(Expression::Objective("tower-key".into())),
+                    Box::new(Expression::Objective("magma-key".into())),
+                )),
+                Box::new(Expression::Objective("luca-key".into())),
+            ),
+        );
+
+        test_expressions(
+            &vec!["stage(sword) >= 2", " stage ( sword ) >=2"],
+            Expression::Stage("sword".into(), 2),
+        );
+        test_expressions(&vec!["stage"], Expression::Objective("stage".into()));
+
+        test_expressions(
+            &vec!["complete(hook) || complete(magma-key)"],
+            Expression::Or(
+                Box::new(Expression::ObjectiveComplete("hook".into())),
+                Box::new(Expression::ObjectiveComplete("magma-key".into())),
+            ),
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |s: &str| Expression::parse(s).unwrap_err().to_string();
+
+        assert_eq!(
+            error("a-"),
+            "error parsing expression `a-`: unexpected `-` at offset 1"
+        );
+        assert_eq!(
+            error("a && 0b"),
+            "error parsing expression `a && 0b`: unexpected `&& 0b` at offset 2"
+        );
+        assert_eq!(
+            error("(a || b"),
+            "error parsing expression `(a || b`: unexpected `(a || b` at offset 0"
+        );
+        assert_eq!(
+            error(""),
+            "error parsing expression ``: unexpected end of input at offset 0"
+        );
+
+        assert_eq!(Expression::parse(" param ").unwrap(), Expression::Manual);
+        assert_eq!(
+            Expression::parse("param-key").unwrap(),
+            Expression::Objective("param-key".into())
+        );
+    }
+
+    #[test]
+    fn resolve_sets() {
+        let bosses = vec!["golbez".to_string(), "zeromus".to_string()];
+        let empty = vec![];
+        let lookup = |name: &str| match name {
+            "bosses" => Some(&bosses),
+            "empty" => Some(&empty),
+            _ => None,
+        };
+        let resolve = |s: &str| {
+            Expression::parse(s)
+                .unwrap()
+                .resolve_sets(&lookup)
+                .map(|e| e.to_string())
+        };
+
+        assert_eq!(
+            resolve("hook && any(bosses)").unwrap(),
+            "hook && (golbez || zeromus)"
+        );
+        assert_eq!(resolve("!all(bosses)").unwrap(), "!(golbez && zeromus)");
+        assert_eq!(resolve("any(empty)").unwrap(), "false");
+        assert_eq!(resolve("all(empty)").unwrap(), "true");
+        assert_eq!(
+            resolve("any(dragons)").unwrap_err().to_string(),
+            "unknown tag or group 'dragons'"
+        );
+    }
+
+    #[test]
+    fn resolve_aliases() {
+        let lantern = "lantern".to_string();
+        let lookup = |name: &str| match name {
+            "lamp" => Some(&lantern),
+            _ => None,
+        };
+        let resolve = |s: &str| {
+            Expression::parse(s)
+                .unwrap()
+                .resolve_aliases(&lookup)
+                .to_string()
+        };
+
+        assert_eq!(resolve("lamp && key"), "lantern && key");
+        assert_eq!(resolve("!complete(lamp)"), resolve("!complete(lantern)"));
+        assert_eq!(resolve("any(lamp)"), "any(lamp)");
+    }
+
+    #[test]
+    fn deps() {
+        assert_eq!(
+            Expression::parse("complete(hook) || complete(magma-key)")
+                .unwrap()
+                .deps(),
+            vec!["hook".to_string(), "magma-key".to_string()]
+        );
+
+        assert_eq!(
+            Expression::parse("complete(hook) || !complete(magma-key)")
+                .unwrap()
+                .deps(),
+            vec!["hook".to_string(), "magma-key".to_string()]
+        );
+
+        assert_eq!(
+            Expression::parse("tower-key && complete(magma-key) || !luca-key")
+                .unwrap()
+                .deps(),
+            vec![
+                "tower-key".to_string(),
+                "magma-key".to_string(),
+                "luca-key".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn stage() {
+        let e = Expression::parse("stage(sword) >= 2").unwrap();
+        assert_eq!(e.deps(), vec!["sword:stage2".to_string()]);
+
+        let mut state = HashMap::new();
+        state.insert("sword:stage2".to_string(), ObjectiveState::Unlocked);
+        assert_eq!(e.evaluate_unlocked(&state).unwrap(), false);
+        state.insert("sword:stage2".to_string(), ObjectiveState::Complete);
+        assert_eq!(e.evaluate_unlocked(&state).unwrap(), true);
+
+        let e = Expression::parse("stage(sword) >= 0").unwrap();
+        assert!(e.deps().is_empty());
+        assert_eq!(e.evaluate_unlocked(&HashMap::new()).unwrap(), true);
+    }
+
+    fn obj(id: &str) -> Box<Expression> {
+        Box::new(Expression::Objective(id.into()))
+    }
+
+    #[test]
+    fn display() {
+        let canonical = vec![
+            ("a", "a"),
+            (" ( a ) ", "a"),
+            ("complete( a )", "complete(a)"),
+            ("disabled(a)", "disabled(a)"),
+            ("unlocked(a)", "unlocked(a)"),
+            ("stage( sword )>=2", "stage(sword) >= 2"),
+            ("stage(sword) >= 2 && a", "stage(sword) >= 2 && a"),
+            ("any( bosses )", "any(bosses)"),
+            ("all(bosses)", "all(bosses)"),
+            ("true && false", "true && false"),
+            ("(a) && (b) && c", "a && b && c"),
+            ("a && b || c", "a && b || c"),
+            ("(a && b) || c", "a && b || c"),
+            ("a && (b || c)", "a && (b || c)"),
+            ("(a || b) && c", "(a || b) && c"),
+            ("!a", "!a"),
+            ("!(a && b)", "!(a && b)"),
+            ("a && !b", "a && !b"),
+            ("(!a) || b", "(!a) || b"),
+        ];
+        for (input, output) in canonical {
+            assert_eq!(Expression::parse(input).unwrap().to_string(), output);
+        }
+
+        // Trees that can't be written the way they're nested.
+        let exprs = vec![
+            Expression::And(Box::new(Expression::And(obj("a"), obj("b"))), obj("c")),
+            Expression::Or(Box::new(Expression::Or(obj("a"), obj("b"))), obj("c")),
+            Expression::Or(
+                Box::new(Expression::And(
+                    obj("a"),
+                    Box::new(Expression::Not(obj("b"))),
+                )),
+                obj("c"),
+            ),
+            Expression::Not(Box::new(Expression::Not(obj("a")))),
+        ];
+        for e in exprs {
+            println!("TEST == {}", e);
+            assert_eq!(Expression::parse(&e.to_string()).unwrap(), e);
+        }
+    }
+
+    #[test]
+    fn serialize() {
+        let e = Expression::parse("a && !complete(b)").unwrap();
+        assert_eq!(serde_json::to_string(&e).unwrap(), r#""a && !complete(b)""#);
+    }
+
+    #[test]
+    fn missing() {
+        let mut state = HashMap::new();
+        state.insert("a".to_string(), ObjectiveState::Complete);
+        state.insert("b".to_string(), ObjectiveState::Locked);
+        state.insert("c".to_string(), ObjectiveState::Locked);
+        state.insert("d".to_string(), ObjectiveState::Locked);
+        let missing = |s: &str| {
+            Expression::parse(s)
+                .unwrap()
+                .missing_unlocked(&state)
+                .unwrap()
+        };
+        let ids = |ids: &[&str]| Some(ids.iter().map(|id| id.to_string()).collect::<Vec<_>>());
+
+        assert_eq!(missing("a"), ids(&[]));
+        assert_eq!(missing("b"), ids(&["b"]));
+        assert_eq!(missing("a && b && c"), ids(&["b", "c"]));
+        assert_eq!(missing("b && b"), ids(&["b"]));
+        assert_eq!(missing("b && c || d"), ids(&["d"]));
+        assert_eq!(missing("(b || a) && c"), ids(&["c"]));
+        assert_eq!(missing("b && !a"), None);
+        assert_eq!(missing("(!a) || c"), ids(&["c"]));
+        assert_eq!(missing("complete(b)"), ids(&["b"]));
+    }
+}
@@ -0,0 +1,73 @@
+//! Appearance options modules can set on their layouts.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeColor {
+    Clear,
+    BgDark,
+    BgLight,
+    Label,
+    Placeholder,
+    PrimaryLight,
+    PrimaryDark,
+}
+
+impl Default for ThemeColor {
+    fn default() -> Self {
+        return ThemeColor::Clear;
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[serde(rename_all = "kebab-case")]
+pub enum CornerRadius {
+    None,
+    Small,
+    Large,
+}
+
+impl Default for CornerRadius {
+    fn default() -> Self {
+        return CornerRadius::None;
+    }
+}
+
+impl Into<f64> for CornerRadius {
+    fn into(self) -> f64 {
+        match self {
+            CornerRadius::None => 0.,
+            CornerRadius::Small => 4.,
+            CornerRadius::Large => 8.,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[serde(rename_all = "kebab-case")]
+pub enum Inset {
+    None,
+    Small,
+    Large,
+}
+
+impl Default for Inset {
+    fn default() -> Self {
+        return Inset::None;
+    }
+}
+
+impl Into<f64> for Inset {
+    fn into(self) -> f64 {
+        match self {
+            Inset::None => 0.,
+            Inset::Small => 4.,
+            Inset::Large => 8.,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Module parsing and objective logic for Pollendina.
+//!
+//! This crate has no UI dependencies so it can be shared by tools other than
+//! the tracker app.  Enable the `druid` feature to derive `druid::Data` on
+//! types the app keeps in its display state.
+
+pub mod builder;
+pub mod checksums;
+pub mod engine;
+pub mod event_log;
+pub mod expression;
+pub mod layout;
+pub mod module;
+pub mod report;
+pub mod schema;
+pub mod template;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// Id of the objective standing for stage `stage` (counting from 1) of the
+// objective `id`.  It is complete once that stage is reached.
+pub fn stage_id(id: &str, stage: u32) -> String {
+    format!("{}:stage{}", id, stage)
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[serde(rename_all = "kebab-case")]
+pub enum ObjectiveState {
+    Disabled,
+    Locked,
+    GlitchLocked,
+    Unlocked,
+    Complete,
+}
+
+impl ObjectiveState {
+    pub fn at_least(&self, threshold: &Self) -> bool {
+        self.ordinal() >= threshold.ordinal()
+    }
+
+    pub fn is(&self, threshold: &Self) -> bool {
+        self == threshold
+    }
+
+    fn ordinal(&self) -> u32 {
+        match self {
+            ObjectiveState::Disabled => 0,
+            ObjectiveState::Locked => 1,
+            ObjectiveState::GlitchLocked => 2,
+            ObjectiveState::Unlocked => 3,
+            ObjectiveState::Complete => 4,
+        }
+    }
+}
+
+// A change in an objective's state requested by an update.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateChange {
+    pub id: String,
+    pub ty: String,
+    pub old_state: ObjectiveState,
+    pub new_state: ObjectiveState,
+}
+
+// Where an objective's state was last set from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateSource {
+    Manual,
+    AutoTracker,
+}
@@ -0,0 +1,2504 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::AsRef;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::{format_err, Error};
+use indexmap::IndexMap;
+use path_slash::PathBufExt;
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::{de, de::DeserializeOwned, Deserialize, Deserializer};
+
+use crate::checksums;
+use crate::expression::Expression;
+use crate::layout::{CornerRadius, Inset, ThemeColor};
+use crate::template::Template;
+use crate::{stage_id, ObjectiveState};
+
+#[derive(Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct ObjectiveInfoLoc {
+    #[serde(rename = "type")]
+    ty: String,
+    path: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct MapInfoLoc {
+    id: String,
+    path: String,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type")]
+pub enum Param {
+    TextBox {
+        name: String,
+        // Objective enabled while the text box isn't empty.
+        #[serde(default)]
+        id: String,
+        #[serde(default)]
+        group: String,
+    },
+    CheckBox {
+        id: String,
+        name: String,
+        #[serde(default)]
+        group: String,
+    },
+}
+
+impl Param {
+    // Section of the config window the param is shown in.  Empty for params
+    // shown above the sections.
+    pub fn group(&self) -> &String {
+        match self {
+            Param::TextBox { group, .. } | Param::CheckBox { group, .. } => group,
+        }
+    }
+}
+
+// Limits on how the auto tracker may change objective states.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutoTrackPolicy {
+    // The auto tracker may set any state.
+    Any,
+    // The auto tracker may not lower states the user set.
+    ProtectManual,
+    // The auto tracker may only raise states.
+    UpgradeOnly,
+}
+
+impl Default for AutoTrackPolicy {
+    fn default() -> Self {
+        AutoTrackPolicy::Any
+    }
+}
+
+// What clicking an objective does.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[serde(rename_all = "kebab-case")]
+pub enum ClickAction {
+    // Step through locked, unlocked and complete, then back to locked.
+    Cycle,
+    // Switch between complete and not complete, e.g. for bosses.
+    ToggleComplete,
+    // Step towards complete and stay there.
+    Increment,
+    // Show the objective's checks, e.g. for locations.
+    OpenPopup,
+    // Ignore clicks.
+    None,
+}
+
+// How a grid shows its disabled objectives.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[serde(rename_all = "kebab-case")]
+pub enum DisabledDisplay {
+    // Leave them out, moving the objectives after them up.
+    Hide,
+    // Draw their locked image faded.
+    Dim,
+    // Keep their space empty so the grid doesn't shift.
+    Placeholder,
+}
+
+// Shape of an objective's marker on a map.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[serde(rename_all = "kebab-case")]
+pub enum MarkerShape {
+    Circle,
+    Square,
+    Diamond,
+    Triangle,
+}
+
+impl Default for MarkerShape {
+    fn default() -> Self {
+        MarkerShape::Circle
+    }
+}
+
+impl Default for DisabledDisplay {
+    fn default() -> Self {
+        DisabledDisplay::Hide
+    }
+}
+
+impl Default for ClickAction {
+    fn default() -> Self {
+        ClickAction::Cycle
+    }
+}
+
+impl ClickAction {
+    // The state a click moves an objective in `state` to.  Popups are opened
+    // by the UI so `OpenPopup` cycles like keyboard activation does.
+    pub fn next_state(&self, state: ObjectiveState) -> ObjectiveState {
+        match (self, state) {
+            (_, ObjectiveState::Disabled) | (ClickAction::None, _) => state,
+            (ClickAction::ToggleComplete, ObjectiveState::Complete) => ObjectiveState::Locked,
+            (ClickAction::ToggleComplete, _) => ObjectiveState::Complete,
+            (ClickAction::Increment, ObjectiveState::Complete) => ObjectiveState::Complete,
+            (_, ObjectiveState::Locked) | (_, ObjectiveState::GlitchLocked) => {
+                ObjectiveState::Unlocked
+            }
+            (_, ObjectiveState::Unlocked) => ObjectiveState::Complete,
+            (_, ObjectiveState::Complete) => ObjectiveState::Locked,
+        }
+    }
+
+    // The state stepping back from `state` moves an objective to, undoing
+    // `next_state`.  Increments stop at locked rather than wrapping.
+    pub fn prev_state(&self, state: ObjectiveState) -> ObjectiveState {
+        match (self, state) {
+            (_, ObjectiveState::Disabled) | (ClickAction::None, _) => state,
+            (ClickAction::ToggleComplete, _) => self.next_state(state),
+            (ClickAction::Increment, ObjectiveState::Locked)
+            | (ClickAction::Increment, ObjectiveState::GlitchLocked) => ObjectiveState::Locked,
+            (_, ObjectiveState::Locked) | (_, ObjectiveState::GlitchLocked) => {
+                ObjectiveState::Complete
+            }
+            (_, ObjectiveState::Unlocked) => ObjectiveState::Locked,
+            (_, ObjectiveState::Complete) => ObjectiveState::Unlocked,
+        }
+    }
+
+    // The stage a click moves an objective at `stage` of `stages` to.
+    pub fn next_stage(&self, stage: u32, stages: u32) -> u32 {
+        match self {
+            ClickAction::None => stage,
+            ClickAction::ToggleComplete if stage == stages => 0,
+            ClickAction::ToggleComplete => stages,
+            ClickAction::Increment => (stage + 1).min(stages),
+            ClickAction::Cycle | ClickAction::OpenPopup => (stage + 1) % (stages + 1),
+        }
+    }
+
+    // The stage stepping back from `stage` of `stages` moves an objective to.
+    pub fn prev_stage(&self, stage: u32, stages: u32) -> u32 {
+        match self {
+            ClickAction::None => stage,
+            ClickAction::ToggleComplete => self.next_stage(stage, stages),
+            ClickAction::Increment => stage.saturating_sub(1),
+            ClickAction::Cycle | ClickAction::OpenPopup => (stage + stages) % (stages + 1),
+        }
+    }
+}
+
+// Where the auto tracker reads game memory from.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutoTrackBackend {
+    // SNES memory through a usb2snes server.
+    Usb2snes,
+    // GameCube and Wii memory of a running Dolphin emulator.
+    Dolphin,
+    // Memory of a local PC game process.  See `ProcessInfo`.
+    Process,
+}
+
+impl Default for AutoTrackBackend {
+    fn default() -> Self {
+        AutoTrackBackend::Usb2snes
+    }
+}
+
+impl AutoTrackBackend {
+    // Byte order of values in the backend's memory.
+    pub fn big_endian(&self) -> bool {
+        match self {
+            AutoTrackBackend::Usb2snes => false,
+            AutoTrackBackend::Dolphin => true,
+            AutoTrackBackend::Process => false,
+        }
+    }
+}
+
+// An auto tracker script the user can pick instead of the module's
+// `auto-track` one.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct AutoTrackScript {
+    pub name: String,
+    pub path: String,
+}
+
+// Name the manifest's `auto-track` script goes by among the module's auto
+// tracker scripts.
+pub const DEFAULT_AUTO_TRACK_SCRIPT: &str = "default";
+
+// The game process read by the `process` backend.  Watch addresses are
+// offsets from the address `pointer_path` leads to.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProcessInfo {
+    // Executable name, e.g. "game.exe".
+    pub name: String,
+    // Module (executable or library) the pointer path starts at.  Defaults
+    // to the executable.
+    #[serde(default)]
+    pub module: String,
+    // The first offset is added to the module's base address.  Each later
+    // one is added to the pointer read at the address so far.
+    #[serde(default, deserialize_with = "deserialize_offsets")]
+    #[schemars(with = "Vec<Address>")]
+    pub pointer_path: Vec<u64>,
+    // Size of the game's pointers in bytes, 4 or 8.
+    #[serde(default = "default_pointer_size")]
+    pub pointer_size: usize,
+}
+
+fn default_pointer_size() -> usize {
+    8
+}
+
+// How a watch decides whether its value matches.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatchCompare {
+    // Any masked bit is set.
+    NonZero,
+    Equal(u32),
+    AtLeast(u32),
+}
+
+impl Default for WatchCompare {
+    fn default() -> Self {
+        WatchCompare::NonZero
+    }
+}
+
+impl WatchCompare {
+    pub fn matches(&self, value: u32) -> bool {
+        match self {
+            WatchCompare::NonZero => value != 0,
+            WatchCompare::Equal(v) => value == *v,
+            WatchCompare::AtLeast(v) => value >= *v,
+        }
+    }
+}
+
+// Logic regression test a module ships in its `tests.json`.  Starting from
+// a fresh tracker, each step sets objective states and then checks the
+// states they lead to.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct ModuleTest {
+    pub name: String,
+    pub steps: Vec<ModuleTestStep>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct ModuleTestStep {
+    #[serde(default)]
+    pub set: HashMap<String, ObjectiveState>,
+    #[serde(default)]
+    pub expect: HashMap<String, ObjectiveState>,
+}
+
+// Name of the optional tests file without its extension.
+pub const TESTS_FILE: &str = "tests";
+
+// Extensions of the formats module files can be written in.  JSON is
+// assumed for any other extension.
+pub const MODULE_FILE_EXTENSIONS: &[&str] = &["json", "yaml", "yml", "toml"];
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "toml")
+}
+
+// Reads a module file in the format its extension names.
+pub fn read_module_file<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let s = fs::read_to_string(path)
+        .map_err(|e| format_err!("Failed to open {}: {}", path.display(), e))?;
+    let decoded = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&s).map_err(Error::from),
+        Some("toml") => toml::from_str(&s).map_err(Error::from),
+        _ => serde_json::from_str(&s).map_err(Error::from),
+    };
+    decoded.map_err(|e| format_err!("Failed to parse {}: {}", path.display(), e))
+}
+
+// Reads a module file holding a list.  A TOML document can't be a list so
+// TOML files hold theirs in an array of tables named `key`, e.g.
+// `[[objectives]]`.
+fn read_module_list<T: DeserializeOwned>(path: &Path, key: &str) -> Result<Vec<T>, Error> {
+    if !is_toml(path) {
+        return read_module_file(path);
+    }
+    let mut table: HashMap<String, Vec<T>> = read_module_file(path)?;
+    Ok(table.remove(key).unwrap_or_default())
+}
+
+// `dir/<stem>.<ext>` for the first module file extension that exists.
+fn find_module_file(dir: &Path, stem: &str) -> Option<PathBuf> {
+    MODULE_FILE_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .find(|path| path.exists())
+}
+
+// A memory to objective mapping the auto tracker evaluates without a script.
+// When the masked value at `address`, in the byte order of the module's
+// backend, matches `compare`, the objective is set to `state`.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct WatchInfo {
+    #[serde(deserialize_with = "deserialize_address")]
+    #[schemars(with = "Address")]
+    pub address: u32,
+    // Number of bytes read, from 1 to 4.
+    #[serde(default = "default_watch_len")]
+    pub len: usize,
+    #[serde(default = "default_watch_mask")]
+    pub mask: u32,
+    #[serde(default)]
+    pub compare: WatchCompare,
+    pub objective: String,
+    pub state: ObjectiveState,
+}
+
+impl WatchInfo {
+    // Whether the bytes read at `address` match.
+    pub fn matches(&self, data: &[u8], big_endian: bool) -> bool {
+        let data = &data[..data.len().min(4)];
+        let value = if big_endian {
+            data.iter().fold(0u32, |v, b| v << 8 | *b as u32)
+        } else {
+            data.iter().rev().fold(0u32, |v, b| v << 8 | *b as u32)
+        };
+        self.compare.matches(value & self.mask)
+    }
+}
+
+fn default_watch_len() -> usize {
+    1
+}
+
+fn default_watch_mask() -> u32 {
+    0xffff_ffff
+}
+
+// Addresses are numbers or, since JSON has no hex literals, strings like
+// "0x7e1500".
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum Address {
+    Number(u64),
+    String(String),
+}
+
+impl Address {
+    fn value<E: de::Error>(self) -> Result<u64, E> {
+        match self {
+            Address::Number(n) => Ok(n),
+            Address::String(s) => {
+                let parsed = match s.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => s.parse(),
+                };
+                parsed.map_err(|_| E::custom(format!("invalid address {}", s)))
+            }
+        }
+    }
+}
+
+fn deserialize_address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let address = Address::deserialize(deserializer)?.value()?;
+    if address > u32::MAX as u64 {
+        return Err(de::Error::custom(format!(
+            "address {:#x} is too large",
+            address
+        )));
+    }
+    Ok(address as u32)
+}
+
+fn deserialize_offsets<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+    Vec::<Address>::deserialize(deserializer)?
+        .into_iter()
+        .map(Address::value)
+        .collect()
+}
+
+#[derive(Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct Manifest {
+    pub name: String,
+    pub authors: Vec<String>,
+    #[serde(default, rename = "game-url")]
+    pub game_url: String,
+    #[serde(default, rename = "auto-track")]
+    pub auto_track: Option<String>,
+    // More scripts, e.g. one per console port or randomizer branch.  The
+    // user picks one in the config window.
+    #[serde(default, rename = "auto-track-scripts")]
+    pub auto_track_scripts: Vec<AutoTrackScript>,
+    // Lua files auto tracker scripts load with `require("<name>")`.  Each is
+    // named by its path without ".lua".
+    #[serde(default, rename = "auto-track-libs")]
+    pub auto_track_libs: Vec<String>,
+    #[serde(default, rename = "auto-track-policy")]
+    pub auto_track_policy: AutoTrackPolicy,
+    #[serde(default, rename = "auto-track-backend")]
+    pub auto_track_backend: AutoTrackBackend,
+    // Required by the `process` backend.
+    #[serde(default)]
+    pub process: Option<ProcessInfo>,
+    #[serde(default)]
+    pub params: Vec<Param>,
+    pub objectives: Vec<ObjectiveInfoLoc>,
+    #[serde(default)]
+    pub maps: Vec<MapInfoLoc>,
+    // Named lists of objective ids that layouts and expressions can refer
+    // to.
+    #[serde(default)]
+    pub groups: HashMap<String, Vec<String>>,
+    pub layouts: HashMap<String, DisplayViewInfo>,
+    // Memory watches tracked without an auto tracking script.
+    #[serde(default)]
+    pub watches: Vec<WatchInfo>,
+    // Images composed from other images when the module is loaded.
+    #[serde(default)]
+    pub overlays: Vec<OverlayInfo>,
+    // Doors and warps an entrance randomizer shuffles.
+    #[serde(default)]
+    pub entrances: Vec<EntranceInfo>,
+    // Areas of the game world connected by exits.  Objectives placed in a
+    // region are only unlocked once it can be reached.
+    #[serde(default)]
+    pub regions: Vec<RegionInfo>,
+    // Old objective id -> the id it was renamed to.  Expressions, groups,
+    // layouts and maps may keep using the old id.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    // Points of the run the timer records a split at, in the order they're
+    // usually reached.
+    #[serde(default)]
+    pub splits: Vec<SplitInfo>,
+    // True once the run has everything it needs to finish the game.  Tracked
+    // as the objective `go-mode`, complete while the expression holds.
+    #[serde(default, rename = "go-mode")]
+    pub go_mode: Expression,
+    // Slots filled in by the user from a pool of values, such as which boss
+    // is behind each door of a boss shuffle.
+    #[serde(default)]
+    pub assignments: Vec<AssignmentInfo>,
+    // Map marker shapes by objective type.  Maps can override them.
+    #[serde(default, rename = "marker-shapes")]
+    pub marker_shapes: HashMap<String, MarkerShape>,
+}
+
+// A split of the run timer, recorded when `objective` is first completed.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct SplitInfo {
+    pub name: String,
+    pub objective: String,
+}
+
+// Type of the objectives made for regions.
+pub const REGION_TYPE: &str = "region";
+
+// Id and type of the objective made for the manifest's `go-mode`.
+pub const GO_MODE_ID: &str = "go-mode";
+
+// Type of the objectives made for each slot and value of an assignment.
+pub const ASSIGNMENT_TYPE: &str = "assignment";
+
+// A set of slots that are each assigned a value from `pool`.  Every slot and
+// value pair is an objective, `<slot>-is-<value>`, that is complete while
+// the value is assigned to the slot and locked otherwise.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct AssignmentInfo {
+    pub id: String,
+    pub name: String,
+    pub slots: Vec<AssignmentItem>,
+    pub pool: Vec<AssignmentItem>,
+    // Each value can only be assigned to one slot at a time.  Assigning it
+    // to another slot takes it away from the first.
+    #[serde(default)]
+    pub unique: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct AssignmentItem {
+    pub id: String,
+    pub name: String,
+}
+
+// Id of the objective that is complete while `value` is assigned to `slot`.
+pub fn assignment_objective(slot: &str, value: &str) -> String {
+    format!("{}-is-{}", slot, value)
+}
+
+// An area of the game world.  Each region is also an objective that is
+// unlocked once it is reached, either by its own `unlocked-by` or through
+// an exit of a reached region.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct RegionInfo {
+    pub id: String,
+    pub name: String,
+    // Usually `true` for the region the game starts in and omitted for the
+    // others.
+    #[serde(default, rename = "unlocked-by")]
+    pub unlocked_by: Expression,
+    #[serde(default)]
+    pub exits: Vec<RegionExit>,
+}
+
+// A way from one region to another.  One way unless the other region has an
+// exit back.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct RegionExit {
+    pub to: String,
+    #[serde(default)]
+    pub requires: Expression,
+}
+
+// `unlocked_by` of an objective placed in `region`, if any.
+fn in_region(unlocked_by: Expression, region: &str) -> Expression {
+    if region.is_empty() {
+        unlocked_by
+    } else {
+        Expression::Objective(region.to_string()).and(unlocked_by)
+    }
+}
+
+// Type of the objectives made for entrances.
+pub const ENTRANCE_TYPE: &str = "entrance";
+
+// A door, warp or other transition an entrance randomizer can shuffle.
+// Each entrance is also an objective that is unlocked once it is reached,
+// either by its own `unlocked-by` or through the entrance the player
+// connected it to.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct EntranceInfo {
+    pub id: String,
+    pub name: String,
+    // Entrances without one, like the inside of a shuffled door, are only
+    // reached through their connection.
+    #[serde(default, rename = "unlocked-by")]
+    pub unlocked_by: Expression,
+}
+
+// Where an overlay is placed on its base image.
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverlayAnchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+impl Default for OverlayAnchor {
+    fn default() -> Self {
+        OverlayAnchor::BottomRight
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct OverlayLayer {
+    // Image id of the overlay, e.g. "overlay:small-key".
+    pub image: String,
+    #[serde(default)]
+    pub anchor: OverlayAnchor,
+    // Width of the overlay as a fraction of the base's.  The overlay keeps
+    // its own size when omitted.
+    #[serde(default)]
+    pub scale: Option<f64>,
+}
+
+// An image made of `base` with `layers` drawn on top in order, stored as
+// `id`.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct OverlayInfo {
+    pub id: String,
+    pub base: String,
+    pub layers: Vec<OverlayLayer>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct ObjectiveCheck {
+    #[serde(default, rename = "type")]
+    pub ty: String,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, rename = "enabled-by")]
+    pub enabled_by: Expression,
+    #[serde(default, rename = "unlocked-by")]
+    pub unlocked_by: Expression,
+    // Region the check is in.  Defaults to its objective's.
+    #[serde(default)]
+    pub region: String,
+    // Note on where or how to get the check, shown in its objective's popup.
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tooltip: Option<Tooltip>,
+}
+
+// Shown while the mouse rests on an objective.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct Tooltip {
+    pub text: String,
+    // Id of an image asset, like a screenshot of the location, shown below
+    // the text.
+    #[serde(default)]
+    pub image: String,
+}
+
+// One stage of a progressive objective, like the second level of a sword.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct ObjectiveStage {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, rename = "unlocked-by")]
+    pub unlocked_by: Expression,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct ObjectiveInfo {
+    pub id: String,
+    #[serde(default, rename = "type")]
+    pub ty: String,
+    pub name: String,
+    #[serde(skip)]
+    pub completed_by: Expression,
+    #[serde(default, rename = "enabled-by")]
+    pub enabled_by: Expression,
+    #[serde(default, rename = "unlocked-by")]
+    pub unlocked_by: Expression,
+    #[serde(default)]
+    pub checks: Vec<ObjectiveCheck>,
+    // Show a desktop notification when the auto-tracker changes this
+    // objective's state.
+    #[serde(default)]
+    pub notify: bool,
+    // Locations this objective may be found at.  Shown on the map when
+    // looking for the objective.
+    #[serde(default, rename = "hint-locations")]
+    pub hint_locations: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, rename = "click-action")]
+    pub click_action: ClickAction,
+    // Ordered stages of a progressive objective.
+    #[serde(default)]
+    pub stages: Vec<ObjectiveStage>,
+    // Region the objective is in.  It is only unlocked once the region is
+    // reached.
+    #[serde(default)]
+    pub region: String,
+    // Note on where or how to get the objective, shown in its popup.
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tooltip: Option<Tooltip>,
+}
+
+impl ObjectiveInfo {
+    // Create an objective with every other field left at the value it has
+    // when omitted from a module file.
+    pub fn new(id: &str, name: &str) -> ObjectiveInfo {
+        ObjectiveInfo {
+            id: id.to_string(),
+            ty: String::new(),
+            name: name.to_string(),
+            completed_by: Expression::Default,
+            enabled_by: Expression::Default,
+            unlocked_by: Expression::Default,
+            checks: Vec::new(),
+            notify: false,
+            hint_locations: Vec::new(),
+            tags: Vec::new(),
+            click_action: ClickAction::Cycle,
+            stages: Vec::new(),
+            region: String::new(),
+            description: String::new(),
+            tooltip: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct MapObjective {
+    pub id: String,
+    pub x: u64,
+    pub y: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct MapInfo {
+    pub id: String,
+    pub name: String,
+    pub width: u64,
+    pub height: u64,
+    #[serde(rename = "objective-radius")]
+    pub objective_radius: f64,
+    #[serde(default)]
+    pub objectives: Vec<MapObjective>,
+    #[serde(default)]
+    pub regions: Vec<MapRegion>,
+    // Alternate images of the map, such as day and night, sharing its
+    // objectives.  The first layer is shown at startup.
+    #[serde(default)]
+    pub layers: Vec<MapLayer>,
+    // Marker shapes by objective type, over the manifest's.
+    #[serde(default, rename = "marker-shapes")]
+    pub marker_shapes: HashMap<String, MarkerShape>,
+}
+
+impl MapInfo {
+    // Id of the image asset of `layer`, or of the map if it has no layers.
+    pub fn image_id(&self, layer: Option<&MapLayer>) -> String {
+        match layer {
+            Some(layer) => format!("map:{}:{}", self.id, layer.id),
+            None => format!("map:{}", self.id),
+        }
+    }
+}
+
+// One image of a map with layers.  Its image is
+// `assets/map/<map id>/<layer id>.png`.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct MapLayer {
+    pub id: String,
+    pub name: String,
+}
+
+// An area of a map, such as a continent, outlined by the corners of a
+// polygon in map pixels.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct MapRegion {
+    pub id: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+impl MapRegion {
+    // Whether (`x`, `y`) is inside the region.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        // Count the edges a ray going right from the point crosses.
+        let mut inside = false;
+        let mut prev = match self.points.last() {
+            Some(p) => *p,
+            None => return false,
+        };
+        for &(px, py) in &self.points {
+            let (qx, qy) = prev;
+            if (py > y) != (qy > y) && x < (qx - px) * (y - py) / (qy - py) + px {
+                inside = !inside;
+            }
+            prev = (px, py);
+        }
+        inside
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ObjectiveListSpecial {
+    Checks,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(untagged)]
+#[serde(rename_all = "kebab-case")]
+pub enum ObjectiveList {
+    List(Vec<String>),
+    Special(ObjectiveListSpecial),
+    // Tags and groups are replaced with `List`s when the module is loaded.
+    Tag { tag: String },
+    Group { group: String },
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GridColumnsSpecial {
+    // Fit as many columns as the available width allows.
+    Auto,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(untagged)]
+pub enum GridColumns {
+    Count(usize),
+    Special(GridColumnsSpecial),
+}
+
+impl GridColumns {
+    // Returns the column count with 0 meaning auto.
+    pub fn count(&self) -> usize {
+        match self {
+            GridColumns::Count(n) => *n,
+            GridColumns::Special(GridColumnsSpecial::Auto) => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type")]
+pub enum DisplayViewInfoView {
+    Grid {
+        columns: GridColumns,
+        objectives: ObjectiveList,
+        #[serde(default)]
+        disabled: DisabledDisplay,
+    },
+    Count {
+        #[serde(default)]
+        objective_type: String,
+        // Counts these objectives instead of those of `objective_type`.
+        #[serde(default)]
+        objectives: Option<ObjectiveList>,
+    },
+    Map {
+        maps: Vec<String>,
+    },
+    FlexRow {
+        children: Vec<DisplayViewInfo>,
+    },
+    FlexCol {
+        children: Vec<DisplayViewInfo>,
+    },
+    Spacer {},
+    // The module's splits and the time each was reached.
+    Splits {},
+    // How many checks are unlocked but not yet done.
+    ChecksInLogic {},
+    // The slots of the manifest's assignment `assignment` and what each is
+    // assigned.
+    Assignment {
+        assignment: String,
+    },
+    Text {
+        template: Template,
+        #[serde(default)]
+        font_size: f64,
+        #[serde(default)]
+        color: ThemeColor,
+    },
+    Tabs {
+        labels: Vec<String>,
+        children: Vec<DisplayViewInfo>,
+    },
+    Include {
+        path: String,
+    },
+}
+
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct LayoutParamsInfo {
+    #[serde(default)]
+    pub flex: f64,
+
+    #[serde(default)]
+    pub background: ThemeColor,
+
+    #[serde(default)]
+    pub corner_radius: CornerRadius,
+
+    #[serde(default)]
+    pub inset: Inset,
+
+    // window_height and window_width only apply to the root view of
+    // a window.
+    #[serde(default)]
+    pub window_height: f64,
+
+    #[serde(default)]
+    pub window_width: f64,
+
+    // The view is only shown while this evaluates to enabled.  Views are
+    // always shown when it is not set.
+    #[serde(default, rename = "visible-when")]
+    pub visible_when: Expression,
+
+    // Font family and size of text in the view and its children.  Empty or
+    // 0 to inherit them.  Fonts can be shipped in the module's `assets`
+    // directory.
+    #[serde(default)]
+    pub font: String,
+
+    #[serde(default)]
+    pub font_size: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq)]
+pub struct DisplayViewInfo {
+    #[serde(flatten)]
+    pub layout_params: LayoutParamsInfo,
+
+    #[serde(flatten)]
+    pub view: DisplayViewInfoView,
+}
+
+// Translations of module text for a single language.  Loaded from
+// `locales/<language>.json`.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq)]
+pub struct Locale {
+    // Objective id -> translated objective name.
+    #[serde(default)]
+    pub names: HashMap<String, String>,
+    // Untranslated label -> translated label.  Applies to tab labels and
+    // param names.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    // Objective id -> translated objective description.
+    #[serde(default)]
+    pub descriptions: HashMap<String, String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AssetKind {
+    Image,
+    Sound,
+    Font,
+}
+
+#[derive(Debug)]
+pub struct AssetInfo {
+    pub path: PathBuf,
+    pub id: String,
+    pub kind: AssetKind,
+}
+
+pub struct Module {
+    pub manifest: Manifest,
+    // In the order the module declares them.
+    pub objectives: IndexMap<String, ObjectiveInfo>,
+    pub maps: HashMap<String, MapInfo>,
+    // Source of each auto tracker script by name, the `auto-track` one first
+    // as `DEFAULT_AUTO_TRACK_SCRIPT`.
+    pub auto_track_scripts: IndexMap<String, String>,
+    // Source of each file the scripts can `require` by name.
+    pub auto_track_libs: HashMap<String, String>,
+    pub assets: Vec<AssetInfo>,
+    pub locales: HashMap<String, Locale>,
+    // Objective ids for each tag in the order they were loaded.
+    pub tags: HashMap<String, Vec<String>>,
+    // Whether every file of the module matched its author's checksums.
+    pub checksummed: bool,
+    pub tests: Vec<ModuleTest>,
+    // Ids expressions refer to that aren't objectives, each with what
+    // refers to it.  They evaluate as `False`.
+    pub unknown_ids: Vec<(String, String)>,
+}
+
+impl Module {
+    // Create a module with no objectives from `manifest`.  Objectives listed
+    // in the manifest are not loaded.
+    pub fn new(manifest: Manifest) -> Module {
+        Module {
+            manifest,
+            objectives: IndexMap::new(),
+            maps: HashMap::new(),
+            auto_track_scripts: IndexMap::new(),
+            auto_track_libs: HashMap::new(),
+            assets: Vec::new(),
+            locales: HashMap::new(),
+            tags: HashMap::new(),
+            checksummed: false,
+            tests: Vec::new(),
+            unknown_ids: Vec::new(),
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Module, Error> {
+        let path = path.as_ref().canonicalize()?;
+
+        let mut manifest: Manifest = read_module_file(&path)?;
+
+        let base_path = path.parent().ok_or(format_err!(
+            "Can't get parent directory of {}",
+            path.display()
+        ))?;
+        let checksummed = checksums::verify(base_path)?;
+
+        if !manifest.layouts.contains_key(&"main".to_string()) {
+            return Err(format_err!("manifest does not contain 'main' layout."));
+        }
+        if !manifest.layouts.contains_key(&"checks".to_string()) {
+            return Err(format_err!("manifest does not contain 'checks' layout."));
+        }
+
+        for (_, layout) in manifest.layouts.iter_mut() {
+            Self::process_display_includes(base_path, layout)?;
+        }
+
+        let read_script = |path: &String| {
+            let path = base_path.join(path);
+            std::fs::read_to_string(&path)
+                .map_err(|e| format_err!("Failed to open {}: {}", path.display(), e))
+        };
+        let mut auto_track_scripts = IndexMap::new();
+        if let Some(path) = &manifest.auto_track {
+            auto_track_scripts.insert(DEFAULT_AUTO_TRACK_SCRIPT.to_string(), read_script(path)?);
+        }
+        for script in &manifest.auto_track_scripts {
+            if auto_track_scripts.contains_key(&script.name) {
+                return Err(format_err!("duplicate auto tracker script {}", script.name));
+            }
+            auto_track_scripts.insert(script.name.clone(), read_script(&script.path)?);
+        }
+        let mut auto_track_libs = HashMap::new();
+        for path in &manifest.auto_track_libs {
+            let name = path.strip_suffix(".lua").unwrap_or(path).to_string();
+            auto_track_libs.insert(name, read_script(path)?);
+        }
+
+        let mut module = Module {
+            auto_track_scripts,
+            auto_track_libs,
+            checksummed,
+            ..Module::new(manifest)
+        };
+
+        module.import_objectives(&base_path)?;
+        module.add_entrances()?;
+        module.add_regions()?;
+        module.add_go_mode()?;
+        module.add_assignments()?;
+        module.resolve_aliases();
+        module.resolve_sets()?;
+        module.replace_unknown_ids();
+        module.check_watches()?;
+
+        // Tests are optional.
+        if let Some(tests_path) = find_module_file(base_path, TESTS_FILE) {
+            module.tests = read_module_list(&tests_path, "tests")?;
+            module.check_tests()?;
+        }
+        if module.manifest.auto_track_backend == AutoTrackBackend::Process {
+            match &module.manifest.process {
+                Some(p) if p.pointer_size == 4 || p.pointer_size == 8 => (),
+                Some(p) => {
+                    return Err(format_err!(
+                        "process pointer-size is {}, expected 4 or 8",
+                        p.pointer_size
+                    ))
+                }
+                None => return Err(format_err!("process backend needs a 'process' entry")),
+            }
+        }
+
+        let maps = module
+            .manifest
+            .maps
+            .par_iter()
+            .map(|loc| read_module_file(&base_path.join(PathBuf::from_slash(&loc.path))))
+            .collect::<Result<Vec<MapInfo>, Error>>()?;
+        for mut map in maps {
+            for o in map.objectives.iter_mut() {
+                o.id = module.canonical_id(&o.id).clone();
+            }
+            module.maps.insert(map.id.clone(), map);
+        }
+
+        // Traverse `assets` directory looking for images and sounds.
+        let assets_path = base_path.join("assets");
+        Self::visit_asset_dir(&assets_path, &assets_path, &mut module.assets)?;
+
+        module.check_overlays()?;
+
+        // Translations are optional.
+        let locales_path = base_path.join("locales");
+        if locales_path.is_dir() {
+            module.locales = Self::load_locales(&locales_path)?;
+        }
+
+        // TODO(konkers): verify module integrity
+        //  All id references should resolve (display and elsewhere)
+        Ok(module)
+    }
+
+    fn check_tests(&self) -> Result<(), Error> {
+        for test in &self.tests {
+            let ids = test
+                .steps
+                .iter()
+                .flat_map(|step| step.set.keys().chain(step.expect.keys()));
+            for id in ids {
+                if !self.objectives.contains_key(id) {
+                    return Err(format_err!(
+                        "test '{}' refers to unknown objective {}",
+                        test.name,
+                        id
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_watches(&self) -> Result<(), Error> {
+        for watch in &self.manifest.watches {
+            if !self.objectives.contains_key(&watch.objective) {
+                return Err(format_err!(
+                    "watch at {:#x} refers to unknown objective {}",
+                    watch.address,
+                    watch.objective
+                ));
+            }
+            if watch.len == 0 || watch.len > 4 {
+                return Err(format_err!(
+                    "watch at {:#x} has len {}, expected 1 to 4",
+                    watch.address,
+                    watch.len
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Problems with the module that don't keep it from loading.
+    pub fn validate(&self) -> Vec<String> {
+        let mut report = Vec::new();
+        let mut aliases: Vec<(&String, &String)> = self.manifest.aliases.iter().collect();
+        aliases.sort();
+        for (alias, id) in aliases {
+            if self.objectives.contains_key(alias) {
+                report.push(format!("alias {} is ignored, it is an objective id", alias));
+            } else if !self.objectives.contains_key(id) {
+                report.push(format!("alias {} names unknown objective {}", alias, id));
+            }
+        }
+        for (node, id) in &self.unknown_ids {
+            report.push(format!("{} refers to unknown id {}", node, id));
+        }
+        for split in &self.manifest.splits {
+            if !self.objectives.contains_key(&split.objective) {
+                report.push(format!(
+                    "split {} refers to unknown objective {}",
+                    split.name, split.objective
+                ));
+            }
+        }
+        let missing = self.missing_images();
+        if !missing.is_empty() {
+            report.push(format!(
+                "no objective or type image for {}",
+                missing.join(", ")
+            ));
+        }
+        let images: HashSet<&String> = self
+            .assets
+            .iter()
+            .filter(|a| a.kind == AssetKind::Image)
+            .map(|a| &a.id)
+            .collect();
+        let mut maps: Vec<&MapInfo> = self.maps.values().collect();
+        maps.sort_by(|a, b| a.id.cmp(&b.id));
+        for map in maps {
+            for layer in &map.layers {
+                let id = map.image_id(Some(layer));
+                if !images.contains(&id) {
+                    report.push(format!(
+                        "no image {} for layer {} of map {}",
+                        id, layer.id, map.id
+                    ));
+                }
+            }
+            for region in map.regions.iter().filter(|r| r.points.len() < 3) {
+                report.push(format!(
+                    "region {} of map {} needs at least 3 points",
+                    region.id, map.id
+                ));
+            }
+        }
+        report
+    }
+
+    // Ids of the objectives shown in grids that have neither an
+    // `objective:<id>` nor a `type:<ty>` image.
+    pub fn missing_images(&self) -> Vec<String> {
+        let mut images: HashSet<&String> = self
+            .assets
+            .iter()
+            .filter(|a| a.kind == AssetKind::Image)
+            .map(|a| &a.id)
+            .collect();
+        images.extend(self.manifest.overlays.iter().map(|o| &o.id));
+
+        let mut shown = HashSet::new();
+        for layout in self.manifest.layouts.values() {
+            self.grid_objectives(layout, &mut shown);
+        }
+
+        let mut missing: Vec<String> = shown
+            .into_iter()
+            .filter(|id| {
+                let ty = self.objectives.get(*id).map(|o| o.ty.as_str());
+                !images.contains(&format!("objective:{}", id))
+                    && !ty.is_some_and(|ty| images.contains(&format!("type:{}", ty)))
+            })
+            .cloned()
+            .collect();
+        missing.sort();
+        missing
+    }
+
+    fn grid_objectives<'a>(&'a self, info: &'a DisplayViewInfo, ids: &mut HashSet<&'a String>) {
+        match &info.view {
+            DisplayViewInfoView::Grid { objectives, .. } => match objectives {
+                ObjectiveList::List(list) => ids.extend(list),
+                // The checks grid shows the checks of objectives that open a
+                // popup.
+                ObjectiveList::Special(ObjectiveListSpecial::Checks) => {
+                    for id in self.popup_objectives() {
+                        if let Some(o) = self.objectives.get(id) {
+                            ids.extend(o.checks.iter().map(|c| &c.id));
+                        }
+                    }
+                }
+                _ => (),
+            },
+            DisplayViewInfoView::FlexRow { children }
+            | DisplayViewInfoView::FlexCol { children }
+            | DisplayViewInfoView::Tabs { children, .. } => {
+                for child in children {
+                    self.grid_objectives(child, ids);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Ids of objectives that open the checks popup when clicked: those on
+    // maps and those with the open-popup click action.
+    fn popup_objectives(&self) -> impl Iterator<Item = &String> {
+        let on_maps = self
+            .maps
+            .values()
+            .flat_map(|m| m.objectives.iter().map(|o| &o.id));
+        let open_popup = self
+            .objectives
+            .values()
+            .filter(|o| o.click_action == ClickAction::OpenPopup)
+            .map(|o| &o.id);
+        on_maps.chain(open_popup)
+    }
+
+    // Overlays must be made of images in the module and not replace one.
+    fn check_overlays(&self) -> Result<(), Error> {
+        let is_image = |id: &String| {
+            self.assets
+                .iter()
+                .any(|a| a.kind == AssetKind::Image && &a.id == id)
+        };
+        for overlay in &self.manifest.overlays {
+            if is_image(&overlay.id) {
+                return Err(format_err!(
+                    "overlay {} replaces an image of the same id",
+                    overlay.id
+                ));
+            }
+            let images =
+                std::iter::once(&overlay.base).chain(overlay.layers.iter().map(|l| &l.image));
+            for image in images {
+                if !is_image(image) {
+                    return Err(format_err!(
+                        "overlay {} uses unknown image {}",
+                        overlay.id,
+                        image
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn process_display_includes(base_path: &Path, info: &mut DisplayViewInfo) -> Result<(), Error> {
+        match &mut info.view {
+            // Views with no children require no processing.
+            DisplayViewInfoView::Grid { .. }
+            | DisplayViewInfoView::Count { .. }
+            | DisplayViewInfoView::Map { maps: _ }
+            | DisplayViewInfoView::Spacer {}
+            | DisplayViewInfoView::Splits {}
+            | DisplayViewInfoView::ChecksInLogic {}
+            | DisplayViewInfoView::Assignment { .. }
+            | DisplayViewInfoView::Text { .. } => (),
+
+            // Views will children need to recurse.
+            DisplayViewInfoView::FlexRow { children }
+            | DisplayViewInfoView::FlexCol { children }
+            | DisplayViewInfoView::Tabs {
+                labels: _,
+                children,
+            } => {
+                for child in children.iter_mut() {
+                    Self::process_display_includes(base_path, child)?;
+                }
+            }
+
+            DisplayViewInfoView::Include { path } => {
+                *info = Self::open_display_include(base_path, &path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns the id `alias` stands for.  Aliases that are also objective
+    // ids or that name unknown objectives are ignored.
+    pub fn alias_target(&self, alias: &str) -> Option<&String> {
+        if self.objectives.contains_key(alias) {
+            return None;
+        }
+        self.manifest
+            .aliases
+            .get(alias)
+            .filter(|id| self.objectives.contains_key(*id))
+    }
+
+    // Returns the objective `id` stands for, following aliases.
+    pub fn canonical_id<'a>(&'a self, id: &'a String) -> &'a String {
+        self.alias_target(id).unwrap_or(id)
+    }
+
+    // Replace aliases in expressions, groups and layouts with the ids they
+    // stand for.
+    pub(crate) fn resolve_aliases(&mut self) {
+        let aliases: HashMap<String, String> = self
+            .manifest
+            .aliases
+            .keys()
+            .filter_map(|alias| Some((alias.clone(), self.alias_target(alias)?.clone())))
+            .collect();
+        if aliases.is_empty() {
+            return;
+        }
+        let lookup = |alias: &str| aliases.get(alias);
+
+        for o in self.objectives.values_mut() {
+            o.enabled_by = std::mem::take(&mut o.enabled_by).resolve_aliases(&lookup);
+            o.unlocked_by = std::mem::take(&mut o.unlocked_by).resolve_aliases(&lookup);
+            o.completed_by = std::mem::take(&mut o.completed_by).resolve_aliases(&lookup);
+        }
+        for region in self.manifest.regions.iter_mut() {
+            for exit in region.exits.iter_mut() {
+                exit.requires = std::mem::take(&mut exit.requires).resolve_aliases(&lookup);
+            }
+        }
+        for ids in self.manifest.groups.values_mut() {
+            for id in ids.iter_mut() {
+                if let Some(target) = lookup(id) {
+                    *id = target.clone();
+                }
+            }
+        }
+        for split in self.manifest.splits.iter_mut() {
+            if let Some(target) = lookup(&split.objective) {
+                split.objective = target.clone();
+            }
+        }
+        for layout in self.manifest.layouts.values_mut() {
+            Self::resolve_layout_aliases(layout, &lookup);
+        }
+    }
+
+    fn resolve_layout_aliases<'a>(
+        info: &mut DisplayViewInfo,
+        lookup: &impl Fn(&str) -> Option<&'a String>,
+    ) {
+        info.layout_params.visible_when =
+            std::mem::take(&mut info.layout_params.visible_when).resolve_aliases(lookup);
+
+        match &mut info.view {
+            DisplayViewInfoView::Grid {
+                objectives: ObjectiveList::List(ids),
+                ..
+            }
+            | DisplayViewInfoView::Count {
+                objectives: Some(ObjectiveList::List(ids)),
+                ..
+            } => {
+                for id in ids.iter_mut() {
+                    if let Some(target) = lookup(id) {
+                        *id = target.clone();
+                    }
+                }
+            }
+
+            DisplayViewInfoView::FlexRow { children }
+            | DisplayViewInfoView::FlexCol { children }
+            | DisplayViewInfoView::Tabs { children, .. } => {
+                for child in children.iter_mut() {
+                    Self::resolve_layout_aliases(child, lookup);
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    // Returns the objective ids in the tag or group `name`.
+    pub fn objective_set(&self, name: &str) -> Option<&Vec<String>> {
+        self.manifest
+            .groups
+            .get(name)
+            .or_else(|| self.tags.get(name))
+    }
+
+    // Resolves the aliases, tags and groups `expr` refers to the way the
+    // module's own expressions are when it's loaded.
+    pub fn resolve_expression(&self, expr: Expression) -> Result<Expression, Error> {
+        expr.resolve_aliases(&|id: &str| self.alias_target(id))
+            .resolve_sets(&|name: &str| self.objective_set(name))
+    }
+
+    // Replace references to tags and groups in expressions and layouts
+    // with the objectives they contain.
+    pub(crate) fn resolve_sets(&mut self) -> Result<(), Error> {
+        for name in self.manifest.groups.keys() {
+            if self.tags.contains_key(name) {
+                return Err(format_err!("'{}' is both a tag and a group", name));
+            }
+        }
+
+        let mut objectives = std::mem::take(&mut self.objectives);
+        for (id, o) in objectives.iter_mut() {
+            let lookup = |name: &str| self.objective_set(name);
+            let resolve = |e: Expression| {
+                e.resolve_sets(&lookup)
+                    .map_err(|e| format_err!("objective '{}': {}", id, e))
+            };
+            o.enabled_by = resolve(o.enabled_by.clone())?;
+            o.unlocked_by = resolve(o.unlocked_by.clone())?;
+            o.completed_by = resolve(o.completed_by.clone())?;
+        }
+        self.objectives = objectives;
+
+        let mut regions = std::mem::take(&mut self.manifest.regions);
+        for region in regions.iter_mut() {
+            let id = &region.id;
+            for exit in region.exits.iter_mut() {
+                let lookup = |name: &str| self.objective_set(name);
+                exit.requires =
+                    exit.requires.clone().resolve_sets(&lookup).map_err(|e| {
+                        format_err!("exit of region '{}' to '{}': {}", id, exit.to, e)
+                    })?;
+            }
+        }
+        self.manifest.regions = regions;
+
+        let mut layouts = std::mem::take(&mut self.manifest.layouts);
+        for (name, layout) in layouts.iter_mut() {
+            self.resolve_layout_sets(layout)
+                .map_err(|e| format_err!("layout '{}': {}", name, e))?;
+        }
+        self.manifest.layouts = layouts;
+
+        Ok(())
+    }
+
+    // Replace references to ids that aren't objectives with `False` and
+    // record them in `unknown_ids`.
+    pub(crate) fn replace_unknown_ids(&mut self) {
+        let ids: HashSet<String> = self.objectives.keys().cloned().collect();
+        let known = |id: &str| ids.contains(id);
+        let mut found = Vec::new();
+
+        for (id, o) in self.objectives.iter_mut() {
+            let mut unknown = Vec::new();
+            o.enabled_by = std::mem::take(&mut o.enabled_by).replace_unknown(&known, &mut unknown);
+            o.unlocked_by =
+                std::mem::take(&mut o.unlocked_by).replace_unknown(&known, &mut unknown);
+            o.completed_by =
+                std::mem::take(&mut o.completed_by).replace_unknown(&known, &mut unknown);
+            found.extend(
+                unknown
+                    .into_iter()
+                    .map(|u| (format!("objective {}", id), u)),
+            );
+        }
+        for region in self.manifest.regions.iter_mut() {
+            for exit in region.exits.iter_mut() {
+                let mut unknown = Vec::new();
+                exit.requires =
+                    std::mem::take(&mut exit.requires).replace_unknown(&known, &mut unknown);
+                let node = format!("exit of region {} to {}", region.id, exit.to);
+                found.extend(unknown.into_iter().map(|u| (node.clone(), u)));
+            }
+        }
+        found.dedup();
+        self.unknown_ids = found;
+    }
+
+    fn resolve_list_sets(&self, list: &mut ObjectiveList) -> Result<(), Error> {
+        let name = match list {
+            ObjectiveList::Tag { tag } => tag,
+            ObjectiveList::Group { group } => group,
+            ObjectiveList::List(_) | ObjectiveList::Special(_) => return Ok(()),
+        };
+        let ids = self
+            .objective_set(name)
+            .ok_or(format_err!("unknown tag or group '{}'", name))?;
+        *list = ObjectiveList::List(ids.clone());
+        Ok(())
+    }
+
+    fn resolve_layout_sets(&self, info: &mut DisplayViewInfo) -> Result<(), Error> {
+        let visible_when = std::mem::take(&mut info.layout_params.visible_when);
+        info.layout_params.visible_when =
+            visible_when.resolve_sets(&|name| self.objective_set(name))?;
+
+        match &mut info.view {
+            DisplayViewInfoView::Grid { objectives, .. }
+            | DisplayViewInfoView::Count {
+                objectives: Some(objectives),
+                ..
+            } => self.resolve_list_sets(objectives)?,
+
+            DisplayViewInfoView::FlexRow { children }
+            | DisplayViewInfoView::FlexCol { children }
+            | DisplayViewInfoView::Tabs { children, .. } => {
+                for child in children.iter_mut() {
+                    self.resolve_layout_sets(child)?;
+                }
+            }
+
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn open_display_include(base_path: &Path, path: &String) -> Result<DisplayViewInfo, Error> {
+        let path = base_path.join(PathBuf::from_slash(path));
+        let mut info: DisplayViewInfo = read_module_file(&path)?;
+
+        Self::process_display_includes(base_path, &mut info)?;
+        Ok(info)
+    }
+
+    fn import_objectives(&mut self, base_path: &Path) -> Result<(), Error> {
+        let locs = std::mem::take(&mut self.manifest.objectives);
+        // Files are read and parsed in parallel.  Objectives are added in
+        // manifest order so duplicate id errors don't depend on timing.
+        let files = locs
+            .par_iter()
+            .map(|loc| {
+                let path = base_path.join(PathBuf::from_slash(&loc.path));
+                let values: Vec<serde_json::Value> = read_module_list(&path, "objectives")?;
+                Ok((loc, path, values))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        for (loc, path, values) in files {
+            // Objectives are decoded one at a time so errors can name the
+            // objective they occurred in.
+            for value in values {
+                let id = value["id"].as_str().unwrap_or("<unknown>").to_string();
+                let o: ObjectiveInfo = serde_json::from_value(value).map_err(|e| {
+                    format_err!(
+                        "Failed to parse {}: objective '{}': {}",
+                        path.display(),
+                        id,
+                        e
+                    )
+                })?;
+                self.add_objective(o, &loc.ty, &path)?;
+            }
+        }
+        self.manifest.objectives = locs;
+        Ok(())
+    }
+
+    // Add `o` and objectives for each of its checks.  `path` is the file
+    // it came from for error messages.
+    pub fn add_objective(&mut self, o: ObjectiveInfo, ty: &str, path: &Path) -> Result<(), Error> {
+        for tag in &o.tags {
+            self.tags.entry(tag.clone()).or_default().push(o.id.clone());
+        }
+        let mut obj = o.clone();
+        self.check_for_unique_id(&obj.id, path)?;
+        obj.ty = ty.to_string();
+
+        let mut checks_enabled_by = Expression::False;
+        let mut checks_unlocked_by = Expression::False;
+        let mut checks_completed_by = Expression::True;
+        // Create objectives for each check.
+        for (i, check) in obj.checks.iter_mut().enumerate() {
+            // If an ID is not givin. Assign one of the form `objective_id:index`.
+            let id = if check.id == "" {
+                format!("{}:{}", &o.id, i)
+            } else {
+                check.id.clone()
+            };
+            self.check_for_unique_id(&id, path)?;
+
+            check.id = id.clone();
+
+            // Expression defaults for checks should be True
+            let enabled_by = check.enabled_by.clone().eval_default(Expression::True);
+            if check.region.is_empty() {
+                check.region = o.region.clone();
+            }
+            let unlocked_by = in_region(
+                check.unlocked_by.clone().eval_default(Expression::True),
+                &check.region,
+            );
+
+            // Add check conditions to parent objective.
+            checks_enabled_by = checks_enabled_by.or(Expression::Objective(id.clone()));
+            checks_unlocked_by = checks_unlocked_by.or(Expression::ObjectiveUnlocked(id.clone()));
+
+            // Node is complete if all non-disabled checks are complete.
+            checks_completed_by = checks_completed_by.and(Expression::Or(
+                Box::new(Expression::ObjectiveComplete(id.clone())),
+                Box::new(Expression::ObjectiveDisabled(id.clone())),
+            ));
+
+            self.objectives.insert(
+                id.clone(),
+                ObjectiveInfo {
+                    id,
+                    ty: check.ty.clone(),
+                    name: check.name.clone(),
+                    unlocked_by,
+                    enabled_by,
+                    completed_by: Expression::Manual,
+                    checks: vec![],
+                    notify: false,
+                    hint_locations: Vec::new(),
+                    tags: Vec::new(),
+                    click_action: ClickAction::Cycle,
+                    stages: Vec::new(),
+                    region: check.region.clone(),
+                    description: check.description.clone(),
+                    tooltip: check.tooltip.clone(),
+                },
+            );
+        }
+
+        // Create objectives for each stage.  Each is complete once its stage
+        // is reached and is only unlocked after the stage before it.
+        let mut prev_stage = Expression::True;
+        for (i, stage) in obj.stages.iter().enumerate() {
+            let id = stage_id(&o.id, i as u32 + 1);
+            self.check_for_unique_id(&id, path)?;
+            let name = if stage.name.is_empty() {
+                format!("{} {}", o.name, i + 1)
+            } else {
+                stage.name.clone()
+            };
+            let unlocked_by = stage
+                .unlocked_by
+                .clone()
+                .eval_default(Expression::True)
+                .and(prev_stage);
+            prev_stage = Expression::ObjectiveComplete(id.clone());
+            self.objectives.insert(
+                id.clone(),
+                ObjectiveInfo {
+                    unlocked_by,
+                    enabled_by: Expression::True,
+                    completed_by: Expression::Manual,
+                    ..ObjectiveInfo::new(&id, &name)
+                },
+            );
+        }
+
+        if o.checks.len() == 0 {
+            // Objectives with no checks are enabled by default and
+            // unlocked manually.
+            obj.enabled_by = obj.enabled_by.eval_default(Expression::True);
+            obj.unlocked_by = if obj.region.is_empty() {
+                obj.unlocked_by.eval_default(Expression::Manual)
+            } else {
+                in_region(obj.unlocked_by.eval_default(Expression::True), &obj.region)
+            };
+            obj.completed_by = obj.completed_by.eval_default(Expression::Manual);
+        } else {
+            // Objectives with checks have their enabled_by/unlocked_by
+            // ORed with their checks.  The default is False to short circuit
+            // with the checks expression.
+            obj.enabled_by = obj
+                .enabled_by
+                .eval_default(Expression::False)
+                .or(checks_enabled_by);
+            obj.unlocked_by = obj
+                .unlocked_by
+                .eval_default(Expression::False)
+                .or(checks_unlocked_by);
+
+            // No support for explicit `completed_by` expressions.
+            obj.completed_by = checks_completed_by;
+        }
+
+        // Staged objectives are unlocked at the first stage and complete at
+        // the last.
+        if !obj.stages.is_empty() {
+            obj.unlocked_by = Expression::ObjectiveComplete(stage_id(&obj.id, 1));
+            obj.completed_by =
+                Expression::ObjectiveComplete(stage_id(&obj.id, obj.stages.len() as u32));
+        }
+
+        self.objectives.insert(obj.id.clone(), obj);
+        Ok(())
+    }
+
+    fn load_locales(dir: &Path) -> Result<HashMap<String, Locale>, Error> {
+        let mut locales = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_module_file = path
+                .extension()
+                .is_some_and(|e| MODULE_FILE_EXTENSIONS.iter().any(|ext| e == *ext));
+            if !is_module_file {
+                continue;
+            }
+            let lang = match path.file_stem() {
+                Some(stem) => stem.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            let locale: Locale = read_module_file(&path)?;
+            locales.insert(lang, locale);
+        }
+        Ok(locales)
+    }
+
+    // Add an objective for each entrance of the manifest.
+    pub(crate) fn add_entrances(&mut self) -> Result<(), Error> {
+        for entrance in self.manifest.entrances.clone() {
+            self.check_for_unique_id(&entrance.id, Path::new("manifest"))?;
+            self.objectives.insert(
+                entrance.id.clone(),
+                ObjectiveInfo {
+                    ty: ENTRANCE_TYPE.to_string(),
+                    enabled_by: Expression::True,
+                    unlocked_by: entrance.unlocked_by.eval_default(Expression::False),
+                    completed_by: Expression::Manual,
+                    ..ObjectiveInfo::new(&entrance.id, &entrance.name)
+                },
+            );
+        }
+        Ok(())
+    }
+
+    // Add an objective for each region of the manifest.
+    pub(crate) fn add_regions(&mut self) -> Result<(), Error> {
+        let mut regions = std::mem::take(&mut self.manifest.regions);
+        for region in &mut regions {
+            self.check_for_unique_id(&region.id, Path::new("manifest"))?;
+            for exit in &mut region.exits {
+                exit.requires = std::mem::take(&mut exit.requires).eval_default(Expression::True);
+            }
+            self.objectives.insert(
+                region.id.clone(),
+                ObjectiveInfo {
+                    ty: REGION_TYPE.to_string(),
+                    enabled_by: Expression::True,
+                    unlocked_by: region.unlocked_by.clone().eval_default(Expression::False),
+                    completed_by: Expression::Manual,
+                    ..ObjectiveInfo::new(&region.id, &region.name)
+                },
+            );
+        }
+        self.manifest.regions = regions;
+
+        let is_region = |id: &String| self.manifest.regions.iter().any(|r| &r.id == id);
+        for region in &self.manifest.regions {
+            if let Some(exit) = region.exits.iter().find(|e| !is_region(&e.to)) {
+                return Err(format_err!(
+                    "exit of region {} leads to unknown region {}",
+                    region.id,
+                    exit.to
+                ));
+            }
+        }
+        let mut placed: Vec<&ObjectiveInfo> = self
+            .objectives
+            .values()
+            .filter(|o| !o.region.is_empty() && !is_region(&o.region))
+            .collect();
+        placed.sort_by(|a, b| a.id.cmp(&b.id));
+        if let Some(o) = placed.first() {
+            return Err(format_err!(
+                "objective {} is in unknown region {}",
+                o.id,
+                o.region
+            ));
+        }
+        Ok(())
+    }
+
+    // Add the `go-mode` objective if the manifest declares it.
+    pub(crate) fn add_go_mode(&mut self) -> Result<(), Error> {
+        if self.manifest.go_mode == Expression::Default {
+            return Ok(());
+        }
+        let id = GO_MODE_ID.to_string();
+        self.check_for_unique_id(&id, Path::new("manifest"))?;
+        self.objectives.insert(
+            id.clone(),
+            ObjectiveInfo {
+                ty: GO_MODE_ID.to_string(),
+                enabled_by: Expression::True,
+                unlocked_by: Expression::True,
+                completed_by: self.manifest.go_mode.clone(),
+                ..ObjectiveInfo::new(&id, "Go mode")
+            },
+        );
+        Ok(())
+    }
+
+    // Add an objective for each slot and value pair of the manifest's
+    // assignments.
+    pub(crate) fn add_assignments(&mut self) -> Result<(), Error> {
+        for assignment in self.manifest.assignments.clone() {
+            let mut slots = HashSet::new();
+            for slot in &assignment.slots {
+                if !slots.insert(&slot.id) {
+                    return Err(format_err!(
+                        "duplicate slot {} in assignment {}",
+                        slot.id,
+                        assignment.id
+                    ));
+                }
+                for value in &assignment.pool {
+                    let id = assignment_objective(&slot.id, &value.id);
+                    self.check_for_unique_id(&id, Path::new("manifest"))?;
+                    self.objectives.insert(
+                        id.clone(),
+                        ObjectiveInfo {
+                            ty: ASSIGNMENT_TYPE.to_string(),
+                            enabled_by: Expression::True,
+                            unlocked_by: Expression::Manual,
+                            completed_by: Expression::Manual,
+                            ..ObjectiveInfo::new(&id, &format!("{}: {}", slot.name, value.name))
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Shape of the marker of objective `id` on `map`: the map's shape for
+    // the objective's type, then the manifest's, then a circle.
+    pub fn marker_shape(&self, map: &MapInfo, id: &str) -> MarkerShape {
+        let ty = match self.objectives.get(id) {
+            Some(info) => &info.ty,
+            None => return MarkerShape::default(),
+        };
+        map.marker_shapes
+            .get(ty)
+            .or_else(|| self.manifest.marker_shapes.get(ty))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn assignment(&self, id: &str) -> Option<&AssignmentInfo> {
+        self.manifest.assignments.iter().find(|a| a.id == id)
+    }
+
+    pub fn is_entrance(&self, id: &str) -> bool {
+        self.manifest.entrances.iter().any(|e| e.id == id)
+    }
+
+    fn check_for_unique_id(&self, id: &String, path: &Path) -> Result<(), Error> {
+        if self.objectives.contains_key(id) {
+            Err(format_err!(
+                "Duplicate id {} found in {}.",
+                id,
+                path.display()
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn visit_asset_dir(
+        base_dir: &Path,
+        dir: &Path,
+        paths: &mut Vec<AssetInfo>,
+    ) -> Result<(), Error> {
+        if !dir.is_dir() {
+            return Err(format_err!("{} is not a directory.", dir.to_string_lossy()));
+        }
+
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_dir() {
+                Self::visit_asset_dir(base_dir, &path, paths)?;
+            } else {
+                if let Some(extension) = path.extension() {
+                    let kind = if extension == "png" {
+                        Some(AssetKind::Image)
+                    } else if extension == "ogg" || extension == "wav" {
+                        Some(AssetKind::Sound)
+                    } else if extension == "ttf" || extension == "otf" {
+                        Some(AssetKind::Font)
+                    } else {
+                        None
+                    };
+                    if let Some(kind) = kind {
+                        // Create `id` by stripping off the asset directory prefix,
+                        // converting path separators to ':', and stripping the
+                        // extension.  This creates a platform agnostic id
+                        // based on the asset's path.
+                        let mut id = path
+                            .strip_prefix(&base_dir)?
+                            .iter()
+                            .map(|c| c.to_string_lossy().into_owned())
+                            .collect::<Vec<String>>()
+                            .join(":");
+                        // Trims extension and the '.' preceding it.
+                        id.truncate(id.len() - extension.len() - 1);
+
+                        paths.push(AssetInfo {
+                            path: path.to_path_buf(),
+                            id,
+                            kind,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_json_object<'a, T>(s: &'a str, o: &T) -> Result<(), Error>
+    where
+        T: Deserialize<'a> + std::fmt::Debug + PartialEq,
+    {
+        let decoded: T = serde_json::from_str(s)?;
+        assert_eq!(decoded, *o);
+
+        Ok(())
+    }
+
+    #[test]
+    fn param_encoding() -> Result<(), Error> {
+        test_json_object(
+            r#"{"type": "TextBox", "name": "Seed"}"#,
+            &Param::TextBox {
+                name: "Seed".to_string(),
+                id: String::new(),
+                group: String::new(),
+            },
+        )?;
+        let param = Param::CheckBox {
+            id: "flag-k-moon".to_string(),
+            name: "Kmoon".to_string(),
+            group: "Key Items".to_string(),
+        };
+        test_json_object(
+            r#"{"type": "CheckBox", "id": "flag-k-moon", "name": "Kmoon", "group": "Key Items"}"#,
+            &param,
+        )?;
+        assert_eq!(param.group(), "Key Items");
+
+        Ok(())
+    }
+
+    #[test]
+    fn objective_info_encoding() -> Result<(), Error> {
+        // Test for type, children, and deps defaults.
+        test_json_object(
+            r#"{
+    "id": "test",
+    "name": "Test Objective"
+}"#,
+            &ObjectiveInfo {
+                id: "test".to_string(),
+                ty: "".to_string(),
+                name: "Test Objective".to_string(),
+                enabled_by: Expression::default(),
+                unlocked_by: Expression::default(),
+                completed_by: Expression::default(),
+                checks: vec![],
+                notify: false,
+                hint_locations: Vec::new(),
+                tags: Vec::new(),
+                click_action: ClickAction::Cycle,
+                stages: Vec::new(),
+                region: String::new(),
+                description: String::new(),
+                tooltip: None,
+            },
+        )
+        .expect("decoding error");
+
+        // Test for type, children, and deps specified.
+        test_json_object(
+            r#"{
+    "id": "test",
+    "type": "location",
+    "name": "Test Objective",
+    "checks": [{"type": "key-item", "description": "Behind the waterfall"}],
+    "click-action": "open-popup",
+    "description": "Bring a light source",
+    "tooltip": {"text": "Past the bridge", "image": "screenshot:test"}
+}"#,
+            &ObjectiveInfo {
+                id: "test".to_string(),
+                ty: "location".to_string(),
+                name: "Test Objective".to_string(),
+                enabled_by: Expression::default(),
+                unlocked_by: Expression::default(),
+                completed_by: Expression::default(),
+                checks: vec![ObjectiveCheck {
+                    ty: "key-item".to_string(),
+                    id: "".to_string(),
+                    name: "".to_string(),
+                    enabled_by: Expression::default(),
+                    unlocked_by: Expression::default(),
+                    region: String::new(),
+                    description: "Behind the waterfall".to_string(),
+                    tooltip: None,
+                }],
+                notify: false,
+                hint_locations: Vec::new(),
+                tags: Vec::new(),
+                click_action: ClickAction::OpenPopup,
+                stages: Vec::new(),
+                region: String::new(),
+                description: "Bring a light source".to_string(),
+                tooltip: Some(Tooltip {
+                    text: "Past the bridge".to_string(),
+                    image: "screenshot:test".to_string(),
+                }),
+            },
+        )
+        .expect("decoding error");
+
+        Ok(())
+    }
+
+    #[test]
+    fn objective_list_encoding() -> Result<(), Error> {
+        test_json_object(
+            r#"["a", "b"]"#,
+            &ObjectiveList::List(vec!["a".to_string(), "b".to_string()]),
+        )
+        .expect("decoding error");
+
+        test_json_object(
+            r#""checks""#,
+            &ObjectiveList::Special(ObjectiveListSpecial::Checks),
+        )
+        .expect("decoding error");
+
+        test_json_object(
+            r#"{"tag": "boss"}"#,
+            &ObjectiveList::Tag { tag: "boss".into() },
+        )
+        .expect("decoding error");
+
+        test_json_object(
+            r#"{"group": "dungeons"}"#,
+            &ObjectiveList::Group {
+                group: "dungeons".into(),
+            },
+        )
+        .expect("decoding error");
+
+        Ok(())
+    }
+
+    #[test]
+    fn map_regions() -> Result<(), Error> {
+        let map: MapInfo = serde_json::from_str(
+            r#"{
+                "id": "world",
+                "name": "World",
+                "width": 100,
+                "height": 100,
+                "objective-radius": 4.0,
+                "regions": [
+                    {"id": "l", "points": [[0, 0], [10, 0], [10, 5], [5, 5], [5, 10], [0, 10]]},
+                    {"id": "line", "points": [[0, 0], [10, 10]]}
+                ]
+            }"#,
+        )?;
+        let l = &map.regions[0];
+        assert!(l.contains(2.0, 2.0));
+        assert!(l.contains(8.0, 2.0));
+        assert!(l.contains(2.0, 8.0));
+        assert!(!l.contains(8.0, 8.0));
+        assert!(!l.contains(-1.0, 2.0));
+        assert!(!map.regions[1].contains(5.0, 5.0));
+
+        let mut module = crate::builder::ModuleBuilder::new().build()?;
+        module.maps.insert(map.id.clone(), map);
+        assert_eq!(
+            module.validate(),
+            vec!["region line of map world needs at least 3 points"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn marker_shapes() -> Result<(), Error> {
+        let map: MapInfo = serde_json::from_str(
+            r#"{
+                "id": "world",
+                "name": "World",
+                "width": 100,
+                "height": 100,
+                "objective-radius": 4.0,
+                "marker-shapes": {"shop": "square"}
+            }"#,
+        )?;
+        let mut module = crate::builder::ModuleBuilder::new()
+            .objective("armory")
+            .objective_type("shop")
+            .objective("dragon")
+            .objective_type("boss")
+            .objective("chest")
+            .build()?;
+        module
+            .manifest
+            .marker_shapes
+            .insert("shop".into(), MarkerShape::Triangle);
+        module
+            .manifest
+            .marker_shapes
+            .insert("boss".into(), MarkerShape::Diamond);
+
+        assert_eq!(module.marker_shape(&map, "armory"), MarkerShape::Square);
+        assert_eq!(module.marker_shape(&map, "dragon"), MarkerShape::Diamond);
+        assert_eq!(module.marker_shape(&map, "chest"), MarkerShape::Circle);
+        assert_eq!(module.marker_shape(&map, "nowhere"), MarkerShape::Circle);
+        Ok(())
+    }
+
+    #[test]
+    fn map_layers() -> Result<(), Error> {
+        let map: MapInfo = serde_json::from_str(
+            r#"{
+                "id": "world",
+                "name": "World",
+                "width": 100,
+                "height": 100,
+                "objective-radius": 4.0,
+                "layers": [
+                    {"id": "day", "name": "Day"},
+                    {"id": "night", "name": "Night"}
+                ]
+            }"#,
+        )?;
+        assert_eq!(map.image_id(None), "map:world");
+        assert_eq!(map.image_id(map.layers.get(1)), "map:world:night");
+        assert!(map.marker_shapes.is_empty());
+
+        let mut module = crate::builder::ModuleBuilder::new().build()?;
+        module.maps.insert(map.id.clone(), map);
+        module.assets.push(AssetInfo {
+            path: PathBuf::new(),
+            id: "map:world:day".to_string(),
+            kind: AssetKind::Image,
+        });
+        assert_eq!(
+            module.validate(),
+            vec!["no image map:world:night for layer night of map world"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_images() -> Result<(), Error> {
+        let main = serde_json::from_str(
+            r#"{"type": "Grid", "columns": 3, "objectives": ["sword", "shield", "boots"]}"#,
+        )?;
+        let mut module = crate::builder::ModuleBuilder::new()
+            .objective("sword")
+            .objective_type("item")
+            .check("chest", "")
+            .objective("shield")
+            .check("barrel", "")
+            .objective("boots")
+            .layout("main", main)
+            .build()?;
+        for id in &["type:item", "objective:shield"] {
+            module.assets.push(AssetInfo {
+                path: PathBuf::new(),
+                id: id.to_string(),
+                kind: AssetKind::Image,
+            });
+        }
+
+        // Checks only show up in the popup of objectives that open one.
+        assert_eq!(module.missing_images(), vec!["boots"]);
+        module.objectives.get_mut("sword").unwrap().click_action = ClickAction::OpenPopup;
+        assert_eq!(module.missing_images(), vec!["boots", "chest"]);
+        assert_eq!(module.validate().len(), 1);
+
+        module.assets.push(AssetInfo {
+            path: PathBuf::new(),
+            id: "objective:boots".into(),
+            kind: AssetKind::Image,
+        });
+        assert_eq!(module.missing_images(), vec!["chest"]);
+        Ok(())
+    }
+
+    #[test]
+    fn overlay_info_encoding() -> Result<(), Error> {
+        test_json_object(
+            r#"{
+    "id": "objective:sword:stage2",
+    "base": "objective:sword",
+    "layers": [
+        {"image": "overlay:two"},
+        {"image": "overlay:ribbon", "anchor": "top-left", "scale": 0.5}
+    ]
+}"#,
+            &OverlayInfo {
+                id: "objective:sword:stage2".into(),
+                base: "objective:sword".into(),
+                layers: vec![
+                    OverlayLayer {
+                        image: "overlay:two".into(),
+                        anchor: OverlayAnchor::BottomRight,
+                        scale: None,
+                    },
+                    OverlayLayer {
+                        image: "overlay:ribbon".into(),
+                        anchor: OverlayAnchor::TopLeft,
+                        scale: Some(0.5),
+                    },
+                ],
+            },
+        )
+        .expect("decoding error");
+        Ok(())
+    }
+
+    #[test]
+    fn watch_info_encoding() -> Result<(), Error> {
+        test_json_object(
+            r#"{
+    "address": "0x7e1500",
+    "mask": 4,
+    "objective": "cave-key",
+    "state": "complete"
+}"#,
+            &WatchInfo {
+                address: 0x7e1500,
+                len: 1,
+                mask: 4,
+                compare: WatchCompare::NonZero,
+                objective: "cave-key".into(),
+                state: ObjectiveState::Complete,
+            },
+        )
+        .expect("decoding error");
+
+        let watch: WatchInfo = serde_json::from_str(
+            r#"{
+    "address": 8262912,
+    "len": 2,
+    "compare": {"at-least": 300},
+    "objective": "dragon-gate",
+    "state": "unlocked"
+}"#,
+        )?;
+        assert_eq!(watch.address, 0x7e1500);
+        assert!(watch.matches(&[0x2c, 0x01], false));
+        assert!(!watch.matches(&[0x2b, 0x01], false));
+        assert!(watch.matches(&[0x01, 0x2c], true));
+
+        let watch = WatchInfo {
+            mask: 0x0c,
+            compare: WatchCompare::Equal(0x08),
+            ..watch
+        };
+        assert!(watch.matches(&[0xf8], false));
+        assert!(!watch.matches(&[0x0c], false));
+
+        assert!(serde_json::from_str::<WatchInfo>(
+            r#"{"address": "0xzz", "objective": "a", "state": "complete"}"#
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn click_action_states() {
+        use ObjectiveState::*;
+        let steps = |action: ClickAction| {
+            vec![Locked, Unlocked, Complete, Disabled]
+                .into_iter()
+                .map(|s| action.next_state(s))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            steps(ClickAction::Cycle),
+            vec![Unlocked, Complete, Locked, Disabled]
+        );
+        assert_eq!(
+            steps(ClickAction::ToggleComplete),
+            vec![Complete, Complete, Locked, Disabled]
+        );
+        assert_eq!(
+            steps(ClickAction::Increment),
+            vec![Unlocked, Complete, Complete, Disabled]
+        );
+        assert_eq!(
+            steps(ClickAction::None),
+            vec![Locked, Unlocked, Complete, Disabled]
+        );
+
+        let back = |action: ClickAction| {
+            vec![Locked, Unlocked, Complete, Disabled]
+                .into_iter()
+                .map(|s| action.prev_state(s))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            back(ClickAction::Cycle),
+            vec![Complete, Locked, Unlocked, Disabled]
+        );
+        assert_eq!(
+            back(ClickAction::ToggleComplete),
+            vec![Complete, Complete, Locked, Disabled]
+        );
+        assert_eq!(
+            back(ClickAction::Increment),
+            vec![Locked, Locked, Unlocked, Disabled]
+        );
+    }
+
+    #[test]
+    fn click_action_stages() {
+        let stages = |action: ClickAction, step: fn(&ClickAction, u32, u32) -> u32| {
+            (0..=2).map(|s| step(&action, s, 2)).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            stages(ClickAction::Cycle, ClickAction::next_stage),
+            vec![1, 2, 0]
+        );
+        assert_eq!(
+            stages(ClickAction::Cycle, ClickAction::prev_stage),
+            vec![2, 0, 1]
+        );
+        assert_eq!(
+            stages(ClickAction::Increment, ClickAction::prev_stage),
+            vec![0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn process_info_encoding() -> Result<(), Error> {
+        test_json_object(
+            r#"{
+    "name": "game.exe",
+    "pointer-path": ["0x1f2e30", 16, "0x8"]
+}"#,
+            &ProcessInfo {
+                name: "game.exe".into(),
+                module: "".into(),
+                pointer_path: vec![0x1f2e30, 16, 8],
+                pointer_size: 8,
+            },
+        )
+        .expect("decoding error");
+        Ok(())
+    }
+
+    #[test]
+    fn grid_columns_encoding() -> Result<(), Error> {
+        test_json_object(r#"4"#, &GridColumns::Count(4)).expect("decoding error");
+        test_json_object(r#""auto""#, &GridColumns::Special(GridColumnsSpecial::Auto))
+            .expect("decoding error");
+        assert_eq!(GridColumns::Special(GridColumnsSpecial::Auto).count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_yaml_and_toml_module() -> Result<(), Error> {
+        let module = Module::open("src/test_data/yaml_mod/manifest.yaml")?;
+        assert_eq!(module.manifest.name, "World of Example");
+        assert_eq!(
+            module.objectives["dragon-gate"].unlocked_by,
+            Expression::parse("cave-key && !disabled(cave-key)")?
+        );
+        match &module.manifest.layouts["main"].view {
+            DisplayViewInfoView::Grid { objectives, .. } => assert_eq!(
+                *objectives,
+                ObjectiveList::List(vec!["cave-key".into(), "dragon-gate".into()])
+            ),
+            view => panic!("main layout is {:?}", view),
+        }
+        assert_eq!(module.tests.len(), 1);
+        assert_eq!(
+            module.tests[0].steps[0].expect["dragon-gate"],
+            ObjectiveState::Unlocked
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn load_module() -> Result<(), Error> {
+        let module = Module::open("src/test_data/mod/manifest.json")?;
+
+        let locale = module.locales.get("de").expect("de locale not loaded");
+        assert_eq!(
+            locale.names.get("cave-key"),
+            Some(&"Höhlenschlüssel".to_string())
+        );
+        assert_eq!(locale.labels.get("flags"), Some(&"Flaggen".to_string()));
+
+        assert_eq!(
+            module.manifest.auto_track_policy,
+            AutoTrackPolicy::ProtectManual
+        );
+
+        assert_eq!(
+            module.objectives.get("cave-key").unwrap().hint_locations,
+            vec!["mountain-cave".to_string(), "lake-cave".to_string()]
+        );
+
+        // Tags and groups are resolved to the objectives they contain.
+        let keys = vec!["cave-key".to_string(), "sword-of-might".to_string()];
+        assert_eq!(module.objective_set("key"), Some(&keys));
+        assert_eq!(
+            module.objectives.get("dragon-gate").unwrap().unlocked_by,
+            Expression::parse("cave-key && sword-of-might")?
+        );
+        let sets = &module.manifest.layouts.get("sets").unwrap().view;
+        let children = match sets {
+            DisplayViewInfoView::FlexCol { children } => children,
+            v => panic!("unexpected view {:?}", v),
+        };
+        assert_eq!(
+            children[0].view,
+            DisplayViewInfoView::Grid {
+                columns: GridColumns::Special(GridColumnsSpecial::Auto),
+                objectives: ObjectiveList::List(keys),
+                disabled: DisabledDisplay::Dim,
+            }
+        );
+        assert_eq!(
+            children[1].layout_params.visible_when,
+            Expression::ObjectiveComplete("cave-key".into())
+        );
+        assert_eq!(children[1].layout_params.font, "Press Start 2P");
+        assert_eq!(children[1].layout_params.font_size, 20.);
+        assert_eq!(
+            children[1].view,
+            DisplayViewInfoView::Count {
+                objective_type: "".into(),
+                objectives: Some(ObjectiveList::List(vec!["dragon-gate".into()])),
+            }
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::event_log::format_duration;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportItem {
+    pub name: String,
+    // Time since the start of the session the item was acquired.
+    pub time: Duration,
+    pub icon: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportLocation {
+    pub name: String,
+    pub checks_complete: u32,
+    pub checks_total: u32,
+}
+
+// A summary of a tracking session that can be rendered for sharing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub module_name: String,
+    pub elapsed: Duration,
+    // (name, value) pairs of the module params describing the seed.
+    pub seed: Vec<(String, String)>,
+    pub key_items: Vec<ReportItem>,
+    pub locations: Vec<ReportLocation>,
+}
+
+fn icon_data_uri(icon: &Option<PathBuf>) -> Option<String> {
+    let data = fs::read(icon.as_ref()?).ok()?;
+    Some(format!("data:image/png;base64,{}", base64::encode(&data)))
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Report {
+    pub fn checks_complete(&self) -> u32 {
+        self.locations.iter().map(|l| l.checks_complete).sum()
+    }
+
+    pub fn checks_total(&self) -> u32 {
+        self.locations.iter().map(|l| l.checks_total).sum()
+    }
+
+    // Locations whose checks are all complete.
+    pub fn locations_complete(&self) -> usize {
+        self.locations
+            .iter()
+            .filter(|l| l.checks_complete == l.checks_total)
+            .count()
+    }
+
+    // A single line of progress to paste into race chat.
+    pub fn to_summary(&self) -> String {
+        let mut out = format!("{} {}", self.module_name, format_duration(&self.elapsed));
+        out.push_str(&format!(" | {} key items", self.key_items.len()));
+        if !self.key_items.is_empty() {
+            let names: Vec<&str> = self.key_items.iter().map(|i| i.name.as_str()).collect();
+            out.push_str(&format!(": {}", names.join(", ")));
+        }
+        out.push_str(&format!(
+            " | {}/{} checks | {}/{} locations",
+            self.checks_complete(),
+            self.checks_total(),
+            self.locations_complete(),
+            self.locations.len()
+        ));
+        out
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("# {} Session Report\n\n", self.module_name));
+        out.push_str(&format!(
+            "**Elapsed time:** {}\n\n",
+            format_duration(&self.elapsed)
+        ));
+
+        if !self.seed.is_empty() {
+            out.push_str("## Seed\n\n");
+            for (name, value) in &self.seed {
+                out.push_str(&format!("- {}: {}\n", name, value));
+            }
+            out.push('\n');
+        }
+
+        out.push_str("## Key Items\n\n");
+        for (i, item) in self.key_items.iter().enumerate() {
+            let icon = match icon_data_uri(&item.icon) {
+                Some(uri) => format!("![{}]({}) ", item.name, uri),
+                None => "".into(),
+            };
+            out.push_str(&format!(
+                "{}. {}{} ({})\n",
+                i + 1,
+                icon,
+                item.name,
+                format_duration(&item.time)
+            ));
+        }
+        out.push('\n');
+
+        out.push_str(&format!(
+            "## Checks: {} / {}\n\n",
+            self.checks_complete(),
+            self.checks_total()
+        ));
+        out.push_str("| Location | Complete |\n|---|---|\n");
+        for l in &self.locations {
+            out.push_str(&format!(
+                "| {} | {} / {} |\n",
+                l.name, l.checks_complete, l.checks_total
+            ));
+        }
+
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        let title = format!("{} Session Report", escape_html(&self.module_name));
+        out.push_str(&format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n",
+            title
+        ));
+        out.push_str(&format!("<h1>{}</h1>\n", title));
+        out.push_str(&format!(
+            "<p><b>Elapsed time:</b> {}</p>\n",
+            format_duration(&self.elapsed)
+        ));
+
+        if !self.seed.is_empty() {
+            out.push_str("<h2>Seed</h2>\n<ul>\n");
+            for (name, value) in &self.seed {
+                out.push_str(&format!(
+                    "<li>{}: {}</li>\n",
+                    escape_html(name),
+                    escape_html(value)
+                ));
+            }
+            out.push_str("</ul>\n");
+        }
+
+        out.push_str("<h2>Key Items</h2>\n<ol>\n");
+        for item in &self.key_items {
+            let icon = match icon_data_uri(&item.icon) {
+                Some(uri) => format!("<img src=\"{}\" alt=\"\"> ", uri),
+                None => "".into(),
+            };
+            out.push_str(&format!(
+                "<li>{}{} ({})</li>\n",
+                icon,
+                escape_html(&item.name),
+                format_duration(&item.time)
+            ));
+        }
+        out.push_str("</ol>\n");
+
+        out.push_str(&format!(
+            "<h2>Checks: {} / {}</h2>\n<table>\n<tr><th>Location</th><th>Complete</th></tr>\n",
+            self.checks_complete(),
+            self.checks_total()
+        ));
+        for l in &self.locations {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{} / {}</td></tr>\n",
+                escape_html(&l.name),
+                l.checks_complete,
+                l.checks_total
+            ));
+        }
+        out.push_str("</table>\n</body>\n</html>\n");
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report {
+            module_name: "Test".into(),
+            elapsed: Duration::from_secs(90),
+            seed: vec![("Flags".into(), "Kmain".into())],
+            key_items: vec![ReportItem {
+                name: "Hook".into(),
+                time: Duration::from_secs(61),
+                icon: None,
+            }],
+            locations: vec![
+                ReportLocation {
+                    name: "Baron".into(),
+                    checks_complete: 2,
+                    checks_total: 5,
+                },
+                ReportLocation {
+                    name: "Mist <Cave>".into(),
+                    checks_complete: 1,
+                    checks_total: 1,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn markdown() {
+        let md = report().to_markdown();
+        assert!(md.contains("**Elapsed time:** 0:01:30"));
+        assert!(md.contains("- Flags: Kmain"));
+        assert!(md.contains("1. Hook (0:01:01)"));
+        assert!(md.contains("## Checks: 3 / 6"));
+        assert!(md.contains("| Baron | 2 / 5 |"));
+    }
+
+    #[test]
+    fn summary() {
+        assert_eq!(
+            report().to_summary(),
+            "Test 0:01:30 | 1 key items: Hook | 3/6 checks | 1/2 locations"
+        );
+    }
+
+    #[test]
+    fn html() {
+        let html = report().to_html();
+        assert!(html.contains("<li>Hook (0:01:01)</li>"));
+        assert!(html.contains("<td>Mist &lt;Cave&gt;</td>"));
+    }
+}
@@ -0,0 +1,62 @@
+//! JSON Schemas of module files so editors can complete and check them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::{format_err, Error};
+use schemars::{schema::RootSchema, schema_for};
+
+use crate::module::{DisplayViewInfo, Manifest, MapInfo, ModuleTest, ObjectiveInfo};
+
+// File name and schema of each kind of module file.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("manifest.schema.json", schema_for!(Manifest)),
+        ("objectives.schema.json", schema_for!(Vec<ObjectiveInfo>)),
+        ("map.schema.json", schema_for!(MapInfo)),
+        ("layout.schema.json", schema_for!(DisplayViewInfo)),
+        ("tests.schema.json", schema_for!(Vec<ModuleTest>)),
+    ]
+}
+
+// Writes every schema to `dir`, creating it if needed, and returns the
+// paths written.
+pub fn write_schemas(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format_err!("Failed to create {}: {}", dir.display(), e))?;
+    let mut paths = Vec::new();
+    for (name, schema) in schemas() {
+        let path = dir.join(name);
+        let json = serde_json::to_string_pretty(&schema)?;
+        fs::write(&path, json + "\n")
+            .map_err(|e| format_err!("Failed to write {}: {}", path.display(), e))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schemas_describe_module_files() -> Result<(), Error> {
+        let schemas = schemas();
+        let manifest = serde_json::to_value(&schemas[0].1)?;
+        let properties = &manifest["properties"];
+        for key in &["name", "objectives", "layouts", "auto-track", "entrances"] {
+            assert!(properties.get(key).is_some(), "manifest has no {}", key);
+        }
+        let required = manifest["required"].as_array().unwrap();
+        assert!(required.contains(&"objectives".into()));
+        assert!(!required.contains(&"maps".into()));
+
+        // Expressions are strings.
+        let objectives = serde_json::to_value(&schemas[1].1)?;
+        assert_eq!(
+            objectives["definitions"]["Expression"]["type"],
+            serde_json::json!("string")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use failure::{format_err, Error};
+use indexmap::IndexMap;
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    combinator::{all_consuming, map},
+    multi::many0,
+    sequence::{delimited, separated_pair},
+    IResult,
+};
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{module::ObjectiveInfo, ObjectiveState};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateSegment {
+    Literal(String),
+    // Number of objectives of a type that are Unlocked or Complete.
+    Count(String),
+    // Number of objectives of a type that are Complete.
+    Complete(String),
+    // Total number of objectives of a type.
+    Total(String),
+    // State of a single objective.
+    State(String),
+}
+
+// A string with `{key:arg}` placeholders that are filled in from the
+// current objective state.  `{count:<type>-complete}` is the same as
+// `{complete:<type>}`.  `{{` and `}}` produce literal braces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    segments: Vec<TemplateSegment>,
+}
+
+fn literal(input: &str) -> IResult<&str, TemplateSegment> {
+    alt((
+        map(tag("{{"), |_| TemplateSegment::Literal("{".into())),
+        map(tag("}}"), |_| TemplateSegment::Literal("}".into())),
+        map(take_while1(|c| c != '{' && c != '}'), |s: &str| {
+            TemplateSegment::Literal(s.into())
+        }),
+    ))(input)
+}
+
+fn placeholder_arg(input: &str) -> IResult<&str, &str> {
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == ':')(input)
+}
+
+fn placeholder(input: &str) -> IResult<&str, (&str, &str)> {
+    delimited(
+        tag("{"),
+        separated_pair(
+            take_while1(|c: char| c.is_ascii_lowercase()),
+            tag(":"),
+            placeholder_arg,
+        ),
+        tag("}"),
+    )(input)
+}
+
+fn parse_template(input: &str) -> IResult<&str, Vec<Result<TemplateSegment, String>>> {
+    all_consuming(many0(alt((
+        map(literal, Ok),
+        map(placeholder, |(key, arg)| match key {
+            "count" => Ok(match arg.strip_suffix("-complete") {
+                Some(ty) => TemplateSegment::Complete(ty.into()),
+                None => TemplateSegment::Count(arg.into()),
+            }),
+            "complete" => Ok(TemplateSegment::Complete(arg.into())),
+            "total" => Ok(TemplateSegment::Total(arg.into())),
+            "state" => Ok(TemplateSegment::State(arg.into())),
+            _ => Err(key.to_string()),
+        }),
+    ))))(input)
+}
+
+impl Template {
+    pub fn parse(input: &str) -> Result<Template, Error> {
+        let (_, segments) = parse_template(input)
+            .map_err(|e| format_err!("error parsing template \"{}\": {:?}", input, e))?;
+
+        let segments = segments
+            .into_iter()
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(|key| format_err!("unknown template key \"{}\" in \"{}\"", key, input))?;
+
+        Ok(Template { segments })
+    }
+
+    // Render the template against the module's objectives and their
+    // current state.
+    pub fn render(
+        &self,
+        info: &IndexMap<String, ObjectiveInfo>,
+        state: &HashMap<String, ObjectiveState>,
+    ) -> String {
+        let count_type = |ty: &String, f: &dyn Fn(&ObjectiveState) -> bool| {
+            info.values()
+                .filter(|o| o.ty == *ty)
+                .filter(|o| state.get(&o.id).map(f).unwrap_or(false))
+                .count()
+        };
+
+        let mut text = String::new();
+        for segment in &self.segments {
+            match segment {
+                TemplateSegment::Literal(s) => text.push_str(s),
+                TemplateSegment::Count(ty) => text.push_str(
+                    &count_type(ty, &|s| s.at_least(&ObjectiveState::Unlocked)).to_string(),
+                ),
+                TemplateSegment::Complete(ty) => {
+                    text.push_str(&count_type(ty, &|s| s.is(&ObjectiveState::Complete)).to_string())
+                }
+                TemplateSegment::Total(ty) => text.push_str(&count_type(ty, &|_| true).to_string()),
+                TemplateSegment::State(id) => match state.get(id) {
+                    Some(s) => text.push_str(&format!("{:?}", s)),
+                    None => text.push('?'),
+                },
+            }
+        }
+        text
+    }
+}
+
+impl<'de> Deserialize<'de> for Template {
+    fn deserialize<D>(deserializer: D) -> Result<Template, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <String>::deserialize(deserializer)?;
+        Template::parse(&s).map_err(de::Error::custom)
+    }
+}
+
+impl JsonSchema for Template {
+    fn schema_name() -> String {
+        "Template".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let mut schema = String::json_schema(gen).into_object();
+        schema.metadata().description = Some(
+            "Text with counts such as `Crystals: {complete:crystal}/{total:crystal}`.".to_string(),
+        );
+        schema.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::Expression;
+    use crate::module::ClickAction;
+
+    fn objective(id: &str, ty: &str) -> (String, ObjectiveInfo) {
+        (
+            id.to_string(),
+            ObjectiveInfo {
+                id: id.to_string(),
+                ty: ty.to_string(),
+                name: id.to_string(),
+                completed_by: Expression::default(),
+                enabled_by: Expression::default(),
+                unlocked_by: Expression::default(),
+                checks: vec![],
+                notify: false,
+                hint_locations: Vec::new(),
+                tags: Vec::new(),
+                click_action: ClickAction::Cycle,
+                stages: Vec::new(),
+                region: String::new(),
+                description: String::new(),
+                tooltip: None,
+            },
+        )
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Template::parse("Crystals: {complete:crystal}/{total:crystal}").unwrap(),
+            Template {
+                segments: vec![
+                    TemplateSegment::Literal("Crystals: ".into()),
+                    TemplateSegment::Complete("crystal".into()),
+                    TemplateSegment::Literal("/".into()),
+                    TemplateSegment::Total("crystal".into()),
+                ]
+            }
+        );
+
+        assert_eq!(
+            Template::parse("{{{state:mist-cave:0}}}").unwrap(),
+            Template {
+                segments: vec![
+                    TemplateSegment::Literal("{".into()),
+                    TemplateSegment::State("mist-cave:0".into()),
+                    TemplateSegment::Literal("}".into()),
+                ]
+            }
+        );
+
+        assert_eq!(
+            Template::parse("Crystals: {count:crystal-complete}/{total:crystal}").unwrap(),
+            Template::parse("Crystals: {complete:crystal}/{total:crystal}").unwrap()
+        );
+
+        assert!(Template::parse("{bogus:crystal}").is_err());
+        assert!(Template::parse("{count:crystal").is_err());
+        assert!(Template::parse("count}").is_err());
+    }
+
+    #[test]
+    fn render() {
+        let info = vec![
+            objective("a", "crystal"),
+            objective("b", "crystal"),
+            objective("c", "crystal"),
+            objective("d", "key-item"),
+        ]
+        .into_iter()
+        .collect();
+        let state = vec![
+            ("a".to_string(), ObjectiveState::Complete),
+            ("b".to_string(), ObjectiveState::Unlocked),
+            ("c".to_string(), ObjectiveState::Locked),
+            ("d".to_string(), ObjectiveState::Complete),
+        ]
+        .into_iter()
+        .collect();
+
+        let t = Template::parse("{count:crystal} {complete:crystal}/{total:crystal} {state:c}")
+            .unwrap();
+        assert_eq!(t.render(&info, &state), "2 1/3 Locked");
+    }
+}
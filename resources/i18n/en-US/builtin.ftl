@@ -0,0 +1,90 @@
+-app-name = Pollendina
+
+# The 'application' menu on macOS
+macos-menu-about-app = About { -app-name }
+macos-menu-preferences = Preferences...
+macos-menu-hide-app = Hide { -app-name }
+macos-menu-hide-others = Hide Others
+macos-menu-show-all = Show All
+macos-menu-services = Services
+macos-menu-application-menu = { -app-name }
+macos-menu-quit-app = Quit { -app-name }
+
+# common 'File' menu items
+common-menu-file-menu = File
+common-menu-file-new = New
+common-menu-file-new-window = New Window
+
+common-menu-file-open = Open...
+common-menu-file-close = Close
+
+common-menu-file-save = Save
+# used for new files, if we need to show a dialog
+common-menu-file-save-ellipsis = Save...
+common-menu-file-save-as = Save As...
+
+common-menu-file-page-setup = Page Setup...
+common-menu-file-print = Print...
+
+# windows 'File' menu items
+win-menu-file-exit = Exit
+
+# common 'Edit' menu items.
+common-menu-edit-menu = Edit
+
+common-menu-cut = Cut
+common-menu-copy = Copy
+common-menu-paste = Paste
+common-menu-undo = Undo
+common-menu-redo = Redo
+
+# Labels that show tracker state.  druid reads this file in place of its
+# own, so the menu strings above have to be kept.
+
+# Complete and total checks of the popup's objective.
+pollendina-popup-checks = { $complete } / { $total }
+# Objectives the popup's objective is missing to be in logic.
+pollendina-popup-hint = Needs: { $needs }
+
+# A line of the user's note on the hovered objective.
+pollendina-note-line = Note: { $note }
+# Title of the note editor.
+pollendina-note-on = Note on { $name }
+
+# Title of the dialog for noting what a slot holds.
+pollendina-scout-question = What is at { $name }?
+
+# Title of the dialog for connecting an entrance.
+pollendina-connect-question = Where does { $name } lead?
+
+# Shown when a newer release is available.
+pollendina-update-to = Update to { $version }
+
+# Shown in the action bar while following another tracker's session.
+pollendina-spectating = Spectating { $address } (read only)
+# Title of the dialog for carrying on a spectated session.
+pollendina-take-over-question = Take over the session from { $address }?
+
+# Confirmations before changing many objectives at once.
+pollendina-complete-checks-question = Complete { $count } { $count ->
+    [one] check
+   *[other] checks
+} at { $name }?
+pollendina-reset-checks-question = Reset { $count } { $count ->
+    [one] check
+   *[other] checks
+} at { $name }?
+pollendina-complete-grid-question = Complete { $count } { $count ->
+    [one] objective
+   *[other] objectives
+} in this grid?
+
+# Shown after a manual state change, followed by an undo button.
+pollendina-undo-toast = Marked { $changes } —
+
+# Seeds of a multi-seed session, numbered from 1.  The active tab is marked.
+pollendina-seed = Seed { $number }
+pollendina-seed-tab = { $active ->
+    [yes] ▸ Seed { $number }
+   *[no] Seed { $number }
+}
@@ -0,0 +1,87 @@
+// Makes fonts shipped with a module available to the text renderer by
+// family name for the life of the process.
+
+use std::path::Path;
+
+use failure::{format_err, Error};
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub(crate) fn register_font(path: &Path) -> Result<(), Error> {
+    use std::ffi::CString;
+    use std::os::raw::{c_int, c_uchar, c_void};
+    use std::os::unix::ffi::OsStrExt;
+    use std::ptr;
+
+    #[link(name = "fontconfig")]
+    extern "C" {
+        fn FcConfigAppFontAddFile(config: *mut c_void, file: *const c_uchar) -> c_int;
+    }
+
+    let file = CString::new(path.as_os_str().as_bytes())?;
+    // A null config adds the font to the current configuration which is the
+    // one pango looks fonts up in.
+    let added = unsafe { FcConfigAppFontAddFile(ptr::null_mut(), file.as_ptr() as *const c_uchar) };
+    if added == 0 {
+        return Err(format_err!("fontconfig could not load {}", path.display()));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn register_font(path: &Path) -> Result<(), Error> {
+    use std::os::raw::{c_uchar, c_void};
+    use std::os::unix::ffi::OsStrExt;
+    use std::ptr;
+
+    const CT_FONT_MANAGER_SCOPE_PROCESS: u32 = 1;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFURLCreateFromFileSystemRepresentation(
+            allocator: *const c_void,
+            buffer: *const c_uchar,
+            len: isize,
+            is_directory: c_uchar,
+        ) -> *const c_void;
+        fn CFRelease(cf: *const c_void);
+    }
+
+    #[link(name = "CoreText", kind = "framework")]
+    extern "C" {
+        fn CTFontManagerRegisterFontsForURL(
+            font_url: *const c_void,
+            scope: u32,
+            error: *mut *const c_void,
+        ) -> c_uchar;
+    }
+
+    let bytes = path.as_os_str().as_bytes();
+    unsafe {
+        let url = CFURLCreateFromFileSystemRepresentation(
+            ptr::null(),
+            bytes.as_ptr(),
+            bytes.len() as isize,
+            0,
+        );
+        if url.is_null() {
+            return Err(format_err!("could not make a url for {}", path.display()));
+        }
+        let registered =
+            CTFontManagerRegisterFontsForURL(url, CT_FONT_MANAGER_SCOPE_PROCESS, ptr::null_mut());
+        CFRelease(url);
+        if registered == 0 {
+            return Err(format_err!("CoreText could not load {}", path.display()));
+        }
+    }
+    Ok(())
+}
+
+// DirectWrite, which piet uses on Windows, only looks fonts up in the system
+// font collection so module fonts need to be installed.
+#[cfg(target_os = "windows")]
+pub(crate) fn register_font(path: &Path) -> Result<(), Error> {
+    Err(format_err!(
+        "module fonts must be installed on Windows: {}",
+        path.display()
+    ))
+}
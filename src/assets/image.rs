@@ -1,14 +1,22 @@
+use std::collections::HashMap;
 use std::error::Error;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use druid::{
-    piet::{ImageFormat, InterpolationMode},
+    piet::{Image, ImageFormat, InterpolationMode},
     Affine, PaintCtx, RenderContext, Size,
 };
-use image::{self, DynamicImage, Pixel, RgbaImage};
+use failure::format_err;
+use image::{
+    self,
+    imageops::{self, FilterType},
+    DynamicImage, GenericImageView, Pixel, RgbaImage,
+};
 use palette::{Hsva, RgbHue, Srgba};
 
 use super::AssetStore;
+use crate::engine::module::{OverlayAnchor, OverlayInfo};
 
 fn map_image_hsv<F>(img: &mut RgbaImage, f: F)
 where
@@ -52,24 +60,154 @@ fn make_completed_image(src: &DynamicImage) -> DynamicImage {
     DynamicImage::ImageRgba8(img)
 }
 
-pub(crate) fn add_image_to_cache(store: &mut AssetStore<ImageData>, id: &str, data: &[u8]) {
-    let image = image::load_from_memory(data).unwrap();
+fn make_glitchlocked_image(src: &DynamicImage) -> DynamicImage {
+    let mut img = src.clone().to_rgba8();
+    map_image_hsv(&mut img, |hsv| {
+        hsv.hue = RgbHue::from_degrees(60.0);
+        hsv.saturation = 0.8;
+        hsv.value *= 0.6;
+    });
+
+    DynamicImage::ImageRgba8(img)
+}
+
+pub(crate) fn decode_image(data: &[u8]) -> Result<DynamicImage, failure::Error> {
+    Ok(image::load_from_memory(data)?)
+}
+
+// Stands in for an image that couldn't be loaded: a grey square with a
+// magenta border so it's easy to spot.
+pub(crate) fn placeholder_image() -> DynamicImage {
+    const SIZE: u32 = 32;
+    let img = RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+        if x < 2 || y < 2 || x >= SIZE - 2 || y >= SIZE - 2 {
+            image::Rgba([255, 0, 255, 255])
+        } else {
+            image::Rgba([96, 96, 96, 255])
+        }
+    });
+    DynamicImage::ImageRgba8(img)
+}
+
+pub(crate) fn add_image_to_cache(store: &mut AssetStore<ImageData>, id: &str, image: DynamicImage) {
     store.add(&id.to_string(), ImageData::from_dynamic_image(image));
 }
 
-pub(crate) fn add_objective_to_cache(store: &mut AssetStore<ImageData>, id: &str, data: &[u8]) {
-    let image = image::load_from_memory(data).unwrap();
+// Adds the image at `path`, decoded the first time it's shown.  It can be
+// evicted again if the store goes over its budget.
+pub(crate) fn add_image_loader_to_cache(
+    store: &mut AssetStore<ImageData>,
+    id: &str,
+    path: PathBuf,
+) {
+    store.add_loader(
+        &id.to_string(),
+        Box::new(move || {
+            let image = fs::read(&path)
+                .map_err(failure::Error::from)
+                .and_then(|data| decode_image(&data))
+                .unwrap_or_else(|e| {
+                    println!("error loading image {}: {}", path.display(), e);
+                    placeholder_image()
+                });
+            let data = ImageData::from_dynamic_image(image);
+            let size = data.byte_size();
+            (data, size)
+        }),
+    );
+}
+
+pub(crate) fn add_objective_to_cache(
+    store: &mut AssetStore<ImageData>,
+    id: &str,
+    image: DynamicImage,
+) {
     let locked_image = make_locked_image(&image);
     let completed_image = make_completed_image(&image);
-    store.add(&id.to_string(), ImageData::from_dynamic_image(image));
-    store.add(
-        &format!("{}:locked", id),
-        ImageData::from_dynamic_image(locked_image),
-    );
-    store.add(
-        &format!("{}:completed", id),
-        ImageData::from_dynamic_image(completed_image),
-    );
+    let glitchlocked_image = make_glitchlocked_image(&image);
+    add_with_thumbnails(store, id.to_string(), image);
+    add_with_thumbnails(store, format!("{}:locked", id), locked_image);
+    add_with_thumbnails(store, format!("{}:completed", id), completed_image);
+    add_with_thumbnails(store, format!("{}:glitchlocked", id), glitchlocked_image);
+}
+
+// Sizes objective images are scaled down to at load time so grids of
+// large icons aren't scaled down on every paint.
+pub(crate) const THUMBNAIL_SIZES: &[u32] = &[32, 64];
+
+pub(crate) fn thumbnail_id(id: &str, size: u32) -> String {
+    format!("{}@{}", id, size)
+}
+
+// Adds `image` and a thumbnail for each of `THUMBNAIL_SIZES` smaller than
+// it.  Thumbnails fit in a square of their size.
+fn add_with_thumbnails(store: &mut AssetStore<ImageData>, id: String, image: DynamicImage) {
+    for size in THUMBNAIL_SIZES {
+        if image.width().max(image.height()) > *size {
+            let thumbnail = image.resize(*size, *size, FilterType::CatmullRom);
+            store.add(
+                &thumbnail_id(&id, *size),
+                ImageData::from_dynamic_image(thumbnail),
+            );
+        }
+    }
+    store.add(&id, ImageData::from_dynamic_image(image));
+}
+
+// Draw the layers of `info` over its base.  `images` holds the decoded
+// images it uses by id.
+pub(crate) fn compose_overlay(
+    info: &OverlayInfo,
+    images: &HashMap<String, DynamicImage>,
+) -> Result<DynamicImage, failure::Error> {
+    let get = |id: &String| {
+        images
+            .get(id)
+            .ok_or(format_err!("overlay {} uses unknown image {}", info.id, id))
+    };
+    let mut base = get(&info.base)?.to_rgba8();
+    for layer in &info.layers {
+        let mut top = get(&layer.image)?.to_rgba8();
+        if let Some(scale) = layer.scale {
+            // Keep the overlay's aspect ratio.
+            let width = ((base.width() as f64 * scale).round() as u32).max(1);
+            let height = (top.height() as u64 * width as u64 / top.width().max(1) as u64).max(1);
+            top = imageops::resize(&top, width, height as u32, FilterType::Triangle);
+        }
+        let (x, y) = anchor_position(base.dimensions(), top.dimensions(), layer.anchor);
+        imageops::overlay(&mut base, &top, x, y);
+    }
+    Ok(DynamicImage::ImageRgba8(base))
+}
+
+// Top left corner of an image of `size` placed at `anchor` in one of `base`.
+fn anchor_position(base: (u32, u32), size: (u32, u32), anchor: OverlayAnchor) -> (u32, u32) {
+    let right = base.0.saturating_sub(size.0);
+    let bottom = base.1.saturating_sub(size.1);
+    match anchor {
+        OverlayAnchor::TopLeft => (0, 0),
+        OverlayAnchor::TopRight => (right, 0),
+        OverlayAnchor::BottomLeft => (0, bottom),
+        OverlayAnchor::BottomRight => (right, bottom),
+        OverlayAnchor::Center => (right / 2, bottom / 2),
+    }
+}
+
+/// A piet image made from an `ImageData`.  Making the image uploads its
+/// pixels to the render context so widgets hold on to one between paints.
+/// Images are tied to the render context they were made with so each widget
+/// keeps its own.
+#[derive(Default)]
+pub struct PietImageCache {
+    image: Option<Image>,
+}
+
+impl PietImageCache {
+    /// Drop the cached image.  Must be called when the `ImageData` being
+    /// painted changes.
+    pub fn clear(&mut self) {
+        self.image = None;
+    }
 }
 
 /// Stored Image data.
@@ -141,30 +279,42 @@ impl ImageData {
         Ok(ImageData::from_dynamic_image(image_data))
     }
 
+    /// Get the number of bytes the decoded pixels take up.
+    pub fn byte_size(&self) -> usize {
+        self.pixels.len()
+    }
+
     /// Get the size in pixels of the contained image.
     pub fn get_size(&self) -> Size {
         Size::new(self.x_pixels as f64, self.y_pixels as f64)
     }
 
-    /// Convert ImageData into Piet draw instructions.
+    /// Convert ImageData into Piet draw instructions.  The piet image is
+    /// made once and kept in `cache`.
     pub fn to_piet(
         &self,
         offset_matrix: Affine,
         ctx: &mut PaintCtx,
         interpolation: InterpolationMode,
+        cache: &mut PietImageCache,
     ) {
         ctx.with_save(|ctx| {
             ctx.transform(offset_matrix);
             let size = self.get_size();
-            let im = ctx
-                .make_image(
-                    size.width as usize,
-                    size.height as usize,
-                    &self.pixels,
-                    self.format,
-                )
-                .unwrap();
-            ctx.draw_image(&im, size.to_rect(), interpolation);
+            if cache.image.is_none() {
+                cache.image = Some(
+                    ctx.make_image(
+                        size.width as usize,
+                        size.height as usize,
+                        &self.pixels,
+                        self.format,
+                    )
+                    .unwrap(),
+                );
+            }
+            if let Some(im) = &cache.image {
+                ctx.draw_image(im, size.to_rect(), interpolation);
+            }
         })
     }
 }
@@ -182,3 +332,87 @@ impl Default for ImageData {
         ImageData::empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn solid(width: u32, height: u32, color: [u8; 4]) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba(color)))
+    }
+
+    #[test]
+    fn objective_thumbnails() {
+        let mut store = AssetStore::new();
+        add_objective_to_cache(
+            &mut store,
+            "objective:big",
+            solid(128, 64, [255, 0, 0, 255]),
+        );
+        add_objective_to_cache(
+            &mut store,
+            "objective:small",
+            solid(48, 48, [255, 0, 0, 255]),
+        );
+
+        let thumbnail = store.get(&"objective:big:locked@32".into()).unwrap();
+        assert_eq!(thumbnail.get_size(), Size::new(32., 16.));
+        assert!(store.get(&"objective:big@64".into()).is_some());
+
+        // Only thumbnails smaller than the image are made.
+        assert!(store.get(&"objective:small@32".into()).is_some());
+        assert!(store.get(&"objective:small@64".into()).is_none());
+    }
+
+    #[test]
+    fn objective_variants() {
+        let mut store = AssetStore::new();
+        add_objective_to_cache(&mut store, "objective:sword", solid(2, 2, [255, 0, 0, 255]));
+        for postfix in &["", ":locked", ":completed", ":glitchlocked"] {
+            assert!(store.get(&format!("objective:sword{}", postfix)).is_some());
+        }
+    }
+
+    #[test]
+    fn overlays() -> Result<(), failure::Error> {
+        let mut images = HashMap::new();
+        images.insert("base".to_string(), solid(16, 16, [0, 0, 255, 255]));
+        images.insert("key".to_string(), solid(4, 2, [255, 0, 0, 255]));
+        let info: OverlayInfo = serde_json::from_str(
+            r#"{
+                "id": "keyed",
+                "base": "base",
+                "layers": [
+                    {"image": "key"},
+                    {"image": "key", "anchor": "top-left", "scale": 0.5}
+                ]
+            }"#,
+        )?;
+
+        let image = compose_overlay(&info, &images)?.to_rgba8();
+        assert_eq!(image.dimensions(), (16, 16));
+        assert_eq!(image.get_pixel(15, 15), &Rgba([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(11, 13), &Rgba([0, 0, 255, 255]));
+        // Scaled to half the base's width.
+        assert_eq!(image.get_pixel(7, 3), &Rgba([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(8, 0), &Rgba([0, 0, 255, 255]));
+
+        images.remove("key");
+        assert!(compose_overlay(&info, &images).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn anchors() {
+        let at = |anchor| anchor_position((16, 8), (4, 4), anchor);
+        assert_eq!(at(OverlayAnchor::TopRight), (12, 0));
+        assert_eq!(at(OverlayAnchor::BottomLeft), (0, 4));
+        assert_eq!(at(OverlayAnchor::Center), (6, 2));
+        // Overlays bigger than the base are placed at its top left.
+        assert_eq!(
+            anchor_position((4, 4), (8, 8), OverlayAnchor::BottomRight),
+            (0, 0)
+        );
+    }
+}
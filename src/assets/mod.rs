@@ -1,24 +1,59 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::thread_local;
 
+pub mod font;
 pub mod image;
 
-pub(crate) use self::image::{add_image_to_cache, add_objective_to_cache, ImageData};
+pub(crate) use self::font::register_font;
+pub(crate) use self::image::{
+    add_image_loader_to_cache, add_image_to_cache, add_objective_to_cache, compose_overlay,
+    decode_image, placeholder_image, thumbnail_id, ImageData, THUMBNAIL_SIZES,
+};
+
+// Memory the decoded images loaded on demand, such as maps, may use before
+// the least recently used ones are dropped.
+const IMAGE_CACHE_BUDGET: usize = 256 * 1024 * 1024;
 
 thread_local! {
-    pub(crate) static IMAGES: RefCell<AssetStore<ImageData>> = RefCell::new(AssetStore::new());
+    pub(crate) static IMAGES: RefCell<AssetStore<ImageData>> =
+        RefCell::new(AssetStore::with_budget(IMAGE_CACHE_BUDGET));
+    // Encoded (ogg or wav) sound data.
+    pub(crate) static SOUNDS: RefCell<AssetStore<Vec<u8>>> = RefCell::new(AssetStore::new());
+}
+
+// Loads an asset and returns it with the number of bytes it takes up.
+pub(crate) type AssetLoader<T> = Box<dyn Fn() -> (T, usize)>;
+
+struct CachedAsset<T> {
+    asset: Arc<T>,
+    size: usize,
+    last_used: u64,
 }
 
 pub(crate) struct AssetStore<T> {
     assets: HashMap<String, Arc<T>>,
+    // Assets that are loaded when first asked for and can be dropped again.
+    loaders: HashMap<String, AssetLoader<T>>,
+    cache: RefCell<HashMap<String, CachedAsset<T>>>,
+    // Bumped on every cache hit to order entries by use.
+    clock: Cell<u64>,
+    budget: usize,
 }
 
 impl<T> AssetStore<T> {
     pub fn new() -> AssetStore<T> {
+        Self::with_budget(usize::MAX)
+    }
+
+    pub fn with_budget(budget: usize) -> AssetStore<T> {
         AssetStore {
             assets: HashMap::new(),
+            loaders: HashMap::new(),
+            cache: RefCell::new(HashMap::new()),
+            clock: Cell::new(0),
+            budget,
         }
     }
 
@@ -26,10 +61,91 @@ impl<T> AssetStore<T> {
         self.assets.insert(key.clone(), Arc::new(asset));
     }
 
+    // Adds an asset that isn't loaded until it's used.  Widgets keep the
+    // assets they show so evicting one only frees it once it's off screen.
+    pub fn add_loader(&mut self, key: &String, loader: AssetLoader<T>) {
+        self.cache.borrow_mut().remove(key);
+        self.loaders.insert(key.clone(), loader);
+    }
+
     pub fn get(&self, key: &String) -> Option<Arc<T>> {
-        match self.assets.get(key) {
-            None => None,
-            Some(a) => Some(a.clone()),
+        if let Some(a) = self.assets.get(key) {
+            return Some(a.clone());
+        }
+        let loader = self.loaders.get(key)?;
+        let now = self.clock.get() + 1;
+        self.clock.set(now);
+
+        let mut cache = self.cache.borrow_mut();
+        if let Some(cached) = cache.get_mut(key) {
+            cached.last_used = now;
+            return Some(cached.asset.clone());
+        }
+        let (asset, size) = loader();
+        let asset = Arc::new(asset);
+        cache.insert(
+            key.clone(),
+            CachedAsset {
+                asset: asset.clone(),
+                size,
+                last_used: now,
+            },
+        );
+        Self::evict(&mut cache, self.budget, key);
+        Some(asset)
+    }
+
+    // Drops the least recently used entries until the cache fits in
+    // `budget`.  `keep` was just loaded and stays even if it's over budget
+    // on its own.
+    fn evict(cache: &mut HashMap<String, CachedAsset<T>>, budget: usize, keep: &String) {
+        let mut used: usize = cache.values().map(|c| c.size).sum();
+        while used > budget {
+            let oldest = cache
+                .iter()
+                .filter(|(k, _)| *k != keep)
+                .min_by_key(|(_, c)| c.last_used)
+                .map(|(k, _)| k.clone());
+            match oldest {
+                Some(k) => used -= cache.remove(&k).map_or(0, |c| c.size),
+                None => break,
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counting_loader(loads: &Arc<std::sync::Mutex<usize>>) -> AssetLoader<Vec<u8>> {
+        let loads = loads.clone();
+        Box::new(move || {
+            *loads.lock().unwrap() += 1;
+            (vec![0; 10], 10)
+        })
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let loads = Arc::new(std::sync::Mutex::new(0));
+        let mut store = AssetStore::with_budget(20);
+        for key in &["a", "b", "c"] {
+            store.add_loader(&key.to_string(), counting_loader(&loads));
+        }
+
+        // Loaded once and cached after that.
+        store.get(&"a".into()).unwrap();
+        store.get(&"b".into()).unwrap();
+        store.get(&"a".into()).unwrap();
+        assert_eq!(*loads.lock().unwrap(), 2);
+
+        // Loading "c" goes over budget and drops "b", which was used last
+        // the longest ago.
+        store.get(&"c".into()).unwrap();
+        store.get(&"a".into()).unwrap();
+        assert_eq!(*loads.lock().unwrap(), 3);
+        store.get(&"b".into()).unwrap();
+        assert_eq!(*loads.lock().unwrap(), 4);
+    }
+}
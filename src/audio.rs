@@ -0,0 +1,80 @@
+use std::io::Cursor;
+use std::sync::Arc;
+
+use rodio::Device;
+
+use crate::assets::SOUNDS;
+use crate::engine::{
+    Engine, ObjectiveState, Settings, StateChange, StateEvent, StateObserver, StateSource,
+};
+
+// Adapts shared sound data so that it can be fed to a decoder.
+struct SoundBuf(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for SoundBuf {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+pub(crate) struct AudioPlayer {
+    device: Option<Device>,
+}
+
+impl AudioPlayer {
+    pub fn new() -> AudioPlayer {
+        let device = rodio::default_output_device();
+        if device.is_none() {
+            println!("no audio output device found");
+        }
+        AudioPlayer { device }
+    }
+
+    // Plays the sound asset `id`.  Returns false if the module has no such
+    // sound.
+    pub fn play(&self, id: &String) -> bool {
+        let data = match SOUNDS.with(|sounds| sounds.borrow().get(id)) {
+            Some(data) => data,
+            None => return false,
+        };
+
+        if let Some(device) = &self.device {
+            match rodio::play_once(device, Cursor::new(SoundBuf(data))) {
+                Ok(sink) => sink.detach(),
+                Err(e) => println!("error playing sound {}: {}", id, e),
+            }
+        }
+        true
+    }
+
+    // Plays the cue for the most significant of `changes`.  Cues are looked
+    // up as `sound:<objective type>:<event>` then `sound:<event>` where
+    // event is `complete` or `unlocked`.
+    pub fn play_state_changes(&self, changes: &[StateChange]) {
+        let change = changes
+            .iter()
+            .filter(|c| {
+                c.new_state == ObjectiveState::Complete || c.new_state == ObjectiveState::Unlocked
+            })
+            .max_by_key(|c| c.new_state == ObjectiveState::Complete);
+
+        if let Some(change) = change {
+            let event = match change.new_state {
+                ObjectiveState::Complete => "complete",
+                _ => "unlocked",
+            };
+            if !self.play(&format!("sound:{}:{}", change.ty, event)) {
+                self.play(&format!("sound:{}", event));
+            }
+        }
+    }
+}
+
+// Plays cues for the changes the auto tracker makes unless muted.
+impl StateObserver for AudioPlayer {
+    fn state_changed(&mut self, _engine: &Engine, settings: &Settings, event: &StateEvent) {
+        if event.source == StateSource::AutoTracker && !settings.muted {
+            self.play_state_changes(&event.changes);
+        }
+    }
+}
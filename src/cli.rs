@@ -0,0 +1,295 @@
+use failure::{format_err, Error};
+use pollendina_core::module::MODULE_FILE_EXTENSIONS;
+
+// Tracker configuration given on the command line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Args {
+    // Module name (e.g. "ff4fe") or path to its manifest.
+    pub module: Option<String>,
+    // Check box params by id or name.
+    pub params: Vec<(String, bool)>,
+    // Flag string enabling the check box params it names.
+    pub flags: Option<String>,
+    // "<host>:<port>" of a tracker to watch read-only.
+    pub spectate: Option<String>,
+    // Session token from the share link of the tracker to watch.
+    pub token: Option<String>,
+    // Directory to write the JSON Schemas of module files to instead of
+    // starting the tracker.
+    pub write_schemas: Option<String>,
+}
+
+pub(crate) const USAGE: &str =
+    "usage: pollendina [--module <name|manifest>] [--flags <flags>] [--param <id>[=true|false]]... [--spectate <host>:<port> --token <token>]\n       pollendina --write-schemas <dir>\n       pollendina pollendina://open?module=<name>&flags=<flags>&param=<id>[=true|false]\n       pollendina pollendina://spectate?module=<name>&address=<host>:<port>&token=<token>";
+
+// Links like "pollendina://open?module=ff4fe&flags=Kmoon%20Nkey" are passed
+// to the tracker as its only argument by the OS.
+const URL_SCHEME: &str = "pollendina://";
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, Error> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter().peekable();
+        if let Some(url) = args.peek() {
+            if url.starts_with(URL_SCHEME) {
+                return Self::parse_url(url);
+            }
+        }
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or(format_err!("{} needs a value\n{}", name, USAGE))
+            };
+            match arg.as_str() {
+                "--module" => parsed.module = Some(value("--module")?),
+                "--flags" => parsed.flags = Some(value("--flags")?),
+                "--param" => parsed.params.push(Self::parse_param(&value("--param")?)?),
+                "--spectate" => parsed.spectate = Some(value("--spectate")?),
+                "--token" => parsed.token = Some(value("--token")?),
+                "--write-schemas" => parsed.write_schemas = Some(value("--write-schemas")?),
+                _ => return Err(format_err!("unknown argument {}\n{}", arg, USAGE)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    // Parses a `pollendina://open?...` or `pollendina://spectate?...` link.
+    // Its query takes the same keys as the command line flags.
+    fn parse_url(url: &str) -> Result<Args, Error> {
+        let mut parsed = Args::default();
+        let rest = &url[URL_SCHEME.len()..];
+        let mut parts = rest.splitn(2, '?');
+        let action = parts.next().unwrap_or_default().trim_end_matches('/');
+        if action != "open" && action != "spectate" {
+            return Err(format_err!("unknown link action {}\n{}", action, USAGE));
+        }
+
+        let query = parts.next().unwrap_or_default();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let mut kv = pair.splitn(2, '=');
+            let key = kv.next().unwrap_or_default();
+            let value = percent_decode(kv.next().unwrap_or_default())?;
+            match key {
+                "module" => parsed.module = Some(value),
+                "flags" => parsed.flags = Some(value),
+                "param" => parsed.params.push(Self::parse_param(&value)?),
+                "address" if action == "spectate" => parsed.spectate = Some(value),
+                "token" if action == "spectate" => parsed.token = Some(value),
+                _ => return Err(format_err!("unknown link key {}\n{}", key, USAGE)),
+            }
+        }
+        if action == "spectate" && (parsed.spectate.is_none() || parsed.token.is_none()) {
+            return Err(format_err!(
+                "spectate link needs an address and token\n{}",
+                USAGE
+            ));
+        }
+        Ok(parsed)
+    }
+
+    // Parses "<id>=<bool>".  A bare "<id>" is true.
+    fn parse_param(param: &str) -> Result<(String, bool), Error> {
+        let mut parts = param.splitn(2, '=');
+        let id = parts.next().unwrap_or_default().to_string();
+        let value = match parts.next() {
+            Some(v) => v
+                .parse()
+                .map_err(|_| format_err!("param {} value {} is not true or false", id, v))?,
+            None => true,
+        };
+        Ok((id, value))
+    }
+
+    // Command line arguments that parse back to these args.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut push = |name: &str, value: &Option<String>| {
+            if let Some(value) = value {
+                args.push(name.to_string());
+                args.push(value.clone());
+            }
+        };
+        push("--module", &self.module);
+        push("--flags", &self.flags);
+        push("--spectate", &self.spectate);
+        push("--token", &self.token);
+        push("--write-schemas", &self.write_schemas);
+        for (id, value) in &self.params {
+            args.push("--param".into());
+            args.push(format!("{}={}", id, value));
+        }
+        args
+    }
+
+    // Path to the module's manifest relative to the mod search paths.
+    pub fn module_path(&self) -> String {
+        match &self.module {
+            Some(m)
+                if MODULE_FILE_EXTENSIONS
+                    .iter()
+                    .any(|ext| m.ends_with(&format!(".{}", ext))) =>
+            {
+                m.clone()
+            }
+            Some(m) => format!("mods/{}/manifest.json", m),
+            None => "mods/ff4fe/manifest.json".into(),
+        }
+    }
+}
+
+// Escapes everything but unreserved characters for a link's query value.
+pub(crate) fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// Decodes `%XX` escapes and `+` for space in a link's query value.
+fn percent_decode(value: &str) -> Result<String, Error> {
+    let mut bytes = Vec::new();
+    let mut iter = value.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'%' => {
+                let hex: Vec<u8> = iter.by_ref().take(2).collect();
+                let decoded = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .filter(|_| hex.len() == 2)
+                    .ok_or(format_err!("bad escape in {}", value))?;
+                bytes.push(decoded);
+            }
+            b'+' => bytes.push(b' '),
+            b => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format_err!("{} is not utf-8", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn to_args_round_trips() {
+        let parsed = Args::parse(args(&[
+            "--module",
+            "/home/me/mods/ff4fe/manifest.json",
+            "--flags",
+            "Kmoon Nkey",
+            "--param",
+            "hard=false",
+            "--spectate",
+            "example.com:9000",
+            "--token",
+            "0123abcd",
+        ]))
+        .unwrap();
+        assert_eq!(Args::parse(parsed.to_args()).unwrap(), parsed);
+    }
+
+    #[test]
+    fn parse() -> Result<(), Error> {
+        let parsed = Args::parse(args(&[
+            "--module",
+            "ff4fe",
+            "--param",
+            "keysanity=true",
+            "--param",
+            "Nkey",
+            "--param",
+            "Kmoon=false",
+            "--flags",
+            "Kmoon Nkey",
+        ]))?;
+        assert_eq!(
+            parsed,
+            Args {
+                module: Some("ff4fe".into()),
+                params: vec![
+                    ("keysanity".into(), true),
+                    ("Nkey".into(), true),
+                    ("Kmoon".into(), false)
+                ],
+                flags: Some("Kmoon Nkey".into()),
+                spectate: None,
+                token: None,
+                write_schemas: None,
+            }
+        );
+        assert_eq!(parsed.module_path(), "mods/ff4fe/manifest.json");
+        assert_eq!(
+            Args::parse(args(&["--module", "my-mod/manifest.yaml"]))?.module_path(),
+            "my-mod/manifest.yaml"
+        );
+        assert_eq!(
+            Args::parse(args(&["--write-schemas", "schemas"]))?.write_schemas,
+            Some("schemas".into())
+        );
+        assert_eq!(Args::parse(args(&[]))?, Args::default());
+
+        assert!(Args::parse(args(&["--module"])).is_err());
+        assert!(Args::parse(args(&["--param", "Nkey=maybe"])).is_err());
+        assert!(Args::parse(args(&["--bogus"])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_url() -> Result<(), Error> {
+        let parsed = Args::parse(args(&[
+            "pollendina://open/?module=ff4fe&flags=Kmoon%20Nkey&param=Nchars%3Dfalse&param=Ksummon",
+        ]))?;
+        assert_eq!(
+            parsed,
+            Args {
+                module: Some("ff4fe".into()),
+                params: vec![("Nchars".into(), false), ("Ksummon".into(), true)],
+                flags: Some("Kmoon Nkey".into()),
+                spectate: None,
+                token: None,
+                write_schemas: None,
+            }
+        );
+        assert_eq!(
+            Args::parse(args(&["pollendina://open?flags=Kmoon+Nkey"]))?.flags,
+            Some("Kmoon Nkey".into())
+        );
+
+        assert!(Args::parse(args(&["pollendina://join?module=ff4fe"])).is_err());
+        assert!(Args::parse(args(&["pollendina://open?flags=%4"])).is_err());
+        assert!(Args::parse(args(&["pollendina://open?bogus=1"])).is_err());
+        assert!(Args::parse(args(&["pollendina://open?address=host%3A1"])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_spectate_url() -> Result<(), Error> {
+        let link = format!(
+            "pollendina://spectate?module={}&address={}&token=0123abcd",
+            percent_encode("my mods/manifest.json"),
+            percent_encode("192.168.1.2:47101")
+        );
+        let parsed = Args::parse(args(&[&link]))?;
+        assert_eq!(parsed.module, Some("my mods/manifest.json".into()));
+        assert_eq!(parsed.spectate, Some("192.168.1.2:47101".into()));
+        assert_eq!(parsed.token, Some("0123abcd".into()));
+
+        assert!(Args::parse(args(&["pollendina://spectate?module=ff4fe"])).is_err());
+        assert!(Args::parse(args(&[
+            "pollendina://spectate?module=ff4fe&address=192.168.1.2%3A47101"
+        ]))
+        .is_err());
+        assert!(Args::parse(args(&["pollendina://open?token=0123abcd"])).is_err());
+        Ok(())
+    }
+}
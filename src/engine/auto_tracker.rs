@@ -1,19 +1,46 @@
 use async_std::{prelude::*, stream::interval, task};
 use futures::{channel::mpsc, select, FutureExt, SinkExt};
-use std::collections::HashMap;
-use std::{io::Cursor, thread, time::Duration};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use std::{io::Cursor, thread};
 
-use byteorder::{LittleEndian, ReadBytesExt};
-use druid::Data;
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use druid::{Data, Lens};
 use failure::{format_err, Error};
-use rlua::{self, Function, Lua, Table, UserData, UserDataMethods};
+use pollendina_core::module::{AutoTrackBackend, ProcessInfo, WatchInfo};
+use rlua::{self, Context, FromLua, Function, Lua, Table, UserData, UserDataMethods};
+use serde_json::Value as JsonValue;
 use usb2snes::Connection;
 
+use super::dolphin::DolphinMemory;
+use super::process::ProcessMemory;
 use crate::{
     engine::{EventSink, ObjectiveState},
-    ENGINE_UPDATE_AUTO_TRACKER_STATE, ENGINE_UPDATE_STATE,
+    ENGINE_SET_HINTS, ENGINE_SET_STAGES, ENGINE_START_TIMERS, ENGINE_UPDATE_AUTO_TRACKER_STATE,
+    ENGINE_UPDATE_DEVICES, ENGINE_UPDATE_ROM_VERSION, ENGINE_UPDATE_STATE,
+    ENGINE_UPDATE_WATCH_DEBUG,
 };
 
+const USB2SNES_URL: &str = "ws://localhost:8080";
+
+// Most keys a script may keep in `storage`.
+const MAX_STORAGE_KEYS: usize = 256;
+
+// Values scripts keep across sessions with `storage.set`.  Shared with the
+// engine which saves them in the module's settings.
+pub(crate) type ScriptStorage = Arc<Mutex<HashMap<String, JsonValue>>>;
+
+// Values of the module's params keyed by both id and name: strings for text
+// boxes and booleans for check boxes.  Read by the script's `get_param()`.
+pub(crate) type ScriptParams = Arc<Mutex<HashMap<String, JsonValue>>>;
+
+// How often the tracker samples memory.  Watches with shorter intervals are
+// read every tick.
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Clone, Debug)]
 struct ObjectiveStateData(ObjectiveState);
 
@@ -23,14 +50,126 @@ impl UserData for ObjectiveStateData {
 
 #[derive(Debug)]
 struct MemWatch {
+    // Address map key the address was looked up with, if any.
+    key: Option<String>,
     address: u32,
     len: usize,
     callback_index: u32,
+    interval: Duration,
+}
+
+// What the watch debugger window shows about a watch.
+#[derive(Clone, Data, Debug, Lens, PartialEq)]
+pub struct WatchDebug {
+    // Address, length, interval and how long the last read took.
+    pub title: String,
+    // Bytes of the last read, 16 per line.
+    pub hex: Arc<Vec<String>>,
+    // Objective states the watch set on its last read.
+    pub set: String,
 }
 
 #[derive(Clone)]
 struct MemData {
     data: Vec<u8>,
+    // Byte order of multi-byte values, set by the tracker's backend.
+    big_endian: bool,
+}
+
+impl MemData {
+    fn new(data: Vec<u8>, big_endian: bool) -> MemData {
+        MemData { data, big_endian }
+    }
+
+    // The end of the `len` bytes at `offset`.  Fails rather than wrapping
+    // when a script passes offsets near `usize::MAX`.
+    fn end(offset: usize, len: usize) -> rlua::Result<usize> {
+        offset.checked_add(len).ok_or_else(|| {
+            rlua::Error::external(format_err!(
+                "offset {:#x} plus length {} overflows",
+                offset,
+                len
+            ))
+        })
+    }
+
+    // The `len` bytes at `offset` as an unsigned integer.  Reads past the
+    // end are 0.
+    fn get_uint(&self, offset: usize, len: usize, big_endian: bool) -> rlua::Result<u64> {
+        if MemData::end(offset, len)? > self.data.len() {
+            return Ok(0);
+        }
+        let mut c = Cursor::new(&self.data[offset..]);
+        let value = if big_endian {
+            c.read_uint::<BigEndian>(len)
+        } else {
+            c.read_uint::<LittleEndian>(len)
+        };
+        Ok(value.unwrap_or(0))
+    }
+
+    // Like `get_uint` but sign extended when `signed`.  `big_endian`
+    // of `None` uses the backend's byte order.
+    fn get_int(
+        &self,
+        offset: usize,
+        len: usize,
+        signed: bool,
+        big_endian: Option<bool>,
+    ) -> rlua::Result<i64> {
+        let value = self.get_uint(offset, len, big_endian.unwrap_or(self.big_endian))?;
+        if signed {
+            let shift = 64 - 8 * len;
+            Ok(((value << shift) as i64) >> shift)
+        } else {
+            Ok(value as i64)
+        }
+    }
+}
+
+// Integer getters of `MemData` as (name, length, signed, byte order).  A
+// byte order of `None` is the backend's.
+const MEM_DATA_GETTERS: &[(&str, usize, bool, Option<bool>)] = &[
+    ("get_i8", 1, true, None),
+    ("get_u16", 2, false, None),
+    ("get_i16", 2, true, None),
+    ("get_u24", 3, false, None),
+    ("get_u32", 4, false, None),
+    ("get_i32", 4, true, None),
+    ("get_u16_le", 2, false, Some(false)),
+    ("get_i16_le", 2, true, Some(false)),
+    ("get_u24_le", 3, false, Some(false)),
+    ("get_u32_le", 4, false, Some(false)),
+    ("get_i32_le", 4, true, Some(false)),
+    ("get_u16_be", 2, false, Some(true)),
+    ("get_i16_be", 2, true, Some(true)),
+    ("get_u24_be", 3, false, Some(true)),
+    ("get_u32_be", 4, false, Some(true)),
+    ("get_i32_be", 4, true, Some(true)),
+];
+
+// Where the tracker reads game memory from.
+enum MemorySource {
+    Usb2snes(Connection),
+    Dolphin(DolphinMemory),
+    Process(ProcessMemory),
+}
+
+impl MemorySource {
+    async fn read_mem(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+        match self {
+            MemorySource::Usb2snes(c) => c.read_mem(address, data).await,
+            MemorySource::Dolphin(d) => d.read_mem(address, data),
+            MemorySource::Process(p) => p.read_mem(address, data),
+        }
+    }
+
+    fn big_endian(&self) -> bool {
+        match self {
+            MemorySource::Usb2snes(_) | MemorySource::Process(_) => false,
+            MemorySource::Dolphin(_) => true,
+        }
+    }
 }
 
 impl UserData for MemData {
@@ -43,35 +182,41 @@ impl UserData for MemData {
             }
         });
 
-        methods.add_method("get_u16", |_, data, offset: usize| {
-            if offset < data.data.len() - 1 {
-                let mut c = Cursor::new(&data.data[offset..]);
-                c.read_u16::<LittleEndian>()
-                    .map_err(|e| rlua::Error::external(e))
-            } else {
-                Ok(0x0)
-            }
+        // Bytes `offset..offset + len` as text, e.g. a ROM header title.
+        // The text ends early at a NUL byte.
+        methods.add_method("get_string", |_, data, (offset, len): (usize, usize)| {
+            let start = offset.min(data.data.len());
+            let end = MemData::end(offset, len)?.min(data.data.len());
+            let text = &data.data[start..end];
+            let text = match text.iter().position(|b| *b == 0) {
+                Some(nul) => &text[..nul],
+                None => text,
+            };
+            Ok(String::from_utf8_lossy(text).into_owned())
         });
 
-        methods.add_method("get_u24", |_, data, offset: usize| {
-            if offset < data.data.len() - 2 {
-                let mut c = Cursor::new(&data.data[offset..]);
-                c.read_u24::<LittleEndian>()
-                    .map_err(|e| rlua::Error::external(e))
-            } else {
-                Ok(0x0)
-            }
+        for &(name, len, signed, big_endian) in MEM_DATA_GETTERS {
+            methods.add_method(name, move |_, data, offset: usize| {
+                data.get_int(offset, len, signed, big_endian)
+            });
+        }
+
+        // Whether bit `bit` (0 is the lowest) of the byte at `offset` is set.
+        methods.add_method("get_bit", |_, data, (offset, bit): (usize, u8)| {
+            Ok(bit < 8 && data.data.get(offset).map_or(false, |b| b & (1 << bit) != 0))
         });
 
-        methods.add_method("get_u32", |_, data, offset: usize| {
-            if offset < data.data.len() - 3 {
-                let mut c = Cursor::new(&data.data[offset..]);
-                c.read_u32::<LittleEndian>()
-                    .map_err(|e| rlua::Error::external(e))
-            } else {
-                Ok(0x0)
-            }
+        // A copy of `len` bytes from `offset`, e.g. one entry of a table.
+        methods.add_method("slice", |_, data, (offset, len): (usize, usize)| {
+            let start = offset.min(data.data.len());
+            let end = MemData::end(offset, len)?.min(data.data.len());
+            Ok(MemData::new(
+                data.data[start..end].to_vec(),
+                data.big_endian,
+            ))
         });
+
+        methods.add_method("len", |_, data, ()| Ok(data.data.len()));
     }
 }
 
@@ -79,12 +224,39 @@ pub(crate) struct AutoTracker {
     control_channel: mpsc::UnboundedReceiver<AutoTrackerCommand>,
     state: AutoTrackerState,
     lua: Lua,
-    connection: Option<Connection>,
+    backend: AutoTrackBackend,
+    // Game process read by the `Process` backend.
+    process: Option<ProcessInfo>,
+    connection: Option<MemorySource>,
+    // Preferred usb2snes device.  Empty for the first one found.
+    device: String,
+    save_file: Option<SaveFileWatch>,
+    // When each watch, by callback index, was last read.
+    last_reads: HashMap<u32, Instant>,
+    // Watches from the module manifest.  Read every tick.
+    native_watches: Vec<WatchInfo>,
+    // Whether to send `ENGINE_UPDATE_WATCH_DEBUG` after each read.
+    debug: bool,
+    // Objective states last sent to the engine.  Only changes are sent.
+    pushed: HashMap<String, ObjectiveState>,
+    // Last debug info of each watch.  Script watches are keyed by
+    // (0, callback index) and manifest watches by (1, index).
+    watch_debug: BTreeMap<(u8, u32), WatchDebug>,
+}
+
+// A save file or save state tracked in place of live memory.
+struct SaveFileWatch {
+    path: PathBuf,
+    // Modification time of the file when it was last parsed.
+    modified: Option<SystemTime>,
 }
 
 #[derive(Debug)]
 enum AutoTrackerCommand {
-    Start,
+    Start(String),
+    ListDevices,
+    WatchFile(PathBuf),
+    SetDebug(bool),
     Stop,
 }
 
@@ -94,6 +266,9 @@ pub enum AutoTrackerState {
     Connecting,
     Disconnected,
     Running,
+    WatchingFile,
+    // The script has suspended tracking, e.g. while the game is resetting.
+    Paused,
 }
 
 pub(crate) struct AutoTrackerController {
@@ -101,13 +276,34 @@ pub(crate) struct AutoTrackerController {
 }
 
 impl AutoTrackerController {
-    pub async fn start(&mut self) -> Result<(), Error> {
+    pub async fn start(&mut self, device: String) -> Result<(), Error> {
         self.control_channel
-            .send(AutoTrackerCommand::Start)
+            .send(AutoTrackerCommand::Start(device))
             .await
             .map_err(|e| format_err!("error sending start message: {}", e))
     }
 
+    pub async fn list_devices(&mut self) -> Result<(), Error> {
+        self.control_channel
+            .send(AutoTrackerCommand::ListDevices)
+            .await
+            .map_err(|e| format_err!("error sending list devices message: {}", e))
+    }
+
+    pub async fn watch_file(&mut self, path: PathBuf) -> Result<(), Error> {
+        self.control_channel
+            .send(AutoTrackerCommand::WatchFile(path))
+            .await
+            .map_err(|e| format_err!("error sending watch file message: {}", e))
+    }
+
+    pub async fn set_debug(&mut self, enabled: bool) -> Result<(), Error> {
+        self.control_channel
+            .send(AutoTrackerCommand::SetDebug(enabled))
+            .await
+            .map_err(|e| format_err!("error sending debug message: {}", e))
+    }
+
     pub async fn stop(&mut self) -> Result<(), Error> {
         self.control_channel
             .send(AutoTrackerCommand::Stop)
@@ -116,11 +312,89 @@ impl AutoTrackerController {
     }
 }
 
+// Only scalars can be stored.  Tables are rejected.
+fn lua_to_json(value: rlua::Value) -> rlua::Result<JsonValue> {
+    match value {
+        rlua::Value::Nil => Ok(JsonValue::Null),
+        rlua::Value::Boolean(b) => Ok(b.into()),
+        rlua::Value::Integer(i) => Ok(i.into()),
+        rlua::Value::Number(n) => Ok(n.into()),
+        rlua::Value::String(s) => Ok(s.to_str()?.into()),
+        _ => Err(rlua::Error::external(format_err!(
+            "storage only holds booleans, numbers and strings"
+        ))),
+    }
+}
+
+fn json_to_lua<'lua>(ctx: Context<'lua>, value: &JsonValue) -> rlua::Result<rlua::Value<'lua>> {
+    Ok(match value {
+        JsonValue::Bool(b) => rlua::Value::Boolean(*b),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => rlua::Value::Integer(i),
+            None => rlua::Value::Number(n.as_f64().unwrap_or_default()),
+        },
+        JsonValue::String(s) => rlua::Value::String(ctx.create_string(s)?),
+        _ => rlua::Value::Nil,
+    })
+}
+
+// Lines of 16 bytes in hex, each prefixed with its address.
+fn hex_dump(address: u32, data: &[u8]) -> Vec<String> {
+    data.chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{:06x}  {}", address as usize + i * 16, bytes.join(" "))
+        })
+        .collect()
+}
+
 impl AutoTracker {
     pub fn new<T: 'static + EventSink + Send>(
         script: &String,
+        libs: &HashMap<String, String>,
+        backend: AutoTrackBackend,
+        process: Option<ProcessInfo>,
+        native_watches: Vec<WatchInfo>,
+        storage: ScriptStorage,
+        params: ScriptParams,
         event_sink: T,
     ) -> Result<AutoTrackerController, Error> {
+        let lua = Self::load_script(script, libs, backend, storage, params)?;
+        let (tx, rx) = mpsc::unbounded();
+
+        let tracker = AutoTracker {
+            control_channel: rx,
+            state: AutoTrackerState::Idle,
+            lua,
+            backend,
+            process,
+            connection: None,
+            device: String::new(),
+            save_file: None,
+            last_reads: HashMap::new(),
+            native_watches,
+            pushed: HashMap::new(),
+            debug: false,
+            watch_debug: BTreeMap::new(),
+        };
+
+        tracker.start(event_sink);
+
+        Ok(AutoTrackerController {
+            control_channel: tx,
+        })
+    }
+
+    // Set up the script's API and run `script`.  `libs` are the sources the
+    // script can `require` by name.
+    fn load_script(
+        script: &String,
+        libs: &HashMap<String, String>,
+        backend: AutoTrackBackend,
+        storage: ScriptStorage,
+        params: ScriptParams,
+    ) -> Result<Lua, Error> {
         let lua = Lua::new();
 
         lua.context(|ctx| -> Result<(), Error> {
@@ -146,39 +420,215 @@ impl AutoTracker {
             let mem_watch = ctx.create_table()?;
             globals.set("__mem_watch", mem_watch)?;
 
+            // Scripts turn this off while memory can't be trusted.
+            globals.set("__tracking_enabled", true)?;
+            ctx.globals().set(
+                "set_tracking_enabled",
+                ctx.create_function(|ctx, enabled: bool| {
+                    ctx.globals().set("__tracking_enabled", enabled)
+                })?,
+            )?;
+
             ctx.globals().set(
                 "add_mem_watch",
-                ctx.create_function(|ctx, (address, len, callback): (u32, usize, Function)| {
-                    let globals = ctx.globals();
-                    let watches = globals.get::<_, Table>("__mem_watch")?;
-                    let entry = ctx.create_table()?;
-                    entry.set("address", address)?;
-                    entry.set("len", len)?;
-                    entry.set("callback", callback)?;
-                    watches.set(watches.len()? + 1, entry)?;
+                ctx.create_function(
+                    |ctx,
+                     (address, len, callback, options): (
+                        rlua::Value,
+                        usize,
+                        Function,
+                        Option<Table>,
+                    )| {
+                        // Addresses are numbers or keys of the bound
+                        // address map.
+                        match address {
+                            rlua::Value::Integer(_) | rlua::Value::String(_) => (),
+                            _ => {
+                                return Err(rlua::Error::RuntimeError(
+                                    "add_mem_watch address must be a number or address map key"
+                                        .into(),
+                                ))
+                            }
+                        }
+                        let globals = ctx.globals();
+                        let watches = globals.get::<_, Table>("__mem_watch")?;
+                        let entry = ctx.create_table()?;
+                        entry.set("address", address)?;
+                        entry.set("len", len)?;
+                        entry.set("callback", callback)?;
+                        if let Some(options) = options {
+                            entry.set(
+                                "interval_ms",
+                                options.get::<_, Option<u64>>("interval_ms")?,
+                            )?;
+                        }
+                        watches.set(watches.len()? + 1, entry)?;
 
+                        Ok(())
+                    },
+                )?,
+            )?;
+
+            // `storage.set(key, value)` and `storage.get(key)`.  Setting nil
+            // removes the key.
+            let storage_table = ctx.create_table()?;
+            let set_storage = storage.clone();
+            storage_table.set(
+                "set",
+                ctx.create_function(move |_, (key, value): (String, rlua::Value)| {
+                    let value = lua_to_json(value)?;
+                    let mut storage = set_storage.lock().unwrap();
+                    if value.is_null() {
+                        storage.remove(&key);
+                    } else if storage.len() >= MAX_STORAGE_KEYS && !storage.contains_key(&key) {
+                        return Err(rlua::Error::external(format_err!(
+                            "storage is limited to {} keys",
+                            MAX_STORAGE_KEYS
+                        )));
+                    } else {
+                        storage.insert(key, value);
+                    }
                     Ok(())
                 })?,
             )?;
+            let get_storage = storage.clone();
+            storage_table.set(
+                "get",
+                ctx.create_function(move |ctx, key: String| {
+                    match get_storage.lock().unwrap().get(&key) {
+                        Some(value) => json_to_lua(ctx, value),
+                        None => Ok(rlua::Value::Nil),
+                    }
+                })?,
+            )?;
+            globals.set("storage", storage_table)?;
+
+            // `get_param(key)` returns the value of the param with the id or
+            // name `key`, or nil if there is none.
+            globals.set(
+                "get_param",
+                ctx.create_function(move |ctx, key: String| {
+                    match params.lock().unwrap().get(&key) {
+                        Some(value) => json_to_lua(ctx, value),
+                        None => Ok(rlua::Value::Nil),
+                    }
+                })?,
+            )?;
+
+            // Address maps keyed by ROM version.  The one matching the
+            // version `detect_version` returns is bound to `address_map`
+            // on connect.
+            globals.set("__address_maps", ctx.create_table()?)?;
+            ctx.globals().set(
+                "add_address_map",
+                ctx.create_function(|ctx, (version, map): (String, Table)| {
+                    ctx.globals()
+                        .get::<_, Table>("__address_maps")?
+                        .set(version, map)
+                })?,
+            )?;
+            // Where `detect_version` reads the ROM header from.  Defaults to
+            // the LoROM header, or the disc header Dolphin copies to the
+            // start of RAM.  Scripts may change these.
+            // PC games have no header so scripts need to set these.
+            let (header_address, header_len) = match backend {
+                AutoTrackBackend::Usb2snes => (0x7fc0, 0x40),
+                AutoTrackBackend::Dolphin => (0x8000_0000u32, 0x20),
+                AutoTrackBackend::Process => (0, 0),
+            };
+            globals.set("ROM_HEADER_ADDRESS", header_address)?;
+            globals.set("ROM_HEADER_LEN", header_len)?;
+
+            // Replaces Lua's own `require` so scripts can only load the
+            // module's libs.  Each runs once and its result is shared.
+            globals.set("__required", ctx.create_table()?)?;
+            let libs = libs.clone();
+            globals.set(
+                "require",
+                ctx.create_function(move |ctx, name: String| {
+                    let required: Table = ctx.globals().get("__required")?;
+                    match required.get::<_, rlua::Value>(name.as_str())? {
+                        rlua::Value::Nil => (),
+                        value => return Ok(value),
+                    }
+                    let source = libs.get(&name).ok_or_else(|| {
+                        rlua::Error::RuntimeError(format!("no auto tracker lib {}", name))
+                    })?;
+                    let value: rlua::Value =
+                        ctx.load(source).set_name(&name)?.call(name.clone())?;
+                    // Like Lua's `require`, libs that return nothing are
+                    // recorded as loaded.
+                    let value = match value {
+                        rlua::Value::Nil => rlua::Value::Boolean(true),
+                        v => v,
+                    };
+                    required.set(name.as_str(), value.clone())?;
+                    Ok(value)
+                })?,
+            )?;
 
             ctx.load(&script).set_name("auto_tracker")?.exec()?;
             Ok(())
         })?;
 
-        let (tx, rx) = mpsc::unbounded();
+        Ok(lua)
+    }
 
-        let tracker = AutoTracker {
-            control_channel: rx,
-            state: AutoTrackerState::Idle,
-            lua,
-            connection: None,
-        };
+    // Call the script's `detect_version` with `header` and bind the address
+    // map of the version it returns.  Returns the version, or "unknown".
+    fn bind_rom_version(lua: &Lua, header: MemData) -> Result<String, Error> {
+        lua.context(|ctx| -> Result<String, Error> {
+            let globals = ctx.globals();
+            let detect = globals.get::<_, Function>("detect_version")?;
+            let version = detect.call::<_, Option<String>>(header)?;
+            let map = match &version {
+                Some(v) => {
+                    let map = globals
+                        .get::<_, Table>("__address_maps")?
+                        .get::<_, Option<Table>>(v.as_str())?;
+                    if map.is_none() {
+                        println!("No address map for ROM version {}.", v);
+                    }
+                    map
+                }
+                None => None,
+            };
+            globals.set("address_map", map)?;
+            Ok(version.unwrap_or_else(|| "unknown".into()))
+        })
+    }
 
-        tracker.start(event_sink);
+    // Read the ROM header and bind the address map for its version.  Does
+    // nothing for scripts without `detect_version`.
+    async fn detect_rom_version<T: EventSink>(
+        &mut self,
+        c: &mut MemorySource,
+        sink: &T,
+    ) -> Result<(), Error> {
+        let header = self.lua.context(|ctx| -> Result<_, Error> {
+            let globals = ctx.globals();
+            if globals
+                .get::<_, Option<Function>>("detect_version")?
+                .is_none()
+            {
+                return Ok(None);
+            }
+            Ok(Some((
+                globals.get::<_, u32>("ROM_HEADER_ADDRESS")?,
+                globals.get::<_, usize>("ROM_HEADER_LEN")?,
+            )))
+        })?;
+        let (address, len) = match header {
+            Some(header) => header,
+            None => return Ok(()),
+        };
 
-        Ok(AutoTrackerController {
-            control_channel: tx,
-        })
+        let mut data = vec![0u8; len];
+        c.read_mem(address, &mut data).await?;
+        let version = Self::bind_rom_version(&self.lua, MemData::new(data, c.big_endian()))?;
+        println!("Detected ROM version {}.", version);
+        sink.submit_command(ENGINE_UPDATE_ROM_VERSION, version, None)
+            .map_err(|e| format_err!("Failed to send ROM version: {}", e))
     }
 
     async fn sample<T: EventSink>(&mut self, sink: &T) -> Result<(), Error> {
@@ -187,60 +637,321 @@ impl AutoTracker {
                 let mut watches = Vec::new();
                 let globals = ctx.globals();
                 let watches_table = globals.get::<_, Table>("__mem_watch")?;
+                let address_map = globals.get::<_, Option<Table>>("address_map")?;
                 for pair in watches_table.pairs::<u32, Table>() {
                     let (index, table) = pair?;
-                    let address = table.get::<_, u32>("address")?;
+                    let (key, address) = match table.get::<_, rlua::Value>("address")? {
+                        rlua::Value::String(key) => {
+                            // Watches on keys missing from the address map
+                            // (or with no map bound) are skipped.
+                            let address = match &address_map {
+                                Some(map) => match map.get::<_, Option<u32>>(key.clone())? {
+                                    Some(address) => address,
+                                    None => continue,
+                                },
+                                None => continue,
+                            };
+                            (Some(key.to_str()?.to_string()), address)
+                        }
+                        address => (None, u32::from_lua(address, ctx)?),
+                    };
                     let len = table.get::<_, usize>("len")?;
+                    let interval_ms = table.get::<_, Option<u64>>("interval_ms")?;
                     watches.push(MemWatch {
+                        key,
                         address,
                         len,
                         callback_index: index,
+                        interval: Duration::from_millis(interval_ms.unwrap_or(0)),
                     });
                 }
                 Ok(watches)
             })?;
 
+            // Only read watches whose interval has passed.  Half a tick of
+            // slack keeps timer jitter from skipping a whole tick.
+            let now = Instant::now();
+            let last_reads = &mut self.last_reads;
+            let watches: Vec<MemWatch> = watches
+                .into_iter()
+                .filter(|w| match last_reads.get(&w.callback_index) {
+                    Some(last) => now.duration_since(*last) + TICK_INTERVAL / 2 >= w.interval,
+                    None => true,
+                })
+                .collect();
+            if watches.is_empty() && self.native_watches.is_empty() {
+                return Ok(());
+            }
+            for watch in &watches {
+                last_reads.insert(watch.callback_index, now);
+            }
+
             let mut bufs = Vec::new();
+            let mut read_times = Vec::new();
             for watch in &watches {
                 let mut buf = vec![0u8; watch.len as usize];
+                let start = Instant::now();
                 c.read_mem(watch.address, &mut buf).await?;
-                bufs.push(MemData { data: buf });
+                read_times.push(start.elapsed());
+                bufs.push(MemData::new(buf, c.big_endian()));
             }
 
-            let mut updates = HashMap::new();
-
-            self.lua.context(|ctx| -> Result<(), Error> {
-                let globals = ctx.globals();
-                let watches_table = globals.get::<_, Table>("__mem_watch")?;
-
-                // updates is protected by this scope.
-                ctx.scope(|scope| -> Result<(), Error> {
-                    ctx.globals().set(
-                        "set_objective_state",
-                        scope.create_function_mut(
-                            |_, (id, state): (String, ObjectiveStateData)| {
-                                updates.insert(id, state.0);
-                                Ok(())
+            // Unmatched manifest watches leave their objective alone.  When
+            // several watches on one objective match, the last one wins.
+            let mut native_updates = HashMap::new();
+            for (i, watch) in self.native_watches.iter().enumerate() {
+                let mut buf = vec![0u8; watch.len];
+                let start = Instant::now();
+                c.read_mem(watch.address, &mut buf).await?;
+                let read_time = start.elapsed();
+                let matched = watch.matches(&buf, c.big_endian());
+                if matched {
+                    native_updates.insert(watch.objective.clone(), watch.state);
+                }
+                if self.debug {
+                    self.watch_debug.insert(
+                        (1, i as u32),
+                        WatchDebug {
+                            title: format!(
+                                "manifest {:#x}, {} bytes, read in {:.1} ms",
+                                watch.address,
+                                watch.len,
+                                read_time.as_secs_f64() * 1000.0
+                            ),
+                            hex: Arc::new(hex_dump(watch.address, &buf)),
+                            set: if matched {
+                                format!("{}: {:?}", watch.objective, watch.state)
+                            } else {
+                                String::new()
                             },
-                        )?,
-                    )?;
+                        },
+                    );
+                }
+            }
 
+            let mut set_by = HashMap::new();
+            let enabled = Self::run_callbacks(
+                &self.lua,
+                self.connection.as_mut(),
+                &mut self.pushed,
+                sink,
+                native_updates,
+                Some(&mut set_by),
+                |ctx| {
+                    let globals = ctx.globals();
+                    let watches_table = globals.get::<_, Table>("__mem_watch")?;
                     for (i, watch) in watches.iter().enumerate() {
                         let buf = &bufs[i];
                         let table = watches_table.get::<_, Table>(watch.callback_index)?;
                         let callback = table.get::<_, Function>("callback")?;
+                        globals.set("__current_watch", watch.callback_index)?;
                         callback.call::<_, ()>(buf.clone())?;
                     }
+                    globals.set("__current_watch", rlua::Value::Nil)?;
                     Ok(())
-                })?;
+                },
+            )?;
+
+            if self.debug {
+                for (i, watch) in watches.iter().enumerate() {
+                    let name = match &watch.key {
+                        Some(key) => format!("{} ({:#x})", key, watch.address),
+                        None => format!("{:#x}", watch.address),
+                    };
+                    let set: Vec<String> = set_by
+                        .remove(&watch.callback_index)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|(id, state)| format!("{}: {:?}", id, state))
+                        .collect();
+                    self.watch_debug.insert(
+                        (0, watch.callback_index),
+                        WatchDebug {
+                            title: format!(
+                                "{}, {} bytes, every {} ms, read in {:.1} ms",
+                                name,
+                                watch.len,
+                                watch.interval.max(TICK_INTERVAL).as_millis(),
+                                read_times[i].as_secs_f64() * 1000.0
+                            ),
+                            hex: Arc::new(hex_dump(watch.address, &bufs[i].data)),
+                            set: set.join(", "),
+                        },
+                    );
+                }
+                let debug = self.watch_debug.values().cloned().collect::<Vec<_>>();
+                sink.submit_command(ENGINE_UPDATE_WATCH_DEBUG, debug, None)
+                    .map_err(|e| format_err!("Failed to send watch debug info: {}", e))?;
+            }
+            self.set_paused(sink, !enabled)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Run the script's `parse_save` callback on the watched save file if it
+    // has changed since it was last parsed.
+    fn check_save_file<T: EventSink>(&mut self, sink: &T) -> Result<(), Error> {
+        let watch = match self.save_file.as_mut() {
+            Some(watch) => watch,
+            None => return Ok(()),
+        };
+        let modified = fs::metadata(&watch.path)?.modified()?;
+        if watch.modified == Some(modified) {
+            return Ok(());
+        }
+        watch.modified = Some(modified);
+        let data = MemData::new(fs::read(&watch.path)?, self.backend.big_endian());
+
+        let enabled = Self::run_callbacks(
+            &self.lua,
+            None,
+            &mut self.pushed,
+            sink,
+            HashMap::new(),
+            None,
+            |ctx| {
+                let parse_save = ctx
+                    .globals()
+                    .get::<_, Option<Function>>("parse_save")?
+                    .ok_or(format_err!(
+                        "auto tracker script has no parse_save function"
+                    ))?;
+                parse_save.call::<_, ()>(data)?;
                 Ok(())
+            },
+        )?;
+        self.set_paused(sink, !enabled)
+    }
+
+    // Switch between `Paused` and the active tracking state.
+    fn set_paused<T: EventSink>(&mut self, sink: &T, paused: bool) -> Result<(), Error> {
+        let state = if paused {
+            AutoTrackerState::Paused
+        } else if self.save_file.is_some() {
+            AutoTrackerState::WatchingFile
+        } else {
+            AutoTrackerState::Running
+        };
+        if state != self.state {
+            self.update_state(sink, state)?;
+        }
+        Ok(())
+    }
+
+    // Call `f` with `set_objective_state`, `set_node_stage`, `set_hint`,
+    // `start_node_timer` and `read_mem_now` available to the script and send the results to the engine.
+    // `read_mem_now` reads from `connection` and fails without one.  Results
+    // are dropped while the script has tracking disabled.  States the
+    // script sets override those in `updates`.  States set while the global
+    // `__current_watch` holds a callback index are recorded in `set_by`.
+    // Only states that differ from `pushed` are sent.  Returns whether
+    // tracking is enabled.
+    fn run_callbacks<T: EventSink>(
+        lua: &Lua,
+        mut connection: Option<&mut MemorySource>,
+        pushed: &mut HashMap<String, ObjectiveState>,
+        sink: &T,
+        mut updates: HashMap<String, ObjectiveState>,
+        mut set_by: Option<&mut HashMap<u32, Vec<(String, ObjectiveState)>>>,
+        f: impl FnOnce(rlua::Context) -> Result<(), Error>,
+    ) -> Result<bool, Error> {
+        // Scouted items keyed by location.
+        let mut hints = HashMap::new();
+        // Timer lengths in seconds and the states they set, by objective.
+        let mut timers = HashMap::new();
+        // Stages of staged objectives.
+        let mut stages = HashMap::new();
+
+        let enabled = lua.context(|ctx| -> Result<bool, Error> {
+            // updates is protected by this scope.
+            ctx.scope(|scope| -> Result<(), Error> {
+                ctx.globals().set(
+                    "set_objective_state",
+                    scope.create_function_mut(
+                        |ctx, (id, state): (String, ObjectiveStateData)| {
+                            if let Some(set_by) = set_by.as_mut() {
+                                if let Some(watch) =
+                                    ctx.globals().get::<_, Option<u32>>("__current_watch")?
+                                {
+                                    set_by
+                                        .entry(watch)
+                                        .or_insert_with(Vec::new)
+                                        .push((id.clone(), state.0));
+                                }
+                            }
+                            updates.insert(id, state.0);
+                            Ok(())
+                        },
+                    )?,
+                )?;
+                ctx.globals().set(
+                    "set_hint",
+                    scope.create_function_mut(|_, (location, item): (String, String)| {
+                        hints.insert(location, item);
+                        Ok(())
+                    })?,
+                )?;
+                ctx.globals().set(
+                    "set_node_stage",
+                    scope.create_function_mut(|_, (id, stage): (String, u32)| {
+                        stages.insert(id, stage);
+                        Ok(())
+                    })?,
+                )?;
+                ctx.globals().set(
+                    "start_node_timer",
+                    scope.create_function_mut(
+                        |_, (id, secs, state): (String, f64, Option<ObjectiveStateData>)| {
+                            let state = state.map_or(ObjectiveState::Unlocked, |s| s.0);
+                            timers.insert(id, (secs, state));
+                            Ok(())
+                        },
+                    )?,
+                )?;
+                ctx.globals().set(
+                    "read_mem_now",
+                    scope.create_function_mut(|_, (address, len): (u32, usize)| {
+                        let c = connection.as_mut().ok_or(rlua::Error::RuntimeError(
+                            "read_mem_now needs a usb2snes connection".into(),
+                        ))?;
+                        let mut data = vec![0u8; len];
+                        task::block_on(c.read_mem(address, &mut data))
+                            .map_err(|e| rlua::Error::RuntimeError(e.to_string()))?;
+                        Ok(MemData::new(data, c.big_endian()))
+                    })?,
+                )?;
+
+                f(ctx)
             })?;
+            Ok(ctx.globals().get::<_, bool>("__tracking_enabled")?)
+        })?;
+        if !enabled {
+            return Ok(false);
+        }
 
+        if !hints.is_empty() {
+            sink.submit_command(ENGINE_SET_HINTS, hints, None)
+                .map_err(|e| format_err!("Failed to send command: {}", e))?;
+        }
+        if !stages.is_empty() {
+            sink.submit_command(ENGINE_SET_STAGES, stages, None)
+                .map_err(|e| format_err!("Failed to send command: {}", e))?;
+        }
+        if !timers.is_empty() {
+            sink.submit_command(ENGINE_START_TIMERS, timers, None)
+                .map_err(|e| format_err!("Failed to send command: {}", e))?;
+        }
+
+        // Most ticks change nothing.  Skip them rather than make the engine
+        // update the display.
+        updates.retain(|id, state| pushed.get(id) != Some(state));
+        if !updates.is_empty() {
+            pushed.extend(updates.iter().map(|(id, state)| (id.clone(), *state)));
             sink.submit_command(ENGINE_UPDATE_STATE, updates, None)
-                .map_err(|e| format_err!("Failed to send command: {}", e))
-        } else {
-            Ok(())
+                .map_err(|e| format_err!("Failed to send command: {}", e))?;
         }
+        Ok(true)
     }
 
     fn update_state<T: EventSink>(
@@ -256,21 +967,53 @@ impl AutoTracker {
 
     async fn connect_internal<T: EventSink>(&mut self, sink: &T) -> Result<(), Error> {
         self.update_state(sink, AutoTrackerState::Connecting)?;
-        let mut c = Connection::new("ws://localhost:8080").await?;
-        let devs = c.get_device_list().await?;
-        if devs.len() == 0 {
-            return Err(format_err!("No devices found"));
-        }
-        let dev = devs[0].to_string();
-        println!("Attaching to {}.", dev);
-        c.attach(&dev).await?;
+        let mut c = match self.backend {
+            AutoTrackBackend::Usb2snes => MemorySource::Usb2snes(self.connect_usb2snes().await?),
+            AutoTrackBackend::Dolphin => {
+                println!("Attaching to Dolphin {}.", self.device);
+                MemorySource::Dolphin(DolphinMemory::attach(&self.device)?)
+            }
+            AutoTrackBackend::Process => {
+                let process = self
+                    .process
+                    .as_ref()
+                    .ok_or(format_err!("Module has no process to track"))?;
+                println!("Attaching to {}.", process.name);
+                MemorySource::Process(ProcessMemory::attach(process)?)
+            }
+        };
+        self.detect_rom_version(&mut c, sink).await?;
 
         self.update_state(sink, AutoTrackerState::Running)?;
 
         self.connection = Some(c);
+        self.last_reads.clear();
+        // Send every state again in case the engine's changed while
+        // disconnected.
+        self.pushed.clear();
         Ok(())
     }
 
+    async fn connect_usb2snes(&self) -> Result<Connection, Error> {
+        let mut c = Connection::new(USB2SNES_URL).await?;
+        let devs = c.get_device_list().await?;
+        let dev = if self.device.is_empty() {
+            devs.first()
+                .ok_or(format_err!("No devices found"))?
+                .to_string()
+        } else {
+            // Keep waiting for the preferred device rather than attaching
+            // to another one.
+            devs.iter()
+                .find(|d| **d == self.device)
+                .ok_or(format_err!("Device {} not found", self.device))?
+                .to_string()
+        };
+        println!("Attaching to {}.", dev);
+        c.attach(&dev).await?;
+        Ok(c)
+    }
+
     async fn connect<T: EventSink>(&mut self, sink: &T) -> Result<(), Error> {
         let res = self.connect_internal(sink).await;
 
@@ -286,13 +1029,46 @@ impl AutoTracker {
         cmd: &AutoTrackerCommand,
     ) -> Result<(), Error> {
         match cmd {
-            AutoTrackerCommand::Start => {
+            AutoTrackerCommand::Start(device) => {
+                self.device = device.clone();
                 if let Err(e) = self.connect(sink).await {
                     println!("Error connecting: {}", e);
                 };
             }
+            AutoTrackerCommand::ListDevices => {
+                let devs = match (self.backend, self.connection.as_mut()) {
+                    (AutoTrackBackend::Dolphin, _) => DolphinMemory::list()?,
+                    // The module names the process to read.
+                    (AutoTrackBackend::Process, _) => Vec::new(),
+                    (_, Some(MemorySource::Usb2snes(c))) => c.get_device_list().await?,
+                    _ => {
+                        let mut c = Connection::new(USB2SNES_URL).await?;
+                        c.get_device_list().await?
+                    }
+                };
+                let devs = devs.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+                sink.submit_command(ENGINE_UPDATE_DEVICES, devs, None)
+                    .map_err(|e| format_err!("Failed to send devices: {}", e))?;
+            }
+            AutoTrackerCommand::WatchFile(path) => {
+                self.connection = None;
+                self.pushed.clear();
+                self.save_file = Some(SaveFileWatch {
+                    path: path.clone(),
+                    modified: None,
+                });
+                self.update_state(sink, AutoTrackerState::WatchingFile)?;
+            }
+            AutoTrackerCommand::SetDebug(enabled) => {
+                self.debug = *enabled;
+                self.watch_debug.clear();
+            }
             AutoTrackerCommand::Stop => {
                 self.connection = None;
+                self.pushed.clear();
+                self.save_file = None;
+                sink.submit_command(ENGINE_UPDATE_ROM_VERSION, String::new(), None)
+                    .map_err(|e| format_err!("Failed to send ROM version: {}", e))?;
                 self.update_state(sink, AutoTrackerState::Idle)?;
             }
         }
@@ -302,6 +1078,14 @@ impl AutoTracker {
     async fn handle_tick<T: EventSink>(&mut self, sink: &T) -> Result<(), Error> {
         match self.state {
             AutoTrackerState::Running => self.sample(sink).await?,
+            AutoTrackerState::Paused if self.save_file.is_none() => self.sample(sink).await?,
+            AutoTrackerState::WatchingFile | AutoTrackerState::Paused => {
+                // The file may be missing or half written while the emulator
+                // saves.  Try again next tick rather than disconnecting.
+                if let Err(e) = self.check_save_file(sink) {
+                    println!("Error reading save file: {}", e);
+                }
+            }
             AutoTrackerState::Disconnected => {
                 if let Err(e) = self.connect(sink).await {
                     println!("Error re-connecting: {}", e);
@@ -314,7 +1098,7 @@ impl AutoTracker {
     }
 
     async fn auto_track<T: EventSink>(&mut self, sink: T) -> Result<(), Error> {
-        let mut ticker = interval(Duration::from_millis(500));
+        let mut ticker = interval(TICK_INTERVAL);
         loop {
             select! {
                 cmd = self.control_channel.next().fuse() => {
@@ -343,3 +1127,217 @@ impl AutoTracker {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    use druid::{ExtEventError, Selector, Target};
+
+    // Counts the `ENGINE_UPDATE_STATE` commands it is sent.
+    #[derive(Default)]
+    struct CountingEventSink {
+        state_updates: Cell<usize>,
+    }
+
+    impl EventSink for CountingEventSink {
+        fn submit_command<T: 'static + Send + Sync>(
+            &self,
+            sel: Selector<T>,
+            _obj: impl Into<Box<T>>,
+            _target: impl Into<Option<Target>>,
+        ) -> Result<(), ExtEventError> {
+            if sel.to_string() == ENGINE_UPDATE_STATE.to_string() {
+                self.state_updates.set(self.state_updates.get() + 1);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn only_changed_states_are_sent() -> Result<(), Error> {
+        let lua = AutoTracker::load_script(
+            &r#"
+            function track(state)
+                set_objective_state("key", state)
+            end
+            "#
+            .to_string(),
+            &HashMap::new(),
+            AutoTrackBackend::Usb2snes,
+            ScriptStorage::default(),
+            ScriptParams::default(),
+        )?;
+        let sink = CountingEventSink::default();
+        let mut pushed = HashMap::new();
+        let mut run = |state: &str| {
+            AutoTracker::run_callbacks(
+                &lua,
+                None,
+                &mut pushed,
+                &sink,
+                HashMap::new(),
+                None,
+                |ctx| {
+                    let track = ctx.globals().get::<_, Function>("track")?;
+                    let state = ctx.globals().get::<_, ObjectiveStateData>(state)?;
+                    track.call::<_, ()>(state)?;
+                    Ok(())
+                },
+            )
+        };
+
+        run("OBJECTIVE_UNLOCKED")?;
+        run("OBJECTIVE_UNLOCKED")?;
+        assert_eq!(sink.state_updates.get(), 1);
+        run("OBJECTIVE_COMPLETE")?;
+        assert_eq!(sink.state_updates.get(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn mem_data_byte_order() -> Result<(), Error> {
+        let data = vec![0x12, 0x34, 0x56, 0x78];
+        let little = MemData::new(data.clone(), false);
+        assert_eq!(little.get_int(0, 2, false, None)?, 0x3412);
+        assert_eq!(little.get_int(1, 3, false, None)?, 0x78_5634);
+        let big = MemData::new(data, true);
+        assert_eq!(big.get_int(0, 4, false, None)?, 0x1234_5678);
+        assert_eq!(big.get_int(2, 2, false, None)?, 0x5678);
+        assert_eq!(big.get_int(3, 2, false, None)?, 0);
+        assert!(big.get_int(usize::MAX, 2, false, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn mem_data_getters() -> Result<(), Error> {
+        let lua = Lua::new();
+        lua.context(|ctx| -> Result<(), Error> {
+            let data = vec![0xfe, 0xff, 0x12, 0x34, 0x81, b'h', b'i', 0, b'x'];
+            ctx.globals()
+                .set("little", MemData::new(data.clone(), false))?;
+            ctx.globals().set("big", MemData::new(data, true))?;
+            let eval = |expr: &str| ctx.load(expr).eval::<i64>();
+            assert_eq!(eval("little:get_i8(0)")?, -2);
+            assert_eq!(eval("little:get_u16(0)")?, 0xfffe);
+            assert_eq!(eval("little:get_i16(0)")?, -2);
+            assert_eq!(eval("big:get_i16(0)")?, -257);
+            assert_eq!(eval("big:get_u16(2)")?, 0x1234);
+            assert_eq!(eval("big:get_u16_le(2)")?, 0x3412);
+            assert_eq!(eval("little:get_u16_be(2)")?, 0x1234);
+            assert_eq!(eval("little:get_i32_be(1)")?, -0xedcb7f);
+            assert_eq!(eval("little:get_u32(7)")?, 0);
+            assert_eq!(eval("little:slice(2, 2):get_u16_be(0)")?, 0x1234);
+            assert_eq!(eval("little:slice(8, 4):len()")?, 1);
+            assert!(ctx.load("little:get_bit(4, 7)").eval::<bool>()?);
+            assert!(!ctx.load("little:get_bit(4, 1)").eval::<bool>()?);
+            assert!(!ctx.load("little:get_bit(40, 0)").eval::<bool>()?);
+            assert_eq!(ctx.load("little:get_string(5, 4)").eval::<String>()?, "hi");
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn hex_dump_lines() {
+        let data: Vec<u8> = (0..20).collect();
+        assert_eq!(
+            hex_dump(0x7e1500, &data),
+            vec![
+                "7e1500  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f".to_string(),
+                "7e1510  10 11 12 13".to_string(),
+            ]
+        );
+        assert!(hex_dump(0, &[]).is_empty());
+    }
+
+    #[test]
+    fn rom_version_binds_address_map() -> Result<(), Error> {
+        let lua = AutoTracker::load_script(
+            &r#"
+            add_address_map("1.0", {items = 0x10})
+            add_address_map("1.1", {items = 0x20})
+            function detect_version(header)
+                local title = header:get_string(0, 4)
+                if title == "GAME" then
+                    return "1.1"
+                elseif title == "OLD!" then
+                    return "1.0"
+                end
+                return nil
+            end
+            "#
+            .to_string(),
+            &HashMap::new(),
+            AutoTrackBackend::Usb2snes,
+            ScriptStorage::default(),
+            ScriptParams::default(),
+        )?;
+
+        assert_eq!(
+            AutoTracker::bind_rom_version(&lua, MemData::new(b"GAME".to_vec(), false))?,
+            "1.1"
+        );
+        let items = lua.context(|ctx| {
+            ctx.globals()
+                .get::<_, Table>("address_map")?
+                .get::<_, u32>("items")
+        })?;
+        assert_eq!(items, 0x20);
+
+        assert_eq!(
+            AutoTracker::bind_rom_version(&lua, MemData::new(b"????".to_vec(), false))?,
+            "unknown"
+        );
+        let bound = lua.context(|ctx| {
+            ctx.globals()
+                .get::<_, Option<Table>>("address_map")
+                .map(|t| t.is_some())
+        })?;
+        assert!(!bound);
+        Ok(())
+    }
+
+    #[test]
+    fn require_libs() -> Result<(), Error> {
+        let mut libs = HashMap::new();
+        libs.insert(
+            "lib/util".to_string(),
+            r#"
+            loads = (loads or 0) + 1
+            return { double = function(x) return x * 2 end }
+            "#
+            .to_string(),
+        );
+        let lua = AutoTracker::load_script(
+            &r#"
+            local util = require("lib/util")
+            doubled = util.double(21)
+            same = require("lib/util") == util
+            missing = pcall(require, "io")
+            "#
+            .to_string(),
+            &libs,
+            AutoTrackBackend::Usb2snes,
+            ScriptStorage::default(),
+            ScriptParams::default(),
+        )?;
+
+        let (doubled, same, loads, missing) = lua.context(|ctx| {
+            let globals = ctx.globals();
+            Ok::<_, rlua::Error>((
+                globals.get::<_, i64>("doubled")?,
+                globals.get::<_, bool>("same")?,
+                globals.get::<_, i64>("loads")?,
+                globals.get::<_, bool>("missing")?,
+            ))
+        })?;
+        assert_eq!(doubled, 42);
+        // Required once and shared after that.
+        assert!(same);
+        assert_eq!(loads, 1);
+        // Only the module's libs can be required.
+        assert!(!missing);
+        Ok(())
+    }
+}
@@ -1,13 +1,16 @@
 use std::sync::Arc;
 
-use super::AutoTrackerState;
-use druid::{Data, Lens, WindowId};
+use super::{AutoTrackerState, ObjectiveState, WatchDebug};
+use crate::widget::dyn_flex::{DynFlexItem, DynFlexParams};
+use druid::{Color, Data, Lens, WindowId};
+use serde::{Deserialize, Serialize};
 
 mod view;
 pub use view::{
-    CornerRadius, DisplayChild, DisplayView, DisplayViewCount, DisplayViewData, DisplayViewFlex,
-    DisplayViewGrid, DisplayViewMap, DisplayViewSpacer, DisplayViewTabChild, DisplayViewTabs,
-    Inset, LayoutParams, MapInfo, MapObjective, ThemeColor,
+    AssignmentSlot, DisplayChild, DisplayView, DisplayViewAssignment, DisplayViewChecksInLogic,
+    DisplayViewCount, DisplayViewData, DisplayViewFlex, DisplayViewGrid, DisplayViewMap,
+    DisplayViewSpacer, DisplayViewSplits, DisplayViewTabChild, DisplayViewTabs, DisplayViewText,
+    LayoutParams, MapInfo, MapLayer, MapObjective, SplitRow, ThemeColorKey,
 };
 
 #[derive(Clone, Data, Lens, PartialEq)]
@@ -25,18 +28,251 @@ pub enum ModuleParamValue {
 #[derive(Clone, Data, Lens, PartialEq)]
 pub struct ModuleParam {
     pub name: String,
+    // Config window section of the param.  The first param of each run of
+    // params in a group shows the section's header.
+    pub group: String,
+    pub group_start: bool,
+    // Set while the param's section is collapsed.
+    pub collapsed: bool,
     pub value: ModuleParamValue,
 }
 
+// Application wide settings that are not tied to a module.
+#[derive(Clone, Data, Default, Deserialize, Lens, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    // Signal objective state with shapes in addition to color.
+    pub shape_indicators: bool,
+    // Tint map regions by how many of their checks are done.
+    pub map_heatmap: bool,
+    // Language used for module text.  Empty for the module's own text.
+    pub locale: String,
+    // Suppress auto-tracker sound cues.
+    pub muted: bool,
+    // Comma separated objective types to show desktop notifications for.
+    pub notify_types: String,
+    // Hide check details in popups for when the tracker is shown to an
+    // audience.
+    pub restream_safe: bool,
+    // usb2snes device to auto track.  Empty for the first device found.
+    pub usb2snes_device: String,
+    // Read objective state changes aloud.
+    pub announce_changes: bool,
+    // Only mark an unlocked objective complete on a double click.
+    pub double_click_complete: bool,
+    // Step objectives' states with the mouse wheel, forward on scrolling up
+    // unless reversed.
+    pub wheel_steps_state: bool,
+    pub wheel_reversed: bool,
+    // Offer to undo each manual state change.
+    pub undo_toast: bool,
+    // Minimum time between broadcast view updates in milliseconds.  0
+    // updates on every change.
+    pub broadcast_update_ms: f64,
+    // Open the broadcast window without a title bar or border so it can
+    // float over the game.
+    pub broadcast_overlay: bool,
+    // Look for a newer release when the tracker starts.
+    pub check_for_updates: bool,
+    // Paint the broadcast window a solid color instead of the theme
+    // backgrounds so capture software can key it out.
+    pub broadcast_chroma_key: bool,
+    // "#rrggbb" color of the chroma key.  Green if empty or invalid.
+    pub broadcast_chroma_color: String,
+    // Show the first two seeds of a multi-seed session side by side in the
+    // broadcast window.
+    pub broadcast_split: bool,
+    // "host:port" of LiveSplit's server component to split when the
+    // tracker records a split.  Empty to not use LiveSplit.
+    pub livesplit_server: String,
+    // IP address spectators connect to when sharing the tracker.  Empty for
+    // the interface used to reach the network.
+    pub share_address: String,
+    // Monitor to move the broadcast window to when it opens and the window's
+    // position on it.  Empty to leave placement to the window manager.
+    pub broadcast_monitor: String,
+    pub broadcast_x: f64,
+    pub broadcast_y: f64,
+    // Open the broadcast window when the tracker starts.
+    pub broadcast_on_startup: bool,
+    // Shrink the main window's icons, spacing and action bar and leave out
+    // its maps.
+    pub compact: bool,
+}
+
+impl Settings {
+    pub fn chroma_color(&self) -> Color {
+        parse_hex_color(&self.broadcast_chroma_color).unwrap_or(Color::rgb8(0, 0xff, 0))
+    }
+}
+
+// Parses "#rrggbb" with or without the '#'.
+fn parse_hex_color(s: &str) -> Option<Color> {
+    let s = s.trim().trim_start_matches('#');
+    if s.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(s, 16).ok()?;
+    Some(Color::from_rgba32_u32(rgb << 8 | 0xff))
+}
+
+// An objective that can be picked as a practice goal.
+#[derive(Clone, Data, Lens, PartialEq)]
+pub struct Goal {
+    pub id: String,
+    pub name: String,
+    pub selected: bool,
+}
+
+#[derive(Clone, Data, Lens, PartialEq)]
+pub struct GoalStatus {
+    pub name: String,
+    pub complete: bool,
+    // Time since the start of the session the goal was completed.  Empty
+    // while the goal is incomplete.
+    pub time: String,
+}
+
+// Tab of one seed of a multi-seed session.
+#[derive(Clone, Data, Lens, PartialEq)]
+pub struct SeedTab {
+    pub index: usize,
+}
+
+impl DynFlexItem for SeedTab {
+    fn flex_params(&self) -> DynFlexParams {
+        DynFlexParams::default()
+    }
+}
+
+#[derive(Clone, Data, Default, Lens)]
+pub struct GoalsState {
+    // Every objective shown in the goal editor.
+    pub candidates: Arc<Vec<Goal>>,
+    // The goals of the current practice session.
+    pub active: Arc<Vec<GoalStatus>>,
+    // Fraction of active goals that are complete.
+    pub progress: f64,
+}
+
+// An objective in the dependency graph window.  Nodes are placed in columns
+// by `layer`, one more than the deepest objective they depend on.
+#[derive(Clone, Data, PartialEq)]
+pub struct GraphNode {
+    pub id: String,
+    pub name: String,
+    pub state: ObjectiveState,
+    pub layer: usize,
+    // Position within the layer.
+    pub row: usize,
+    // Indices of the nodes this one depends on.
+    pub deps: Arc<Vec<usize>>,
+}
+
+#[derive(Clone, Data, Default, Lens)]
+pub struct DependencyGraph {
+    pub nodes: Arc<Vec<GraphNode>>,
+    // Node clicked last.  It and its ancestors are highlighted.
+    pub focus: Option<usize>,
+}
+
 // DisplayState is owned by the UI and should contain all the information
 // it needs to function.
 #[derive(Clone, Data, Lens)]
 pub struct DisplayState {
     pub layout: DisplayView,
     pub popup: DisplayView,
+    // Objective whose checks the popup shows.  Empty while no popup is open.
+    pub popup_target: String,
+    // (complete, total) enabled checks of the popup's objective.
+    pub popup_checks: (u32, u32),
+    // Names of the objectives the popup's objective is missing to become
+    // unlocked.  Empty when nothing is known to be missing.
+    pub popup_hint: String,
+    // Descriptions of the popup's objective and its checks.
+    pub popup_notes: Arc<Vec<String>>,
+    // Changes described by the undo toast.  Empty when no toast is shown.
+    pub toast: String,
+    // Window position of the hovered objective's tooltip, its text and the
+    // user's note on it, one line per entry, and the id of its image.  No
+    // tooltip is shown while the position is `None`.
+    pub tooltip_pos: Option<(f64, f64)>,
+    pub tooltip_lines: Arc<Vec<String>>,
+    pub tooltip_notes: Arc<Vec<String>>,
+    pub tooltip_image: String,
     pub broadcast: DisplayView,
+    // Broadcast view of the second seed while the broadcast window is split.
+    pub broadcast_split: DisplayView,
     pub params: Arc<Vec<ModuleParam>>,
+    pub settings: Settings,
+    pub goals: GoalsState,
     pub auto_tracker_state: AutoTrackerState,
+    // ROM version detected by the auto tracker.  Empty when unknown.
+    pub rom_version: String,
+    // usb2snes devices found by the last device scan.
+    pub devices: Arc<Vec<String>>,
+    // Names of the monitors the broadcast window can be placed on.
+    pub monitors: Arc<Vec<String>>,
+    // Names of the module's auto tracker scripts and the one picked in the
+    // config window.
+    pub auto_track_scripts: Arc<Vec<String>>,
+    pub auto_track_script: String,
+    // Version of a newer release.  Empty if none was found.
+    pub update_version: String,
     pub config_win: Arc<Option<WindowId>>,
     pub broadcast_win: Arc<Option<WindowId>>,
+    pub watches_win: Arc<Option<WindowId>>,
+    // Auto tracker watches shown in the watch debugger window.
+    pub watch_debug: Arc<Vec<WatchDebug>>,
+    pub logic_win: Arc<Option<WindowId>>,
+    pub graph_win: Arc<Option<WindowId>>,
+    pub graph: DependencyGraph,
+    // Expression typed into the logic console and what it evaluates to.
+    pub logic_input: String,
+    pub logic_result: Arc<Vec<String>>,
+    pub goals_win: Arc<Option<WindowId>>,
+    pub timeline_win: Arc<Option<WindowId>>,
+    // Slider of the timeline window, from the start of the session at 0 to
+    // now at 1, the time it stands for and the main layout at that time.
+    pub timeline_pos: f64,
+    pub timeline_time: String,
+    pub timeline_view: DisplayView,
+    // Seeds of a multi-seed session.  Tabs are only shown once a second
+    // seed is added.
+    pub seeds: Arc<Vec<SeedTab>>,
+    pub active_seed: usize,
+    // Address of the tracker being watched read-only.  Empty unless
+    // spectating.
+    pub spectating: String,
+    // Whether the module's `go-mode` expression holds.
+    pub go_mode: bool,
+    // Text of the note being edited.
+    pub note_text: String,
+}
+
+impl DisplayState {
+    // Whether the broadcast window shows two seeds side by side.
+    pub fn split_broadcast(&self) -> bool {
+        self.settings.broadcast_split && self.seeds.len() > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(
+            parse_hex_color("#00ff00").map(|c| c.as_rgba_u32()),
+            Some(0x00ff00ff)
+        );
+        assert_eq!(
+            parse_hex_color(" FF00ff ").map(|c| c.as_rgba_u32()),
+            Some(0xff00ffff)
+        );
+        assert!(parse_hex_color("#0f0").is_none());
+        assert!(parse_hex_color("green").is_none());
+        assert_eq!(Settings::default().chroma_color().as_rgba_u32(), 0x00ff00ff);
+    }
 }
@@ -1,12 +1,16 @@
 use std::sync::Arc;
 
 use druid::{theme, widget::BackgroundBrush, Color, Data, Key, Lens};
-use serde::Deserialize;
 
 use crate::{
     engine::{
-        module::{DisplayViewInfo, DisplayViewInfoView, ObjectiveList, ObjectiveListSpecial},
-        Engine, ObjectiveState,
+        expression::Expression,
+        layout::{CornerRadius, Inset, ThemeColor},
+        module::{
+            DisplayViewInfo, DisplayViewInfoView, MarkerShape, ObjectiveList, ObjectiveListSpecial,
+        },
+        template::Template,
+        ChangeSet, CheckMark, ClickAction, DisabledDisplay, Engine, ObjectiveState,
     },
     widget::{
         constellation::{Field, Star},
@@ -21,12 +25,40 @@ pub struct DisplayChild {
     pub id: String,
     pub ty: String,
     pub state: ObjectiveState,
+    // Progress of the objective's enabled checks.  `checks_total` is 0 for
+    // objectives without checks.
+    pub checks_complete: u32,
+    pub checks_total: u32,
+    // Set when the objective is a practice goal.
+    pub goal: bool,
+    pub click_action: ClickAction,
+    // Time left on the objective's timer, empty without one.
+    pub timer: String,
+    // Stage reached by a staged objective.
+    pub stage: u32,
+    // How the objective is shown while disabled.
+    pub disabled: DisabledDisplay,
+    // Set while the user has pinned the objective's state.
+    pub locked: bool,
+    // Set for checks, which can be marked junk or with the item found
+    // there.  `found_item` is the item's name.
+    pub check: bool,
+    pub junk: bool,
+    pub found_item: String,
+}
+
+impl DisplayChild {
+    // Hidden objectives take up no space in their grid.
+    pub fn is_hidden(&self) -> bool {
+        self.state == ObjectiveState::Disabled && self.disabled == DisabledDisplay::Hide
+    }
 }
 
 // Data for each view type is broken out here so that we can implements
 // widgets on them.
 #[derive(Clone, Data, Lens)]
 pub struct DisplayViewGrid {
+    // 0 picks the column count based on the available width.
     pub columns: usize,
     pub children: Arc<Vec<DisplayChild>>,
 }
@@ -44,6 +76,13 @@ pub struct MapObjective {
     pub y: f64,
     pub radius: f64,
     pub state: ObjectiveState,
+    pub goal: bool,
+    // Set when the objective is a possible location of the item being
+    // looked for.
+    pub hinted: bool,
+    // Whether the user wrote a note about the objective.
+    pub has_note: bool,
+    pub shape: MarkerShape,
 }
 
 impl Star for MapObjective {
@@ -56,14 +95,56 @@ impl Star for MapObjective {
     }
 }
 
+// An area of a map and the progress of the checks of its objectives.
+#[derive(Clone, Data, Lens)]
+pub struct MapRegion {
+    pub points: Arc<Vec<(f64, f64)>>,
+    // Objectives of the map inside the region.
+    pub objectives: Arc<Vec<String>>,
+    // (complete, total) enabled checks.  Objectives without checks count
+    // as one.
+    pub complete: u32,
+    pub total: u32,
+}
+
+// One of the images a map can switch between.
+#[derive(Clone, Data, Lens)]
+pub struct MapLayer {
+    pub index: usize,
+    pub name: String,
+    // Id of the layer's image asset.
+    pub image: String,
+}
+
+impl DynFlexItem for MapLayer {
+    fn flex_params(&self) -> DynFlexParams {
+        DynFlexParams::default()
+    }
+}
+
 #[derive(Clone, Data, Lens)]
 pub struct MapInfo {
     pub id: String,
+    // Id of the map's image asset when it has no layers.
+    pub image: String,
+    pub layers: Arc<Vec<MapLayer>>,
+    // Index of the layer shown.
+    pub layer: usize,
     pub width: f64,
     pub height: f64,
     // depricated
     pub objective_radius: f64,
     pub objectives: Arc<Vec<MapObjective>>,
+    pub regions: Arc<Vec<MapRegion>>,
+}
+
+impl MapInfo {
+    // Id of the image asset shown.
+    pub fn current_image(&self) -> &String {
+        self.layers
+            .get(self.layer)
+            .map_or(&self.image, |layer| &layer.image)
+    }
 }
 
 impl Field for MapInfo {
@@ -103,6 +184,45 @@ pub struct DisplayViewFlex {
 #[derive(Clone, Data, Lens)]
 pub struct DisplayViewSpacer {}
 
+#[derive(Clone, Data, Lens)]
+pub struct DisplayViewText {
+    pub text: String,
+    pub font_size: f64,
+    pub color: ThemeColor,
+}
+
+// A split of the run timer.  `time` is empty until it's reached.
+#[derive(Clone, Data, Lens, PartialEq)]
+pub struct SplitRow {
+    pub name: String,
+    pub time: String,
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct DisplayViewSplits {
+    pub splits: Arc<Vec<SplitRow>>,
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct DisplayViewChecksInLogic {
+    pub count: usize,
+}
+
+// A slot of an assignment and the name of its value.  `value` is empty
+// while nothing is assigned.
+#[derive(Clone, Data, Lens, PartialEq)]
+pub struct AssignmentSlot {
+    pub assignment: String,
+    pub id: String,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct DisplayViewAssignment {
+    pub slots: Arc<Vec<AssignmentSlot>>,
+}
+
 #[derive(Clone, Data, Lens)]
 pub struct DisplayViewTabChild {
     pub label: String,
@@ -130,6 +250,10 @@ pub enum DisplayViewData {
     FlexRow(DisplayViewFlex),
     FlexCol(DisplayViewFlex),
     Spacer(DisplayViewSpacer),
+    Text(DisplayViewText),
+    Splits(DisplayViewSplits),
+    ChecksInLogic(DisplayViewChecksInLogic),
+    Assignment(DisplayViewAssignment),
     Tabs(DisplayViewTabs),
     None,
 }
@@ -140,74 +264,21 @@ impl Default for DisplayViewData {
     }
 }
 
-#[derive(Clone, Data, Debug, Deserialize, PartialEq)]
-#[serde(rename_all = "kebab-case")]
-pub enum ThemeColor {
-    Clear,
-    BgDark,
-    BgLight,
+// Maps a module's theme color to the druid theme key it stands for.
+pub trait ThemeColorKey {
+    fn color_key(&self) -> Option<Key<Color>>;
 }
 
-impl Default for ThemeColor {
-    fn default() -> Self {
-        return ThemeColor::Clear;
-    }
-}
-
-impl ThemeColor {
-    pub fn color_key(&self) -> Option<Key<Color>> {
+impl ThemeColorKey for ThemeColor {
+    fn color_key(&self) -> Option<Key<Color>> {
         match self {
             ThemeColor::Clear => None,
             ThemeColor::BgLight => Some(theme::BACKGROUND_LIGHT),
             ThemeColor::BgDark => Some(theme::BACKGROUND_DARK),
-        }
-    }
-}
-
-#[derive(Clone, Data, Debug, Deserialize, PartialEq)]
-#[serde(rename_all = "kebab-case")]
-pub enum CornerRadius {
-    None,
-    Small,
-    Large,
-}
-
-impl Default for CornerRadius {
-    fn default() -> Self {
-        return CornerRadius::None;
-    }
-}
-
-impl Into<f64> for CornerRadius {
-    fn into(self) -> f64 {
-        match self {
-            CornerRadius::None => 0.,
-            CornerRadius::Small => 4.,
-            CornerRadius::Large => 8.,
-        }
-    }
-}
-
-#[derive(Clone, Data, Debug, Deserialize, PartialEq)]
-#[serde(rename_all = "kebab-case")]
-pub enum Inset {
-    None,
-    Small,
-    Large,
-}
-
-impl Default for Inset {
-    fn default() -> Self {
-        return Inset::None;
-    }
-}
-
-impl Into<f64> for Inset {
-    fn into(self) -> f64 {
-        match self {
-            Inset::None => 0.,
-            Inset::Small => 4.,
-            Inset::Large => 8.,
+            ThemeColor::Label => Some(theme::LABEL_COLOR),
+            ThemeColor::Placeholder => Some(theme::PLACEHOLDER_COLOR),
+            ThemeColor::PrimaryLight => Some(theme::PRIMARY_LIGHT),
+            ThemeColor::PrimaryDark => Some(theme::PRIMARY_DARK),
         }
     }
 }
@@ -218,16 +289,24 @@ pub struct LayoutParams {
     pub background: ThemeColor,
     pub corner_radius: CornerRadius,
     pub inset: Inset,
+    // Font family and size of text in the view.  Empty or 0 to inherit them.
+    pub font: String,
+    pub font_size: f64,
 }
 
 #[derive(Clone, Data, Default, Lens)]
 pub struct DisplayView {
     pub layout_params: LayoutParams,
+    // Hidden views take up no space.
+    pub visible: bool,
     pub data: DisplayViewData,
 }
 
 impl DynFlexItem for DisplayView {
     fn flex_params(&self) -> DynFlexParams {
+        if !self.visible {
+            return 0.0.into();
+        }
         return self.layout_params.flex.into();
     }
 }
@@ -251,26 +330,55 @@ impl ContainerParams for DisplayView {
 
 impl DisplayView {
     pub fn new(engine: &Engine, info: &DisplayViewInfo) -> Self {
+        Self::with_checks(engine, info, &[])
+    }
+
+    // Creates a view whose `checks` lists show `checks`.  Only popups have
+    // checks to show.
+    pub fn with_checks(engine: &Engine, info: &DisplayViewInfo, checks: &[String]) -> Self {
         let data = match &info.view {
             DisplayViewInfoView::Grid {
                 columns,
                 objectives,
-            } => DisplayViewData::Grid(DisplayViewGrid::new(engine, *columns, objectives)),
-            DisplayViewInfoView::Count { objective_type } => {
-                DisplayViewData::Count(DisplayViewCount::new(engine, objective_type))
-            }
+                disabled,
+            } => DisplayViewData::Grid(DisplayViewGrid::new(
+                engine,
+                columns.count(),
+                objectives,
+                checks,
+                *disabled,
+            )),
+            DisplayViewInfoView::Count { .. } => DisplayViewData::Count(DisplayViewCount::new()),
             DisplayViewInfoView::Map { maps } => {
                 DisplayViewData::Map(DisplayViewMap::new(engine, maps))
             }
             DisplayViewInfoView::FlexRow { children } => {
-                DisplayViewData::FlexRow(DisplayViewFlex::new(engine, children))
+                DisplayViewData::FlexRow(DisplayViewFlex::new(engine, children, checks))
             }
             DisplayViewInfoView::FlexCol { children } => {
-                DisplayViewData::FlexCol(DisplayViewFlex::new(engine, children))
+                DisplayViewData::FlexCol(DisplayViewFlex::new(engine, children, checks))
             }
             DisplayViewInfoView::Spacer {} => DisplayViewData::Spacer(DisplayViewSpacer {}),
+            DisplayViewInfoView::Splits {} => DisplayViewData::Splits(DisplayViewSplits {
+                splits: Arc::new(engine.split_rows()),
+            }),
+            DisplayViewInfoView::ChecksInLogic {} => {
+                DisplayViewData::ChecksInLogic(DisplayViewChecksInLogic {
+                    count: engine.checks_in_logic(),
+                })
+            }
+            DisplayViewInfoView::Assignment { assignment } => {
+                DisplayViewData::Assignment(DisplayViewAssignment {
+                    slots: Arc::new(engine.assignment_slots(assignment)),
+                })
+            }
+            DisplayViewInfoView::Text {
+                template,
+                font_size,
+                color,
+            } => DisplayViewData::Text(DisplayViewText::new(engine, template, *font_size, color)),
             DisplayViewInfoView::Tabs { labels, children } => {
-                DisplayViewData::Tabs(DisplayViewTabs::new(engine, labels, children))
+                DisplayViewData::Tabs(DisplayViewTabs::new(engine, labels, children, checks))
             }
             DisplayViewInfoView::Include { path: _ } => {
                 panic!("encountered unprocessed display view include");
@@ -283,52 +391,130 @@ impl DisplayView {
                 background: info.layout_params.background.clone(),
                 corner_radius: info.layout_params.corner_radius.clone(),
                 inset: info.layout_params.inset.clone(),
+                font: info.layout_params.font.clone(),
+                font_size: info.layout_params.font_size,
             },
+            visible: Self::is_visible(engine, info),
             data: data,
         }
     }
 
+    fn is_visible(engine: &Engine, info: &DisplayViewInfo) -> bool {
+        let visible_when = &info.layout_params.visible_when;
+        if *visible_when == Expression::Default {
+            return true;
+        }
+        visible_when
+            .evaluate_enabled(engine.logic.objectives())
+            .unwrap_or_else(|e| {
+                println!("error evaluating visible-when: {}", e);
+                true
+            })
+    }
+
     pub fn update(&mut self, engine: &Engine, info: &DisplayViewInfo) {
+        self.update_with_checks(engine, info, &[])
+    }
+
+    pub fn update_with_checks(
+        &mut self,
+        engine: &Engine,
+        info: &DisplayViewInfo,
+        checks: &[String],
+    ) {
+        self.refresh(engine, info, checks, None)
+    }
+
+    // Like `update_with_checks()` but grid and map objectives that `changes`
+    // doesn't affect are left as they are.
+    pub fn update_changed(
+        &mut self,
+        engine: &Engine,
+        info: &DisplayViewInfo,
+        checks: &[String],
+        changes: &ChangeSet,
+    ) {
+        self.refresh(engine, info, checks, Some(changes))
+    }
+
+    fn refresh(
+        &mut self,
+        engine: &Engine,
+        info: &DisplayViewInfo,
+        checks: &[String],
+        changes: Option<&ChangeSet>,
+    ) {
+        self.visible = Self::is_visible(engine, info);
         match &info.view {
             DisplayViewInfoView::Grid {
                 columns,
                 objectives,
+                ..
             } => {
                 if let DisplayViewData::Grid(g) = &mut self.data {
-                    g.update(engine, *columns, objectives);
+                    g.update(engine, columns.count(), objectives, checks, changes);
                 }
             }
-            DisplayViewInfoView::Count { objective_type } => {
+            DisplayViewInfoView::Count {
+                objective_type,
+                objectives,
+            } => {
                 if let DisplayViewData::Count(c) = &mut self.data {
-                    c.update(engine, objective_type);
+                    c.update(engine, objective_type, objectives, checks);
                 }
             }
             DisplayViewInfoView::Map { maps: _maps } => {
                 if let DisplayViewData::Map(m) = &mut self.data {
-                    m.update(engine);
+                    m.update(engine, changes);
                 }
             }
             DisplayViewInfoView::FlexRow {
                 children: children_info,
             } => {
                 if let DisplayViewData::FlexRow(f) = &mut self.data {
-                    f.update(engine, &children_info)
+                    f.update(engine, &children_info, checks, changes)
                 }
             }
             DisplayViewInfoView::FlexCol {
                 children: children_info,
             } => {
                 if let DisplayViewData::FlexCol(f) = &mut self.data {
-                    f.update(engine, &children_info)
+                    f.update(engine, &children_info, checks, changes)
                 }
             }
             DisplayViewInfoView::Spacer {} => {}
+            DisplayViewInfoView::Splits {} => {
+                if let DisplayViewData::Splits(s) = &mut self.data {
+                    let splits = engine.split_rows();
+                    if *s.splits != splits {
+                        s.splits = Arc::new(splits);
+                    }
+                }
+            }
+            DisplayViewInfoView::ChecksInLogic {} => {
+                if let DisplayViewData::ChecksInLogic(c) = &mut self.data {
+                    c.count = engine.checks_in_logic();
+                }
+            }
+            DisplayViewInfoView::Assignment { assignment } => {
+                if let DisplayViewData::Assignment(a) = &mut self.data {
+                    let slots = engine.assignment_slots(assignment);
+                    if *a.slots != slots {
+                        a.slots = Arc::new(slots);
+                    }
+                }
+            }
+            DisplayViewInfoView::Text { template, .. } => {
+                if let DisplayViewData::Text(t) = &mut self.data {
+                    t.update(engine, template);
+                }
+            }
             DisplayViewInfoView::Tabs {
                 labels: _labels,
                 children: children_info,
             } => {
                 if let DisplayViewData::Tabs(t) = &mut self.data {
-                    t.update(engine, &children_info)
+                    t.update(engine, &children_info, checks, changes)
                 }
             }
             DisplayViewInfoView::Include { path: _ } => {
@@ -339,21 +525,29 @@ impl DisplayView {
 }
 
 impl DisplayViewGrid {
-    fn deref_objectives<'a>(engine: &'a Engine, objectives: &'a ObjectiveList) -> &'a Vec<String> {
+    fn deref_objectives<'a>(objectives: &'a ObjectiveList, checks: &'a [String]) -> &'a [String] {
         match objectives {
             ObjectiveList::List(objectives) => objectives,
-            ObjectiveList::Special(ObjectiveListSpecial::Checks) => &engine.checks,
+            ObjectiveList::Special(ObjectiveListSpecial::Checks) => checks,
+            // Resolved to `List` by the module loader.
+            ObjectiveList::Tag { .. } | ObjectiveList::Group { .. } => &[],
         }
     }
 
-    fn new(engine: &Engine, columns: usize, objectives: &ObjectiveList) -> Self {
+    fn new(
+        engine: &Engine,
+        columns: usize,
+        objectives: &ObjectiveList,
+        checks: &[String],
+        disabled: DisabledDisplay,
+    ) -> Self {
         let mut children = Vec::new();
-        let objectives = Self::deref_objectives(engine, objectives);
+        let objectives = Self::deref_objectives(objectives, checks);
         for objective in objectives {
-            let ty = if let Some(o) = engine.module.objectives.get(objective) {
-                o.ty.clone()
+            let (ty, click_action) = if let Some(o) = engine.module.objectives.get(objective) {
+                (o.ty.clone(), o.click_action)
             } else {
-                "unknown".into()
+                ("unknown".into(), ClickAction::default())
             };
 
             // All objectives start in the Locked state.  The normal
@@ -362,6 +556,17 @@ impl DisplayViewGrid {
                 id: objective.clone(),
                 ty: ty,
                 state: ObjectiveState::Locked,
+                checks_complete: 0,
+                checks_total: 0,
+                goal: false,
+                click_action,
+                timer: String::new(),
+                stage: 0,
+                disabled,
+                locked: false,
+                check: engine.is_check(objective),
+                junk: false,
+                found_item: String::new(),
             });
         }
         DisplayViewGrid {
@@ -370,9 +575,23 @@ impl DisplayViewGrid {
         }
     }
 
-    fn update(&mut self, engine: &Engine, columns: usize, objectives: &ObjectiveList) {
+    fn update(
+        &mut self,
+        engine: &Engine,
+        columns: usize,
+        objectives: &ObjectiveList,
+        checks: &[String],
+        changes: Option<&ChangeSet>,
+    ) {
         self.columns = columns;
-        let objectives = Self::deref_objectives(engine, objectives);
+        let objectives = Self::deref_objectives(objectives, checks);
+        if let Some(changes) = changes {
+            // Leave the children alone, and the grid's data the same, when
+            // none of them changed.
+            if !objectives.iter().any(|id| engine.is_affected(id, changes)) {
+                return;
+            }
+        }
         let mut ids = objectives.iter();
         let children = Arc::make_mut(&mut self.children);
         for child in children {
@@ -380,33 +599,59 @@ impl DisplayViewGrid {
                 Some(i) => i,
                 None => return,
             };
+            if changes.map_or(false, |c| !engine.is_affected(id, c)) {
+                continue;
+            }
 
-            if let Some(state) = engine.objectives.get(id) {
+            if let Some(state) = engine.logic.objectives().get(id) {
                 child.state = *state;
             }
+
+            let (complete, total) = engine.check_progress(id);
+            child.checks_complete = complete;
+            child.checks_total = total;
+            child.goal = engine.is_goal(id);
+            child.timer = engine.timer_text(id);
+            child.stage = engine.stage(id);
+            child.locked = engine.is_locked(id);
+            let mark = engine.check_mark(id);
+            child.junk = mark == Some(&CheckMark::Junk);
+            child.found_item = match mark {
+                Some(CheckMark::Item(item)) => engine.objective_name(item),
+                _ => String::new(),
+            };
         }
     }
 }
 
 impl DisplayViewCount {
-    fn new(_engine: &Engine, _objective_type: &String) -> Self {
+    fn new() -> Self {
         DisplayViewCount { found: 0, total: 0 }
     }
 
-    fn update(&mut self, engine: &Engine, objective_type: &String) {
+    fn update(
+        &mut self,
+        engine: &Engine,
+        objective_type: &String,
+        objectives: &Option<ObjectiveList>,
+        checks: &[String],
+    ) {
         // We're filtering the objectives every update.  If this becomes a bottleneck,
         // we can cache this filtering.
-        let objectives: Vec<String> = engine
-            .module
-            .objectives
-            .iter()
-            .filter(|(_, o)| o.ty == *objective_type)
-            .map(|(id, _)| id.clone())
-            .collect();
+        let objectives: Vec<String> = match objectives {
+            Some(list) => DisplayViewGrid::deref_objectives(list, checks).to_vec(),
+            None => engine
+                .module
+                .objectives
+                .iter()
+                .filter(|(_, o)| o.ty == *objective_type)
+                .map(|(id, _)| id.clone())
+                .collect(),
+        };
         let total = objectives.len();
         let mut found = 0;
         for o in objectives {
-            if let Some(state) = engine.objectives.get(&o) {
+            if let Some(state) = engine.logic.objectives().get(&o) {
                 found += match state {
                     ObjectiveState::Disabled => 0,
                     ObjectiveState::Locked => 0,
@@ -436,15 +681,52 @@ impl DisplayViewMap {
                     y: info.y as f64,
                     radius: obj_info.objective_radius,
                     state: ObjectiveState::Locked,
+                    goal: false,
+                    hinted: false,
+                    has_note: false,
+                    shape: engine.module.marker_shape(obj_info, &info.id),
                 });
             }
 
+            let regions = obj_info
+                .regions
+                .iter()
+                .map(|region| MapRegion {
+                    points: Arc::new(region.points.clone()),
+                    objectives: Arc::new(
+                        obj_info
+                            .objectives
+                            .iter()
+                            .filter(|o| region.contains(o.x as f64, o.y as f64))
+                            .map(|o| o.id.clone())
+                            .collect(),
+                    ),
+                    complete: 0,
+                    total: 0,
+                })
+                .collect();
+
+            let layers = obj_info
+                .layers
+                .iter()
+                .enumerate()
+                .map(|(index, layer)| MapLayer {
+                    index,
+                    name: engine.translate_label(&layer.name),
+                    image: obj_info.image_id(Some(layer)),
+                })
+                .collect();
+
             maps.push(MapInfo {
                 id: id.clone(),
+                image: obj_info.image_id(None),
+                layers: Arc::new(layers),
+                layer: 0,
                 width: obj_info.width as f64,
                 height: obj_info.height as f64,
                 objective_radius: obj_info.objective_radius,
                 objectives: Arc::new(objectives),
+                regions: Arc::new(regions),
             });
         }
         DisplayViewMap {
@@ -452,25 +734,65 @@ impl DisplayViewMap {
         }
     }
 
-    fn update(&mut self, engine: &Engine) {
+    fn update(&mut self, engine: &Engine, changes: Option<&ChangeSet>) {
+        let affected = |id: &String| changes.map_or(true, |c| engine.is_affected(id, c));
         let maps = Arc::make_mut(&mut self.maps);
         for map in maps {
             let objectives = Arc::make_mut(&mut map.objectives);
-            for mut o in objectives.iter_mut() {
-                if let Some(state) = engine.objectives.get(&o.id) {
+            for mut o in objectives.iter_mut().filter(|o| affected(&o.id)) {
+                if let Some(state) = engine.logic.objectives().get(&o.id) {
                     o.state = *state;
                 }
+                o.goal = engine.is_goal(&o.id);
+                o.hinted = engine.is_hinted(&o.id);
+                o.has_note = engine.note(&o.id).is_some();
+            }
+            let regions = Arc::make_mut(&mut map.regions);
+            for region in regions
+                .iter_mut()
+                .filter(|r| r.objectives.iter().any(affected))
+            {
+                let (complete, total) = region_progress(engine, &region.objectives);
+                region.complete = complete;
+                region.total = total;
             }
         }
     }
 }
 
+// Progress of the checks of `objectives`.  Objectives without checks count
+// as one check that is done once they are complete.
+fn region_progress(engine: &Engine, objectives: &[String]) -> (u32, u32) {
+    let mut complete = 0;
+    let mut total = 0;
+    for id in objectives {
+        let has_checks = engine
+            .objective_info(id)
+            .map_or(false, |info| !info.checks.is_empty());
+        if has_checks {
+            let progress = engine.check_progress(id);
+            complete += progress.0;
+            total += progress.1;
+            continue;
+        }
+        match engine.logic.objectives().get(id) {
+            Some(ObjectiveState::Disabled) | None => (),
+            Some(ObjectiveState::Complete) => {
+                complete += 1;
+                total += 1;
+            }
+            Some(_) => total += 1,
+        }
+    }
+    (complete, total)
+}
+
 impl DisplayViewFlex {
-    fn new(engine: &Engine, children: &Vec<DisplayViewInfo>) -> Self {
+    fn new(engine: &Engine, children: &Vec<DisplayViewInfo>, checks: &[String]) -> Self {
         let mut views = Vec::new();
 
         for child in children {
-            let view = DisplayView::new(engine, child);
+            let view = DisplayView::with_checks(engine, child, checks);
             views.push(view);
         }
 
@@ -479,7 +801,13 @@ impl DisplayViewFlex {
         }
     }
 
-    fn update(&mut self, engine: &Engine, children_info: &Vec<DisplayViewInfo>) {
+    fn update(
+        &mut self,
+        engine: &Engine,
+        children_info: &Vec<DisplayViewInfo>,
+        checks: &[String],
+        changes: Option<&ChangeSet>,
+    ) {
         let views = Arc::make_mut(&mut self.children);
         let mut infos = children_info.iter();
 
@@ -488,13 +816,32 @@ impl DisplayViewFlex {
                 Some(i) => i,
                 None => return,
             };
-            view.update(engine, info);
+            view.refresh(engine, info, checks, changes);
         }
     }
 }
 
+impl DisplayViewText {
+    fn new(engine: &Engine, template: &Template, font_size: f64, color: &ThemeColor) -> Self {
+        DisplayViewText {
+            text: template.render(&engine.module.objectives, engine.logic.objectives()),
+            font_size,
+            color: color.clone(),
+        }
+    }
+
+    fn update(&mut self, engine: &Engine, template: &Template) {
+        self.text = template.render(&engine.module.objectives, engine.logic.objectives());
+    }
+}
+
 impl DisplayViewTabs {
-    fn new(engine: &Engine, labels: &Vec<String>, children: &Vec<DisplayViewInfo>) -> Self {
+    fn new(
+        engine: &Engine,
+        labels: &Vec<String>,
+        children: &Vec<DisplayViewInfo>,
+        checks: &[String],
+    ) -> Self {
         let mut tabs = Vec::new();
 
         let mut labels = labels.iter();
@@ -504,9 +851,9 @@ impl DisplayViewTabs {
                 Some(l) => l,
                 None => continue,
             };
-            let view = DisplayView::new(engine, child);
+            let view = DisplayView::with_checks(engine, child, checks);
             let tab = DisplayViewTabChild {
-                label: label.clone(),
+                label: engine.translate_label(label),
                 index: i,
                 view,
             };
@@ -519,7 +866,13 @@ impl DisplayViewTabs {
         }
     }
 
-    fn update(&mut self, engine: &Engine, children_info: &Vec<DisplayViewInfo>) {
+    fn update(
+        &mut self,
+        engine: &Engine,
+        children_info: &Vec<DisplayViewInfo>,
+        checks: &[String],
+        changes: Option<&ChangeSet>,
+    ) {
         let tabs = Arc::make_mut(&mut self.tabs);
         let mut infos = children_info.iter();
 
@@ -528,7 +881,7 @@ impl DisplayViewTabs {
                 Some(i) => i,
                 None => return,
             };
-            tab.view.update(engine, info);
+            tab.view.refresh(engine, info, checks, changes);
         }
     }
 }
@@ -0,0 +1,184 @@
+// Reads the emulated memory of a running Dolphin.
+//
+// Dolphin keeps the console's RAM in a shared memory segment named
+// "dolphin-emu.<pid>" that it unlinks right after mapping, so the segment is
+// found through the process's memory maps and read through /proc/<pid>/mem.
+// Like Dolphin Memory Engine, this needs permission to trace the Dolphin
+// process.
+
+use failure::{format_err, Error};
+
+// MEM1 as seen by games.  The uncached mirror at 0xc0000000 is accepted too.
+const MEM1_BASE: u32 = 0x8000_0000;
+const MEM1_SIZE: u32 = 0x0180_0000;
+
+pub(crate) struct DolphinMemory {
+    #[cfg(target_os = "linux")]
+    mem: std::fs::File,
+    // Where MEM1 is mapped in Dolphin's address space.
+    #[cfg(target_os = "linux")]
+    mem1: u64,
+}
+
+// Offset of `address` in MEM1, if `len` bytes from it are in MEM1.
+fn mem1_offset(address: u32, len: usize) -> Option<u32> {
+    // Cached (0x8...) and uncached (0xc...) addresses map to the same RAM.
+    if address & 0xb000_0000 != MEM1_BASE {
+        return None;
+    }
+    let offset = address & 0x0fff_ffff;
+    if offset as u64 + len as u64 > MEM1_SIZE as u64 {
+        return None;
+    }
+    Some(offset)
+}
+
+#[cfg(target_os = "linux")]
+impl DolphinMemory {
+    // Running Dolphins by process id.
+    pub fn list() -> Result<Vec<String>, Error> {
+        let mut pids = Vec::new();
+        for entry in std::fs::read_dir("/proc")? {
+            let entry = entry?;
+            let pid = entry.file_name().to_string_lossy().into_owned();
+            if pid.parse::<u32>().is_err() {
+                continue;
+            }
+            // Processes can exit while being listed.
+            let comm = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            if comm.starts_with("dolphin-emu") {
+                pids.push(pid);
+            }
+        }
+        Ok(pids)
+    }
+
+    // Attach to the Dolphin with process id `pid`, or the first one found if
+    // `pid` is empty.
+    pub fn attach(pid: &str) -> Result<DolphinMemory, Error> {
+        let pid = if pid.is_empty() {
+            Self::list()?
+                .into_iter()
+                .next()
+                .ok_or(format_err!("Dolphin is not running"))?
+        } else {
+            pid.to_string()
+        };
+
+        let maps = std::fs::read_to_string(format!("/proc/{}/maps", pid))
+            .map_err(|e| format_err!("Can't read memory maps of Dolphin {}: {}", pid, e))?;
+        let mem1 = maps
+            .lines()
+            .filter_map(|line| parse_map(line, &pid))
+            .find(|(_, size)| *size >= MEM1_SIZE as u64)
+            .map(|(start, _)| start)
+            .ok_or(format_err!("Dolphin {} has no game running", pid))?;
+
+        let mem = std::fs::File::open(format!("/proc/{}/mem", pid))
+            .map_err(|e| format_err!("Can't open memory of Dolphin {}: {}", pid, e))?;
+        Ok(DolphinMemory { mem, mem1 })
+    }
+
+    pub fn read_mem(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+        use std::os::unix::fs::FileExt;
+
+        let offset = mem1_offset(address, data.len()).ok_or(format_err!(
+            "{:#x} (+{:#x}) is outside of MEM1",
+            address,
+            data.len()
+        ))?;
+        self.mem.read_exact_at(data, self.mem1 + offset as u64)?;
+        Ok(())
+    }
+}
+
+// Start and size of a mapping of the start of Dolphin's RAM segment in a
+// line of /proc/<pid>/maps:
+//
+//     7f0000000000-7f0002000000 rw-s 00000000 00:01 1234   /dev/shm/dolphin-emu.42 (deleted)
+#[cfg(target_os = "linux")]
+fn parse_map(line: &str, pid: &str) -> Option<(u64, u64)> {
+    let mut fields = line.split_whitespace();
+    let range = fields.next()?;
+    let _perms = fields.next()?;
+    let offset = fields.next()?;
+    let path = fields.nth(2)?;
+    if path != format!("/dev/shm/dolphin-emu.{}", pid) || u64::from_str_radix(offset, 16).ok()? != 0
+    {
+        return None;
+    }
+    let mut range = range.splitn(2, '-');
+    let start = u64::from_str_radix(range.next()?, 16).ok()?;
+    let end = u64::from_str_radix(range.next()?, 16).ok()?;
+    Some((start, end - start))
+}
+
+// Reading another process's memory needs platform APIs that aren't wired up
+// yet outside of Linux.
+#[cfg(not(target_os = "linux"))]
+impl DolphinMemory {
+    pub fn list() -> Result<Vec<String>, Error> {
+        Ok(Vec::new())
+    }
+
+    pub fn attach(_pid: &str) -> Result<DolphinMemory, Error> {
+        Err(format_err!(
+            "Dolphin auto tracking is only supported on Linux"
+        ))
+    }
+
+    pub fn read_mem(&mut self, _address: u32, _data: &mut [u8]) -> Result<(), Error> {
+        Err(format_err!(
+            "Dolphin auto tracking is only supported on Linux"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mem1_addresses() {
+        assert_eq!(mem1_offset(0x8000_0000, 6), Some(0));
+        assert_eq!(mem1_offset(0xc040_1234, 4), Some(0x40_1234));
+        assert_eq!(mem1_offset(0x817f_fffc, 4), Some(0x17f_fffc));
+        assert_eq!(mem1_offset(0x817f_fffe, 4), None);
+        assert_eq!(mem1_offset(0x9000_0000, 4), None);
+        assert_eq!(mem1_offset(0x7e_1500, 1), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn maps_lines() {
+        assert_eq!(
+            parse_map(
+                "7f0000000000-7f0002000000 rw-s 00000000 00:01 1234    /dev/shm/dolphin-emu.42 (deleted)",
+                "42"
+            ),
+            Some((0x7f00_0000_0000, 0x200_0000))
+        );
+        // Other parts of the segment and other processes' segments.
+        assert_eq!(
+            parse_map(
+                "7f0000000000-7f0002000000 rw-s 02000000 00:01 1234    /dev/shm/dolphin-emu.42 (deleted)",
+                "42"
+            ),
+            None
+        );
+        assert_eq!(
+            parse_map(
+                "7f0000000000-7f0002000000 rw-s 00000000 00:01 1234    /dev/shm/dolphin-emu.43 (deleted)",
+                "42"
+            ),
+            None
+        );
+        assert_eq!(
+            parse_map(
+                "7f0000000000-7f0000001000 r--p 00000000 08:01 99    /usr/lib/libc.so.6",
+                "42"
+            ),
+            None
+        );
+    }
+}
@@ -1,32 +1,52 @@
 use async_std::task;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use druid::{Data, ExtEventError, Selector, Target};
+use druid::{ExtEventError, Selector, Target};
 use failure::{format_err, Error};
-use petgraph::{algo::toposort, graph::DiGraph};
+use indexmap::IndexMap;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 mod auto_tracker;
 mod display;
-pub mod expression;
-pub mod module;
+mod dolphin;
+mod process;
+
+use pollendina_core::engine::{self as logic, EngineObserver};
+pub use pollendina_core::engine::{ChangeSet, ModuleTestResult, StateEvent};
+pub use pollendina_core::{
+    event_log, expression, layout, module, report, stage_id, template, ObjectiveState, StateChange,
+    StateSource,
+};
 
 pub use display::{
-    CheckBoxParamValue, CornerRadius, DisplayChild, DisplayState, DisplayView, DisplayViewCount,
-    DisplayViewData, DisplayViewFlex, DisplayViewGrid, DisplayViewMap, DisplayViewSpacer,
-    DisplayViewTabChild, DisplayViewTabs, Inset, LayoutParams, MapInfo, MapObjective, ModuleParam,
-    ModuleParamValue, ThemeColor,
+    AssignmentSlot, CheckBoxParamValue, DependencyGraph, DisplayChild, DisplayState, DisplayView,
+    DisplayViewAssignment, DisplayViewChecksInLogic, DisplayViewCount, DisplayViewData,
+    DisplayViewFlex, DisplayViewGrid, DisplayViewMap, DisplayViewSpacer, DisplayViewSplits,
+    DisplayViewTabChild, DisplayViewTabs, DisplayViewText, Goal, GoalStatus, GoalsState, GraphNode,
+    LayoutParams, MapInfo, MapLayer, MapObjective, ModuleParam, ModuleParamValue, SeedTab,
+    Settings, SplitRow, ThemeColorKey,
 };
+use event_log::{format_duration, SessionEvent};
 use expression::Expression;
+use module::{assignment_objective, AssetInfo, AssignmentInfo, AssignmentItem, GO_MODE_ID};
 pub use module::{
-    DisplayViewInfo, DisplayViewInfoView, LayoutParamsInfo, Module, ObjectiveList,
+    AssetKind, AutoTrackBackend, ClickAction, DisabledDisplay, DisplayViewInfo,
+    DisplayViewInfoView, LayoutParamsInfo, Module, ObjectiveInfo, ObjectiveList,
     ObjectiveListSpecial, Param,
 };
 
-use crate::assets::{add_image_to_cache, add_objective_to_cache, IMAGES};
-pub use auto_tracker::AutoTrackerState;
-use auto_tracker::{AutoTracker, AutoTrackerController};
+use crate::assets::{
+    add_image_loader_to_cache, add_image_to_cache, add_objective_to_cache, compose_overlay,
+    decode_image, placeholder_image, register_font, IMAGES, SOUNDS,
+};
+use auto_tracker::{AutoTracker, AutoTrackerController, ScriptParams, ScriptStorage};
+pub use auto_tracker::{AutoTrackerState, WatchDebug};
+use report::{Report, ReportItem, ReportLocation};
 
 pub trait EventSink {
     fn submit_command<T: 'static + Send + Sync>(
@@ -37,45 +57,296 @@ pub trait EventSink {
     ) -> Result<(), ExtEventError>;
 }
 
-#[derive(Clone, Copy, Data, Debug, PartialEq)]
-pub enum ObjectiveState {
-    Disabled,
-    Locked,
-    GlitchLocked,
-    Unlocked,
-    Complete,
+const MAIN_LAYOUT: &str = "main";
+const MAIN_LAYOUT_PREFIX: &str = "main:";
+
+// Names of a module's main layout variants: "main" followed by the variants
+// named "main:<variant>" in the manifest.
+pub fn main_layouts(module: &Module) -> Vec<String> {
+    let mut variants: Vec<String> = module
+        .manifest
+        .layouts
+        .keys()
+        .filter(|name| name.starts_with(MAIN_LAYOUT_PREFIX))
+        .cloned()
+        .collect();
+    variants.sort();
+    variants.insert(0, MAIN_LAYOUT.to_string());
+    variants
+}
+
+// Window size a window's root view asks for, if any.
+pub fn window_size(info: &DisplayViewInfo) -> Option<(f64, f64)> {
+    let params = &info.layout_params;
+    if params.window_width > 0. && params.window_height > 0. {
+        Some((params.window_width, params.window_height))
+    } else {
+        None
+    }
+}
+
+// Loads the images, sounds and fonts of `module` into the asset stores.
+// Seeds of a multi-seed session share them.  Assets that fail to load don't
+// stop the module from loading.  Images are replaced with a placeholder and
+// the problems are returned.
+fn load_assets(module: &Module) -> Vec<String> {
+    let mut problems = Vec::new();
+    let overlays = &module.manifest.overlays;
+    let overlay_source = |id: &String| {
+        overlays
+            .iter()
+            .any(|o| &o.base == id || o.layers.iter().any(|l| &l.image == id))
+    };
+    // Map images can be large so they are decoded when first shown rather
+    // than all up front.
+    let (lazy, eager): (Vec<&AssetInfo>, Vec<&AssetInfo>) = module
+        .assets
+        .iter()
+        .filter(|a| a.kind == AssetKind::Image)
+        .partition(|a| a.id.starts_with("map:") && !overlay_source(&a.id));
+    // Decoding dominates the load time of modules with many images so it's
+    // spread over every core.  The image store is only touched from this
+    // thread.
+    let decoded: Vec<_> = eager
+        .into_par_iter()
+        .map(|asset| {
+            let image = fs::read(&asset.path)
+                .map_err(Error::from)
+                .and_then(|data| decode_image(&data));
+            (asset, image)
+        })
+        .collect();
+    IMAGES.with(|images| {
+        let mut store = images.borrow_mut();
+        for asset in lazy {
+            add_image_loader_to_cache(&mut store, &asset.id, asset.path.clone());
+        }
+        // Decoded images that overlays are made from.
+        let mut sources = HashMap::new();
+        for (asset, image) in decoded {
+            let image = match image {
+                Ok(image) => image,
+                Err(e) => {
+                    problems.push(format!("image {}: {}", asset.path.display(), e));
+                    placeholder_image()
+                }
+            };
+            if overlay_source(&asset.id) {
+                sources.insert(asset.id.clone(), image.clone());
+            }
+            if asset.id.starts_with("map:") {
+                // Don't cal
+                add_image_to_cache(&mut store, &asset.id, image);
+            } else {
+                add_objective_to_cache(&mut store, &asset.id, image);
+            }
+        }
+        for overlay in overlays {
+            let image = compose_overlay(overlay, &sources).unwrap_or_else(|e| {
+                problems.push(e.to_string());
+                placeholder_image()
+            });
+            if overlay.id.starts_with("map:") {
+                add_image_to_cache(&mut store, &overlay.id, image);
+            } else {
+                add_objective_to_cache(&mut store, &overlay.id, image);
+            }
+        }
+    });
+
+    // Objectives whose sound is missing fall back on the default cue.
+    SOUNDS.with(|sounds| {
+        let mut store = sounds.borrow_mut();
+        for asset in module.assets.iter().filter(|a| a.kind == AssetKind::Sound) {
+            match fs::read(&asset.path) {
+                Ok(data) => store.add(&asset.id, data),
+                Err(e) => problems.push(format!("sound {}: {}", asset.path.display(), e)),
+            }
+        }
+    });
+
+    // Layouts fall back on the default font.
+    for asset in module.assets.iter().filter(|a| a.kind == AssetKind::Font) {
+        if let Err(e) = register_font(&asset.path) {
+            problems.push(format!("font {}: {}", asset.path.display(), e));
+        }
+    }
+
+    problems
+}
+
+// Starts an auto tracker running the module's script of the given name, or
+// an empty one for modules with only manifest watches.
+type TrackerFactory = dyn Fn(&String) -> Result<AutoTrackerController, Error>;
+
+fn tracker_factory<T: 'static + EventSink + Clone + Send>(
+    module: Arc<Module>,
+    script_storage: ScriptStorage,
+    script_params: ScriptParams,
+    event_sink: T,
+) -> Arc<TrackerFactory> {
+    Arc::new(move |name: &String| {
+        AutoTracker::new(
+            &module
+                .auto_track_scripts
+                .get(name)
+                .cloned()
+                .unwrap_or_default(),
+            &module.auto_track_libs,
+            module.manifest.auto_track_backend,
+            module.manifest.process.clone(),
+            module.manifest.watches.clone(),
+            script_storage.clone(),
+            script_params.clone(),
+            event_sink.clone(),
+        )
+    })
+}
+
+// Told about the state changes applied to an engine.  Events are queued as
+// updates are applied and handed to observers by `publish_state_events()`.
+pub trait StateObserver {
+    fn state_changed(&mut self, engine: &Engine, settings: &Settings, event: &StateEvent);
+}
+
+// What the tracker derives from the objectives, borrowed from an `Engine` so
+// it is kept up to date as its logic engine evaluates.  See `logic_mut()`.
+struct Progress<'a> {
+    checks: &'a HashSet<String>,
+    check_marks: &'a HashMap<String, CheckMark>,
+    goals: &'a Vec<String>,
+    in_logic: &'a mut HashSet<String>,
+    goal_times: &'a mut HashMap<String, Duration>,
+    split_times: &'a mut Vec<Option<Duration>>,
+    state_events: &'a mut Vec<StateEvent>,
 }
 
-impl ObjectiveState {
-    pub fn at_least(&self, threshold: &Self) -> bool {
-        self.ordinal() >= threshold.ordinal()
+impl Progress<'_> {
+    // Re-checks whether each of `ids` that is a check is in logic.
+    fn update_in_logic<'a>(
+        &mut self,
+        logic: &logic::Engine,
+        ids: impl Iterator<Item = &'a String>,
+    ) {
+        for id in ids {
+            if !self.checks.contains(id) {
+                continue;
+            }
+            let junk = self.check_marks.get(id) == Some(&CheckMark::Junk);
+            if logic.objectives().get(id) == Some(&ObjectiveState::Unlocked) && !junk {
+                self.in_logic.insert(id.clone());
+            } else {
+                self.in_logic.remove(id);
+            }
+        }
     }
 
-    pub fn is(&self, threshold: &Self) -> bool {
-        self == threshold
+    fn record_goal_times(&mut self, logic: &logic::Engine) {
+        let elapsed = logic.elapsed();
+        for id in self.goals {
+            if logic.objectives().get(id) == Some(&ObjectiveState::Complete)
+                && !self.goal_times.contains_key(id)
+            {
+                self.goal_times.insert(id.clone(), elapsed);
+            }
+        }
     }
 
-    fn ordinal(&self) -> u32 {
-        match self {
-            ObjectiveState::Disabled => 0,
-            ObjectiveState::Locked => 1,
-            ObjectiveState::GlitchLocked => 2,
-            ObjectiveState::Unlocked => 3,
-            ObjectiveState::Complete => 4,
+    // Splits are only recorded once, even if their objective is cleared
+    // again.
+    fn record_splits(&mut self, logic: &logic::Engine) {
+        let elapsed = logic.elapsed();
+        for (split, time) in logic
+            .module()
+            .manifest
+            .splits
+            .iter()
+            .zip(self.split_times.iter_mut())
+        {
+            if time.is_none()
+                && logic.objectives().get(&split.objective) == Some(&ObjectiveState::Complete)
+            {
+                *time = Some(elapsed);
+            }
         }
     }
 }
 
+impl EngineObserver for Progress<'_> {
+    fn evaluated(&mut self, logic: &logic::Engine, before: &HashMap<String, ObjectiveState>) {
+        self.update_in_logic(logic, before.keys());
+        self.record_goal_times(logic);
+        self.record_splits(logic);
+    }
+
+    fn state_changed(&mut self, _logic: &logic::Engine, event: &StateEvent) {
+        self.state_events.push(event.clone());
+    }
+}
+
 pub struct Engine {
-    module: Module,
+    module: Arc<Module>,
     popup_info: DisplayViewInfo,
     broadcast_info: Option<DisplayViewInfo>,
-    objectives: HashMap<String, ObjectiveState>,
-    eval_order: Vec<String>,
+    // Objective states and the logic evaluating them.
+    logic: logic::Engine,
     auto_tracker: Option<AutoTrackerController>,
-
-    // Active checks.  This will need to be redone for pinned objectives.
-    checks: Vec<String>,
+    // Starts the auto tracker again when another script is picked.  Handed
+    // over with the auto tracker.
+    tracker_factory: Option<Arc<TrackerFactory>>,
+    // Name of the module's auto tracker script in use.
+    auto_track_script: String,
+    // Values the auto tracker script keeps across sessions.
+    script_storage: ScriptStorage,
+    // Shared with the auto tracker like `script_storage`.  Seeds share it too
+    // and the one being played keeps it up to date.
+    script_params: ScriptParams,
+    // Text box param values by param name.
+    text_params: HashMap<String, String>,
+    // Assets of the module that failed to load.
+    asset_problems: Vec<String>,
+
+    // Language of the module translations in use.  Empty when untranslated.
+    locale: String,
+
+    // Practice goals and the time each was first completed.
+    goals: Vec<String>,
+    goal_times: HashMap<String, Duration>,
+    // When each of the module's splits was reached, if it has been.
+    split_times: Vec<Option<Duration>>,
+
+    // Objective whose hint locations are highlighted on the map.
+    hint_target: Option<String>,
+
+    // Hint locations learned while playing, such as from the auto tracker
+    // scouting shops.  Keyed by objective like `ObjectiveInfo::hint_locations`.
+    scouted_hints: HashMap<String, Vec<String>>,
+
+    // What the user noted about checks, keyed by check.
+    check_marks: HashMap<String, CheckMark>,
+    // What the user wrote down about objectives, keyed by objective.
+    notes: HashMap<String, String>,
+
+    // Every check of the module and the ones that are unlocked but not
+    // complete or junk.  Kept up to date with the objectives that change.
+    checks: HashSet<String>,
+    in_logic: HashSet<String>,
+
+    // Subscribed with `subscribe()`.
+    observers: Vec<Box<dyn StateObserver>>,
+    // Updates the observers haven't been told about yet.
+    state_events: Vec<StateEvent>,
+
+    // Timers started by the auto tracker script: when each runs out and the
+    // state its objective is set to then.
+    timers: HashMap<String, (Instant, ObjectiveState)>,
+
+    // Name of the main layout variant in use.  See `main_layouts`.
+    main_layout: String,
+    // The main layout variant in use with the user's overrides applied.
+    main_info: DisplayViewInfo,
+    // The user's overrides keyed by main layout variant.
+    layout_overrides: HashMap<String, LayoutOverrides>,
 }
 
 impl Engine {
@@ -83,32 +354,74 @@ impl Engine {
         module: Module,
         event_sink: T,
     ) -> Result<Engine, Error> {
-        let mut objectives = HashMap::new();
-        for (id, _) in module.objectives.iter() {
-            objectives.insert(id.clone(), ObjectiveState::Disabled);
-        }
-
-        let auto_tracker = match &module.auto_track {
-            Some(script) => Some(AutoTracker::new(script, event_sink.clone())?),
+        let script_storage = ScriptStorage::default();
+        let script_params = ScriptParams::default();
+        let asset_problems = load_assets(&module);
+        let module = Arc::new(module);
+
+        // Modules with only manifest watches get a tracker with an empty
+        // script.
+        let has_tracker =
+            !module.auto_track_scripts.is_empty() || !module.manifest.watches.is_empty();
+        let tracker_factory = if has_tracker {
+            Some(tracker_factory(
+                module.clone(),
+                script_storage.clone(),
+                script_params.clone(),
+                event_sink,
+            ))
+        } else {
+            None
+        };
+        let auto_track_script = module
+            .auto_track_scripts
+            .keys()
+            .next()
+            .cloned()
+            .unwrap_or_default();
+        let auto_tracker = match &tracker_factory {
+            Some(factory) => Some(factory(&auto_track_script)?),
             None => None,
         };
-        let eval_order = Self::calc_eval_order(&module)?;
-
-        // Load all the assets into the asset store.
-        IMAGES.with(|images| -> Result<(), Error> {
-            let mut store = images.borrow_mut();
-            for asset in &module.assets {
-                let data = fs::read(&asset.path)?;
-                if asset.id.starts_with("map:") {
-                    // Don't cal
-                    add_image_to_cache(&mut store, &asset.id, &data);
-                } else {
-                    add_objective_to_cache(&mut store, &asset.id, &data);
-                }
-            }
 
-            Ok(())
-        })?;
+        let mut engine = Self::with_module(module, auto_tracker, script_storage, script_params)?;
+        engine.tracker_factory = tracker_factory;
+        engine.auto_track_script = auto_track_script;
+        engine.asset_problems = asset_problems;
+        engine.sync_script_params();
+        Ok(engine)
+    }
+
+    // Starts another seed of the same module with fresh state.  It shares
+    // the module, its loaded assets and the script storage with `self` but
+    // has no auto tracker of its own.  See `hand_over_auto_tracker`.
+    pub fn new_seed(&self) -> Result<Engine, Error> {
+        let mut engine = Self::with_module(
+            self.module.clone(),
+            None,
+            self.script_storage.clone(),
+            self.script_params.clone(),
+        )?;
+        engine.locale = self.locale.clone();
+        engine.main_layout = self.main_layout.clone();
+        engine.set_layout_overrides(self.layout_overrides.clone());
+        engine.set_goals(self.goals.clone());
+        Ok(engine)
+    }
+
+    fn with_module(
+        module: Arc<Module>,
+        auto_tracker: Option<AutoTrackerController>,
+        script_storage: ScriptStorage,
+        script_params: ScriptParams,
+    ) -> Result<Engine, Error> {
+        let checks = module
+            .objectives
+            .values()
+            .flat_map(|o| o.checks.iter().map(|c| c.id.clone()))
+            .collect();
+        let logic = logic::Engine::new(module.clone(), &mut ())?;
+        let split_times = vec![None; module.manifest.splits.len()];
 
         let popup_info = module
             .manifest
@@ -123,159 +436,281 @@ impl Engine {
             .get(&"boradcast".to_string())
             .map(|o| o.clone());
 
+        let main_info = module
+            .manifest
+            .layouts
+            .get(MAIN_LAYOUT)
+            .ok_or(format_err!("Can't find '{}' layout", MAIN_LAYOUT))?
+            .clone();
+
         let mut engine = Engine {
             module,
             popup_info: popup_info,
             broadcast_info: broadcast_info,
-            objectives,
-            eval_order,
+            logic,
             auto_tracker,
-            checks: Vec::new(),
+            tracker_factory: None,
+            auto_track_script: String::new(),
+            script_storage,
+            script_params,
+            text_params: HashMap::new(),
+            asset_problems: Vec::new(),
+            locale: String::new(),
+            goals: Vec::new(),
+            goal_times: HashMap::new(),
+            split_times,
+            hint_target: None,
+            scouted_hints: HashMap::new(),
+            check_marks: HashMap::new(),
+            notes: HashMap::new(),
+            checks,
+            in_logic: HashSet::new(),
+            observers: Vec::new(),
+            state_events: Vec::new(),
+            timers: HashMap::new(),
+            main_layout: MAIN_LAYOUT.to_string(),
+            main_info,
+            layout_overrides: HashMap::new(),
         };
 
-        engine.eval_objectives()?;
+        engine.refresh_progress();
 
         Ok(engine)
     }
 
-    pub fn calc_eval_order(module: &Module) -> Result<Vec<String>, Error> {
-        // `petgraph` requires indexes to be integers so we first enumerate our
-        // objectives and assign the integer indexes.  We keep maps from
-        // id -> index and index -> id so we can create the graph then
-        // return the topological sort order by id.
-        //
-        // We expect the node count to be fairly low so this conversion
-        // happening once at module load and updates requiring several
-        // HashMap lookups.  If this becomes a performance bottleneck,
-        // we can switch to storing everything in a Vec and converting
-        // ids to indexes at module load and keeping the that way.
-        let mut id_map = HashMap::new();
-        let mut index_map = HashMap::new();
-        let mut index = 0;
-
-        for (id, _) in &module.objectives {
-            id_map.insert(index, id.clone());
-            index_map.insert(id.clone(), index);
-            index += 1;
-        }
-
-        // Generate a list of edges a tuples of (node, dependant node).
-        // Dependencies come from the objective unlocked_by and
-        // enabled_by expressions.
-        let mut edges = Vec::new();
-        for (id, info) in &module.objectives {
-            let idx = index_map.get(id).unwrap();
-            let mut deps = info.enabled_by.deps();
-            deps.append(&mut info.unlocked_by.deps());
-            deps.append(&mut info.completed_by.deps());
-
-            // TODO(konkers): we could de-dup these for a performance gain.
-            for dep in deps {
-                if let Some(dep_idx) = index_map.get(&dep) {
-                    edges.push((*dep_idx, *idx));
-                } else {
-                    println!("unknown id {}", dep);
-                }
-            }
-        }
+    // The logic engine and what the tracker derives from it, to pass as its
+    // observer.
+    fn logic_mut(&mut self) -> (&mut logic::Engine, Progress<'_>) {
+        let progress = Progress {
+            checks: &self.checks,
+            check_marks: &self.check_marks,
+            goals: &self.goals,
+            in_logic: &mut self.in_logic,
+            goal_times: &mut self.goal_times,
+            split_times: &mut self.split_times,
+            state_events: &mut self.state_events,
+        };
+        (&mut self.logic, progress)
+    }
 
-        let graph = DiGraph::<u32, ()>::from_edges(&edges);
+    fn eval_objectives(&mut self) -> Result<(), Error> {
+        let (logic, mut progress) = self.logic_mut();
+        logic.eval_objectives(&mut progress)
+    }
 
-        // A topological sort gives us a static traversal order allowing
-        // os to propagate objective state changes in a single pass.
-        let nodes = toposort(&graph, None)
-            .map_err(|e| format_err!("cycle detected in objective dependencies: {:?}", e))?;
+    // Brings what is derived from the objectives up to date with all of
+    // them, for when they were evaluated without it.
+    fn refresh_progress(&mut self) {
+        let (logic, mut progress) = self.logic_mut();
+        let checks = progress.checks;
+        progress.update_in_logic(logic, checks.iter());
+        progress.record_goal_times(logic);
+        progress.record_splits(logic);
+    }
 
-        // Convert the eval_order back into a Vec of String ids.
-        let mut eval_order = Vec::new();
-        for node_index in nodes {
-            eval_order.push(id_map.get(&(node_index.index() as u32)).unwrap().clone());
-        }
+    // Number of checks that can be done now: unlocked, not complete and not
+    // marked as junk.
+    pub fn checks_in_logic(&self) -> usize {
+        self.in_logic.len()
+    }
 
-        Ok(eval_order)
+    // Connects entrances `a` and `b` so reaching either reaches the other.
+    // Earlier connections of either are removed.
+    pub fn connect_entrances(&mut self, a: &String, b: &String) -> Result<(), Error> {
+        let (logic, mut progress) = self.logic_mut();
+        logic.connect_entrances(a, b, &mut progress)
     }
 
-    fn eval_objectives(&mut self) -> Result<(), Error> {
-        for id in &self.eval_order {
-            let info = self
-                .module
-                .objectives
-                .get(id)
-                .ok_or(format_err!("Can't get info for objective '{}'", id))?;
+    pub fn disconnect_entrance(&mut self, id: &String) -> Result<(), Error> {
+        let (logic, mut progress) = self.logic_mut();
+        logic.disconnect_entrance(id, &mut progress)
+    }
 
-            let mut state = *self
-                .objectives
-                .get(id)
-                .ok_or(format_err!("can't get objective state for '{}`", id))?;
+    // The module's entrances in manifest order with what each connects to.
+    pub fn entrance_connections(&self) -> Vec<EntranceConnection> {
+        self.module
+            .manifest
+            .entrances
+            .iter()
+            .map(|entrance| EntranceConnection {
+                id: entrance.id.clone(),
+                name: self.objective_name(&entrance.id),
+                state: self
+                    .logic
+                    .objectives()
+                    .get(&entrance.id)
+                    .copied()
+                    .unwrap_or(ObjectiveState::Locked),
+                connected: self
+                    .logic
+                    .connection(&entrance.id)
+                    .map(|id| self.objective_name(id)),
+            })
+            .collect()
+    }
 
-            if info.enabled_by != Expression::Manual {
-                let enabled = info.enabled_by.evaluate_enabled(&self.objectives)?;
-                if state == ObjectiveState::Disabled && enabled {
-                    state = ObjectiveState::Locked;
-                }
-            }
+    // The value assigned to `slot` of `info`, if any.
+    fn assigned_value<'a>(
+        &self,
+        info: &'a AssignmentInfo,
+        slot: &str,
+    ) -> Option<&'a AssignmentItem> {
+        info.pool.iter().find(|value| {
+            let id = assignment_objective(slot, &value.id);
+            self.logic.objectives().get(&id) == Some(&ObjectiveState::Complete)
+        })
+    }
 
-            if info.unlocked_by != Expression::Manual {
-                let unlocked = info.unlocked_by.evaluate_unlocked(&self.objectives)?;
-                if state == ObjectiveState::Locked && unlocked {
-                    state = ObjectiveState::Unlocked;
-                }
-            }
+    // Each slot of the assignment `id` with the name of its value.
+    pub fn assignment_slots(&self, id: &str) -> Vec<AssignmentSlot> {
+        let info = match self.module.assignment(id) {
+            Some(info) => info,
+            None => return Vec::new(),
+        };
+        info.slots
+            .iter()
+            .map(|slot| AssignmentSlot {
+                assignment: info.id.clone(),
+                id: slot.id.clone(),
+                name: self.translate_label(&slot.name),
+                value: self
+                    .assigned_value(info, &slot.id)
+                    .map(|value| self.translate_label(&value.name))
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
 
-            if info.completed_by != Expression::Manual {
-                let completed = info.completed_by.evaluate_unlocked(&self.objectives)?;
-                if completed {
-                    state = ObjectiveState::Complete;
-                }
-                if state == ObjectiveState::Complete && !completed {
-                    state = ObjectiveState::Unlocked;
-                }
+    // The values `slot` of the assignment `id` can be given as (id, name),
+    // with the slot each is already assigned to for unique assignments.
+    pub fn assignment_choices(&self, id: &str, slot: &str) -> Vec<AssignmentChoice> {
+        let info = match self.module.assignment(id) {
+            Some(info) => info,
+            None => return Vec::new(),
+        };
+        info.pool
+            .iter()
+            .map(|value| AssignmentChoice {
+                id: value.id.clone(),
+                name: self.translate_label(&value.name),
+                taken_by: info
+                    .slots
+                    .iter()
+                    .filter(|s| info.unique && s.id != slot)
+                    .find(|s| {
+                        self.assigned_value(info, &s.id)
+                            .map_or(false, |v| v.id == value.id)
+                    })
+                    .map(|s| self.translate_label(&s.name)),
+            })
+            .collect()
+    }
+
+    // Assigns `value` to `slot` of the assignment `id`, or clears the slot
+    // for `None`.  In unique assignments the value is taken from any other
+    // slot that had it.
+    pub fn assign(
+        &mut self,
+        id: &str,
+        slot: &str,
+        value: Option<&String>,
+    ) -> Result<ChangeSet, Error> {
+        let info = self
+            .module
+            .assignment(id)
+            .ok_or(format_err!("assign: assignment {} not found", id))?;
+        if !info.slots.iter().any(|s| s.id == slot) {
+            return Err(format_err!("assign: {} has no slot {}", id, slot));
+        }
+        if let Some(value) = value {
+            if !info.pool.iter().any(|v| &v.id == value) {
+                return Err(format_err!("assign: {} has no value {}", id, value));
             }
+        }
 
-            if info.unlocked_by != Expression::Manual {
-                let unlocked = info.unlocked_by.evaluate_unlocked(&self.objectives)?;
-                // Re-lock if a dependencies become locked.
-                if state == ObjectiveState::Unlocked && !unlocked {
-                    state = ObjectiveState::Locked;
-                }
+        let mut updates = HashMap::new();
+        for v in &info.pool {
+            let state = if Some(&v.id) == value {
+                ObjectiveState::Complete
+            } else {
+                ObjectiveState::Locked
+            };
+            updates.insert(assignment_objective(slot, &v.id), state);
+        }
+        if let (Some(value), true) = (value, info.unique) {
+            for other in info.slots.iter().filter(|s| s.id != slot) {
+                updates.insert(
+                    assignment_objective(&other.id, value),
+                    ObjectiveState::Locked,
+                );
             }
+        }
+        updates.retain(|id, state| self.logic.objectives().get(id) != Some(state));
+        self.update_state(&updates, StateSource::Manual)
+    }
 
-            if info.enabled_by != Expression::Manual {
-                let enabled = info.enabled_by.evaluate_enabled(&self.objectives)?;
-                if !enabled {
-                    state = ObjectiveState::Disabled;
-                }
+    // Objectives `id` is missing to become unlocked.  `None` unless `id` is
+    // locked and can be unlocked by finding objectives.
+    pub fn logic_hint(&self, id: &String) -> Option<&Vec<String>> {
+        self.logic.logic_hint(id)
+    }
+
+    fn logic_hint_names(&self, id: &String) -> String {
+        match self.logic_hint(id) {
+            Some(missing) => {
+                let names: Vec<String> = missing.iter().map(|id| self.objective_name(id)).collect();
+                names.join(", ")
             }
-            *self
-                .objectives
-                .get_mut(id)
-                .ok_or(format_err!("can't get objective state for '{}`", id))? = state;
+            None => String::new(),
         }
-        Ok(())
     }
+
+    // Whether the module's `go-mode` expression holds.  Always false for
+    // modules without one.
+    pub fn go_mode(&self) -> bool {
+        self.logic.objectives().get(GO_MODE_ID) == Some(&ObjectiveState::Complete)
+    }
+
+    // How many of the module's splits have been reached.
+    pub fn splits_reached(&self) -> usize {
+        self.split_times.iter().filter(|t| t.is_some()).count()
+    }
+
+    pub fn split_rows(&self) -> Vec<SplitRow> {
+        self.module
+            .manifest
+            .splits
+            .iter()
+            .zip(&self.split_times)
+            .map(|(split, time)| SplitRow {
+                name: self.translate_label(&split.name),
+                time: time.as_ref().map(format_duration).unwrap_or_default(),
+            })
+            .collect()
+    }
+
     pub fn new_display_state(&self) -> DisplayState {
-        let layout = DisplayView::new(
-            self,
-            &self
-                .module
-                .manifest
-                .layouts
-                .get(&"main".to_string())
-                .unwrap(),
-        );
-        let mut params = Vec::new();
+        let layout = DisplayView::new(self, self.main_layout_info());
+        let mut params: Vec<ModuleParam> = Vec::new();
         for p in &self.module.manifest.params {
-            let (name, value) = match p {
-                Param::TextBox { name } => (name.clone(), ModuleParamValue::TextBox("".into())),
-                Param::CheckBox { id, name } => (
-                    name.clone(),
-                    ModuleParamValue::CheckBox(CheckBoxParamValue {
-                        id: id.clone(),
-                        value: false,
-                    }),
-                ),
+            let value = match p {
+                Param::TextBox { .. } => ModuleParamValue::TextBox("".into()),
+                Param::CheckBox { id, .. } => ModuleParamValue::CheckBox(CheckBoxParamValue {
+                    id: id.clone(),
+                    value: false,
+                }),
             };
-            params.push(ModuleParam { name, value });
+            let group = self.translate_label(p.group());
+            // A header starts each run of params in the same group.
+            let group_start =
+                !group.is_empty() && params.last().map_or(true, |last| last.group != group);
+            params.push(ModuleParam {
+                name: self.param_name(p),
+                group,
+                group_start,
+                collapsed: false,
+                value,
+            });
         }
 
         let popup = DisplayView::new(self, &self.popup_info);
@@ -287,205 +722,1641 @@ impl Engine {
         let mut state = DisplayState {
             layout: layout,
             popup: popup,
+            popup_target: String::new(),
+            popup_checks: (0, 0),
+            popup_hint: String::new(),
+            popup_notes: Arc::new(Vec::new()),
+            toast: String::new(),
+            tooltip_pos: None,
+            tooltip_lines: Arc::new(Vec::new()),
+            tooltip_notes: Arc::new(Vec::new()),
+            tooltip_image: String::new(),
             broadcast,
+            broadcast_split: Default::default(),
             params: Arc::new(params),
+            settings: Default::default(),
+            goals: GoalsState {
+                candidates: Arc::new(self.goal_candidates()),
+                ..Default::default()
+            },
             auto_tracker_state: AutoTrackerState::Idle,
+            rom_version: String::new(),
+            devices: Arc::new(Vec::new()),
+            monitors: Arc::new(Vec::new()),
+            auto_track_scripts: Arc::new(self.auto_track_scripts()),
+            auto_track_script: self.auto_track_script.clone(),
+            update_version: String::new(),
             config_win: Arc::new(None),
             broadcast_win: Arc::new(None),
+            watches_win: Arc::new(None),
+            watch_debug: Arc::new(Vec::new()),
+            logic_win: Arc::new(None),
+            graph_win: Arc::new(None),
+            graph: Default::default(),
+            logic_input: String::new(),
+            logic_result: Arc::new(Vec::new()),
+            goals_win: Arc::new(None),
+            timeline_win: Arc::new(None),
+            timeline_pos: 1.0,
+            timeline_time: String::new(),
+            timeline_view: Default::default(),
+            seeds: Arc::new(Vec::new()),
+            active_seed: 0,
+            spectating: String::new(),
+            go_mode: false,
+            note_text: String::new(),
         };
         self.update_display_state(&mut state);
 
         state
     }
 
-    pub fn update_display_state(&self, data: &mut DisplayState) {
-        let layout = self
-            .module
-            .manifest
-            .layouts
-            .get(&"main".to_string())
-            .unwrap();
-        data.layout.update(self, layout);
-        data.popup.update(self, &self.popup_info);
-        if let Some(info) = &self.broadcast_info {
-            data.broadcast.update(self, info);
+    fn param_name(&self, param: &Param) -> String {
+        match param {
+            Param::TextBox { name, .. } | Param::CheckBox { name, .. } => {
+                self.translate_label(name)
+            }
         }
     }
 
-    pub fn update_param_state(&self, data: &mut DisplayState) {
-        let params = Arc::make_mut(&mut data.params).iter_mut();
-        for p in params {
-            if let ModuleParamValue::CheckBox(v) = &mut p.value {
-                let state = match self.objectives.get(&v.id) {
-                    Some(state) => state,
-                    None => continue,
-                };
+    // Translate a module provided label into the current locale.  Labels
+    // without a translation are returned as is.
+    pub fn translate_label(&self, label: &String) -> String {
+        self.module
+            .locales
+            .get(&self.locale)
+            .and_then(|l| l.labels.get(label))
+            .unwrap_or(label)
+            .clone()
+    }
 
-                v.value = match state {
-                    ObjectiveState::Disabled => false,
-                    _ => true,
-                }
-            }
+    // Returns the name of an objective in the current locale.
+    pub fn objective_name(&self, id: &String) -> String {
+        if let Some(name) = self
+            .module
+            .locales
+            .get(&self.locale)
+            .and_then(|l| l.names.get(id))
+        {
+            return name.clone();
+        }
+
+        match self.module.objectives.get(id) {
+            Some(info) => info.name.clone(),
+            None => id.clone(),
         }
     }
 
-    pub fn save_param_state(&mut self, data: &mut DisplayState) -> Result<(), Error> {
-        for p in &*data.params {
-            if let ModuleParamValue::CheckBox(v) = &p.value {
-                let new_state = if v.value {
-                    ObjectiveState::Unlocked
-                } else {
-                    ObjectiveState::Disabled
-                };
-                *self
-                    .objectives
-                    .get_mut(&v.id)
-                    .ok_or(format_err!("objective {} not found", &v.id))? = new_state;
-            }
+    // Returns the description of an objective in the current locale.  Empty
+    // when it has none.
+    pub fn objective_description(&self, id: &String) -> String {
+        if let Some(description) = self
+            .module
+            .locales
+            .get(&self.locale)
+            .and_then(|l| l.descriptions.get(id))
+        {
+            return description.clone();
         }
-        self.eval_objectives()?;
-        self.update_display_state(data);
 
-        Ok(())
+        match self.module.objectives.get(id) {
+            Some(info) => info.description.clone(),
+            None => String::new(),
+        }
     }
 
-    pub fn toggle_state(&mut self, id: &String) -> Result<(), Error> {
-        if let Some(o) = self.objectives.get_mut(id) {
-            let new_state = match *o {
-                ObjectiveState::Disabled => ObjectiveState::Disabled,
-                ObjectiveState::Locked => ObjectiveState::Unlocked,
-                ObjectiveState::GlitchLocked => ObjectiveState::Unlocked,
-                ObjectiveState::Unlocked => ObjectiveState::Complete,
-                ObjectiveState::Complete => ObjectiveState::Locked,
-            };
-            *o = new_state;
-            self.eval_objectives()?;
-            Ok(())
-        } else {
-            Err(format_err!("toggle_state: id {} not found", &id))
+    // Ids of the checks shown in the popup of `target`.
+    fn popup_checks(&self, target: &String) -> Vec<String> {
+        match self.module.objectives.get(target) {
+            Some(info) => info.checks.iter().map(|c| c.id.clone()).collect(),
+            None => Vec::new(),
         }
     }
 
-    pub fn start_auto_tracking(&mut self) -> Result<(), Error> {
-        if let Some(tracker) = &mut self.auto_tracker {
-            println!("starting");
-            task::block_on(tracker.start())
-                .map_err(|e| format_err!("could not send start tracker message: {}", e))
-        } else {
-            println!("no auto tracker");
-            Err(format_err!("no auto tracker support in this module"))
+    // Descriptions of the popup's objective and its checks, one per line.
+    // None are shown in restream safe mode as they name the checks.
+    fn popup_notes(&self, data: &DisplayState, checks: &[String]) -> Vec<String> {
+        let mut notes = Vec::new();
+        if data.settings.restream_safe {
+            return notes;
         }
+        let description = self.objective_description(&data.popup_target);
+        if !description.is_empty() {
+            notes.push(description);
+        }
+        for id in checks {
+            let description = self.objective_description(id);
+            if !description.is_empty() {
+                notes.push(format!("{}: {}", self.objective_name(id), description));
+            }
+        }
+        notes
     }
 
-    pub fn stop_auto_tracking(&mut self) -> Result<(), Error> {
-        if let Some(tracker) = &mut self.auto_tracker {
-            task::block_on(tracker.stop())
-                .map_err(|e| format_err!("could not send stop tracker message: {}", e))
-        } else {
-            Err(format_err!("no auto tracker support in this module"))
+    // Returns (complete, total) counts of an objective's enabled checks.
+    pub fn check_progress(&self, id: &String) -> (u32, u32) {
+        let info = match self.module.objectives.get(id) {
+            Some(info) => info,
+            None => return (0, 0),
+        };
+
+        self.count_checks(info.checks.iter().map(|c| &c.id))
+    }
+
+    // Updates setting each enabled check of `location` to `state`.  Checks
+    // already in that state are left out.
+    pub fn check_updates(
+        &self,
+        location: &String,
+        state: ObjectiveState,
+    ) -> HashMap<String, ObjectiveState> {
+        let checks = match self.module.objectives.get(location) {
+            Some(info) => info.checks.iter().map(|c| &c.id).collect(),
+            None => Vec::new(),
+        };
+        self.enabled_updates(checks.into_iter(), state)
+    }
+
+    // Updates setting each enabled objective of the main layout grid showing
+    // `id` to `state`.
+    pub fn grid_updates(
+        &self,
+        id: &String,
+        state: ObjectiveState,
+    ) -> HashMap<String, ObjectiveState> {
+        match grid_containing(&self.main_info, id) {
+            Some(ids) => self.enabled_updates(ids.iter(), state),
+            None => HashMap::new(),
         }
     }
 
-    pub fn update_state(&mut self, updates: &HashMap<String, ObjectiveState>) -> Result<(), Error> {
-        for (id, state) in updates {
-            self.objectives.insert(id.clone(), state.clone());
-            self.eval_objectives()?;
+    fn enabled_updates<'a>(
+        &self,
+        ids: impl Iterator<Item = &'a String>,
+        state: ObjectiveState,
+    ) -> HashMap<String, ObjectiveState> {
+        ids.filter(|id| match self.logic.objectives().get(*id) {
+            Some(ObjectiveState::Disabled) | None => false,
+            Some(s) => *s != state,
+        })
+        .map(|id| (id.clone(), state))
+        .collect()
+    }
+
+    // Checks marked junk aren't counted.
+    fn count_checks<'a>(&self, ids: impl Iterator<Item = &'a String>) -> (u32, u32) {
+        let mut complete = 0;
+        let mut total = 0;
+        for id in ids {
+            if self.is_junk(id) {
+                continue;
+            }
+            match self.logic.objectives().get(id) {
+                Some(ObjectiveState::Disabled) | None => (),
+                Some(ObjectiveState::Complete) => {
+                    complete += 1;
+                    total += 1;
+                }
+                Some(_) => total += 1,
+            }
         }
-        Ok(())
+        (complete, total)
     }
 
-    pub fn build_popup(&mut self, data: &mut DisplayState, id: &String) -> Result<(), Error> {
-        let obj = self
+    // Builds a report of the session so far.  Key items are objectives of
+    // type `key-item` listed in the order they were found.
+    pub fn session_report(&self, params: &Vec<ModuleParam>) -> Report {
+        let seed = params
+            .iter()
+            .map(|p| {
+                let value = match &p.value {
+                    ModuleParamValue::TextBox(v) => v.clone(),
+                    ModuleParamValue::CheckBox(v) => if v.value { "on" } else { "off" }.into(),
+                };
+                (p.name.clone(), value)
+            })
+            .collect();
+
+        let key_items = self
+            .logic
+            .event_log()
+            .first_reached(&ObjectiveState::Unlocked)
+            .iter()
+            .filter(|e| e.ty == "key-item")
+            .map(|e| ReportItem {
+                name: self.objective_name(&e.id),
+                time: e.time,
+                icon: self
+                    .module
+                    .assets
+                    .iter()
+                    .find(|a| a.id == format!("objective:{}", e.id))
+                    .map(|a| a.path.clone()),
+            })
+            .collect();
+
+        let mut locations: Vec<ReportLocation> = self
             .module
             .objectives
-            .get(id)
-            .ok_or(format_err!("Can't find objective {}", id))?;
+            .values()
+            .filter(|o| !o.checks.is_empty())
+            .map(|o| {
+                let (checks_complete, checks_total) = self.check_progress(&o.id);
+                ReportLocation {
+                    name: self.objective_name(&o.id),
+                    checks_complete,
+                    checks_total,
+                }
+            })
+            .filter(|l| l.checks_total > 0)
+            .collect();
+        locations.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Report {
+            module_name: self.module.manifest.name.clone(),
+            elapsed: self.logic.elapsed(),
+            seed,
+            key_items,
+            locations,
+        }
+    }
 
-        let mut checks = Vec::new();
-        for check in &obj.checks {
-            checks.push(check.id.clone());
+    // Progress of this seed for the summary of a multi-seed session.
+    pub fn seed_summary(&self) -> SeedSummary {
+        let check_ids = self
+            .module
+            .objectives
+            .values()
+            .flat_map(|o| o.checks.iter().map(|c| &c.id));
+        SeedSummary {
+            elapsed: self.logic.elapsed(),
+            checks: self.count_checks(check_ids),
+            key_items: self
+                .logic
+                .event_log()
+                .first_reached(&ObjectiveState::Unlocked)
+                .iter()
+                .filter(|e| e.ty == "key-item")
+                .count(),
+            goals: (self.goal_times.len(), self.goals.len()),
         }
-        self.checks = checks;
+    }
 
-        // Recreate pop view with new checks set.
-        data.popup = DisplayView::new(self, &self.popup_info);
+    // Seeds that aren't being played have their clock stopped.
+    pub fn pause_clock(&mut self) {
+        self.logic.pause_clock();
+    }
 
-        self.update_display_state(data);
-        Ok(())
+    pub fn resume_clock(&mut self) {
+        self.logic.resume_clock();
     }
 
-    pub fn broadcast_window_size(&self) -> Option<(f64, f64)> {
-        let (w, h) = if let Some(info) = &self.broadcast_info {
-            (
-                info.layout_params.window_width,
-                info.layout_params.window_height,
-            )
-        } else {
-            (0., 0.)
-        };
+    pub fn subscribe(&mut self, observer: Box<dyn StateObserver>) {
+        self.observers.push(observer);
+    }
 
-        if w > 0. && h > 0. {
-            Some((w, h))
-        } else {
-            None
+    // Tells the observers about the updates applied since they were last
+    // told.
+    pub fn publish_state_events(&mut self, settings: &Settings) {
+        if self.state_events.is_empty() {
+            return;
+        }
+        let events = std::mem::take(&mut self.state_events);
+        let mut observers = std::mem::take(&mut self.observers);
+        for event in &events {
+            for observer in observers.iter_mut() {
+                observer.state_changed(self, settings, event);
+            }
         }
+        self.observers = observers;
     }
 
-    pub fn dump_state(&self) -> Result<(), Error> {
-        for id in &self.eval_order {
-            let obj = self
-                .module
-                .objectives
-                .get(id)
-                .ok_or(format_err!("Can't find objective {}", id))?;
-            let state = self
-                .objectives
-                .get(id)
-                .ok_or(format_err!("Can't find objective state {}", id))?;
+    // Moves the observers to `other` so they follow the seed being played.
+    pub fn hand_over_observers(&mut self, other: &mut Engine) {
+        other.observers.append(&mut self.observers);
+    }
 
-            println!("{}:", id);
-            println!("  state: {:?}", state);
-            println!("  enabled_by: {:?}", obj.enabled_by);
-            println!("  unlocked_by: {:?}", obj.unlocked_by);
-            println!("  completed_by: {:?}", obj.completed_by);
+    // Moves the auto tracker to `other` so it tracks the seed being played.
+    pub fn hand_over_auto_tracker(&mut self, other: &mut Engine) {
+        if self.auto_tracker.is_some() {
+            other.auto_tracker = self.auto_tracker.take();
+            other.tracker_factory = self.tracker_factory.take();
+            other.auto_track_script = std::mem::take(&mut self.auto_track_script);
         }
-        Ok(())
+        other.sync_script_params();
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    pub fn asset_problems(&self) -> &Vec<String> {
+        &self.asset_problems
+    }
 
-    #[derive(Clone)]
-    struct TestEventSink;
-    impl EventSink for TestEventSink {
-        fn submit_command<T: 'static + Send + Sync>(
-            &self,
-            _sel: Selector<T>,
-            _obj: impl Into<Box<T>>,
-            _target: impl Into<Option<Target>>,
-        ) -> Result<(), ExtEventError> {
-            Ok(())
+    pub fn objective_info(&self, id: &String) -> Option<&ObjectiveInfo> {
+        self.module.objectives.get(id)
+    }
+
+    // Apply changes to `data.settings` that require engine involvement.
+    pub fn apply_settings(&mut self, data: &mut DisplayState) {
+        if data.settings.locale == self.locale {
+            return;
+        }
+
+        if data.settings.locale != "" && !self.module.locales.contains_key(&data.settings.locale) {
+            println!("no translations for locale {}", data.settings.locale);
+        }
+        self.locale = data.settings.locale.clone();
+
+        // Views capture translated text when they are created so they need
+        // to be rebuilt.
+        let layout = self.main_layout_info();
+        data.layout = DisplayView::new(self, layout);
+        let checks = self.popup_checks(&data.popup_target);
+        data.popup = DisplayView::with_checks(self, &self.popup_info, &checks);
+        if let Some(info) = &self.broadcast_info {
+            data.broadcast = DisplayView::new(self, info);
+        }
+        data.broadcast_split = Default::default();
+        data.goals.candidates = Arc::new(self.goal_candidates());
+        let params = Arc::make_mut(&mut data.params);
+        for (p, info) in params.iter_mut().zip(&self.module.manifest.params) {
+            p.name = self.param_name(info);
+            p.group = self.translate_label(info.group());
         }
+        self.update_display_state(data);
     }
 
-    fn assert_state(engine: &Engine, id: &str, state: ObjectiveState) {
-        assert_eq!(*engine.objectives.get(id).unwrap(), state);
+    // Like `update_display_state()` after `changes` were applied, only
+    // refreshing the grid and map objectives they affect.
+    pub fn update_display_changes(&self, data: &mut DisplayState, changes: &ChangeSet) {
+        if changes.is_empty() {
+            return;
+        }
+        let layout = self.main_layout_info();
+        data.layout.update_changed(self, layout, &[], changes);
+        let checks = self.popup_checks(&data.popup_target);
+        data.popup
+            .update_changed(self, &self.popup_info, &checks, changes);
+        data.popup_checks = self.count_checks(checks.iter());
+        data.popup_hint = if data.settings.restream_safe {
+            String::new()
+        } else {
+            self.logic_hint_names(&data.popup_target)
+        };
+        data.popup_notes = Arc::new(self.popup_notes(data, &checks));
+        if let Some(info) = &self.broadcast_info {
+            data.broadcast.update_changed(self, info, &[], changes);
+        }
+        self.update_goals_state(&mut data.goals);
+        data.go_mode = self.go_mode();
+        self.update_logic_console(data);
+        if data.graph_win.is_some() {
+            data.graph.nodes = Arc::new(self.dependency_graph());
+        }
     }
 
-    fn update_state(engine: &mut Engine, updates: &[(&str, ObjectiveState)]) -> Result<(), Error> {
-        let updates = updates.iter().map(|x| (x.0.to_string(), x.1)).collect();
+    pub fn update_display_state(&self, data: &mut DisplayState) {
+        let layout = self.main_layout_info();
+        data.layout.update(self, layout);
+        let checks = self.popup_checks(&data.popup_target);
+        data.popup
+            .update_with_checks(self, &self.popup_info, &checks);
+        data.popup_checks = self.count_checks(checks.iter());
+        data.popup_hint = if data.settings.restream_safe {
+            String::new()
+        } else {
+            self.logic_hint_names(&data.popup_target)
+        };
+        data.popup_notes = Arc::new(self.popup_notes(data, &checks));
+        if let Some(info) = &self.broadcast_info {
+            data.broadcast.update(self, info);
+        }
+        self.update_goals_state(&mut data.goals);
+        data.go_mode = self.go_mode();
+        self.update_logic_console(data);
+        if data.graph_win.is_some() {
+            data.graph.nodes = Arc::new(self.dependency_graph());
+        }
+    }
 
-        engine.update_state(&updates)
+    // Every objective placed in layers so each comes after the objectives
+    // its expressions depend on.
+    pub fn dependency_graph(&self) -> Vec<GraphNode> {
+        let index: HashMap<&String, usize> = self
+            .logic
+            .eval_order()
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id, i))
+            .collect();
+        let mut nodes: Vec<GraphNode> = Vec::new();
+        let mut rows: Vec<usize> = Vec::new();
+        // The evaluation order is topological so dependencies are placed
+        // before the objectives that need them.
+        for id in self.logic.eval_order() {
+            let info = &self.module.objectives[id];
+            let mut deps: Vec<usize> = info
+                .enabled_by
+                .deps()
+                .iter()
+                .chain(info.unlocked_by.deps().iter())
+                .chain(info.completed_by.deps().iter())
+                .filter_map(|dep| index.get(dep).copied())
+                .filter(|d| *d < nodes.len())
+                .collect();
+            deps.sort();
+            deps.dedup();
+            let layer = deps.iter().map(|d| nodes[*d].layer + 1).max().unwrap_or(0);
+            if rows.len() <= layer {
+                rows.resize(layer + 1, 0);
+            }
+            nodes.push(GraphNode {
+                id: id.clone(),
+                name: self.objective_name(id),
+                state: self
+                    .logic
+                    .objectives()
+                    .get(id)
+                    .copied()
+                    .unwrap_or(ObjectiveState::Disabled),
+                layer,
+                row: rows[layer],
+                deps: Arc::new(deps),
+            });
+            rows[layer] += 1;
+        }
+        nodes
     }
 
-    #[test]
+    // Re-evaluates the logic console's expression while its window is open.
+    pub fn update_logic_console(&self, data: &mut DisplayState) {
+        if data.logic_win.is_none() || data.logic_input.trim().is_empty() {
+            data.logic_result = Arc::new(Vec::new());
+            return;
+        }
+        let lines = self
+            .evaluate_expression(&data.logic_input)
+            .unwrap_or_else(|e| vec![format!("error: {}", e)]);
+        data.logic_result = Arc::new(lines);
+    }
+
+    // Evaluates `input` against the current state for module authors:
+    // the expression as resolved, the states it's true for, what it's
+    // missing to become unlocked and the state of each objective it uses.
+    pub fn evaluate_expression(&self, input: &str) -> Result<Vec<String>, Error> {
+        let expr = self.module.resolve_expression(Expression::parse(input)?)?;
+        let at_least = |threshold| expr.evaluate_by(self.logic.objectives(), &threshold);
+        let mut lines = vec![
+            expr.to_string(),
+            format!("enabled: {}", at_least(ObjectiveState::Locked)?),
+            format!("unlocked: {}", at_least(ObjectiveState::Unlocked)?),
+            format!("complete: {}", at_least(ObjectiveState::Complete)?),
+        ];
+        match expr.missing_unlocked(self.logic.objectives())? {
+            Some(missing) if !missing.is_empty() => {
+                lines.push(format!("missing: {}", missing.join(", ")))
+            }
+            Some(_) => (),
+            None => lines.push("missing: can't be unlocked by collecting objectives".into()),
+        }
+        lines.push("depends on:".into());
+        let mut deps = expr.deps();
+        let mut seen = HashSet::new();
+        deps.retain(|dep| seen.insert(dep.clone()));
+        for dep in deps {
+            let state = self
+                .logic
+                .objectives()
+                .get(&dep)
+                .ok_or(format_err!("can't find id {}", dep))?;
+            lines.push(format!(
+                "  {} ({}): {:?}",
+                dep,
+                self.objective_name(&dep),
+                state
+            ));
+        }
+        Ok(lines)
+    }
+
+    pub fn main_layouts(&self) -> Vec<String> {
+        main_layouts(&self.module)
+    }
+
+    pub fn main_layout(&self) -> &String {
+        &self.main_layout
+    }
+
+    // Switches the main view to the layout variant `name`.
+    pub fn set_main_layout(&mut self, name: &str, data: &mut DisplayState) -> Result<(), Error> {
+        if name != MAIN_LAYOUT && !name.starts_with(MAIN_LAYOUT_PREFIX) {
+            return Err(format_err!("'{}' is not a main layout", name));
+        }
+        if !self.module.manifest.layouts.contains_key(name) {
+            return Err(format_err!("Can't find '{}' layout", name));
+        }
+        self.main_layout = name.to_string();
+        self.rebuild_main_layout(data);
+        Ok(())
+    }
+
+    fn rebuild_main_layout(&mut self, data: &mut DisplayState) {
+        self.apply_layout_overrides();
+        data.layout = DisplayView::new(self, self.main_layout_info());
+        self.update_display_state(data);
+    }
+
+    fn apply_layout_overrides(&mut self) {
+        let mut info = self.module.manifest.layouts[&self.main_layout].clone();
+        if let Some(overrides) = self.layout_overrides.get(&self.main_layout) {
+            overrides.apply(&mut info);
+        }
+        self.main_info = info;
+    }
+
+    pub fn layout_overrides(&self) -> &HashMap<String, LayoutOverrides> {
+        &self.layout_overrides
+    }
+
+    // Replaces the user's overrides.  Takes effect when the main view is
+    // next built.
+    pub fn set_layout_overrides(&mut self, overrides: HashMap<String, LayoutOverrides>) {
+        self.layout_overrides = overrides;
+        self.apply_layout_overrides();
+    }
+
+    // Leaves `id` out of the grids of the main layout in use.
+    pub fn hide_objective(&mut self, id: &String, data: &mut DisplayState) {
+        let overrides = self
+            .layout_overrides
+            .entry(self.main_layout.clone())
+            .or_default();
+        if !overrides.hidden.contains(id) {
+            overrides.hidden.push(id.clone());
+        }
+        self.rebuild_main_layout(data);
+    }
+
+    // Moves `id` `offset` places later in its grid of the main layout in
+    // use, or earlier for negative offsets.
+    pub fn move_objective(
+        &mut self,
+        id: &String,
+        offset: isize,
+        data: &mut DisplayState,
+    ) -> Result<(), Error> {
+        let mut ids = grid_containing(&self.main_info, id)
+            .ok_or(format_err!("{} isn't in a grid of the layout", id))?
+            .clone();
+        let pos = ids.iter().position(|i| i == id).unwrap();
+        let new_pos = (pos as isize + offset).max(0).min(ids.len() as isize - 1) as usize;
+        if new_pos == pos {
+            return Ok(());
+        }
+        let moved = ids.remove(pos);
+        ids.insert(new_pos, moved);
+
+        // The whole grid is listed so its order no longer depends on the
+        // module's.
+        let overrides = self
+            .layout_overrides
+            .entry(self.main_layout.clone())
+            .or_default();
+        overrides.order.retain(|o| !ids.contains(o));
+        overrides.order.extend(ids);
+        self.rebuild_main_layout(data);
+        Ok(())
+    }
+
+    // Shows the main layout in use as the module has it.
+    pub fn reset_layout_overrides(&mut self, data: &mut DisplayState) {
+        self.layout_overrides.remove(&self.main_layout);
+        self.rebuild_main_layout(data);
+    }
+
+    // Window size the main layout variant in use asks for, if any.
+    pub fn main_window_size(&self) -> Option<(f64, f64)> {
+        window_size(self.main_layout_info())
+    }
+
+    fn main_layout_info(&self) -> &DisplayViewInfo {
+        &self.main_info
+    }
+
+    // States of every objective in module order.
+    pub fn objective_states(&self) -> IndexMap<String, ObjectiveState> {
+        self.module
+            .objectives
+            .keys()
+            .filter_map(|id| Some((id.clone(), *self.logic.objectives().get(id)?)))
+            .collect()
+    }
+
+    pub fn module_name(&self) -> &String {
+        &self.module.manifest.name
+    }
+
+    pub fn script_storage(&self) -> HashMap<String, serde_json::Value> {
+        self.script_storage.lock().unwrap().clone()
+    }
+
+    pub fn set_script_storage(&mut self, storage: HashMap<String, serde_json::Value>) {
+        *self.script_storage.lock().unwrap() = storage;
+    }
+
+    pub fn goals(&self) -> &Vec<String> {
+        &self.goals
+    }
+
+    // Set `id` to `state` once `secs` seconds have passed.  Restarts a
+    // running timer.
+    pub fn start_timer(
+        &mut self,
+        id: &String,
+        secs: f64,
+        state: ObjectiveState,
+    ) -> Result<(), Error> {
+        if !self.logic.objectives().contains_key(id) {
+            return Err(format_err!("start_timer: id {} not found", id));
+        }
+        let duration = Duration::from_secs_f64(if secs > 0. { secs } else { 0. });
+        self.timers
+            .insert(id.clone(), (Instant::now() + duration, state));
+        Ok(())
+    }
+
+    pub fn has_timers(&self) -> bool {
+        !self.timers.is_empty()
+    }
+
+    // Removes the timers that have run out and returns the states they set.
+    pub fn expired_timers(&mut self) -> HashMap<String, ObjectiveState> {
+        let now = Instant::now();
+        let expired: HashMap<String, ObjectiveState> = self
+            .timers
+            .iter()
+            .filter(|(_, (deadline, _))| *deadline <= now)
+            .map(|(id, (_, state))| (id.clone(), *state))
+            .collect();
+        self.timers.retain(|_, (deadline, _)| *deadline > now);
+        expired
+    }
+
+    // Time left on `id`'s timer as "m:ss", or empty if it has none.
+    pub fn timer_text(&self, id: &String) -> String {
+        match self.timers.get(id) {
+            Some((deadline, _)) => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                // Round up so the timer runs out as it shows 0:00.
+                let secs = (left.as_millis() as u64 + 999) / 1000;
+                format!("{}:{:02}", secs / 60, secs % 60)
+            }
+            None => String::new(),
+        }
+    }
+
+    pub fn is_locked(&self, id: &String) -> bool {
+        self.logic.is_locked(id)
+    }
+
+    // Pin `id` at its current state, or release it if it already is.
+    // Released objectives are evaluated again right away.
+    pub fn toggle_lock(&mut self, id: &String) -> Result<(), Error> {
+        let (logic, mut progress) = self.logic_mut();
+        logic.toggle_lock(id, &mut progress)
+    }
+
+    pub fn is_goal(&self, id: &String) -> bool {
+        self.goals.contains(id)
+    }
+
+    // Replace the practice goals.  Completion times of goals that are kept
+    // are preserved.
+    pub fn set_goals(&mut self, goals: Vec<String>) {
+        self.goal_times.retain(|id, _| goals.contains(id));
+        self.goals = goals;
+        let (logic, mut progress) = self.logic_mut();
+        progress.record_goal_times(logic);
+    }
+
+    // Returns all named objectives, sorted by name, for the goal editor.
+    pub fn goal_candidates(&self) -> Vec<Goal> {
+        let mut candidates: Vec<Goal> = self
+            .module
+            .objectives
+            .values()
+            .filter(|o| o.name != "")
+            .map(|o| Goal {
+                id: o.id.clone(),
+                name: self.objective_name(&o.id),
+                selected: self.is_goal(&o.id),
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.name.cmp(&b.name));
+        candidates
+    }
+
+    fn update_goals_state(&self, data: &mut GoalsState) {
+        let active: Vec<GoalStatus> = self
+            .goals
+            .iter()
+            .map(|id| {
+                let time = self.goal_times.get(id);
+                GoalStatus {
+                    name: self.objective_name(id),
+                    complete: time.is_some(),
+                    time: time.map(format_duration).unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        let complete = active.iter().filter(|g| g.complete).count();
+        data.progress = if active.is_empty() {
+            0.
+        } else {
+            complete as f64 / active.len() as f64
+        };
+        if *data.active != active {
+            data.active = Arc::new(active);
+        }
+    }
+
+    pub fn update_param_state(&self, data: &mut DisplayState) {
+        let params = Arc::make_mut(&mut data.params).iter_mut();
+        for (p, info) in params.zip(&self.module.manifest.params) {
+            match &mut p.value {
+                ModuleParamValue::CheckBox(v) => {
+                    let state = match self.logic.objectives().get(&v.id) {
+                        Some(state) => state,
+                        None => continue,
+                    };
+
+                    v.value = match state {
+                        ObjectiveState::Disabled => false,
+                        _ => true,
+                    }
+                }
+                ModuleParamValue::TextBox(text) => {
+                    if let Param::TextBox { name, .. } = info {
+                        *text = self.text_params.get(name).cloned().unwrap_or_default();
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn save_param_state(&mut self, data: &mut DisplayState) -> Result<(), Error> {
+        let module = self.module.clone();
+        for (p, info) in data.params.iter().zip(&module.manifest.params) {
+            match (&p.value, info) {
+                (ModuleParamValue::CheckBox(v), _) => self.set_param_objective(&v.id, v.value)?,
+                (ModuleParamValue::TextBox(text), Param::TextBox { id, name, .. }) => {
+                    self.text_params.insert(name.clone(), text.clone());
+                    if !id.is_empty() {
+                        self.set_param_objective(id, !text.is_empty())?;
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.eval_objectives()?;
+        self.sync_script_params();
+        self.update_display_state(data);
+
+        Ok(())
+    }
+
+    // Publishes the param values to the auto tracker script.
+    fn sync_script_params(&self) {
+        let mut values = HashMap::new();
+        for p in &self.module.manifest.params {
+            let (id, name, value) = match p {
+                Param::TextBox { id, name, .. } => {
+                    let text = self.text_params.get(name).cloned().unwrap_or_default();
+                    (id, name, serde_json::Value::from(text))
+                }
+                Param::CheckBox { id, name, .. } => {
+                    let enabled = self
+                        .logic
+                        .objectives()
+                        .get(id)
+                        .map_or(false, |state| *state != ObjectiveState::Disabled);
+                    (id, name, serde_json::Value::from(enabled))
+                }
+            };
+            if !id.is_empty() {
+                values.insert(id.clone(), value.clone());
+            }
+            values.insert(name.clone(), value);
+        }
+        *self.script_params.lock().unwrap() = values;
+    }
+
+    fn set_param_objective(&mut self, id: &String, value: bool) -> Result<(), Error> {
+        let new_state = if value {
+            ObjectiveState::Unlocked
+        } else {
+            ObjectiveState::Disabled
+        };
+        self.logic.set_param_state(id, new_state)
+    }
+
+    // Set the check box param with the id or name `key`.
+    pub fn set_param(&mut self, key: &str, value: bool) -> Result<(), Error> {
+        let id = self
+            .module
+            .manifest
+            .params
+            .iter()
+            .find_map(|p| match p {
+                Param::CheckBox { id, name, .. } if id == key || name == key => Some(id.clone()),
+                _ => None,
+            })
+            .ok_or(format_err!("check box param {} not found", key))?;
+        self.set_param_objective(&id, value)?;
+        self.eval_objectives()?;
+        self.sync_script_params();
+        Ok(())
+    }
+
+    // Enable the check box params named in `flags` and disable the rest.
+    // Flags are separated by spaces, commas or slashes, e.g. "Kmoon/Nkey".
+    pub fn set_flags(&mut self, flags: &str) -> Result<(), Error> {
+        let names: Vec<&str> = flags
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '/')
+            .filter(|f| !f.is_empty())
+            .collect();
+        let params: Vec<(String, String)> = self
+            .module
+            .manifest
+            .params
+            .iter()
+            .filter_map(|p| match p {
+                Param::CheckBox { id, name, .. } => Some((id.clone(), name.clone())),
+                _ => None,
+            })
+            .collect();
+        for name in &names {
+            if !params.iter().any(|(_, n)| n == name) {
+                return Err(format_err!("unknown flag {}", name));
+            }
+        }
+        for (id, name) in &params {
+            self.set_param_objective(id, names.contains(&name.as_str()))?;
+        }
+        self.eval_objectives()?;
+        self.sync_script_params();
+        Ok(())
+    }
+
+    // Record that `item` has been seen at `location`.
+    pub fn set_hint(&mut self, location: &String, item: &String) -> Result<(), Error> {
+        if !self.module.objectives.contains_key(location) {
+            return Err(format_err!("set_hint: location {} not found", location));
+        }
+        if !self.module.objectives.contains_key(item) {
+            return Err(format_err!("set_hint: item {} not found", item));
+        }
+        self.add_hint(location, item);
+        self.logic.record_event(SessionEvent::Hint {
+            location: location.clone(),
+            item: item.clone(),
+        });
+        Ok(())
+    }
+
+    fn add_hint(&mut self, location: &String, item: &String) {
+        let locations = self.scouted_hints.entry(item.clone()).or_default();
+        if !locations.contains(location) {
+            locations.push(location.clone());
+        }
+    }
+
+    pub fn is_check(&self, id: &String) -> bool {
+        self.checks.contains(id)
+    }
+
+    // Notes `mark` on the check `id`, or clears its mark for `None`.
+    pub fn mark_check(&mut self, id: &String, mark: Option<CheckMark>) -> Result<(), Error> {
+        if !self.is_check(id) {
+            return Err(format_err!("mark_check: check {} not found", id));
+        }
+        match mark {
+            Some(CheckMark::Item(item)) if !self.module.objectives.contains_key(&item) => {
+                Err(format_err!("mark_check: item {} not found", item))
+            }
+            Some(mark) => {
+                self.check_marks.insert(id.clone(), mark);
+                let (logic, mut progress) = self.logic_mut();
+                progress.update_in_logic(logic, std::iter::once(id));
+                Ok(())
+            }
+            None => {
+                self.check_marks.remove(id);
+                let (logic, mut progress) = self.logic_mut();
+                progress.update_in_logic(logic, std::iter::once(id));
+                Ok(())
+            }
+        }
+    }
+
+    pub fn check_mark(&self, id: &String) -> Option<&CheckMark> {
+        self.check_marks.get(id)
+    }
+
+    fn is_junk(&self, id: &String) -> bool {
+        self.check_marks.get(id) == Some(&CheckMark::Junk)
+    }
+
+    // Sets the user's note on the objective `id`.  An empty note removes it.
+    pub fn set_note(&mut self, id: &String, note: &str) -> Result<(), Error> {
+        if !self.module.objectives.contains_key(id) {
+            return Err(format_err!("set_note: objective {} not found", id));
+        }
+        let note = note.trim();
+        if note.is_empty() {
+            self.notes.remove(id);
+        } else {
+            self.notes.insert(id.clone(), note.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn note(&self, id: &String) -> Option<&String> {
+        self.notes.get(id)
+    }
+
+    // Key items that can be recorded as found at a check, as (id, name)
+    // sorted by name.
+    pub fn item_choices(&self) -> Vec<(String, String)> {
+        let mut items: Vec<(String, String)> = self
+            .module
+            .objectives
+            .values()
+            .filter(|o| o.ty == "key-item")
+            .map(|o| (o.id.clone(), self.objective_name(&o.id)))
+            .collect();
+        items.sort_by(|a, b| a.1.cmp(&b.1));
+        items
+    }
+
+    // Locations `id` may be found at from both the module and scouting.
+    fn hint_locations<'a>(&'a self, id: &String) -> impl Iterator<Item = &'a String> {
+        let module = self
+            .module
+            .objectives
+            .get(id)
+            .map(|info| &info.hint_locations);
+        let scouted = self.scouted_hints.get(id);
+        module.into_iter().chain(scouted).flatten()
+    }
+
+    // Highlight the locations `id` may be found at.  Showing hints for the
+    // current target again hides them.
+    pub fn show_hints(&mut self, id: &String) -> Result<(), Error> {
+        if !self.module.objectives.contains_key(id) {
+            return Err(format_err!("show_hints: id {} not found", &id));
+        }
+        if self.hint_locations(id).next().is_none() || self.hint_target.as_ref() == Some(id) {
+            self.hint_target = None;
+        } else {
+            self.hint_target = Some(id.clone());
+        }
+        Ok(())
+    }
+
+    // Hints are only shown until their objective is found.
+    pub fn is_hinted(&self, location: &String) -> bool {
+        let id = match &self.hint_target {
+            Some(id) => id,
+            None => return false,
+        };
+        if let Some(state) = self.logic.objectives().get(id) {
+            if state.at_least(&ObjectiveState::Unlocked) {
+                return false;
+            }
+        }
+        self.hint_locations(id).any(|l| l == location)
+    }
+
+    // Whether `changes` touches anything shown for `id`: its own state, its
+    // checks, its stages or whether it's hinted.
+    pub fn is_affected(&self, id: &String, changes: &ChangeSet) -> bool {
+        if changes.contains(id) {
+            return true;
+        }
+        if let Some(info) = self.module.objectives.get(id) {
+            if info.checks.iter().any(|c| changes.contains(&c.id)) {
+                return true;
+            }
+        }
+        if (1..=self.stage_count(id)).any(|s| changes.contains(&stage_id(id, s))) {
+            return true;
+        }
+        self.hint_target
+            .as_ref()
+            .map_or(false, |target| changes.contains(target))
+    }
+
+    // Number of stages of `id`, 0 if it isn't staged.
+    fn stage_count(&self, id: &String) -> u32 {
+        self.module
+            .objectives
+            .get(id)
+            .map_or(0, |info| info.stages.len() as u32)
+    }
+
+    // The stage `id` has reached, 0 for none.
+    pub fn stage(&self, id: &String) -> u32 {
+        (1..=self.stage_count(id))
+            .take_while(|stage| {
+                self.logic.objectives().get(&stage_id(id, *stage))
+                    == Some(&ObjectiveState::Complete)
+            })
+            .count() as u32
+    }
+
+    // Stage objective states that move `id` to `stage`, leaving out stages
+    // already reached or not reached as they should be.
+    pub fn stage_updates(
+        &self,
+        id: &String,
+        stage: u32,
+    ) -> Result<HashMap<String, ObjectiveState>, Error> {
+        let count = self.stage_count(id);
+        if count == 0 {
+            return Err(format_err!("{} has no stages", id));
+        }
+        if stage > count {
+            return Err(format_err!("{} has only {} stages", id, count));
+        }
+        Ok((1..=count)
+            .map(|s| {
+                let state = if s <= stage {
+                    ObjectiveState::Complete
+                } else {
+                    ObjectiveState::Locked
+                };
+                (stage_id(id, s), state)
+            })
+            .filter(|(s, state)| {
+                let reached = self.logic.objectives().get(s) == Some(&ObjectiveState::Complete);
+                reached != (*state == ObjectiveState::Complete)
+            })
+            .collect())
+    }
+
+    // Move `id` to the next state of its click action, or the next stage for
+    // staged objectives.  Returns what changed as a result.
+    pub fn toggle_state(&mut self, id: &String) -> Result<ChangeSet, Error> {
+        self.step_state(id, true)
+    }
+
+    // Undoes a `toggle_state()` of `id`, stepping it back through its states
+    // or stages.
+    pub fn step_state_back(&mut self, id: &String) -> Result<ChangeSet, Error> {
+        self.step_state(id, false)
+    }
+
+    fn step_state(&mut self, id: &String, forward: bool) -> Result<ChangeSet, Error> {
+        if self.is_locked(id) {
+            return Ok(ChangeSet::default());
+        }
+        if let Some(o) = self.logic.objectives().get(id) {
+            let action = self
+                .module
+                .objectives
+                .get(id)
+                .map_or(ClickAction::default(), |info| info.click_action);
+            let count = self.stage_count(id);
+            if count > 0 {
+                let stage = if forward {
+                    action.next_stage(self.stage(id), count)
+                } else {
+                    action.prev_stage(self.stage(id), count)
+                };
+                let updates = self.stage_updates(id, stage)?;
+                return self.update_state(&updates, StateSource::Manual);
+            }
+            let new_state = if forward {
+                action.next_state(*o)
+            } else {
+                action.prev_state(*o)
+            };
+            if new_state == *o {
+                return Ok(ChangeSet::default());
+            }
+            let mut updates = HashMap::new();
+            updates.insert(id.clone(), new_state);
+            self.update_state(&updates, StateSource::Manual)
+        } else {
+            Err(format_err!("toggle_state: id {} not found", &id))
+        }
+    }
+
+    // Mark a locked `id` as reachable out of logic, or back to locked if it
+    // already is.  Objectives in any other state are left alone.
+    pub fn toggle_glitch_locked(&mut self, id: &String) -> Result<ChangeSet, Error> {
+        if self.is_locked(id) {
+            return Ok(ChangeSet::default());
+        }
+        let new_state = match self.logic.objectives().get(id) {
+            Some(ObjectiveState::Locked) => ObjectiveState::GlitchLocked,
+            Some(ObjectiveState::GlitchLocked) => ObjectiveState::Locked,
+            Some(_) => return Ok(ChangeSet::default()),
+            None => return Err(format_err!("toggle_glitch_locked: id {} not found", &id)),
+        };
+        let mut updates = HashMap::new();
+        updates.insert(id.clone(), new_state);
+        self.update_state(&updates, StateSource::Manual)
+    }
+
+    // Start auto tracking with the usb2snes `device`.  An empty `device`
+    // picks the first one found.
+    pub fn start_auto_tracking(&mut self, device: &String) -> Result<(), Error> {
+        if let Some(tracker) = &mut self.auto_tracker {
+            println!("starting");
+            task::block_on(tracker.start(device.clone()))
+                .map_err(|e| format_err!("could not send start tracker message: {}", e))
+        } else {
+            println!("no auto tracker");
+            Err(format_err!("no auto tracker support in this module"))
+        }
+    }
+
+    // Track the save file or save state at `path` instead of live memory.
+    pub fn watch_save_file(&mut self, path: PathBuf) -> Result<(), Error> {
+        if let Some(tracker) = &mut self.auto_tracker {
+            task::block_on(tracker.watch_file(path))
+                .map_err(|e| format_err!("could not send watch file message: {}", e))
+        } else {
+            Err(format_err!("no auto tracker support in this module"))
+        }
+    }
+
+    // Scan for usb2snes devices.  The results are sent back with
+    // `ENGINE_UPDATE_DEVICES`.
+    pub fn list_devices(&mut self) -> Result<(), Error> {
+        if let Some(tracker) = &mut self.auto_tracker {
+            task::block_on(tracker.list_devices())
+                .map_err(|e| format_err!("could not send list devices message: {}", e))
+        } else {
+            Err(format_err!("no auto tracker support in this module"))
+        }
+    }
+
+    // Name of the game process the auto tracker reads, if the module reads
+    // one.  Users have to agree to it being read.
+    pub fn tracked_process(&self) -> Option<String> {
+        match self.module.manifest.auto_track_backend {
+            AutoTrackBackend::Process => self
+                .module
+                .manifest
+                .process
+                .as_ref()
+                .map(|p| p.name.clone()),
+            _ => None,
+        }
+    }
+
+    // Turn sending `ENGINE_UPDATE_WATCH_DEBUG` after each read on or off.
+    pub fn set_watch_debug(&mut self, enabled: bool) -> Result<(), Error> {
+        if let Some(tracker) = &mut self.auto_tracker {
+            task::block_on(tracker.set_debug(enabled))
+                .map_err(|e| format_err!("could not send debug message: {}", e))
+        } else {
+            Err(format_err!("no auto tracker support in this module"))
+        }
+    }
+
+    // Names of the module's auto tracker scripts.
+    pub fn auto_track_scripts(&self) -> Vec<String> {
+        self.module.auto_track_scripts.keys().cloned().collect()
+    }
+
+    pub fn auto_track_script(&self) -> &String {
+        &self.auto_track_script
+    }
+
+    // Replaces the auto tracker with one running the module's script `name`.
+    // Tracking has to be started again.
+    pub fn select_auto_track_script(&mut self, name: &String) -> Result<(), Error> {
+        if *name == self.auto_track_script {
+            return Ok(());
+        }
+        if !self.module.auto_track_scripts.contains_key(name) {
+            return Err(format_err!("no auto tracker script {}", name));
+        }
+        let factory = match &self.tracker_factory {
+            Some(factory) => factory.clone(),
+            None => return Err(format_err!("no auto tracker support in this engine")),
+        };
+        // Dropping the old tracker's controller stops it.
+        self.auto_tracker = Some(factory(name)?);
+        self.auto_track_script = name.clone();
+        self.sync_script_params();
+        Ok(())
+    }
+
+    pub fn stop_auto_tracking(&mut self) -> Result<(), Error> {
+        if let Some(tracker) = &mut self.auto_tracker {
+            task::block_on(tracker.stop())
+                .map_err(|e| format_err!("could not send stop tracker message: {}", e))
+        } else {
+            Err(format_err!("no auto tracker support in this module"))
+        }
+    }
+
+    // Applies `updates` and re-evaluates the objectives.  Returns the
+    // objectives whose state changed so only they need to be redrawn.
+    pub fn update_state(
+        &mut self,
+        updates: &HashMap<String, ObjectiveState>,
+        source: StateSource,
+    ) -> Result<ChangeSet, Error> {
+        let (logic, mut progress) = self.logic_mut();
+        logic.update_state(updates, source, &mut progress)
+    }
+
+    // Like `update_state()` but the objectives are only evaluated once all of
+    // `updates` are applied, which is much faster for many updates at once.
+    pub fn batch_update(
+        &mut self,
+        updates: &HashMap<String, ObjectiveState>,
+        source: StateSource,
+    ) -> Result<ChangeSet, Error> {
+        let (logic, mut progress) = self.logic_mut();
+        logic.batch_update(updates, source, &mut progress)
+    }
+
+    pub fn build_popup(&self, data: &mut DisplayState, id: &String) -> Result<(), Error> {
+        if !self.module.objectives.contains_key(id) {
+            return Err(format_err!("Can't find objective {}", id));
+        }
+        data.popup_target = id.clone();
+
+        // Recreate pop view with new checks set.
+        let checks = self.popup_checks(id);
+        data.popup = DisplayView::with_checks(self, &self.popup_info, &checks);
+
+        self.update_display_state(data);
+        Ok(())
+    }
+
+    // Shows the tooltip of `id` next to `pos`, followed by the user's note
+    // on it unless restream safe mode is on.  Objectives with neither hide
+    // any shown tooltip.
+    pub fn show_tooltip(&self, data: &mut DisplayState, id: &String, pos: (f64, f64)) {
+        let tooltip = self
+            .module
+            .objectives
+            .get(id)
+            .and_then(|o| o.tooltip.as_ref());
+        let note = match data.settings.restream_safe {
+            true => None,
+            false => self.notes.get(id),
+        };
+        if tooltip.is_none() && note.is_none() {
+            data.tooltip_pos = None;
+            return;
+        }
+        let lines = tooltip
+            .map(|t| t.text.lines().map(String::from).collect())
+            .unwrap_or_default();
+        let notes = note
+            .map(|n| n.lines().map(String::from).collect())
+            .unwrap_or_default();
+        data.tooltip_lines = Arc::new(lines);
+        data.tooltip_notes = Arc::new(notes);
+        data.tooltip_image = tooltip.map(|t| t.image.clone()).unwrap_or_default();
+        data.tooltip_pos = Some(pos);
+    }
+
+    // Shows this engine's state in `view`, a view of the broadcast layout
+    // that is built first if it's empty.
+    pub fn update_broadcast_view(&self, view: &mut DisplayView) {
+        if let Some(info) = &self.broadcast_info {
+            match view.data {
+                DisplayViewData::None => *view = DisplayView::new(self, info),
+                _ => view.update(self, info),
+            }
+        }
+    }
+
+    pub fn has_broadcast_layout(&self) -> bool {
+        self.broadcast_info.is_some()
+    }
+
+    pub fn broadcast_window_size(&self) -> Option<(f64, f64)> {
+        self.broadcast_info.as_ref().and_then(window_size)
+    }
+
+    // Every objective's expressions, dependencies and state in evaluation
+    // order.
+    pub fn state_dump(&self) -> Result<StateDump, Error> {
+        let mut objectives = Vec::new();
+        for id in self.logic.eval_order() {
+            let obj = self
+                .module
+                .objectives
+                .get(id)
+                .ok_or(format_err!("Can't find objective {}", id))?;
+            let state = self
+                .logic
+                .objectives()
+                .get(id)
+                .ok_or(format_err!("Can't find objective state {}", id))?;
+
+            let mut deps = obj.enabled_by.deps();
+            deps.append(&mut obj.unlocked_by.deps());
+            deps.append(&mut obj.completed_by.deps());
+            let mut seen = HashSet::new();
+            deps.retain(|dep| seen.insert(dep.clone()));
+
+            objectives.push(ObjectiveDump {
+                id: id.clone(),
+                name: self.objective_name(id),
+                state: *state,
+                enabled_by: obj.enabled_by.to_string(),
+                unlocked_by: obj.unlocked_by.to_string(),
+                completed_by: obj.completed_by.to_string(),
+                deps,
+            });
+        }
+        Ok(StateDump {
+            module: self.module.manifest.name.clone(),
+            objectives,
+        })
+    }
+
+    // Time played this session.
+    pub fn elapsed(&self) -> Duration {
+        self.logic.elapsed()
+    }
+
+    // The main layout as it was `time` into the session.
+    pub fn view_at(&self, time: Duration) -> Result<DisplayView, Error> {
+        self.at_time(time, |engine| {
+            let mut view = DisplayView::new(engine, &engine.main_info);
+            view.update(engine, &engine.main_info);
+            view
+        })
+    }
+
+    // Calls `f` with the tracker as it was `time` into the session.  The
+    // updates and session events logged up to then are replayed into a
+    // scratch engine, leaving this one as it is.
+    fn at_time<R>(&self, time: Duration, f: impl FnOnce(&Engine) -> R) -> Result<R, Error> {
+        let mut scratch = Self::with_module(
+            self.module.clone(),
+            None,
+            self.script_storage.clone(),
+            self.script_params.clone(),
+        )?;
+        scratch.locale = self.locale.clone();
+        scratch.main_layout = self.main_layout.clone();
+        scratch.main_info = self.main_info.clone();
+        scratch.layout_overrides = self.layout_overrides.clone();
+        scratch.text_params = self.text_params.clone();
+        scratch.check_marks = self.check_marks.clone();
+        scratch.notes = self.notes.clone();
+        scratch.hint_target = self.hint_target.clone();
+        scratch.goals = self.goals.clone();
+        scratch.goal_times = self.goal_times.clone();
+        scratch.goal_times.retain(|_, t| *t <= time);
+        scratch.split_times = self
+            .split_times
+            .iter()
+            .map(|t| t.filter(|t| *t <= time))
+            .collect();
+
+        scratch.logic = self.logic.at_time(time, &mut ())?;
+        for entry in self.logic.event_log().events_until(time) {
+            if let SessionEvent::Hint { location, item } = &entry.event {
+                scratch.add_hint(location, item);
+            }
+        }
+        scratch.refresh_progress();
+
+        Ok(f(&scratch))
+    }
+
+    // Runs the module's `tests.json`.  The session's state is left as it is.
+    pub fn run_module_tests(&self) -> Result<Vec<ModuleTestResult>, Error> {
+        logic::run_module_tests(&self.module)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeedSummary {
+    pub elapsed: Duration,
+    // (complete, total) enabled checks.
+    pub checks: (u32, u32),
+    pub key_items: usize,
+    // (complete, total) practice goals.
+    pub goals: (usize, usize),
+}
+
+// The user's changes to the grids of a main layout, kept in their settings
+// rather than the module.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct LayoutOverrides {
+    // Objectives left out of the layout's grids.
+    pub hidden: Vec<String>,
+    // Grids show the objectives listed here first, in this order, followed
+    // by the rest in module order.
+    pub order: Vec<String>,
+}
+
+impl LayoutOverrides {
+    // Applies the overrides to every grid of `info`.
+    fn apply(&self, info: &mut DisplayViewInfo) {
+        match &mut info.view {
+            DisplayViewInfoView::Grid {
+                objectives: ObjectiveList::List(ids),
+                ..
+            } => {
+                ids.retain(|id| !self.hidden.contains(id));
+                // The sort is stable so unlisted objectives keep their order.
+                ids.sort_by_key(|id| {
+                    self.order
+                        .iter()
+                        .position(|o| o == id)
+                        .unwrap_or(usize::MAX)
+                });
+            }
+            DisplayViewInfoView::FlexRow { children }
+            | DisplayViewInfoView::FlexCol { children }
+            | DisplayViewInfoView::Tabs { children, .. } => {
+                for child in children {
+                    self.apply(child);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// The objectives of the grid of `info` showing `id`.
+fn grid_containing<'a>(info: &'a DisplayViewInfo, id: &String) -> Option<&'a Vec<String>> {
+    match &info.view {
+        DisplayViewInfoView::Grid {
+            objectives: ObjectiveList::List(ids),
+            ..
+        } if ids.contains(id) => Some(ids),
+        DisplayViewInfoView::FlexRow { children }
+        | DisplayViewInfoView::FlexCol { children }
+        | DisplayViewInfoView::Tabs { children, .. } => {
+            children.iter().find_map(|c| grid_containing(c, id))
+        }
+        _ => None,
+    }
+}
+
+// What the user noted about a check.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CheckMark {
+    // Nothing useful is there.
+    Junk,
+    // The key item with this id was found there.
+    Item(String),
+}
+
+// A value an assignment slot can be given and the name of the slot it's
+// already assigned to, if any.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssignmentChoice {
+    pub id: String,
+    pub name: String,
+    pub taken_by: Option<String>,
+}
+
+// An entrance and the name of the entrance it's connected to, if any.
+#[derive(Clone, Debug)]
+pub struct EntranceConnection {
+    pub id: String,
+    pub name: String,
+    pub state: ObjectiveState,
+    pub connected: Option<String>,
+}
+
+// Snapshot of the tracker's logic for debugging a module.  See
+// `Engine::state_dump`.
+#[derive(Clone, Debug, Serialize)]
+pub struct StateDump {
+    pub module: String,
+    pub objectives: Vec<ObjectiveDump>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ObjectiveDump {
+    pub id: String,
+    pub name: String,
+    pub state: ObjectiveState,
+    pub enabled_by: String,
+    pub unlocked_by: String,
+    pub completed_by: String,
+    // Objectives the expressions refer to.
+    pub deps: Vec<String>,
+}
+
+impl StateDump {
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pollendina_core::builder::ModuleBuilder;
+
+    #[derive(Clone)]
+    struct TestEventSink;
+    impl EventSink for TestEventSink {
+        fn submit_command<T: 'static + Send + Sync>(
+            &self,
+            _sel: Selector<T>,
+            _obj: impl Into<Box<T>>,
+            _target: impl Into<Option<Target>>,
+        ) -> Result<(), ExtEventError> {
+            Ok(())
+        }
+    }
+
+    fn assert_state(engine: &Engine, id: &str, state: ObjectiveState) {
+        assert_eq!(engine.logic.objectives()[id], state);
+    }
+
+    #[test]
+    fn dependency_graph() -> Result<(), Error> {
+        let engine = build_engine(
+            ModuleBuilder::new()
+                .objective("a")
+                .objective("b")
+                .unlocked_by("a")
+                .objective("c")
+                .unlocked_by("a && b")
+                .objective("d"),
+        )?;
+        let nodes = engine.dependency_graph();
+        let node = |id: &str| nodes.iter().find(|n| n.id == id).unwrap();
+        assert_eq!(node("a").layer, 0);
+        assert_eq!(node("b").layer, 1);
+        assert_eq!(node("c").layer, 2);
+        assert_eq!(node("d").layer, 0);
+        assert_ne!(node("a").row, node("d").row);
+
+        let deps: Vec<&String> = node("c").deps.iter().map(|d| &nodes[*d].id).collect();
+        assert_eq!(deps.len(), 2);
+        assert!(deps.contains(&&"a".to_string()) && deps.contains(&&"b".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_expression() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("hook")
+                .objective("bomb")
+                .alias("grapple", "hook"),
+        )?;
+        update_state(
+            &mut engine,
+            &[
+                ("hook", ObjectiveState::Complete),
+                ("bomb", ObjectiveState::Locked),
+            ],
+        )?;
+
+        let lines = engine.evaluate_expression("grapple && bomb")?;
+        assert_eq!(lines[0], "hook && bomb");
+        assert_eq!(lines[2], "unlocked: false");
+        assert!(lines.contains(&"missing: bomb".to_string()));
+        assert!(lines.contains(&"  hook (hook): Complete".to_string()));
+
+        assert!(engine.evaluate_expression("hook &&").is_err());
+        assert!(engine.evaluate_expression("sword").is_err());
+        Ok(())
+    }
+
+    fn update_state(engine: &mut Engine, updates: &[(&str, ObjectiveState)]) -> Result<(), Error> {
+        let updates = updates.iter().map(|x| (x.0.to_string(), x.1)).collect();
+
+        engine.update_state(&updates, StateSource::Manual)?;
+        Ok(())
+    }
+
+    #[test]
     fn load_fe_module() -> Result<(), Error> {
-        // While we are bootstrapping everything we'll be using the FE module for
-        // tests.  Eventually the unique cases should be extracted into `test_data/mod`
+        // Logic is tested with `ModuleBuilder` modules.  This makes sure a
+        // real module loads.
         let module = Module::open("mods/ff4fe/manifest.json")?;
-        let mut engine = Engine::new(module, TestEventSink)?;
+        let engine = Engine::new(module, TestEventSink)?;
         let _state = engine.new_display_state();
 
         // Make sure assets loaded.
@@ -506,82 +2377,954 @@ mod tests {
         assert_state(&engine, &"d-castle", ObjectiveState::Locked);
         assert_state(&engine, &"bahamut-cave", ObjectiveState::Locked);
 
-        // Dwarf Castle should still be locked if Magma Key is only Unlocked.
-        update_state(&mut engine, &[("magma-key", ObjectiveState::Unlocked)])?;
-        assert_state(&engine, &"d-castle", ObjectiveState::Locked);
+        Ok(())
+    }
+
+    #[test]
+    fn missing_assets() -> Result<(), Error> {
+        let mut module = ModuleBuilder::new().objective("sword").build()?;
+        module.assets.push(AssetInfo {
+            path: PathBuf::from("does/not/exist.png"),
+            id: "objective:missing-sword".into(),
+            kind: AssetKind::Image,
+        });
+        module.assets.push(AssetInfo {
+            path: PathBuf::from("does/not/exist.ogg"),
+            id: "missing-sound".into(),
+            kind: AssetKind::Sound,
+        });
+
+        // The module still loads with a placeholder for the image.
+        let engine = Engine::new(module, TestEventSink)?;
+        assert_eq!(engine.asset_problems().len(), 2);
+        IMAGES.with(|images| {
+            assert!(images
+                .borrow()
+                .get(&"objective:missing-sword:locked".into())
+                .is_some());
+        });
+
+        Ok(())
+    }
+
+    fn build_engine(builder: ModuleBuilder) -> Result<Engine, Error> {
+        Engine::new(builder.build()?, TestEventSink)
+    }
+
+    // Gives `engine` a session clock that only moves forward when the
+    // returned function is called with a number of seconds.
+    fn manual_clock(engine: &mut Engine) -> impl Fn(u64) {
+        let now = Arc::new(std::sync::Mutex::new(Instant::now()));
+        let clock_now = now.clone();
+        engine
+            .logic
+            .set_clock(Arc::new(move || *clock_now.lock().unwrap()));
+        move |secs| *now.lock().unwrap() += Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn script_storage() -> Result<(), Error> {
+        let storage = ScriptStorage::default();
+        storage
+            .lock()
+            .unwrap()
+            .insert("count".into(), serde_json::json!(1));
+        let script = r#"
+            storage.set("rom", "1.1")
+            storage.set("count", storage.get("count") + 1)
+            storage.set("ratio", 0.5)
+            storage.set("gone", true)
+            storage.set("gone", nil)
+        "#;
+        AutoTracker::new(
+            &script.to_string(),
+            &HashMap::new(),
+            Default::default(),
+            None,
+            Vec::new(),
+            storage.clone(),
+            ScriptParams::default(),
+            TestEventSink,
+        )?;
+
+        let storage = storage.lock().unwrap();
+        assert_eq!(storage.get("rom"), Some(&serde_json::json!("1.1")));
+        assert_eq!(storage.get("count"), Some(&serde_json::json!(2)));
+        assert_eq!(storage.get("ratio"), Some(&serde_json::json!(0.5)));
+        assert_eq!(storage.get("gone"), None);
+
+        assert!(AutoTracker::new(
+            &"storage.set(\"table\", {})".to_string(),
+            &HashMap::new(),
+            Default::default(),
+            None,
+            Vec::new(),
+            ScriptStorage::default(),
+            ScriptParams::default(),
+            TestEventSink
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn script_params() -> Result<(), Error> {
+        let module = Module::open("core/src/test_data/mod/manifest.json")?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let mut data = engine.new_display_state();
+        Arc::make_mut(&mut data.params)[0].value = ModuleParamValue::TextBox("Kmoon".into());
+        engine.save_param_state(&mut data)?;
+        assert_eq!(
+            engine.script_params.lock().unwrap().get("flags"),
+            Some(&serde_json::json!("Kmoon"))
+        );
+
+        // Values survive rebuilding the display state.
+        let mut data = engine.new_display_state();
+        engine.update_param_state(&mut data);
+        assert!(data.params[0].value == ModuleParamValue::TextBox("Kmoon".into()));
+
+        let storage = ScriptStorage::default();
+        AutoTracker::new(
+            &r#"storage.set("flags", get_param("flags"))
+                storage.set("missing", get_param("missing"))"#
+                .to_string(),
+            &HashMap::new(),
+            Default::default(),
+            None,
+            Vec::new(),
+            storage.clone(),
+            engine.script_params.clone(),
+            TestEventSink,
+        )?;
+        let storage = storage.lock().unwrap();
+        assert_eq!(storage.get("flags"), Some(&serde_json::json!("Kmoon")));
+        assert_eq!(storage.get("missing"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn set_flags() -> Result<(), Error> {
+        let module = Module::open("mods/ff4fe/manifest.json")?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+
+        engine.set_flags("Kmoon/Nkey")?;
+        assert_eq!(
+            engine.logic.objectives()["flag-k-moon"],
+            ObjectiveState::Unlocked
+        );
+        assert_eq!(
+            engine.logic.objectives()["flag-n-key"],
+            ObjectiveState::Unlocked
+        );
+        assert_eq!(
+            engine.logic.objectives()["flag-n-chars"],
+            ObjectiveState::Disabled
+        );
+
+        engine.set_param("flag-n-key", false)?;
+        engine.set_param("Nchars", true)?;
+        assert_eq!(
+            engine.logic.objectives()["flag-n-key"],
+            ObjectiveState::Disabled
+        );
+        assert_eq!(
+            engine.logic.objectives()["flag-n-chars"],
+            ObjectiveState::Unlocked
+        );
+
+        assert!(engine.set_flags("Kmoon Kbogus").is_err());
+        assert!(engine.set_param("bogus", true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn goals() -> Result<(), Error> {
+        let module = Module::open("mods/ff4fe/manifest.json")?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        engine.set_goals(vec!["magma-key".to_string(), "hook".to_string()]);
+
+        let mut goals = GoalsState::default();
+        engine.update_goals_state(&mut goals);
+        assert_eq!(goals.active.len(), 2);
+        assert_eq!(goals.progress, 0.);
 
-        // Completing Magma Key now unlocks Dwarf Castle.
         update_state(&mut engine, &[("magma-key", ObjectiveState::Complete)])?;
-        assert_state(&engine, &"d-castle", ObjectiveState::Unlocked);
+        engine.update_goals_state(&mut goals);
+        assert!(goals.active[0].complete);
+        assert!(!goals.active[1].complete);
+        assert_eq!(goals.progress, 0.5);
 
-        // Un-completing the Magma Key should re-locks Dwarf Castle.
-        update_state(&mut engine, &[("magma-key", ObjectiveState::Unlocked)])?;
-        assert_state(&engine, &"d-castle", ObjectiveState::Locked);
+        // Dropping a goal forgets its completion time.
+        engine.set_goals(vec!["hook".to_string()]);
+        assert!(!engine.goal_times.contains_key("magma-key"));
 
-        // Unlocking Darkness Crystal is enough to unlock Moon objectives.
-        update_state(
-            &mut engine,
-            &[("darkness-crystal", ObjectiveState::Complete)],
+        Ok(())
+    }
+
+    #[test]
+    fn stages() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("sword")
+            .stages(&["", ""])
+            .build()?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let sword = "sword".to_string();
+        let state = |engine: &Engine| engine.logic.objectives()[&sword];
+
+        assert_eq!(engine.stage(&sword), 0);
+        assert_eq!(state(&engine), ObjectiveState::Locked);
+        engine.toggle_state(&sword)?;
+        assert_eq!(engine.stage(&sword), 1);
+        assert_eq!(state(&engine), ObjectiveState::Unlocked);
+        engine.toggle_state(&sword)?;
+        assert_eq!(engine.stage(&sword), 2);
+        assert_eq!(state(&engine), ObjectiveState::Complete);
+        engine.toggle_state(&sword)?;
+        assert_eq!(engine.stage(&sword), 0);
+        assert_eq!(state(&engine), ObjectiveState::Locked);
+
+        // Stepping back wraps around to the last stage.
+        engine.step_state_back(&sword)?;
+        assert_eq!(engine.stage(&sword), 2);
+        engine.step_state_back(&sword)?;
+        assert_eq!(engine.stage(&sword), 1);
+
+        // Stages already as asked for aren't updated again.
+        assert!(engine.stage_updates(&sword, 0)?.is_empty());
+        assert_eq!(engine.stage_updates(&sword, 1)?.len(), 1);
+        assert!(engine.stage_updates(&sword, 3).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn at_time() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("door")
+                .unlocked_by("complete(key)"),
         )?;
-        assert_state(&engine, &"bahamut-cave", ObjectiveState::Unlocked);
-
-        // Completing D. Mist slot should complete Mist Cave.
-        update_state(&mut engine, &[("mist-cave:0", ObjectiveState::Complete)])?;
-        assert_state(&engine, &"mist-cave", ObjectiveState::Complete);
-
-        // Completing all non-disabled checks should cause the location to be
-        // completed.  We need to turn on Nchars to ensure the char check is
-        // disabled.
-        update_state(&mut engine, &[("flag-n-chars", ObjectiveState::Unlocked)])?;
-        assert_state(&engine, &"mt-ordeals:0", ObjectiveState::Disabled);
-        assert_state(&engine, &"mt-ordeals", ObjectiveState::Unlocked);
-        update_state(
-            &mut engine,
-            &[
-                ("mt-ordeals:1", ObjectiveState::Complete),
-                ("mt-ordeals:2", ObjectiveState::Complete),
-                ("mt-ordeals-key-item-check", ObjectiveState::Complete),
-                ("mt-ordeals:4", ObjectiveState::Complete),
-            ],
+        let advance = manual_clock(&mut engine);
+        let key = "key".to_string();
+        let start = engine.elapsed();
+        advance(1);
+        update_state(&mut engine, &[("key", ObjectiveState::Complete)])?;
+
+        // Objectives unlocked by a later update are still locked.
+        let states_at = |engine: &Engine, time| {
+            engine.at_time(time, |e| {
+                (e.logic.objectives().clone(), e.logic.state_source(&key))
+            })
+        };
+        let (states, source) = states_at(&engine, start)?;
+        assert_eq!(states["key"], ObjectiveState::Locked);
+        assert_eq!(states["door"], ObjectiveState::Locked);
+        assert_eq!(source, None);
+        let (states, source) = states_at(&engine, engine.elapsed())?;
+        assert_eq!(states["door"], ObjectiveState::Unlocked);
+        assert_eq!(source, Some(StateSource::Manual));
+
+        // The session's state is left as it was.
+        assert_eq!(engine.logic.objectives()["key"], ObjectiveState::Complete);
+        assert_eq!(engine.logic.objectives()["door"], ObjectiveState::Unlocked);
+
+        Ok(())
+    }
+
+    #[test]
+    fn at_time_connections() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .entrance("house-door", "true")
+                .entrance("cave-front", "")
+                .objective("cave-chest")
+                .check("cave-chest:0", "cave-front")
+                .objective("sword")
+                .objective("shop"),
         )?;
-        assert_state(&engine, &"mt-ordeals", ObjectiveState::Complete);
-
-        // baron has 5 objectives that are not gated and 2 that are gated by
-        // the baron key.  It should:
-        // * start Unlocked due to the 5 non-gated checks.
-        // * should transition to Locked when those are complete.
-        // * should transition to Unlocked when the baron-key is unlocked.
-        // * should transition to Complete once the last two checks are complete.
-        assert_state(&engine, &"baron", ObjectiveState::Unlocked);
-        update_state(
-            &mut engine,
-            &[
-                ("baron:0", ObjectiveState::Complete),
-                ("baron:3", ObjectiveState::Complete),
-                ("baron:4", ObjectiveState::Complete),
-                ("baron:5", ObjectiveState::Complete),
-                ("baron-inn-key-item-check", ObjectiveState::Complete),
-            ],
+        let advance = manual_clock(&mut engine);
+        let cave = "cave-front".to_string();
+        let start = engine.elapsed();
+        advance(1);
+        engine.connect_entrances(&"house-door".to_string(), &cave)?;
+        engine.set_hint(&"shop".to_string(), &"sword".to_string())?;
+        let connected = engine.elapsed();
+        advance(1);
+        engine.disconnect_entrance(&cave)?;
+
+        // Connections and hints found later aren't known yet, and ones
+        // removed later are still there.
+        let at = |time| {
+            engine.at_time(time, |e| {
+                (e.logic.objectives()["cave-chest"], e.scouted_hints.clone())
+            })
+        };
+        let (state, hints) = at(start)?;
+        assert_eq!(state, ObjectiveState::Locked);
+        assert!(hints.is_empty());
+        let (state, hints) = at(connected)?;
+        assert_eq!(state, ObjectiveState::Unlocked);
+        assert_eq!(hints["sword"], vec!["shop".to_string()]);
+
+        assert_state(&engine, "cave-chest", ObjectiveState::Locked);
+        Ok(())
+    }
+
+    #[test]
+    fn checks_in_logic() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("cave")
+                .objective_type("location")
+                .check("cave:0", "")
+                .check("cave:1", "complete(key)"),
         )?;
-        assert_state(&engine, &"baron", ObjectiveState::Locked);
-        update_state(&mut engine, &[("baron-key", ObjectiveState::Unlocked)])?;
-        assert_state(&engine, &"baron", ObjectiveState::Unlocked);
-        update_state(
-            &mut engine,
-            &[
-                ("baron:1", ObjectiveState::Complete),
-                ("baron:2", ObjectiveState::Complete),
-            ],
+        let advance = manual_clock(&mut engine);
+        let start = engine.elapsed();
+        assert_eq!(engine.checks_in_logic(), 1);
+        advance(1);
+        update_state(&mut engine, &[("key", ObjectiveState::Complete)])?;
+        assert_eq!(engine.checks_in_logic(), 2);
+        update_state(&mut engine, &[("cave:0", ObjectiveState::Complete)])?;
+        assert_eq!(engine.checks_in_logic(), 1);
+
+        // Junk isn't worth going for.
+        let check = "cave:1".to_string();
+        engine.mark_check(&check, Some(CheckMark::Junk))?;
+        assert_eq!(engine.checks_in_logic(), 0);
+        engine.mark_check(&check, None)?;
+        assert_eq!(engine.checks_in_logic(), 1);
+
+        // Looking back counts the checks in logic back then and leaves the
+        // current ones as they were.
+        let earlier = engine.at_time(start, |e| e.in_logic.clone())?;
+        assert_eq!(earlier, ["cave:0".to_string()].iter().cloned().collect());
+        assert_eq!(engine.in_logic, [check].iter().cloned().collect());
+        Ok(())
+    }
+
+    #[test]
+    fn assignments() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .assignment("bosses", &["door-a", "door-b"], &["dragon", "golem"], true)
+                .objective("prize")
+                .unlocked_by("door-a-is-dragon"),
+        )?;
+        let prize = "prize".to_string();
+        let dragon = "dragon".to_string();
+        let values = |engine: &Engine| {
+            engine
+                .assignment_slots("bosses")
+                .into_iter()
+                .map(|s| s.value)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(&engine), vec!["", ""]);
+        assert_eq!(engine.logic.objectives()[&prize], ObjectiveState::Locked);
+
+        let changes = engine.assign("bosses", "door-a", Some(&dragon))?;
+        assert!(changes.contains(&prize));
+        assert_eq!(values(&engine), vec!["dragon", ""]);
+        assert_eq!(
+            engine.assignment_choices("bosses", "door-b")[0].taken_by,
+            Some("door-a".to_string())
+        );
+
+        // A unique value moves to the slot it's assigned to last.
+        engine.assign("bosses", "door-b", Some(&dragon))?;
+        assert_eq!(values(&engine), vec!["", "dragon"]);
+        assert_eq!(engine.logic.objectives()[&prize], ObjectiveState::Locked);
+
+        engine.assign("bosses", "door-b", None)?;
+        assert_eq!(values(&engine), vec!["", ""]);
+        assert!(engine.assign("bosses", "door-c", None).is_err());
+        assert!(engine
+            .assign("bosses", "door-a", Some(&"hydra".to_string()))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn batch_updates() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("cave")
+                .objective_type("location")
+                .check("cave:0", "")
+                .check("cave:1", "complete(key)"),
+        )?;
+        let cave = "cave".to_string();
+        let complete = engine.check_updates(&cave, ObjectiveState::Complete);
+        assert_eq!(complete.len(), 2);
+
+        let changes = engine.batch_update(&complete, StateSource::Manual)?;
+        assert_eq!(changes.changes.len(), 2);
+        assert_eq!(engine.check_progress(&cave), (2, 2));
+        assert!(engine
+            .check_updates(&cave, ObjectiveState::Complete)
+            .is_empty());
+
+        // Reset checks are evaluated again.
+        let reset = engine.check_updates(&cave, ObjectiveState::Locked);
+        engine.batch_update(&reset, StateSource::Manual)?;
+        assert_eq!(
+            engine.logic.objectives()["cave:0"],
+            ObjectiveState::Unlocked
+        );
+        assert_eq!(engine.logic.objectives()["cave:1"], ObjectiveState::Locked);
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_marks() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("cave")
+                .objective_type("location")
+                .check("cave:0", "")
+                .check("cave:1", "")
+                .objective("lamp")
+                .objective_type("key-item"),
+        )?;
+        let cave = "cave".to_string();
+        let check = "cave:0".to_string();
+        let lamp = "lamp".to_string();
+        assert_eq!(engine.check_progress(&cave), (0, 2));
+
+        engine.mark_check(&check, Some(CheckMark::Junk))?;
+        assert_eq!(engine.check_progress(&cave), (0, 1));
+        engine.mark_check(&check, Some(CheckMark::Item(lamp.clone())))?;
+        assert_eq!(
+            engine.check_mark(&check),
+            Some(&CheckMark::Item(lamp.clone()))
+        );
+        assert_eq!(engine.check_progress(&cave), (0, 2));
+        engine.mark_check(&check, None)?;
+        assert_eq!(engine.check_mark(&check), None);
+
+        assert!(engine.mark_check(&cave, Some(CheckMark::Junk)).is_err());
+        assert!(engine
+            .mark_check(&check, Some(CheckMark::Item("nope".to_string())))
+            .is_err());
+        assert_eq!(engine.item_choices(), vec![(lamp.clone(), lamp)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tooltips() -> Result<(), Error> {
+        let engine = build_engine(
+            ModuleBuilder::new()
+                .objective("cave")
+                .tooltip("Behind the waterfall\nBring a lamp", "screenshot:cave")
+                .objective("town"),
+        )?;
+        let mut data = engine.new_display_state();
+
+        engine.show_tooltip(&mut data, &"cave".to_string(), (10.0, 20.0));
+        assert_eq!(data.tooltip_pos, Some((10.0, 20.0)));
+        assert_eq!(
+            *data.tooltip_lines,
+            vec![
+                "Behind the waterfall".to_string(),
+                "Bring a lamp".to_string()
+            ]
+        );
+        assert_eq!(data.tooltip_image, "screenshot:cave");
+
+        engine.show_tooltip(&mut data, &"town".to_string(), (10.0, 20.0));
+        assert_eq!(data.tooltip_pos, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn notes() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("cave")
+                .tooltip("Behind the waterfall", "")
+                .objective("town"),
+        )?;
+        let cave = "cave".to_string();
+        let town = "town".to_string();
+        engine.set_note(&cave, "shop sells bombs")?;
+        engine.set_note(&town, " inn ")?;
+        assert_eq!(engine.note(&town), Some(&"inn".to_string()));
+        assert!(engine.set_note(&"nowhere".to_string(), "x").is_err());
+
+        // Notes follow the module's tooltip.
+        let mut data = engine.new_display_state();
+        engine.show_tooltip(&mut data, &cave, (0.0, 0.0));
+        assert_eq!(
+            *data.tooltip_lines,
+            vec!["Behind the waterfall".to_string()]
+        );
+        assert_eq!(*data.tooltip_notes, vec!["shop sells bombs".to_string()]);
+        engine.show_tooltip(&mut data, &town, (0.0, 0.0));
+        assert!(data.tooltip_lines.is_empty());
+        assert_eq!(*data.tooltip_notes, vec!["inn".to_string()]);
+
+        // Restream safe mode leaves notes out.
+        data.settings.restream_safe = true;
+        engine.show_tooltip(&mut data, &cave, (0.0, 0.0));
+        assert_eq!(
+            *data.tooltip_lines,
+            vec!["Behind the waterfall".to_string()]
+        );
+        assert!(data.tooltip_notes.is_empty());
+        data.tooltip_pos = None;
+        engine.show_tooltip(&mut data, &town, (0.0, 0.0));
+        assert_eq!(data.tooltip_pos, None);
+        data.settings.restream_safe = false;
+
+        engine.set_note(&town, "")?;
+        assert_eq!(engine.note(&town), None);
+        engine.show_tooltip(&mut data, &town, (0.0, 0.0));
+        assert_eq!(data.tooltip_pos, None);
+        Ok(())
+    }
+
+    #[test]
+    fn glitch_locked() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("key")
+            .objective("door")
+            .unlocked_by("complete(key)")
+            .build()?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let key = "key".to_string();
+        let door = "door".to_string();
+
+        assert!(engine.toggle_glitch_locked(&door)?.contains(&door));
+        assert_eq!(
+            engine.logic.objectives()[&door],
+            ObjectiveState::GlitchLocked
+        );
+        engine.toggle_glitch_locked(&door)?;
+        assert_eq!(engine.logic.objectives()[&door], ObjectiveState::Locked);
+
+        // Once in logic the mark is replaced by unlocked.
+        engine.toggle_glitch_locked(&door)?;
+        update_state(&mut engine, &[("key", ObjectiveState::Complete)])?;
+        assert_eq!(engine.logic.objectives()[&door], ObjectiveState::Unlocked);
+        assert!(engine.toggle_glitch_locked(&door)?.is_empty());
+        assert!(engine.toggle_glitch_locked(&key)?.is_empty());
+        assert!(engine.toggle_glitch_locked(&"nope".to_string()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn splits() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("boss")
+                .split("Key", "key")
+                .split("Boss", "boss"),
+        )?;
+        assert_eq!(engine.splits_reached(), 0);
+
+        update_state(&mut engine, &[("boss", ObjectiveState::Complete)])?;
+        assert_eq!(engine.splits_reached(), 1);
+        let rows = engine.split_rows();
+        assert_eq!(rows[0].name, "Key");
+        assert!(rows[0].time.is_empty());
+        assert!(!rows[1].time.is_empty());
+
+        // Splits stay once recorded.
+        update_state(&mut engine, &[("boss", ObjectiveState::Locked)])?;
+        assert_eq!(engine.splits_reached(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn change_sets() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("key")
+            .objective("door")
+            .unlocked_by("complete(key)")
+            .objective("chest")
+            .build()?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let key = "key".to_string();
+        let door = "door".to_string();
+
+        // The door is re-evaluated but only the key was updated.
+        engine.toggle_state(&key)?;
+        let changes = engine.toggle_state(&key)?;
+        assert_eq!(
+            changes.changes.iter().map(|c| &c.id).collect::<Vec<_>>(),
+            vec![&key]
+        );
+        assert_eq!(
+            changes.changed,
+            vec![key.clone(), door.clone()].into_iter().collect()
+        );
+        assert!(engine.is_affected(&door, &changes));
+        assert!(!engine.is_affected(&"chest".to_string(), &changes));
+
+        // Setting a state it already has changes nothing.
+        let mut updates = HashMap::new();
+        updates.insert(key.clone(), ObjectiveState::Complete);
+        assert!(engine
+            .update_state(&updates, StateSource::Manual)?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn entrance_connections() -> Result<(), Error> {
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .entrance("house-door", "true")
+                .entrance("cave-front", "")
+                .entrance("tower-door", ""),
+        )?;
+        engine.connect_entrances(&"house-door".to_string(), &"tower-door".to_string())?;
+        let connections = engine.entrance_connections();
+        assert_eq!(connections[0].connected, Some("tower-door".to_string()));
+        assert_eq!(connections[0].state, ObjectiveState::Unlocked);
+        assert_eq!(connections[1].connected, None);
+        assert_eq!(connections[2].state, ObjectiveState::Unlocked);
+        Ok(())
+    }
+
+    #[test]
+    fn seeds() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("key")
+            .objective("door")
+            .unlocked_by("complete(key)")
+            .build()?;
+        let mut first = Engine::new(module, TestEventSink)?;
+        first.set_goals(vec!["door".to_string()]);
+        update_state(&mut first, &[("key", ObjectiveState::Complete)])?;
+
+        // Seeds share the module but not their state.
+        let second = first.new_seed()?;
+        assert!(Arc::ptr_eq(&first.module, &second.module));
+        assert_state(&first, "door", ObjectiveState::Unlocked);
+        assert_state(&second, "door", ObjectiveState::Locked);
+        assert_eq!(second.goals(), first.goals());
+
+        first.pause_clock();
+        let summary = first.seed_summary();
+        assert_eq!(summary.goals, (0, 1));
+        assert_eq!(first.seed_summary(), summary);
+
+        Ok(())
+    }
+
+    #[test]
+    fn main_layouts() -> Result<(), Error> {
+        let compact = serde_json::from_str(
+            r#"{"type": "Spacer", "window_width": 200, "window_height": 100}"#,
+        )?;
+        let module = ModuleBuilder::new()
+            .layout("main:compact", compact)
+            .build()?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let mut data = engine.new_display_state();
+        assert_eq!(engine.main_layouts(), vec!["main", "main:compact"]);
+        assert_eq!(engine.main_window_size(), None);
+
+        engine.set_main_layout("main:compact", &mut data)?;
+        assert_eq!(engine.main_layout(), "main:compact");
+        assert_eq!(engine.main_window_size(), Some((200., 100.)));
+        assert!(engine.set_main_layout("checks", &mut data).is_err());
+        assert!(engine.set_main_layout("main:vertical", &mut data).is_err());
+        assert_eq!(engine.main_layout(), "main:compact");
+
+        Ok(())
+    }
+
+    #[test]
+    fn layout_overrides() -> Result<(), Error> {
+        let grid = serde_json::from_str(
+            r#"{"type": "FlexCol", "children": [
+                {"type": "Grid", "columns": 3, "objectives": ["a", "b", "c"]}
+            ]}"#,
+        )?;
+        let module = ModuleBuilder::new()
+            .objective("a")
+            .objective("b")
+            .objective("c")
+            .layout("main", grid)
+            .build()?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let mut data = engine.new_display_state();
+        let ids = |data: &DisplayState| match &data.layout.data {
+            DisplayViewData::FlexCol(flex) => match &flex.children[0].data {
+                DisplayViewData::Grid(g) => g.children.iter().map(|c| c.id.clone()).collect(),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        assert_eq!(ids(&data), vec!["a", "b", "c"]);
+
+        engine.move_objective(&"c".to_string(), -1, &mut data)?;
+        assert_eq!(ids(&data), vec!["a", "c", "b"]);
+        // Moving past the ends does nothing.
+        engine.move_objective(&"a".to_string(), -1, &mut data)?;
+        assert_eq!(ids(&data), vec!["a", "c", "b"]);
+        engine.hide_objective(&"a".to_string(), &mut data);
+        assert_eq!(ids(&data), vec!["c", "b"]);
+        assert!(engine
+            .move_objective(&"a".to_string(), 1, &mut data)
+            .is_err());
+
+        // Seeds and later sessions get the overrides through settings.
+        let mut seed = engine.new_seed()?;
+        let mut seed_data = seed.new_display_state();
+        seed.set_main_layout("main", &mut seed_data)?;
+        assert_eq!(ids(&seed_data), vec!["c", "b"]);
+
+        engine.reset_layout_overrides(&mut data);
+        assert_eq!(ids(&data), vec!["a", "b", "c"]);
+        assert!(engine.layout_overrides().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn state_observers() -> Result<(), Error> {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        struct Recorder(Rc<RefCell<Vec<StateEvent>>>);
+        impl StateObserver for Recorder {
+            fn state_changed(&mut self, _engine: &Engine, _settings: &Settings, e: &StateEvent) {
+                self.0.borrow_mut().push(e.clone());
+            }
+        }
+
+        let mut engine = build_engine(ModuleBuilder::new().objective("key"))?;
+        let events = Rc::new(RefCell::new(Vec::new()));
+        engine.subscribe(Box::new(Recorder(events.clone())));
+        let settings = Settings::default();
+
+        let key = "key".to_string();
+        let old_state = engine.logic.objectives()[&key];
+        engine.toggle_state(&key)?;
+        let new_state = engine.logic.objectives()[&key];
+        // Nothing is told until the events are published.
+        assert!(events.borrow().is_empty());
+        engine.publish_state_events(&settings);
+        assert_eq!(
+            *events.borrow(),
+            vec![StateEvent {
+                changes: vec![StateChange {
+                    id: key.clone(),
+                    ty: "objective".to_string(),
+                    old_state,
+                    new_state,
+                }],
+                source: StateSource::Manual,
+            }]
+        );
+
+        // Module tests don't reach observers.
+        engine.run_module_tests()?;
+        engine.publish_state_events(&settings);
+        assert_eq!(events.borrow().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn go_mode() -> Result<(), Error> {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        struct Recorder(Rc<RefCell<Vec<StateChange>>>);
+        impl StateObserver for Recorder {
+            fn state_changed(&mut self, _engine: &Engine, _settings: &Settings, e: &StateEvent) {
+                self.0.borrow_mut().extend(e.changes.iter().cloned());
+            }
+        }
+
+        let mut engine = build_engine(
+            ModuleBuilder::new()
+                .objective("key")
+                .objective("sword")
+                .go_mode("key && sword"),
         )?;
-        assert_state(&engine, &"baron", ObjectiveState::Complete);
-
-        // Damncyan's character is gated by !Nchars.
-        update_state(&mut engine, &[("flag-n-chars", ObjectiveState::Disabled)])?;
-        assert_state(&engine, &"damcyan:0", ObjectiveState::Unlocked);
-        assert_state(&engine, &"damcyan", ObjectiveState::Unlocked);
-        update_state(&mut engine, &[("flag-n-chars", ObjectiveState::Unlocked)])?;
-        assert_state(&engine, &"damcyan:0", ObjectiveState::Disabled);
-        assert_state(&engine, &"damcyan", ObjectiveState::Disabled);
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        engine.subscribe(Box::new(Recorder(changes.clone())));
+        let settings = Settings::default();
+        let go_mode_changes = || {
+            changes
+                .borrow()
+                .iter()
+                .filter(|c| c.id == GO_MODE_ID)
+                .map(|c| c.new_state)
+                .collect::<Vec<_>>()
+        };
+
+        engine.toggle_state(&"key".to_string())?;
+        assert!(!engine.go_mode());
+        engine.toggle_state(&"sword".to_string())?;
+        assert!(engine.go_mode());
+        assert!(engine.new_display_state().go_mode);
+        engine.publish_state_events(&settings);
+        assert_eq!(go_mode_changes(), vec![ObjectiveState::Complete]);
+
+        // Losing what it needs leaves go mode.
+        engine.toggle_state(&"sword".to_string())?;
+        engine.publish_state_events(&settings);
+        assert!(!engine.go_mode());
+        assert_eq!(
+            go_mode_changes(),
+            vec![ObjectiveState::Complete, ObjectiveState::Unlocked]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn popups() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("key")
+            .objective("loc")
+            .check("loc:0", "")
+            .check("loc:1", "key")
+            .build()?;
+        let engine = Engine::new(module, TestEventSink)?;
+        let mut data = engine.new_display_state();
+        let grid_len = |view: &DisplayView| match &view.data {
+            DisplayViewData::Grid(g) => g.children.len(),
+            _ => 0,
+        };
+        assert_eq!(data.popup_target, "");
+        assert_eq!(grid_len(&data.popup), 0);
+
+        engine.build_popup(&mut data, &"loc".to_string())?;
+        assert_eq!(data.popup_target, "loc");
+        assert_eq!(data.popup_checks, (0, 2));
+        assert_eq!(grid_len(&data.popup), 2);
+
+        // Other views of the checks layout don't show the popup's checks.
+        let checks = engine.module.manifest.layouts["checks"].clone();
+        assert_eq!(grid_len(&DisplayView::new(&engine, &checks)), 0);
+
+        assert!(engine.build_popup(&mut data, &"cave".to_string()).is_err());
+        assert_eq!(data.popup_target, "loc");
+
+        Ok(())
+    }
+
+    #[test]
+    fn restream_safe_popup() -> Result<(), Error> {
+        let module = ModuleBuilder::new()
+            .objective("hook")
+            .objective("loc")
+            .description("Behind the waterfall")
+            .check("loc:0", "complete(hook)")
+            .build()?;
+        let engine = Engine::new(module, TestEventSink)?;
+        let mut data = engine.new_display_state();
+        engine.build_popup(&mut data, &"loc".to_string())?;
+        assert_eq!(*data.popup_notes, vec!["Behind the waterfall".to_string()]);
+        assert_eq!(data.popup_hint, "hook");
+
+        data.settings.restream_safe = true;
+        engine.update_display_state(&mut data);
+        assert!(data.popup_notes.is_empty());
+        assert_eq!(data.popup_hint, "");
+        assert_eq!(data.popup_checks, (0, 1));
+
+        data.settings.restream_safe = false;
+        engine.update_display_state(&mut data);
+        assert_eq!(data.popup_notes.len(), 1);
+        assert_eq!(data.popup_hint, "hook");
+        Ok(())
+    }
+
+    #[test]
+    fn timers() -> Result<(), Error> {
+        let module = Module::open("mods/ff4fe/manifest.json")?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let hook = "hook".to_string();
+        let key = "magma-key".to_string();
+        assert!(engine
+            .start_timer(&"nope".to_string(), 1., ObjectiveState::Unlocked)
+            .is_err());
+
+        engine.start_timer(&hook, 300., ObjectiveState::Complete)?;
+        engine.start_timer(&key, 0., ObjectiveState::Unlocked)?;
+        assert_eq!(engine.timer_text(&hook), "5:00");
+
+        let expired = engine.expired_timers();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired.get(&key), Some(&ObjectiveState::Unlocked));
+        assert_eq!(engine.timer_text(&key), "");
+        assert!(engine.has_timers());
+
+        Ok(())
+    }
+
+    #[test]
+    fn hints() -> Result<(), Error> {
+        let module = Module::open("core/src/test_data/mod/manifest.json")?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let lake_cave = "lake-cave".to_string();
+        assert!(!engine.is_hinted(&lake_cave));
+
+        engine.show_hints(&"cave-key".to_string())?;
+        assert!(engine.is_hinted(&lake_cave));
+        assert!(!engine.is_hinted(&"volcano".to_string()));
+
+        // Hints go away once the item is found.
+        update_state(&mut engine, &[("cave-key", ObjectiveState::Unlocked)])?;
+        assert!(!engine.is_hinted(&lake_cave));
+
+        Ok(())
+    }
+
+    #[test]
+    fn visible_when() -> Result<(), Error> {
+        let module = Module::open("core/src/test_data/mod/manifest.json")?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let info = engine.module.manifest.layouts.get("sets").unwrap().clone();
+        let count_visible = |view: &DisplayView| match &view.data {
+            DisplayViewData::FlexCol(f) => f.children[1].visible,
+            _ => panic!("unexpected view"),
+        };
+
+        let mut view = DisplayView::new(&engine, &info);
+        assert!(!count_visible(&view));
+
+        update_state(&mut engine, &[("cave-key", ObjectiveState::Complete)])?;
+        view.update(&engine, &info);
+        assert!(count_visible(&view));
+
+        Ok(())
+    }
+
+    #[test]
+    fn scouted_hints() -> Result<(), Error> {
+        let module = Module::open("core/src/test_data/mod/manifest.json")?;
+        let mut engine = Engine::new(module, TestEventSink)?;
+        let sword = "sword-of-might".to_string();
+        let gate = "dragon-gate".to_string();
+
+        // Items without hints don't show any.
+        engine.show_hints(&sword)?;
+        assert!(!engine.is_hinted(&gate));
+
+        engine.set_hint(&gate, &sword)?;
+        engine.show_hints(&sword)?;
+        assert!(engine.is_hinted(&gate));
+
+        assert!(engine.set_hint(&"nowhere".to_string(), &sword).is_err());
 
         Ok(())
     }
@@ -0,0 +1,391 @@
+// Reads the memory of a local PC game process.
+//
+// The module's manifest names the process and a pointer path from one of
+// its modules to the game's data.  The pointer path is followed on every
+// read since games move their data around, e.g. when loading a level.
+
+use failure::{format_err, Error};
+use pollendina_core::module::ProcessInfo;
+
+pub(crate) struct ProcessMemory {
+    process: sys::Process,
+    // Base address of the pointer path's module in the process.
+    module_base: u64,
+    info: ProcessInfo,
+}
+
+impl ProcessMemory {
+    pub fn attach(info: &ProcessInfo) -> Result<ProcessMemory, Error> {
+        let module = if info.module.is_empty() {
+            &info.name
+        } else {
+            &info.module
+        };
+        let (process, module_base) = sys::Process::open(&info.name, module)?;
+        Ok(ProcessMemory {
+            process,
+            module_base,
+            info: info.clone(),
+        })
+    }
+
+    // Read `data.len()` bytes at `offset` from where the pointer path leads.
+    pub fn read_mem(&mut self, offset: u32, data: &mut [u8]) -> Result<(), Error> {
+        let process = &self.process;
+        let size = self.info.pointer_size;
+        let base = follow_pointer_path(self.module_base, &self.info.pointer_path, |address| {
+            let mut buf = [0u8; 8];
+            process.read(address, &mut buf[..size])?;
+            Ok(u64::from_le_bytes(buf))
+        })?;
+        self.process.read(base.wrapping_add(offset as u64), data)
+    }
+}
+
+// The address `path` leads to from `base`.  `read_ptr` reads the pointer at
+// an address.
+fn follow_pointer_path(
+    base: u64,
+    path: &[u64],
+    mut read_ptr: impl FnMut(u64) -> Result<u64, Error>,
+) -> Result<u64, Error> {
+    let mut address = base;
+    for (i, offset) in path.iter().enumerate() {
+        if i > 0 {
+            address = read_ptr(address)?;
+            // Usually the game hasn't set up its data yet.
+            if address == 0 {
+                return Err(format_err!("pointer path reached a null pointer"));
+            }
+        }
+        address = address.wrapping_add(*offset);
+    }
+    Ok(address)
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::fs::{self, File};
+    use std::os::unix::fs::FileExt;
+
+    use failure::{format_err, Error};
+
+    pub struct Process {
+        mem: File,
+    }
+
+    impl Process {
+        // Open the process running `name` and find where `module` is
+        // loaded in it.
+        pub fn open(name: &str, module: &str) -> Result<(Process, u64), Error> {
+            let pid = find_pid(name)?.ok_or(format_err!("{} is not running", name))?;
+            let maps = fs::read_to_string(format!("/proc/{}/maps", pid))
+                .map_err(|e| format_err!("Can't read memory maps of {}: {}", name, e))?;
+            let base = maps
+                .lines()
+                .filter_map(|line| parse_map(line, module))
+                .min()
+                .ok_or(format_err!("{} has not loaded {}", name, module))?;
+            let mem = File::open(format!("/proc/{}/mem", pid))
+                .map_err(|e| format_err!("Can't open memory of {}: {}", name, e))?;
+            Ok((Process { mem }, base))
+        }
+
+        pub fn read(&self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+            self.mem
+                .read_exact_at(data, address)
+                .map_err(|e| format_err!("Can't read {:#x}: {}", address, e))
+        }
+    }
+
+    // Executables run under Wine have Windows paths.
+    fn file_name(path: &str) -> &str {
+        path.rsplit(|c| c == '/' || c == '\\')
+            .next()
+            .unwrap_or(path)
+    }
+
+    fn find_pid(name: &str) -> Result<Option<String>, Error> {
+        for entry in fs::read_dir("/proc")? {
+            let entry = entry?;
+            let pid = entry.file_name().to_string_lossy().into_owned();
+            if pid.parse::<u32>().is_err() {
+                continue;
+            }
+            // Processes can exit while being listed.
+            let cmdline = fs::read(entry.path().join("cmdline")).unwrap_or_default();
+            let exe = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+            if file_name(&String::from_utf8_lossy(exe)).eq_ignore_ascii_case(name) {
+                return Ok(Some(pid));
+            }
+        }
+        Ok(None)
+    }
+
+    // Start of a mapping of `module` in a line of /proc/<pid>/maps.  Paths
+    // may contain spaces.
+    pub(super) fn parse_map(line: &str, module: &str) -> Option<u64> {
+        let mut fields = line.splitn(6, ' ');
+        let range = fields.next()?;
+        let path = fields.nth(4)?.trim_start();
+        if !file_name(path).eq_ignore_ascii_case(module) {
+            return None;
+        }
+        u64::from_str_radix(range.split('-').next()?, 16).ok()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod sys {
+    use std::ffi::OsString;
+    use std::io;
+    use std::mem;
+    use std::os::raw::c_void;
+    use std::os::windows::ffi::OsStringExt;
+
+    use failure::{format_err, Error};
+
+    type Handle = *mut c_void;
+
+    const INVALID_HANDLE_VALUE: Handle = -1isize as Handle;
+    const TH32CS_SNAPPROCESS: u32 = 0x2;
+    const TH32CS_SNAPMODULE: u32 = 0x8;
+    const TH32CS_SNAPMODULE32: u32 = 0x10;
+    const PROCESS_VM_READ: u32 = 0x10;
+    const PROCESS_QUERY_INFORMATION: u32 = 0x400;
+
+    #[repr(C)]
+    struct ProcessEntry32W {
+        size: u32,
+        usage: u32,
+        process_id: u32,
+        default_heap_id: usize,
+        module_id: u32,
+        threads: u32,
+        parent_process_id: u32,
+        pri_class_base: i32,
+        flags: u32,
+        exe_file: [u16; 260],
+    }
+
+    #[repr(C)]
+    struct ModuleEntry32W {
+        size: u32,
+        module_id: u32,
+        process_id: u32,
+        global_usage: u32,
+        process_usage: u32,
+        base_addr: *mut u8,
+        base_size: u32,
+        module: Handle,
+        module_name: [u16; 256],
+        exe_path: [u16; 260],
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateToolhelp32Snapshot(flags: u32, process_id: u32) -> Handle;
+        fn Process32FirstW(snapshot: Handle, entry: *mut ProcessEntry32W) -> i32;
+        fn Process32NextW(snapshot: Handle, entry: *mut ProcessEntry32W) -> i32;
+        fn Module32FirstW(snapshot: Handle, entry: *mut ModuleEntry32W) -> i32;
+        fn Module32NextW(snapshot: Handle, entry: *mut ModuleEntry32W) -> i32;
+        fn OpenProcess(access: u32, inherit_handle: i32, process_id: u32) -> Handle;
+        fn ReadProcessMemory(
+            process: Handle,
+            base_address: *const c_void,
+            buffer: *mut c_void,
+            size: usize,
+            bytes_read: *mut usize,
+        ) -> i32;
+        fn CloseHandle(handle: Handle) -> i32;
+    }
+
+    pub struct Process {
+        handle: Handle,
+    }
+
+    // The handle is only used from the auto tracker thread it's moved to.
+    unsafe impl Send for Process {}
+
+    impl Drop for Process {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.handle) };
+        }
+    }
+
+    impl Process {
+        // Open the process running `name` and find where `module` is
+        // loaded in it.
+        pub fn open(name: &str, module: &str) -> Result<(Process, u64), Error> {
+            let pid = find_pid(name)?.ok_or(format_err!("{} is not running", name))?;
+            let base = module_base(pid, module)?.ok_or(format_err!(
+                "{} has not loaded {}",
+                name,
+                module
+            ))?;
+            let handle =
+                unsafe { OpenProcess(PROCESS_VM_READ | PROCESS_QUERY_INFORMATION, 0, pid) };
+            if handle.is_null() {
+                return Err(format_err!(
+                    "Can't open {}: {}",
+                    name,
+                    io::Error::last_os_error()
+                ));
+            }
+            Ok((Process { handle }, base))
+        }
+
+        pub fn read(&self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+            let mut read = 0;
+            let ok = unsafe {
+                ReadProcessMemory(
+                    self.handle,
+                    address as usize as *const c_void,
+                    data.as_mut_ptr() as *mut c_void,
+                    data.len(),
+                    &mut read,
+                )
+            };
+            if ok == 0 || read != data.len() {
+                return Err(format_err!(
+                    "Can't read {:#x}: {}",
+                    address,
+                    io::Error::last_os_error()
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    struct Snapshot(Handle);
+
+    impl Snapshot {
+        fn new(flags: u32, pid: u32) -> Result<Snapshot, Error> {
+            let handle = unsafe { CreateToolhelp32Snapshot(flags, pid) };
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(format_err!(
+                    "Can't list processes: {}",
+                    io::Error::last_os_error()
+                ));
+            }
+            Ok(Snapshot(handle))
+        }
+    }
+
+    impl Drop for Snapshot {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    fn wide_to_string(wide: &[u16]) -> String {
+        let len = wide.iter().position(|c| *c == 0).unwrap_or(wide.len());
+        OsString::from_wide(&wide[..len])
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn find_pid(name: &str) -> Result<Option<u32>, Error> {
+        let snapshot = Snapshot::new(TH32CS_SNAPPROCESS, 0)?;
+        let mut entry: ProcessEntry32W = unsafe { mem::zeroed() };
+        entry.size = mem::size_of::<ProcessEntry32W>() as u32;
+        let mut found = unsafe { Process32FirstW(snapshot.0, &mut entry) } != 0;
+        while found {
+            if wide_to_string(&entry.exe_file).eq_ignore_ascii_case(name) {
+                return Ok(Some(entry.process_id));
+            }
+            found = unsafe { Process32NextW(snapshot.0, &mut entry) } != 0;
+        }
+        Ok(None)
+    }
+
+    fn module_base(pid: u32, module: &str) -> Result<Option<u64>, Error> {
+        let snapshot = Snapshot::new(TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32, pid)?;
+        let mut entry: ModuleEntry32W = unsafe { mem::zeroed() };
+        entry.size = mem::size_of::<ModuleEntry32W>() as u32;
+        let mut found = unsafe { Module32FirstW(snapshot.0, &mut entry) } != 0;
+        while found {
+            if wide_to_string(&entry.module_name).eq_ignore_ascii_case(module) {
+                return Ok(Some(entry.base_addr as usize as u64));
+            }
+            found = unsafe { Module32NextW(snapshot.0, &mut entry) } != 0;
+        }
+        Ok(None)
+    }
+}
+
+// macOS needs an entitlement to read other processes.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+mod sys {
+    use failure::{format_err, Error};
+
+    pub struct Process;
+
+    impl Process {
+        pub fn open(_name: &str, _module: &str) -> Result<(Process, u64), Error> {
+            Err(format_err!(
+                "Reading game processes is not supported on this platform"
+            ))
+        }
+
+        pub fn read(&self, _address: u64, _data: &mut [u8]) -> Result<(), Error> {
+            Err(format_err!(
+                "Reading game processes is not supported on this platform"
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pointer_paths() -> Result<(), Error> {
+        let read_ptr = |address| match address {
+            0x1010 => Ok(0x2000),
+            0x2008 => Ok(0x3000),
+            0x3008 => Ok(0),
+            _ => Err(format_err!("bad read {:#x}", address)),
+        };
+        assert_eq!(follow_pointer_path(0x1000, &[], read_ptr)?, 0x1000);
+        assert_eq!(follow_pointer_path(0x1000, &[0x10], read_ptr)?, 0x1010);
+        assert_eq!(
+            follow_pointer_path(0x1000, &[0x10, 0x8, 0x20], read_ptr)?,
+            0x3020
+        );
+        assert!(follow_pointer_path(0x1000, &[0x10, 0x8, 0x8, 0], read_ptr).is_err());
+        assert!(follow_pointer_path(0x1000, &[0x20, 0], read_ptr).is_err());
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn maps_lines() {
+        assert_eq!(
+            sys::parse_map(
+                "555555554000-555555556000 r--p 00000000 08:01 1234                       /opt/game/Game",
+                "game"
+            ),
+            Some(0x5555_5555_4000)
+        );
+        assert_eq!(
+            sys::parse_map(
+                "140000000-140001000 r--p 00000000 08:01 99    /home/a/.wine/drive_c/Program Files/Game/game.exe",
+                "game.exe"
+            ),
+            Some(0x1_4000_0000)
+        );
+        assert_eq!(
+            sys::parse_map(
+                "7ffc0000-7ffc1000 rw-p 00000000 00:00 0                          [stack]",
+                "game"
+            ),
+            None
+        );
+        assert_eq!(
+            sys::parse_map("7ffc0000-7ffc1000 rw-p 00000000 00:00 0 ", "game"),
+            None
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::thread;
+
+use crate::engine::{Engine, Settings, StateEvent, StateObserver};
+
+// Splits LiveSplit's timer through its server component when the tracker
+// records a split.
+pub(crate) struct LiveSplitter {
+    // Splits reached the last time the engine was looked at.
+    reached: usize,
+}
+
+impl LiveSplitter {
+    pub fn new() -> LiveSplitter {
+        LiveSplitter { reached: 0 }
+    }
+}
+
+impl StateObserver for LiveSplitter {
+    fn state_changed(&mut self, engine: &Engine, settings: &Settings, _event: &StateEvent) {
+        let reached = engine.splits_reached();
+        // Fewer splits means another seed is being played.
+        let new_splits = reached.saturating_sub(self.reached);
+        self.reached = reached;
+        if new_splits == 0 || settings.livesplit_server.is_empty() {
+            return;
+        }
+
+        let server = settings.livesplit_server.clone();
+        thread::spawn(move || {
+            if let Err(e) = send_splits(&server, new_splits) {
+                println!("error splitting LiveSplit at {}: {}", server, e);
+            }
+        });
+    }
+}
+
+// `startorsplit` starts the timer for the first split of a run.
+fn send_splits(server: &str, count: usize) -> io::Result<()> {
+    let mut stream = TcpStream::connect(server)?;
+    for _ in 0..count {
+        stream.write_all(b"startorsplit\r\n")?;
+    }
+    Ok(())
+}
@@ -4,46 +4,213 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use druid::widget::{Button, Checkbox, Flex, Label, List, Padding, TextBox};
+use druid::widget::{
+    Button, Checkbox, CrossAxisAlignment, Either, EnvScope, Flex, Label, List, Padding, Painter,
+    ProgressBar, Scroll, SizedBox, Slider, Stepper, TextBox,
+};
 use druid::{
-    platform_menus, AppDelegate, AppLauncher, Command, Data, DelegateCtx, Env, ExtEventError,
-    ExtEventSink, LocalizedString, MenuDesc, Point, Selector, Target, Widget, WidgetExt,
-    WindowDesc, WindowId,
+    lens, platform_menus, theme, AppDelegate, AppLauncher, Application, Color, Command, Data,
+    DelegateCtx, Env, ExtEventError, ExtEventSink, FileDialogOptions, FileSpec, Key, LensExt,
+    LocalizedString, MenuDesc, MenuItem, Point, RenderContext, Selector, SysMods, Target, Widget,
+    WidgetExt, WindowDesc, WindowId,
 };
 use failure::{format_err, Error};
 use match_macro::match_widget;
 
 mod assets;
+mod audio;
+mod cli;
 mod engine;
+mod livesplit;
+mod module_picker;
+mod monitors;
+mod notifications;
+mod settings;
+mod share;
+mod speech;
+mod update;
 mod views;
 mod widget;
 
+use audio::AudioPlayer;
+use cli::Args;
+use engine::event_log::format_duration;
 use engine::{
-    AutoTrackerState, CheckBoxParamValue, DisplayState, Engine, EventSink, Module, ModuleParam,
-    ModuleParamValue, ObjectiveState,
+    AssignmentChoice, AutoTrackerState, CheckBoxParamValue, CheckMark, DisplayState, DisplayView,
+    Engine, EntranceConnection, EventSink, Goal, GoalStatus, GoalsState, Module, ModuleParam,
+    ModuleParamValue, ModuleTestResult, ObjectiveState, SeedSummary, SeedTab, Settings,
+    StateSource, WatchDebug,
+};
+use livesplit::LiveSplitter;
+use notifications::Notifier;
+use pollendina_core::module::MODULE_FILE_EXTENSIONS;
+use settings::{get_settings_path, get_user_module_dir, SettingsFile};
+use share::{ShareHost, Spectator, ENGINE_HANDED_OVER, ENGINE_SPECTATE_STATE};
+use speech::{state_change_phrase, Announcer};
+use update::{check_for_update, install_update, UpdateInfo, UI_UPDATE_AVAILABLE};
+use views::{display_widget, DOUBLE_CLICK_COMPLETE, WHEEL_STEP};
+use widget::{
+    Asset, DependencyGraphView, DynFlex, ModalHost, OnChangeExt, OverlayFrame, Scale, Stack, Toast,
+    Tooltip, DENSITY, MAP_HEATMAP, NO_BACKGROUNDS, RESTREAM_SAFE, SHAPE_INDICATORS,
 };
-use views::display_widget;
-use widget::ModalHost;
 
 pub(crate) const UI_OPEN_CONFIG: Selector<()> = Selector::new("ui:open_config");
 pub(crate) const UI_CANCEL_CONFIG: Selector<()> = Selector::new("ui:cancel_config");
 pub(crate) const UI_APPLY_CONFIG: Selector<()> = Selector::new("ui:update_config");
+pub(crate) const UI_OPEN_GOALS: Selector<()> = Selector::new("ui:open_goals");
+pub(crate) const UI_CANCEL_GOALS: Selector<()> = Selector::new("ui:cancel_goals");
+pub(crate) const UI_APPLY_GOALS: Selector<()> = Selector::new("ui:apply_goals");
 const UI_OPEN_POPUP: Selector<((f64, f64), String)> = Selector::new("ui:open_popup");
+// Shows the tooltip of an objective at a window position, or hides it.
+const UI_SHOW_TOOLTIP: Selector<((f64, f64), String)> = Selector::new("ui:show_tooltip");
+const UI_HIDE_TOOLTIP: Selector<()> = Selector::new("ui:hide_tooltip");
+pub(crate) const UI_UNDO: Selector<()> = Selector::new("ui:undo");
+const UI_FLUSH_BROADCAST: Selector<()> = Selector::new("ui:flush_broadcast");
+const UI_TICK_TIMERS: Selector<()> = Selector::new("ui:tick_timers");
+const UI_INSTALL_UPDATE: Selector<()> = Selector::new("ui:install_update");
 
 pub(crate) const UI_OPEN_BROADCAST: Selector<()> = Selector::new("ui:open_broadcast");
+pub(crate) const UI_OPEN_WATCHES: Selector<()> = Selector::new("ui:open_watches");
+const UI_OPEN_LOGIC: Selector<()> = Selector::new("ui:open_logic");
+const UI_OPEN_GRAPH: Selector<()> = Selector::new("ui:open_graph");
+// Sent when the logic console's expression is edited.
+const UI_EVAL_EXPRESSION: Selector<()> = Selector::new("ui:eval_expression");
+// Opens the timeline window and shows the main layout at the time its
+// slider picks.
+const UI_OPEN_TIMELINE: Selector<()> = Selector::new("ui:open_timeline");
+const UI_SCRUB_TIMELINE: Selector<()> = Selector::new("ui:scrub_timeline");
+// Switches the main window to the named main layout variant.
+const UI_SET_MAIN_LAYOUT: Selector<String> = Selector::new("ui:set_main_layout");
+// Seeds of a multi-seed session, such as the games of a best-of-3 race.
+const UI_ADD_SEED: Selector<()> = Selector::new("ui:add_seed");
+const UI_SWITCH_SEED: Selector<usize> = Selector::new("ui:switch_seed");
+const UI_SHOW_SEED_SUMMARY: Selector<()> = Selector::new("ui:show_seed_summary");
+// Starts sharing the tracker's state and copies the link spectators open.
+const UI_SHARE_READ_ONLY: Selector<()> = Selector::new("ui:share_read_only");
+// Asks whether to take over the spectated session.
+const UI_CONFIRM_TAKE_OVER: Selector<()> = Selector::new("ui:confirm_take_over");
+// Stops spectating and carries on the session here, starting auto tracking
+// if set.
+const UI_TAKE_OVER: Selector<bool> = Selector::new("ui:take_over");
+// Lists the module's entrances and, for one entrance, where it can lead.
+const UI_OPEN_ENTRANCES: Selector<()> = Selector::new("ui:open_entrances");
+const UI_PICK_ENTRANCE: Selector<String> = Selector::new("ui:pick_entrance");
+const UI_TOGGLE_PARAM_GROUP: Selector<String> = Selector::new("ui:toggle_param_group");
 
 pub(crate) const ENGINE_TOGGLE_STATE: Selector<String> = Selector::new("engine:toggle_state");
+// Moves an objective back to the state before the one a click moves it to.
+pub(crate) const ENGINE_STEP_STATE_BACK: Selector<String> = Selector::new("engine:step_state_back");
+// Marks a locked objective reachable out of logic or back to locked.
+pub(crate) const ENGINE_TOGGLE_GLITCH_LOCKED: Selector<String> =
+    Selector::new("engine:toggle_glitch_locked");
+pub(crate) const ENGINE_SHOW_HINTS: Selector<String> = Selector::new("engine:show_hints");
+// Pins an objective at its state or releases it.
+pub(crate) const ENGINE_TOGGLE_LOCK: Selector<String> = Selector::new("engine:toggle_lock");
+// Asks before changing many objectives at once.
+pub(crate) const UI_CONFIRM_BATCH: Selector<BatchAction> = Selector::new("ui:confirm_batch");
+// Applies updates evaluating the objectives only once at the end.
+const ENGINE_BATCH_UPDATE: Selector<HashMap<String, ObjectiveState>> =
+    Selector::new("engine:batch_update");
+// Marks a check junk or with the item found there, or clears its mark.
+pub(crate) const ENGINE_MARK_CHECK: Selector<(String, Option<CheckMark>)> =
+    Selector::new("engine:mark_check");
+// Lists the items that can be recorded as found at a check.
+pub(crate) const UI_PICK_CHECK_ITEM: Selector<String> = Selector::new("ui:pick_check_item");
+// Lists the values a slot of an assignment can be given, by (assignment,
+// slot).
+pub(crate) const UI_PICK_ASSIGNMENT: Selector<(String, String)> =
+    Selector::new("ui:pick_assignment");
+// Opens the editor of the user's note on an objective.
+pub(crate) const UI_EDIT_NOTE: Selector<String> = Selector::new("ui:edit_note");
+// Saves the note being edited as the note on an objective.
+const ENGINE_SET_NOTE: Selector<String> = Selector::new("engine:set_note");
+// Assigns a value to a slot of an assignment, by (assignment, slot, value),
+// or clears the slot.
+const ENGINE_ASSIGN: Selector<(String, String, Option<String>)> = Selector::new("engine:assign");
+// Leaves an objective out of the main layout's grids.
+pub(crate) const UI_HIDE_OBJECTIVE: Selector<String> = Selector::new("ui:hide_objective");
+// Moves an objective by an offset within its grid of the main layout.
+pub(crate) const UI_MOVE_OBJECTIVE: Selector<(String, isize)> = Selector::new("ui:move_objective");
+// Drops the user's hidden and reordered objectives of the main layout.
+const UI_RESET_LAYOUT_OVERRIDES: Selector<()> = Selector::new("ui:reset_layout_overrides");
 pub(crate) const ENGINE_UPDATE_STATE: Selector<HashMap<String, ObjectiveState>> =
     Selector::new("engine:update_state");
+// Items scouted by the auto tracker keyed by location.
+pub(crate) const ENGINE_SET_HINTS: Selector<HashMap<String, String>> =
+    Selector::new("engine:set_hints");
+// Stages of staged objectives set by the auto tracker script.
+pub(crate) const ENGINE_SET_STAGES: Selector<HashMap<String, u32>> =
+    Selector::new("engine:set_stages");
+// Timers started by the auto tracker script as lengths in seconds and the
+// states they set, keyed by objective.
+pub(crate) const ENGINE_START_TIMERS: Selector<HashMap<String, (f64, ObjectiveState)>> =
+    Selector::new("engine:start_timers");
 pub(crate) const ENGINE_DUMP_STATE: Selector<()> = Selector::new("engine:dump_state");
+const UI_COPY_DUMP: Selector<()> = Selector::new("ui:copy_dump");
+const UI_COPY_SUMMARY: Selector<()> = Selector::new("ui:copy_summary");
+pub(crate) const ENGINE_RUN_TESTS: Selector<()> = Selector::new("engine:run_tests");
+// Entrances the player found to lead to each other.
+pub(crate) const ENGINE_CONNECT_ENTRANCES: Selector<(String, String)> =
+    Selector::new("engine:connect_entrances");
+pub(crate) const ENGINE_DISCONNECT_ENTRANCE: Selector<String> =
+    Selector::new("engine:disconnect_entrance");
 
 pub(crate) const ENGINE_UPDATE_AUTO_TRACKER_STATE: Selector<AutoTrackerState> =
     Selector::new("engine:update_auto_tracker_state");
+// Sent by the consent prompt when the user allows reading the game process.
+const UI_ALLOW_PROCESS_ACCESS: Selector<String> = Selector::new("ui:allow_process_access");
 pub(crate) const ENGINE_START_AUTO_TRACKING: Selector<()> =
     Selector::new("engine:start_auto_tracking");
 pub(crate) const ENGINE_STOP_AUTO_TRACKING: Selector<()> =
     Selector::new("engine:stop_auto_tracking");
+pub(crate) const ENGINE_LIST_DEVICES: Selector<()> = Selector::new("engine:list_devices");
+pub(crate) const ENGINE_UPDATE_DEVICES: Selector<Vec<String>> =
+    Selector::new("engine:update_devices");
+// ROM version detected by the auto tracker script.  Empty when not tracking.
+pub(crate) const ENGINE_UPDATE_ROM_VERSION: Selector<String> =
+    Selector::new("engine:update_rom_version");
+// Watch debugger info sent after each read while the watches window is open.
+pub(crate) const ENGINE_UPDATE_WATCH_DEBUG: Selector<Vec<WatchDebug>> =
+    Selector::new("engine:update_watch_debug");
+
+// Changes to many objectives at once, each confirmed first.
+#[derive(Clone)]
+pub(crate) enum BatchAction {
+    // Completes every check of a location.
+    CompleteChecks(String),
+    // Puts every check of a location back to locked.
+    ResetChecks(String),
+    // Completes everything in the main layout grid showing an objective.
+    CompleteGrid(String),
+}
+
+// Density of the main window in compact mode.  See `DENSITY`.
+const COMPACT_DENSITY: f64 = 0.6;
+
+// Images in tooltips are scaled to fit in this size.
+const TOOLTIP_IMAGE_SIZE: (f64, f64) = (240.0, 160.0);
+
+// Go mode is shown by a badge in the action bar and a glow this wide
+// around the edge of the broadcast window.
+const GO_MODE_COLOR: Color = Color::rgb8(0xff, 0xc8, 0x2e);
+const GO_MODE_GLOW_WIDTH: f64 = 8.0;
+
+// Title the broadcast window is found by when placing it on a monitor.
+const BROADCAST_WINDOW_TITLE: &str = "Broadcast View";
+
+// Size of the broadcast preview relative to the broadcast window.
+const BROADCAST_PREVIEW_SCALE: f64 = 0.5;
+// Broadcast window size to preview for layouts that don't fix one.
+const BROADCAST_PREVIEW_DEFAULT_SIZE: (f64, f64) = (400., 300.);
+
+const MARKDOWN_FILE_TYPE: FileSpec = FileSpec::new("Markdown", &["md"]);
+const JSON_FILE_TYPE: FileSpec = FileSpec::new("JSON", &["json"]);
+const YAML_FILE_TYPE: FileSpec = FileSpec::new("YAML", &["yaml", "yml"]);
+const SAVE_FILE_TYPE: FileSpec = FileSpec::new("Save file", &["srm", "sav"]);
+const SAVE_STATE_FILE_TYPE: FileSpec = FileSpec::new("Save state", &["state", "sst"]);
 
 #[derive(Clone)]
 struct ExtEventSinkProxy(ExtEventSink);
@@ -61,6 +228,32 @@ impl EventSink for ExtEventSinkProxy {
 
 struct Delegate {
     engine: Engine,
+    settings_path: PathBuf,
+    settings_file: SettingsFile,
+    // States to restore when the undo toast is clicked.
+    undo: HashMap<String, ObjectiveState>,
+    sink: ExtEventSink,
+    // When the broadcast view last changed and whether held back changes are
+    // waiting to be shown.
+    broadcast_updated: Instant,
+    broadcast_flush_pending: bool,
+    // Whether a tick of the objective timers is scheduled.
+    timer_tick_pending: bool,
+    // Newer release found by the update check.
+    update: Option<UpdateInfo>,
+    main_win: WindowId,
+    // Every seed of a multi-seed session by tab.  The slot of the active
+    // seed is empty as it is `engine`.  Empty until a second seed is added.
+    seeds: Vec<Option<Engine>>,
+    // Spectators watching this tracker, once sharing has started.
+    share: Option<ShareHost>,
+    // Connection to the tracker being watched while spectating.
+    spectator: Option<Spectator>,
+    // How spectators find the module.  See `ShareHost::start`.
+    module_path: String,
+    // Unknown ids and assets that failed to load.  Shown once the main
+    // window opens.
+    load_problems: Vec<String>,
 }
 
 impl Delegate {
@@ -73,16 +266,241 @@ impl Delegate {
             None => println!("tried closing config window when not open"),
         }
     }
-}
 
-impl AppDelegate<DisplayState> for Delegate {
-    fn command(
+    fn close_goals_window(&self, data: &mut DisplayState, ctx: &mut DelegateCtx) {
+        match *data.goals_win {
+            Some(id) => {
+                let command = Command::new(druid::commands::CLOSE_WINDOW, ());
+                ctx.submit_command(command, id);
+            }
+            None => println!("tried closing goals window when not open"),
+        }
+    }
+
+    fn save_settings(&mut self, data: &DisplayState) {
+        self.settings_file.settings = data.settings.clone();
+        let module_settings = self
+            .settings_file
+            .modules
+            .entry(self.engine.module_name().clone())
+            .or_default();
+        module_settings.goals = self.engine.goals().clone();
+        module_settings.script_storage = self.engine.script_storage();
+        module_settings.main_layout = self.engine.main_layout().clone();
+        module_settings.auto_track_script = self.engine.auto_track_script().clone();
+        module_settings.layout_overrides = self.engine.layout_overrides().clone();
+        if let Err(e) = self.settings_file.save(&self.settings_path) {
+            println!("error saving settings: {}", e);
+        }
+    }
+
+    // Keeps the other seeds' layouts in step with the active one and saves
+    // the user's layout overrides.
+    fn layout_overrides_changed(&mut self, data: &DisplayState) {
+        for seed in self.seeds.iter_mut().flatten() {
+            seed.set_layout_overrides(self.engine.layout_overrides().clone());
+        }
+        self.save_settings(data);
+    }
+
+    // Shows the main layout at the time picked in the timeline window.
+    fn scrub_timeline(&mut self, data: &mut DisplayState) {
+        let elapsed = self.engine.elapsed();
+        let time = elapsed.mul_f64(data.timeline_pos.max(0.0).min(1.0));
+        match self.engine.view_at(time) {
+            Ok(view) => data.timeline_view = view,
+            Err(e) => println!("error rewinding to {}: {}", format_duration(&time), e),
+        }
+        data.timeline_time = format!("{} / {}", format_duration(&time), format_duration(&elapsed));
+    }
+
+    fn add_seed(&mut self, data: &mut DisplayState) -> Result<(), Error> {
+        let seed = self.engine.new_seed()?;
+        if self.seeds.is_empty() {
+            self.seeds.push(None);
+        }
+        self.seeds.push(Some(seed));
+        data.seeds = Arc::new(
+            (0..self.seeds.len())
+                .map(|index| SeedTab { index })
+                .collect(),
+        );
+        self.switch_seed(self.seeds.len() - 1, data)
+    }
+
+    fn switch_seed(&mut self, index: usize, data: &mut DisplayState) -> Result<(), Error> {
+        if index == data.active_seed {
+            return Ok(());
+        }
+        let mut engine = self
+            .seeds
+            .get_mut(index)
+            .and_then(Option::take)
+            .ok_or(format_err!("no seed {}", index + 1))?;
+
+        // Only the seed being played keeps time and is auto tracked.
+        self.engine.pause_clock();
+        self.engine.hand_over_auto_tracker(&mut engine);
+        self.engine.hand_over_observers(&mut engine);
+        engine.resume_clock();
+        let layout = self.engine.main_layout().clone();
+        let engine = std::mem::replace(&mut self.engine, engine);
+        self.seeds[data.active_seed] = Some(engine);
+        data.active_seed = index;
+
+        self.undo.clear();
+        data.toast.clear();
+        self.engine.apply_settings(data);
+        self.engine.update_param_state(data);
+        self.engine.set_main_layout(&layout, data)
+    }
+
+    // Shows the first two seeds side by side in the broadcast window while
+    // it is split.
+    fn update_split_broadcast(&self, data: &mut DisplayState) {
+        if !data.split_broadcast() {
+            return;
+        }
+        let seed = |index: usize| match &self.seeds[index] {
+            Some(engine) => engine,
+            None => &self.engine,
+        };
+        seed(0).update_broadcast_view(&mut data.broadcast);
+        seed(1).update_broadcast_view(&mut data.broadcast_split);
+    }
+
+    // Size of the broadcast window, which is twice as wide while it is
+    // split.
+    fn broadcast_window_size(&self, data: &DisplayState) -> Option<(f64, f64)> {
+        let size = self.engine.broadcast_window_size();
+        match size {
+            Some((width, height)) if data.split_broadcast() => Some((width * 2., height)),
+            _ => size,
+        }
+    }
+
+    fn seed_summaries(&self) -> Vec<SeedSummary> {
+        self.seeds
+            .iter()
+            .map(|seed| match seed {
+                Some(engine) => engine.seed_summary(),
+                None => self.engine.seed_summary(),
+            })
+            .collect()
+    }
+
+    // Starts sharing on `address` the first time.  See `ShareHost::start`.
+    fn share_read_only(&mut self, address: &str) -> Result<String, Error> {
+        if self.share.is_none() {
+            self.share = Some(ShareHost::start(
+                self.engine.module_name(),
+                &self.module_path,
+                address,
+                self.sink.clone(),
+            )?);
+        }
+        let share = self.share.as_ref().unwrap();
+        share.publish(
+            &self.engine.objective_states(),
+            &self.engine.script_storage(),
+        )?;
+        Application::global().clipboard().put_string(share.link());
+        Ok(share.link().clone())
+    }
+
+    fn has_process_consent(&self, process: &str) -> bool {
+        self.settings_file
+            .modules
+            .get(self.engine.module_name())
+            .map_or(false, |m| m.process_consent == process)
+    }
+
+    // Apply objective states from the auto tracker.  The observers play the
+    // sounds, notifications and announcements they come with.
+    fn apply_tracked_updates(
+        &mut self,
+        data: &mut DisplayState,
+        updates: &HashMap<String, ObjectiveState>,
+    ) {
+        match self.engine.update_state(updates, StateSource::AutoTracker) {
+            Ok(changes) => self.engine.update_display_changes(data, &changes),
+            Err(e) => println!("error updating state: {}", e),
+        }
+    }
+
+    // Tick the objective timers once a second while any are running so their
+    // badges count down.
+    fn schedule_timer_tick(&mut self) {
+        if self.timer_tick_pending || !self.engine.has_timers() {
+            return;
+        }
+        self.timer_tick_pending = true;
+        let sink = self.sink.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(1));
+            if let Err(e) = sink.submit_command(UI_TICK_TIMERS, (), None) {
+                println!("error ticking timers: {}", e);
+            }
+        });
+    }
+
+    // Write a session report to `path`.  The format is picked based on the
+    // file's extension.
+    fn save_report(&self, data: &DisplayState, path: &Path) -> Result<(), Error> {
+        let report = self.engine.session_report(&data.params);
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some("htm") | Some("html") => report.to_html(),
+            _ => report.to_markdown(),
+        };
+        std::fs::write(path, contents)?;
+        println!("saved report to {}", path.display());
+        Ok(())
+    }
+
+    fn save_dump(&self, path: &Path) -> Result<(), Error> {
+        let dump = self.engine.state_dump()?;
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => dump.to_yaml()?,
+            _ => dump.to_json()?,
+        };
+        std::fs::write(path, contents)?;
+        println!("saved state dump to {}", path.display());
+        Ok(())
+    }
+
+    // Hold back broadcast view changes that come sooner than the configured
+    // interval after the last one.  They're shown by a later flush.
+    fn throttle_broadcast(&mut self, data: &mut DisplayState, old: (DisplayView, DisplayView)) {
+        if data.broadcast.same(&old.0) && data.broadcast_split.same(&old.1) {
+            return;
+        }
+        let interval = Duration::from_millis(data.settings.broadcast_update_ms as u64);
+        let elapsed = self.broadcast_updated.elapsed();
+        if elapsed >= interval {
+            self.broadcast_updated = Instant::now();
+            return;
+        }
+
+        data.broadcast = old.0;
+        data.broadcast_split = old.1;
+        if !self.broadcast_flush_pending {
+            self.broadcast_flush_pending = true;
+            let sink = self.sink.clone();
+            thread::spawn(move || {
+                thread::sleep(interval - elapsed);
+                if let Err(e) = sink.submit_command(UI_FLUSH_BROADCAST, (), None) {
+                    println!("error flushing broadcast view: {}", e);
+                }
+            });
+        }
+    }
+
+    fn handle_command(
         &mut self,
         ctx: &mut DelegateCtx,
-        _target: Target,
+        target: Target,
         cmd: &Command,
         data: &mut DisplayState,
-        _env: &Env,
     ) -> bool {
         if cmd.is(UI_OPEN_BROADCAST) {
             match *data.broadcast_win {
@@ -92,17 +510,221 @@ impl AppDelegate<DisplayState> for Delegate {
                 }
                 None => {
                     self.engine.update_param_state(data);
-                    let mut window = WindowDesc::new(broadcast_ui_builder).title("Broadcast View");
+                    let overlay = data.settings.broadcast_overlay;
+                    let mut window = WindowDesc::new(move || -> Box<dyn Widget<DisplayState>> {
+                        if overlay {
+                            Box::new(OverlayFrame::new(
+                                broadcast_ui_builder(),
+                                BROADCAST_WINDOW_TITLE,
+                            ))
+                        } else {
+                            Box::new(broadcast_ui_builder())
+                        }
+                    })
+                    .title(
+                        LocalizedString::new("pollendina-broadcast-window-title")
+                            .with_placeholder(BROADCAST_WINDOW_TITLE),
+                    );
 
-                    if let Some(size) = self.engine.broadcast_window_size() {
+                    if let Some(size) = self.broadcast_window_size(data) {
                         window = window.window_size(size).resizable(false);
                     }
+                    if overlay {
+                        // Without a title bar the window manager can't raise
+                        // it over the game.
+                        window = window.show_titlebar(false);
+                        monitors::keep_above(BROADCAST_WINDOW_TITLE);
+                    }
                     let win_id = window.id;
                     ctx.new_window(window);
+                    if !data.settings.broadcast_monitor.is_empty() {
+                        monitors::place_window(
+                            BROADCAST_WINDOW_TITLE,
+                            &data.settings.broadcast_monitor,
+                            data.settings.broadcast_x,
+                            data.settings.broadcast_y,
+                        );
+                    }
                     *Arc::make_mut(&mut data.broadcast_win) = Some(win_id);
                 }
             };
             false
+        } else if let Some(name) = cmd.get(UI_SET_MAIN_LAYOUT) {
+            if name == self.engine.main_layout() {
+                return false;
+            }
+            if let Err(e) = self.engine.set_main_layout(name, data) {
+                println!("error switching layout: {}", e);
+                return false;
+            }
+            // Windows can't be resized once open so one sized for the
+            // layout replaces the main window.
+            if let Some(size) = self.engine.main_window_size() {
+                let window = main_window(&self.engine.main_layouts(), Some(size));
+                let old_win = std::mem::replace(&mut self.main_win, window.id);
+                ctx.new_window(window);
+                let command = Command::new(druid::commands::CLOSE_WINDOW, ());
+                ctx.submit_command(command, old_win);
+            }
+            self.save_settings(data);
+            false
+        } else if cmd.is(UI_ADD_SEED) {
+            if let Err(e) = self.add_seed(data) {
+                println!("error adding seed: {}", e);
+            }
+            ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+            false
+        } else if let Some(index) = cmd.get(UI_SWITCH_SEED) {
+            if let Err(e) = self.switch_seed(*index, data) {
+                println!("error switching seed: {}", e);
+            }
+            ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+            false
+        } else if cmd.is(UI_SHOW_SEED_SUMMARY) {
+            let summaries = self.seed_summaries();
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                seed_summary_builder(summaries)
+            });
+            ctx.submit_command(cmd, None);
+            false
+        } else if cmd.is(UI_SHARE_READ_ONLY) {
+            match self.share_read_only(&data.settings.share_address) {
+                Ok(link) => {
+                    let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                        share_link_builder(link)
+                    });
+                    ctx.submit_command(cmd, None);
+                }
+                Err(e) => println!("error sharing tracker: {}", e),
+            }
+            false
+        } else if let Some(state) = cmd.get(ENGINE_SPECTATE_STATE) {
+            // Lines can still be in flight after taking over.
+            if data.spectating.is_empty() {
+                return false;
+            }
+            if let Err(e) = self.engine.update_state(&state.states, StateSource::Manual) {
+                println!("error updating spectated state: {}", e);
+            }
+            self.engine.set_script_storage(state.script_storage.clone());
+            self.engine.update_param_state(data);
+            self.engine.update_display_state(data);
+            true
+        } else if cmd.is(UI_CONFIRM_TAKE_OVER) {
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), take_over_builder);
+            ctx.submit_command(cmd, None);
+            false
+        } else if let Some(auto_track) = cmd.get(UI_TAKE_OVER) {
+            ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+            if let Some(spectator) = self.spectator.take() {
+                if let Err(e) = spectator.take_over() {
+                    println!("error taking over session: {}", e);
+                }
+            }
+            data.spectating.clear();
+            if *auto_track {
+                ctx.submit_command(ENGINE_START_AUTO_TRACKING, None);
+            }
+            false
+        } else if cmd.is(ENGINE_HANDED_OVER) {
+            // Two trackers auto tracking the same session would fight.
+            if let Err(e) = self.engine.stop_auto_tracking() {
+                println!("error stopping auto tracking: {}", e);
+            }
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), handed_over_builder);
+            ctx.submit_command(cmd, None);
+            true
+        } else if cmd.is(UI_OPEN_WATCHES) {
+            match *data.watches_win {
+                Some(id) => {
+                    let command = Command::new(druid::commands::SHOW_WINDOW, ());
+                    ctx.submit_command(command, id);
+                }
+                None => {
+                    if let Err(e) = self.engine.set_watch_debug(true) {
+                        println!("error opening watches window: {}", e);
+                        return false;
+                    }
+                    let window = WindowDesc::new(watches_ui_builder)
+                        .title(
+                            LocalizedString::new("pollendina-watches-window-title")
+                                .with_placeholder("Memory Watches"),
+                        )
+                        .window_size((520., 600.));
+                    let win_id = window.id;
+                    ctx.new_window(window);
+                    *Arc::make_mut(&mut data.watches_win) = Some(win_id);
+                }
+            };
+            false
+        } else if cmd.is(UI_OPEN_LOGIC) {
+            match *data.logic_win {
+                Some(id) => {
+                    let command = Command::new(druid::commands::SHOW_WINDOW, ());
+                    ctx.submit_command(command, id);
+                }
+                None => {
+                    let window = WindowDesc::new(logic_ui_builder)
+                        .title(
+                            LocalizedString::new("pollendina-logic-window-title")
+                                .with_placeholder("Logic Console"),
+                        )
+                        .window_size((520., 400.));
+                    let win_id = window.id;
+                    ctx.new_window(window);
+                    *Arc::make_mut(&mut data.logic_win) = Some(win_id);
+                    self.engine.update_logic_console(data);
+                }
+            };
+            false
+        } else if cmd.is(UI_OPEN_GRAPH) {
+            match *data.graph_win {
+                Some(id) => {
+                    let command = Command::new(druid::commands::SHOW_WINDOW, ());
+                    ctx.submit_command(command, id);
+                }
+                None => {
+                    let window = WindowDesc::new(graph_ui_builder)
+                        .title(
+                            LocalizedString::new("pollendina-graph-window-title")
+                                .with_placeholder("Dependency Graph"),
+                        )
+                        .window_size((800., 600.));
+                    let win_id = window.id;
+                    ctx.new_window(window);
+                    *Arc::make_mut(&mut data.graph_win) = Some(win_id);
+                    data.graph.nodes = Arc::new(self.engine.dependency_graph());
+                    data.graph.focus = None;
+                }
+            };
+            false
+        } else if cmd.is(UI_OPEN_TIMELINE) {
+            match *data.timeline_win {
+                Some(id) => {
+                    let command = Command::new(druid::commands::SHOW_WINDOW, ());
+                    ctx.submit_command(command, id);
+                }
+                None => {
+                    let window = WindowDesc::new(timeline_ui_builder)
+                        .title(
+                            LocalizedString::new("pollendina-timeline-window-title")
+                                .with_placeholder("Timeline"),
+                        )
+                        .window_size((800., 600.));
+                    let win_id = window.id;
+                    ctx.new_window(window);
+                    *Arc::make_mut(&mut data.timeline_win) = Some(win_id);
+                    data.timeline_pos = 1.0;
+                    self.scrub_timeline(data);
+                }
+            };
+            false
+        } else if cmd.is(UI_SCRUB_TIMELINE) {
+            self.scrub_timeline(data);
+            false
+        } else if cmd.is(UI_EVAL_EXPRESSION) {
+            self.engine.update_logic_console(data);
+            true
         } else if cmd.is(UI_OPEN_CONFIG) {
             match *data.config_win {
                 Some(id) => {
@@ -111,7 +733,16 @@ impl AppDelegate<DisplayState> for Delegate {
                 }
                 None => {
                     self.engine.update_param_state(data);
-                    let window = WindowDesc::new(config_ui_builder).menu(app_menu());
+                    let preview_size = if self.engine.has_broadcast_layout() {
+                        Some(
+                            self.broadcast_window_size(data)
+                                .unwrap_or(BROADCAST_PREVIEW_DEFAULT_SIZE),
+                        )
+                    } else {
+                        None
+                    };
+                    let window =
+                        WindowDesc::new(move || config_ui_builder(preview_size)).menu(app_menu());
                     let win_id = window.id;
                     ctx.new_window(window);
                     *Arc::make_mut(&mut data.config_win) = Some(win_id);
@@ -127,9 +758,81 @@ impl AppDelegate<DisplayState> for Delegate {
             if let Err(e) = self.engine.save_param_state(data) {
                 println!("error saving config changes: {}", e);
             }
+            self.engine.apply_settings(data);
+            if data.auto_track_script != *self.engine.auto_track_script() {
+                match self
+                    .engine
+                    .select_auto_track_script(&data.auto_track_script)
+                {
+                    Ok(()) => data.auto_tracker_state = AutoTrackerState::Idle,
+                    Err(e) => println!("error switching auto tracker script: {}", e),
+                }
+            }
+            self.save_settings(data);
             self.close_config_window(data, ctx);
             false
+        } else if cmd.is(UI_OPEN_GOALS) {
+            match *data.goals_win {
+                Some(id) => {
+                    let command = Command::new(druid::commands::SHOW_WINDOW, ());
+                    ctx.submit_command(command, id);
+                }
+                None => {
+                    let window = WindowDesc::new(goals_ui_builder)
+                        .menu(app_menu())
+                        .title(
+                            LocalizedString::new("pollendina-goals-window-title")
+                                .with_placeholder("Practice Goals"),
+                        )
+                        .window_size((300., 500.));
+                    let win_id = window.id;
+                    ctx.new_window(window);
+                    *Arc::make_mut(&mut data.goals_win) = Some(win_id);
+                }
+            };
+            false
+        } else if cmd.is(UI_CANCEL_GOALS) {
+            data.goals.candidates = Arc::new(self.engine.goal_candidates());
+            self.close_goals_window(data, ctx);
+            false
+        } else if cmd.is(UI_APPLY_GOALS) {
+            let goals = data
+                .goals
+                .candidates
+                .iter()
+                .filter(|g| g.selected)
+                .map(|g| g.id.clone())
+                .collect();
+            self.engine.set_goals(goals);
+            self.engine.update_display_state(data);
+            self.save_settings(data);
+            self.close_goals_window(data, ctx);
+            false
+        } else if let Some(group) = cmd.get(UI_TOGGLE_PARAM_GROUP) {
+            for p in Arc::make_mut(&mut data.params).iter_mut() {
+                if p.group == *group {
+                    p.collapsed = !p.collapsed;
+                }
+            }
+            false
+        } else if cmd.is(ModalHost::MODAL_DISMISSED) {
+            data.popup_target.clear();
+            false
+        } else if let Some(payload) = cmd.get(UI_SHOW_TOOLTIP) {
+            // Tooltips are only drawn in the main window.
+            if target == Target::Window(self.main_win) {
+                self.engine.show_tooltip(data, &payload.1, payload.0);
+            }
+            false
+        } else if cmd.is(UI_HIDE_TOOLTIP) {
+            data.tooltip_pos = None;
+            false
         } else if let Some(payload) = cmd.get(UI_OPEN_POPUP) {
+            // The popup is already showing this objective.
+            if data.popup_target == payload.1 {
+                return false;
+            }
+            // Showing the popup replaces any open one.
             if let Err(e) = self.engine.build_popup(data, &payload.1) {
                 println!("error building popup: {}", e);
             } else {
@@ -138,46 +841,437 @@ impl AppDelegate<DisplayState> for Delegate {
                 ctx.submit_command(cmd, None);
             }
             false
-        } else if let Some(id) = cmd.get(ENGINE_TOGGLE_STATE) {
-            if let Err(e) = self.engine.toggle_state(&id) {
-                println!("error toggling state: {}", e);
+        } else if let Some(result) = cmd
+            .get(ENGINE_TOGGLE_STATE)
+            .map(|id| self.engine.toggle_state(&id))
+            .or_else(|| {
+                cmd.get(ENGINE_STEP_STATE_BACK)
+                    .map(|id| self.engine.step_state_back(&id))
+            })
+            .or_else(|| {
+                cmd.get(ENGINE_TOGGLE_GLITCH_LOCKED)
+                    .map(|id| self.engine.toggle_glitch_locked(&id))
+            })
+            .or_else(|| {
+                cmd.get(ENGINE_BATCH_UPDATE)
+                    .map(|updates| self.engine.batch_update(updates, StateSource::Manual))
+            })
+            .or_else(|| {
+                cmd.get(ENGINE_ASSIGN).map(|(assignment, slot, value)| {
+                    self.engine.assign(assignment, slot, value.as_ref())
+                })
+            })
+        {
+            match result {
+                Ok(changes) => {
+                    if data.settings.undo_toast && !changes.changes.is_empty() {
+                        self.undo = changes
+                            .changes
+                            .iter()
+                            .map(|c| (c.id.clone(), c.old_state.clone()))
+                            .collect();
+                        let phrases: Vec<String> = changes
+                            .changes
+                            .iter()
+                            .map(|c| state_change_phrase(&self.engine, c))
+                            .collect();
+                        data.toast = phrases.join(", ");
+                    }
+                    self.engine.update_display_changes(data, &changes);
+                }
+                Err(e) => println!("error toggling state: {}", e),
+            }
+            true
+        } else if cmd.is(UI_UNDO) {
+            let undo = std::mem::take(&mut self.undo);
+            match self.engine.update_state(&undo, StateSource::Manual) {
+                Ok(changes) => self.engine.update_display_changes(data, &changes),
+                Err(e) => println!("error undoing state change: {}", e),
+            }
+            data.toast.clear();
+            true
+        } else if let Some(id) = cmd.get(ENGINE_SHOW_HINTS) {
+            if let Err(e) = self.engine.show_hints(&id) {
+                println!("error showing hints: {}", e);
+            } else {
+                self.engine.update_display_state(data);
+            }
+            true
+        } else if cmd.is(UI_OPEN_ENTRANCES) {
+            let entrances = self.engine.entrance_connections();
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                entrances_builder(entrances)
+            });
+            ctx.submit_command(cmd, None);
+            false
+        } else if let Some(id) = cmd.get(UI_PICK_ENTRANCE) {
+            let from = id.clone();
+            let entrances = self.engine.entrance_connections();
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                entrance_picker_builder(from, entrances)
+            });
+            ctx.submit_command(cmd, None);
+            false
+        } else if let Some((a, b)) = cmd.get(ENGINE_CONNECT_ENTRANCES) {
+            if let Err(e) = self.engine.connect_entrances(a, b) {
+                println!("error connecting entrances: {}", e);
+            }
+            self.engine.update_display_state(data);
+            ctx.submit_command(Command::new(UI_OPEN_ENTRANCES, ()), None);
+            true
+        } else if let Some(id) = cmd.get(ENGINE_DISCONNECT_ENTRANCE) {
+            if let Err(e) = self.engine.disconnect_entrance(id) {
+                println!("error disconnecting entrance: {}", e);
+            }
+            self.engine.update_display_state(data);
+            ctx.submit_command(Command::new(UI_OPEN_ENTRANCES, ()), None);
+            true
+        } else if let Some(id) = cmd.get(ENGINE_TOGGLE_LOCK) {
+            if let Err(e) = self.engine.toggle_lock(&id) {
+                println!("error toggling lock: {}", e);
+            } else {
+                self.engine.update_display_state(data);
+            }
+            true
+        } else if let Some(action) = cmd.get(UI_CONFIRM_BATCH) {
+            let (updates, question) = match action {
+                BatchAction::CompleteChecks(id) => {
+                    let updates = self.engine.check_updates(id, ObjectiveState::Complete);
+                    let question = checks_question(
+                        "pollendina-complete-checks-question",
+                        updates.len(),
+                        self.engine.objective_name(id),
+                    );
+                    (updates, question)
+                }
+                BatchAction::ResetChecks(id) => {
+                    let updates = self.engine.check_updates(id, ObjectiveState::Locked);
+                    let question = checks_question(
+                        "pollendina-reset-checks-question",
+                        updates.len(),
+                        self.engine.objective_name(id),
+                    );
+                    (updates, question)
+                }
+                BatchAction::CompleteGrid(id) => {
+                    let updates = self.engine.grid_updates(id, ObjectiveState::Complete);
+                    let count = updates.len();
+                    let question = LocalizedString::new("pollendina-complete-grid-question")
+                        .with_placeholder("Complete these objectives?")
+                        .with_arg("count", move |_data: &DisplayState, _env| count.into());
+                    (updates, question)
+                }
+            };
+            if updates.is_empty() {
+                return false;
+            }
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                batch_confirm_builder(question, updates)
+            });
+            ctx.submit_command(cmd, None);
+            false
+        } else if let Some((id, mark)) = cmd.get(ENGINE_MARK_CHECK) {
+            if let Err(e) = self.engine.mark_check(id, mark.clone()) {
+                println!("error marking check: {}", e);
+            } else {
+                self.engine.update_display_state(data);
+            }
+            true
+        } else if let Some(id) = cmd.get(UI_EDIT_NOTE) {
+            data.note_text = self.engine.note(id).cloned().unwrap_or_default();
+            let id = id.clone();
+            let name = self.engine.objective_name(&id);
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                note_editor_builder(id, name)
+            });
+            ctx.submit_command(cmd, None);
+            false
+        } else if let Some(id) = cmd.get(ENGINE_SET_NOTE) {
+            if let Err(e) = self.engine.set_note(id, &data.note_text) {
+                println!("error setting note: {}", e);
             } else {
                 self.engine.update_display_state(data);
             }
             true
+        } else if let Some(id) = cmd.get(UI_PICK_CHECK_ITEM) {
+            let check = id.clone();
+            let items = self.engine.item_choices();
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                check_item_picker_builder(check, items)
+            });
+            ctx.submit_command(cmd, None);
+            false
+        } else if let Some((assignment, slot)) = cmd.get(UI_PICK_ASSIGNMENT) {
+            let name = self
+                .engine
+                .assignment_slots(assignment)
+                .into_iter()
+                .find(|s| &s.id == slot)
+                .map_or(slot.clone(), |s| s.name);
+            let choices = self.engine.assignment_choices(assignment, slot);
+            let target = (assignment.clone(), slot.clone());
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                assignment_picker_builder(target, name, choices)
+            });
+            ctx.submit_command(cmd, None);
+            false
+        } else if let Some(id) = cmd.get(UI_HIDE_OBJECTIVE) {
+            self.engine.hide_objective(id, data);
+            self.layout_overrides_changed(data);
+            false
+        } else if let Some((id, offset)) = cmd.get(UI_MOVE_OBJECTIVE) {
+            if let Err(e) = self.engine.move_objective(id, *offset, data) {
+                println!("error moving objective: {}", e);
+                return false;
+            }
+            self.layout_overrides_changed(data);
+            false
+        } else if cmd.is(UI_RESET_LAYOUT_OVERRIDES) {
+            self.engine.reset_layout_overrides(data);
+            self.layout_overrides_changed(data);
+            false
         } else if cmd.is(ENGINE_START_AUTO_TRACKING) {
-            if let Err(e) = self.engine.start_auto_tracking() {
+            if let Some(process) = self.engine.tracked_process() {
+                if !self.has_process_consent(&process) {
+                    let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                        process_consent_builder(process)
+                    });
+                    ctx.submit_command(cmd, None);
+                    return false;
+                }
+            }
+            if let Err(e) = self
+                .engine
+                .start_auto_tracking(&data.settings.usb2snes_device)
+            {
                 println!("error starting auto tracking: {}", e);
             }
             true
+        } else if let Some(process) = cmd.get(UI_ALLOW_PROCESS_ACCESS) {
+            self.settings_file
+                .modules
+                .entry(self.engine.module_name().clone())
+                .or_default()
+                .process_consent = process.clone();
+            self.save_settings(data);
+            ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+            ctx.submit_command(ENGINE_START_AUTO_TRACKING, None);
+            false
         } else if cmd.is(ENGINE_STOP_AUTO_TRACKING) {
             if let Err(e) = self.engine.stop_auto_tracking() {
                 println!("error stopping auto tracking: {}", e);
             }
             true
+        } else if cmd.is(ENGINE_LIST_DEVICES) {
+            if let Err(e) = self.engine.list_devices() {
+                println!("error listing devices: {}", e);
+            }
+            true
+        } else if let Some(devices) = cmd.get(ENGINE_UPDATE_DEVICES) {
+            data.devices = Arc::new(devices.clone());
+            true
+        } else if let Some(watches) = cmd.get(ENGINE_UPDATE_WATCH_DEBUG) {
+            // Reads can still be in flight when the window closes.
+            if data.watches_win.is_some() {
+                data.watch_debug = Arc::new(watches.clone());
+            }
+            true
+        } else if let Some(version) = cmd.get(ENGINE_UPDATE_ROM_VERSION) {
+            data.rom_version = version.clone();
+            true
         } else if let Some(state) = cmd.get(ENGINE_UPDATE_AUTO_TRACKER_STATE) {
             data.auto_tracker_state = state.clone();
             true
         } else if let Some(updates) = cmd.get(ENGINE_UPDATE_STATE) {
-            if let Err(e) = self.engine.update_state(updates) {
-                println!("error updating state: {}", e);
-            } else {
+            self.apply_tracked_updates(data, updates);
+            true
+        } else if let Some(stages) = cmd.get(ENGINE_SET_STAGES) {
+            let mut updates = HashMap::new();
+            for (id, stage) in stages {
+                match self.engine.stage_updates(id, *stage) {
+                    Ok(u) => updates.extend(u),
+                    Err(e) => println!("error setting stage: {}", e),
+                }
+            }
+            // Scripts set stages on every read so most change nothing.
+            if !updates.is_empty() {
+                self.apply_tracked_updates(data, &updates);
+            }
+            true
+        } else if let Some(timers) = cmd.get(ENGINE_START_TIMERS) {
+            for (id, (secs, state)) in timers {
+                if let Err(e) = self.engine.start_timer(id, *secs, *state) {
+                    println!("error starting timer: {}", e);
+                }
+            }
+            self.engine.update_display_state(data);
+            self.schedule_timer_tick();
+            true
+        } else if cmd.is(UI_TICK_TIMERS) {
+            self.timer_tick_pending = false;
+            let expired = self.engine.expired_timers();
+            if expired.is_empty() {
                 self.engine.update_display_state(data);
+            } else {
+                self.apply_tracked_updates(data, &expired);
+            }
+            self.schedule_timer_tick();
+            true
+        } else if let Some(hints) = cmd.get(ENGINE_SET_HINTS) {
+            for (location, item) in hints {
+                if let Err(e) = self.engine.set_hint(location, item) {
+                    println!("error setting hint: {}", e);
+                }
+            }
+            self.engine.update_display_state(data);
+            true
+        } else if let Some(info) = cmd.get(druid::commands::OPEN_FILE) {
+            if let Err(e) = self.engine.watch_save_file(info.path().to_path_buf()) {
+                println!("error watching save file: {}", e);
+            }
+            true
+        } else if let Some(Some(info)) = cmd.get(druid::commands::SAVE_FILE) {
+            // Reports and state dumps share the save panel and are told
+            // apart by the type picked.
+            let path = info.path();
+            let result = match path.extension().and_then(|e| e.to_str()) {
+                Some("json") | Some("yaml") | Some("yml") => self.save_dump(path),
+                _ => self.save_report(data, path),
+            };
+            if let Err(e) = result {
+                println!("error saving {}: {}", path.display(), e);
             }
             true
         } else if cmd.is(ENGINE_DUMP_STATE) {
-            if let Err(e) = self.engine.dump_state() {
-                println!("Error dumping state: {}", e);
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), dump_builder);
+            ctx.submit_command(cmd, None);
+            true
+        } else if cmd.is(UI_COPY_DUMP) {
+            match self.engine.state_dump().and_then(|dump| dump.to_json()) {
+                Ok(json) => Application::global().clipboard().put_string(json),
+                Err(e) => println!("error dumping state: {}", e),
+            }
+            true
+        } else if cmd.is(UI_COPY_SUMMARY) {
+            let summary = self.engine.session_report(&data.params).to_summary();
+            Application::global().clipboard().put_string(summary);
+            true
+        } else if cmd.is(ENGINE_RUN_TESTS) {
+            match self.engine.run_module_tests() {
+                Ok(results) if results.is_empty() => println!("module has no tests"),
+                Ok(results) => {
+                    for result in &results {
+                        let status = if result.failures.is_empty() {
+                            "ok"
+                        } else {
+                            "FAILED"
+                        };
+                        println!("test {} ... {}", result.name, status);
+                        for failure in &result.failures {
+                            println!("  {}", failure);
+                        }
+                    }
+                    let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                        test_results_builder(results)
+                    });
+                    ctx.submit_command(cmd, None);
+                }
+                Err(e) => println!("error running module tests: {}", e),
             }
+            self.engine.update_display_state(data);
+            true
+        } else if let Some(info) = cmd.get(UI_UPDATE_AVAILABLE) {
+            data.update_version = info.version.clone();
+            self.update = Some(info.clone());
+            true
+        } else if cmd.is(UI_INSTALL_UPDATE) {
+            if let Some(info) = self.update.clone() {
+                install_update(info);
+            }
+            true
+        } else if cmd.is(UI_FLUSH_BROADCAST) {
+            self.broadcast_flush_pending = false;
+            self.engine.update_display_state(data);
             true
         } else {
             true
         }
     }
-    fn window_removed(
-        &mut self,
-        id: WindowId,
-        data: &mut DisplayState,
+}
+
+// Whether `cmd` changes objective state other than through a spectated
+// tracker.
+fn changes_state(cmd: &Command) -> bool {
+    cmd.is(ENGINE_TOGGLE_STATE)
+        || cmd.is(ENGINE_STEP_STATE_BACK)
+        || cmd.is(ENGINE_TOGGLE_GLITCH_LOCKED)
+        || cmd.is(ENGINE_BATCH_UPDATE)
+        || cmd.is(ENGINE_ASSIGN)
+        || cmd.is(ENGINE_TOGGLE_LOCK)
+        || cmd.is(ENGINE_UPDATE_STATE)
+        || cmd.is(ENGINE_SET_STAGES)
+        || cmd.is(ENGINE_START_TIMERS)
+        || cmd.is(ENGINE_START_AUTO_TRACKING)
+        || cmd.is(ENGINE_CONNECT_ENTRANCES)
+        || cmd.is(ENGINE_DISCONNECT_ENTRANCE)
+        || cmd.is(UI_UNDO)
+        || cmd.is(UI_ADD_SEED)
+        || cmd.is(druid::commands::OPEN_FILE)
+}
+
+impl AppDelegate<DisplayState> for Delegate {
+    fn command(
+        &mut self,
+        ctx: &mut DelegateCtx,
+        target: Target,
+        cmd: &Command,
+        data: &mut DisplayState,
+        _env: &Env,
+    ) -> bool {
+        // Spectators only show the state of the tracker they watch.
+        if !data.spectating.is_empty() && changes_state(cmd) {
+            return false;
+        }
+        // Neither does the timeline window's past layout.
+        if let Some(id) = *data.timeline_win {
+            if target == Target::Window(id) && changes_state(cmd) {
+                return false;
+            }
+        }
+        let broadcast = (data.broadcast.clone(), data.broadcast_split.clone());
+        let handled = self.handle_command(ctx, target, cmd, data);
+        self.engine.publish_state_events(&data.settings);
+        if let Some(share) = &self.share {
+            let states = self.engine.objective_states();
+            if let Err(e) = share.publish(&states, &self.engine.script_storage()) {
+                println!("error sharing state: {}", e);
+            }
+        }
+        self.update_split_broadcast(data);
+        self.throttle_broadcast(data, broadcast);
+        handled
+    }
+
+    fn window_added(
+        &mut self,
+        id: WindowId,
+        _data: &mut DisplayState,
+        _env: &Env,
+        ctx: &mut DelegateCtx,
+    ) {
+        if id == self.main_win && !self.load_problems.is_empty() {
+            let problems = std::mem::take(&mut self.load_problems);
+            let cmd = ModalHost::make_modal_command(Point::new(8.0, 40.0), move || {
+                load_problems_builder(problems)
+            });
+            ctx.submit_command(cmd, Target::Window(id));
+        }
+    }
+
+    fn window_removed(
+        &mut self,
+        id: WindowId,
+        data: &mut DisplayState,
         _env: &Env,
         _ctx: &mut DelegateCtx,
     ) {
@@ -186,6 +1280,46 @@ impl AppDelegate<DisplayState> for Delegate {
                 *Arc::make_mut(&mut data.config_win) = None;
             }
         }
+        if let Some(broadcast_win_id) = *data.broadcast_win {
+            if id == broadcast_win_id {
+                *Arc::make_mut(&mut data.broadcast_win) = None;
+            }
+        }
+        if let Some(graph_win_id) = *data.graph_win {
+            if id == graph_win_id {
+                *Arc::make_mut(&mut data.graph_win) = None;
+                data.graph = Default::default();
+            }
+        }
+        if let Some(logic_win_id) = *data.logic_win {
+            if id == logic_win_id {
+                *Arc::make_mut(&mut data.logic_win) = None;
+            }
+        }
+        if let Some(timeline_win_id) = *data.timeline_win {
+            if id == timeline_win_id {
+                *Arc::make_mut(&mut data.timeline_win) = None;
+                data.timeline_view = Default::default();
+            }
+        }
+        if let Some(goals_win_id) = *data.goals_win {
+            if id == goals_win_id {
+                *Arc::make_mut(&mut data.goals_win) = None;
+            }
+        }
+        if let Some(watches_win_id) = *data.watches_win {
+            if id == watches_win_id {
+                *Arc::make_mut(&mut data.watches_win) = None;
+                data.watch_debug = Arc::new(Vec::new());
+                if let Err(e) = self.engine.set_watch_debug(false) {
+                    println!("error closing watches window: {}", e);
+                }
+            }
+        }
+
+        // Catch settings changed outside of the config window, like muting,
+        // before the app exits.
+        self.save_settings(data);
     }
 }
 
@@ -240,6 +1374,10 @@ fn get_mod_paths() -> Vec<PathBuf> {
         paths.push(p);
     }
 
+    if let Ok(p) = get_user_module_dir() {
+        paths.push(p);
+    }
+
     // Include both the exe directory and CWD as fallbacks.
     if let Ok(p) = get_exe_dir() {
         paths.push(p);
@@ -251,99 +1389,1410 @@ fn get_mod_paths() -> Vec<PathBuf> {
     paths
 }
 
+// Every path a module manifest at `path` is looked for at, in order.
+fn module_search_paths(path: &Path) -> Vec<PathBuf> {
+    // Modules picked by name may have their manifest in any format.
+    let mut candidates = vec![path.to_path_buf()];
+    if path.file_name().map_or(false, |n| n == "manifest.json") {
+        candidates.extend(
+            MODULE_FILE_EXTENSIONS
+                .iter()
+                .map(|ext| path.with_extension(ext)),
+        );
+    }
+    get_mod_paths()
+        .iter()
+        .flat_map(|dir| candidates.iter().map(move |c| dir.join(c)))
+        .collect()
+}
+
 fn resolve_module_path<P: AsRef<Path>>(path: P) -> Result<PathBuf, Error> {
     let path = path.as_ref();
-    let mut dirs = get_mod_paths();
-
-    for dir in &mut dirs {
-        dir.push(&path);
-        if dir.exists() {
-            println!("found {}", dir.to_string_lossy());
-            return Ok(dir.clone());
+    match module_search_paths(path).into_iter().find(|p| p.exists()) {
+        Some(found) => {
+            println!("found {}", found.to_string_lossy());
+            Ok(found)
         }
+        None => Err(format_err!("Can't find {:?}", path.to_string_lossy())),
     }
+}
 
-    Err(format_err!(
-        "Can't find {:?} in {:?}",
-        path.to_string_lossy(),
-        &dirs
-    ))
+// The main window for a module with main layout variants `layouts`.  `size`
+// is the size the layout in use asks for, if any.
+fn main_window(layouts: &[String], size: Option<(f64, f64)>) -> WindowDesc<DisplayState> {
+    let window = WindowDesc::new(ui_builder)
+        .menu(main_menu(layouts))
+        .title(LocalizedString::new("pollendina-main-window-title").with_placeholder("Pollendina"));
+    match size {
+        Some(size) => window.window_size(size),
+        None => window.window_size((650., 500.)).with_min_size((650., 500.)),
+    }
 }
 
 fn main() -> Result<(), Error> {
-    let main_window = WindowDesc::new(ui_builder)
-        .menu(app_menu())
-        .title("Pollendina")
-        .window_size((650., 500.))
-        .with_min_size((650., 500.));
-    let app = AppLauncher::with_window(main_window);
-
     println!("{:?}", std::env::current_exe());
-    let module_path = resolve_module_path("mods/ff4fe/manifest.json")?;
+    let args = Args::parse(std::env::args().skip(1))?;
+    if let Some(dir) = &args.write_schemas {
+        for path in pollendina_core::schema::write_schemas(Path::new(dir))? {
+            println!("wrote {}", path.display());
+        }
+        return Ok(());
+    }
+    let module_path = match resolve_module_path(args.module_path()) {
+        Ok(path) => path,
+        Err(e) => {
+            println!("{}", e);
+            let searched = module_search_paths(Path::new(&args.module_path()));
+            return module_picker::run(args, searched);
+        }
+    };
     let module = Module::open(&module_path)?;
-    let engine = Engine::new(module, ExtEventSinkProxy(app.get_external_handle()))?;
-    let data = engine.new_display_state();
+    let mut load_problems: Vec<String> = module
+        .unknown_ids
+        .iter()
+        .map(|(node, id)| format!("{} refers to unknown id {}, treated as false", node, id))
+        .collect();
+    for problem in module.validate() {
+        println!("warning: {}: {}", module_path.display(), problem);
+    }
+    if !module.auto_track_scripts.is_empty() && !module.checksummed {
+        println!(
+            "warning: {} has no checksums, only use its auto tracker script if you trust where it came from",
+            module_path.display()
+        );
+    }
+
+    let settings_path = get_settings_path()?;
+    let settings_file = SettingsFile::load(&settings_path).unwrap_or_else(|e| {
+        println!("error loading settings: {}", e);
+        Default::default()
+    });
+    let module_settings = settings_file
+        .modules
+        .get(&module.manifest.name)
+        .cloned()
+        .unwrap_or_default();
+
+    // Open with the layout variant used last.
+    let layouts = engine::main_layouts(&module);
+    let main_layout = if layouts.contains(&module_settings.main_layout) {
+        module_settings.main_layout.clone()
+    } else {
+        layouts[0].clone()
+    };
+    let size = engine::window_size(&module.manifest.layouts[&main_layout]);
+    let window = main_window(&layouts, size);
+    let main_win = window.id;
+    let app = AppLauncher::with_window(window);
+
+    let sink = app.get_external_handle();
+    let mut engine = Engine::new(module, ExtEventSinkProxy(sink.clone()))?;
+    for problem in engine.asset_problems() {
+        println!("warning: {}: {}", module_path.display(), problem);
+        load_problems.push(problem.clone());
+    }
+    engine.set_goals(module_settings.goals);
+    engine.set_script_storage(module_settings.script_storage);
+    if !module_settings.auto_track_script.is_empty() {
+        if let Err(e) = engine.select_auto_track_script(&module_settings.auto_track_script) {
+            println!("error picking auto tracker script: {}", e);
+        }
+    }
+
+    if let Some(flags) = &args.flags {
+        engine.set_flags(flags)?;
+    }
+    for (id, value) in &args.params {
+        engine.set_param(id, *value)?;
+    }
+
+    let mut data = engine.new_display_state();
+    let mut spectator = None;
+    if let Some(addr) = &args.spectate {
+        let token = args.token.as_ref().ok_or(format_err!(
+            "--spectate needs the --token from the share link"
+        ))?;
+        spectator = Some(share::spectate(
+            addr,
+            token,
+            engine.module_name(),
+            sink.clone(),
+        )?);
+        data.spectating = addr.clone();
+    }
+    engine.set_layout_overrides(module_settings.layout_overrides.clone());
+    engine.set_main_layout(&main_layout, &mut data)?;
+    engine.update_param_state(&mut data);
+    data.settings = settings_file.settings.clone();
+    engine.apply_settings(&mut data);
+    engine.subscribe(Box::new(AudioPlayer::new()));
+    engine.subscribe(Box::new(Notifier));
+    engine.subscribe(Box::new(Announcer));
+    engine.subscribe(Box::new(LiveSplitter::new()));
+    if data.settings.check_for_updates {
+        check_for_update(sink.clone());
+    }
+    data.monitors = Arc::new(
+        monitors::list_monitors()
+            .into_iter()
+            .map(|m| m.name)
+            .collect(),
+    );
+    if data.settings.broadcast_on_startup {
+        sink.submit_command(UI_OPEN_BROADCAST, (), None)?;
+    }
 
     //    let auto_tracker = AutoTracker::new(ki_info, app.get_external_handle());
 
-    app.delegate(Delegate { engine })
-        .launch(data)
-        .expect("launch failed");
+    app.delegate(Delegate {
+        engine,
+        settings_path,
+        settings_file,
+        undo: HashMap::new(),
+        sink,
+        broadcast_updated: Instant::now(),
+        broadcast_flush_pending: false,
+        timer_tick_pending: false,
+        update: None,
+        main_win,
+        seeds: Vec::new(),
+        share: None,
+        spectator,
+        module_path: args.module_path(),
+        load_problems,
+    })
+    .launch(data)
+    .expect("launch failed");
     println!("done");
 
     Ok(())
 }
 
+// Exposes `Settings` to the widgets of a window through the `Env`.
+fn settings_scope(widget: impl Widget<DisplayState> + 'static) -> impl Widget<DisplayState> {
+    EnvScope::new(
+        |env, data: &DisplayState| {
+            env.set(SHAPE_INDICATORS, data.settings.shape_indicators);
+            env.set(MAP_HEATMAP, data.settings.map_heatmap);
+            env.set(RESTREAM_SAFE, data.settings.restream_safe);
+            env.set(DOUBLE_CLICK_COMPLETE, data.settings.double_click_complete);
+            let wheel_step = match (
+                data.settings.wheel_steps_state,
+                data.settings.wheel_reversed,
+            ) {
+                (false, _) => 0.0,
+                (true, false) => 1.0,
+                (true, true) => -1.0,
+            };
+            env.set(WHEEL_STEP, wheel_step);
+        },
+        widget,
+    )
+}
+
 fn modal_builder() -> impl Widget<DisplayState> {
-    display_widget().lens(DisplayState::popup)
+    Flex::column()
+        .with_child(Either::new(
+            |data: &DisplayState, _env| data.settings.restream_safe || data.popup_notes.is_empty(),
+            SizedBox::empty(),
+            List::new(|| Label::new(|note: &String, _env: &_| note.clone()))
+                .lens(DisplayState::popup_notes)
+                .padding(8.0)
+                .background(theme::BACKGROUND_DARK),
+        ))
+        .with_child(Either::new(
+            |data: &DisplayState, _env| data.settings.restream_safe || data.popup_hint.is_empty(),
+            SizedBox::empty(),
+            Label::new(
+                LocalizedString::new("pollendina-popup-hint")
+                    .with_placeholder("Needs")
+                    .with_arg("needs", |data: &DisplayState, _env| {
+                        data.popup_hint.clone().into()
+                    }),
+            )
+            .padding(8.0)
+            .background(theme::BACKGROUND_DARK),
+        ))
+        .with_child(Either::new(
+            |data: &DisplayState, _env| data.settings.restream_safe,
+            Label::new(
+                LocalizedString::new("pollendina-popup-checks")
+                    .with_placeholder("Checks")
+                    .with_arg("complete", |data: &DisplayState, _env| {
+                        data.popup_checks.0.into()
+                    })
+                    .with_arg("total", |data: &DisplayState, _env| {
+                        data.popup_checks.1.into()
+                    }),
+            )
+            .padding(8.0)
+            .background(theme::BACKGROUND_DARK),
+            display_widget().lens(DisplayState::popup),
+        ))
+        .with_child(Either::new(
+            |data: &DisplayState, _env| data.popup_checks.1 == 0,
+            SizedBox::empty(),
+            Flex::row()
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-complete-all")
+                            .with_placeholder("Complete all"),
+                    )
+                    .on_click(|ctx, data: &mut DisplayState, _env| {
+                        let action = BatchAction::CompleteChecks(data.popup_target.clone());
+                        ctx.submit_command(UI_CONFIRM_BATCH.with(action), None);
+                    }),
+                )
+                .with_child(
+                    Button::new(LocalizedString::new("pollendina-reset").with_placeholder("Reset"))
+                        .on_click(|ctx, data: &mut DisplayState, _env| {
+                            let action = BatchAction::ResetChecks(data.popup_target.clone());
+                            ctx.submit_command(UI_CONFIRM_BATCH.with(action), None);
+                        }),
+                )
+                .padding(4.0)
+                .background(theme::BACKGROUND_DARK),
+        ))
 }
 
-fn ui_builder() -> impl Widget<DisplayState> {
-    let mut root = Flex::column();
+// Asks before the auto tracker reads the memory of the game `process`.
+fn process_consent_builder(process: String) -> impl Widget<DisplayState> {
+    Flex::column()
+        .with_child(Label::new(format!(
+            "This module's auto tracker reads the memory of {}.",
+            process
+        )))
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-allow-process-access")
+                .with_placeholder("Allow Pollendina to read it?"),
+        ))
+        .with_spacer(8.0)
+        .with_child(
+            Flex::row()
+                .with_flex_spacer(1.0)
+                .with_child(
+                    Button::new(LocalizedString::new("pollendina-allow").with_placeholder("Allow"))
+                        .on_click(move |ctx, _data: &mut DisplayState, _env| {
+                            ctx.submit_command(UI_ALLOW_PROCESS_ACCESS.with(process.clone()), None);
+                        }),
+                )
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                    }),
+                ),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Lists the unknown ids the module's expressions refer to and the assets
+// that couldn't be loaded.
+fn load_problems_builder(problems: Vec<String>) -> impl Widget<DisplayState> {
+    let mut list = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    for problem in problems {
+        list.add_child(Label::new(problem));
+    }
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-load-problems")
+                .with_placeholder("This module loaded with problems:"),
+        ))
+        .with_spacer(8.0)
+        .with_child(SizedBox::new(Scroll::new(list).vertical()).height(200.))
+        .with_spacer(8.0)
+        .with_child(Flex::row().with_flex_spacer(1.0).with_child(
+            Button::new(LocalizedString::new("pollendina-ok").with_placeholder("Ok")).on_click(
+                |ctx, _data: &mut DisplayState, _env| {
+                    ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                },
+            ),
+        ))
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Summary of a run of the module's tests with each failed check.
+fn test_results_builder(results: Vec<ModuleTestResult>) -> impl Widget<DisplayState> {
+    let passed = results.iter().filter(|r| r.failures.is_empty()).count();
+    let mut col = Flex::column().with_child(Label::new(format!(
+        "{} of {} module tests passed",
+        passed,
+        results.len()
+    )));
+    for result in results.iter().filter(|r| !r.failures.is_empty()) {
+        col.add_child(Label::new(format!("{}:", result.name)));
+        for failure in &result.failures {
+            col.add_child(Label::new(format!("  {}", failure)));
+        }
+    }
+    col.with_spacer(8.0)
+        .with_child(Flex::row().with_flex_spacer(1.0).with_child(
+            Button::new(LocalizedString::new("pollendina-ok").with_placeholder("Ok")).on_click(
+                |ctx, _data: &mut DisplayState, _env| {
+                    ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                },
+            ),
+        ))
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Every entrance of the module and where it leads.  Clicking one picks its
+// connection.
+fn entrances_builder(entrances: Vec<EntranceConnection>) -> impl Widget<DisplayState> {
+    let mut list = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    if entrances.is_empty() {
+        list.add_child(Label::new(
+            LocalizedString::new("pollendina-no-entrances")
+                .with_placeholder("This module has no entrances."),
+        ));
+    }
+    for entrance in entrances {
+        let text = match &entrance.connected {
+            Some(to) => format!("{} \u{2194} {}", entrance.name, to),
+            None => format!("{} \u{2194} ?", entrance.name),
+        };
+        let id = entrance.id.clone();
+        list.add_child(
+            Button::new(text).on_click(move |ctx, _data: &mut DisplayState, _env| {
+                ctx.submit_command(UI_PICK_ENTRANCE.with(id.clone()), None);
+            }),
+        );
+    }
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-entrances").with_placeholder("Entrances"),
+        ))
+        .with_spacer(8.0)
+        .with_child(SizedBox::new(Scroll::new(list).vertical()).height(400.))
+        .with_spacer(8.0)
+        .with_child(Flex::row().with_flex_spacer(1.0).with_child(
+            Button::new(LocalizedString::new("pollendina-ok").with_placeholder("Ok")).on_click(
+                |ctx, _data: &mut DisplayState, _env| {
+                    ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                },
+            ),
+        ))
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Lists `items`, as (id, name), to record one as found at `check`.
+fn check_item_picker_builder(
+    check: String,
+    items: Vec<(String, String)>,
+) -> impl Widget<DisplayState> {
+    let mut list = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    for (id, name) in items {
+        let mark = (check.clone(), Some(CheckMark::Item(id)));
+        list.add_child(
+            Button::new(name).on_click(move |ctx, _data: &mut DisplayState, _env| {
+                ctx.submit_command(ENGINE_MARK_CHECK.with(mark.clone()), None);
+                ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+            }),
+        );
+    }
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-record-check-question")
+                .with_placeholder("What was found there?"),
+        ))
+        .with_spacer(8.0)
+        .with_child(SizedBox::new(Scroll::new(list).vertical()).height(400.))
+        .with_spacer(8.0)
+        .with_child(
+            Flex::row().with_flex_spacer(1.0).with_child(
+                Button::new(LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"))
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                    }),
+            ),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Edits the user's note on the objective `id` named `name`.  Saving an
+// empty note removes it.
+fn note_editor_builder(id: String, name: String) -> impl Widget<DisplayState> {
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-note-on")
+                .with_placeholder("Note")
+                .with_arg("name", move |_data: &DisplayState, _env| {
+                    name.clone().into()
+                }),
+        ))
+        .with_spacer(8.0)
+        .with_child(
+            TextBox::new()
+                .fix_width(300.0)
+                .lens(DisplayState::note_text),
+        )
+        .with_spacer(8.0)
+        .with_child(
+            Flex::row()
+                .with_flex_spacer(1.0)
+                .with_child(
+                    Button::new(LocalizedString::new("pollendina-save").with_placeholder("Save"))
+                        .on_click(move |ctx, _data: &mut DisplayState, _env| {
+                            ctx.submit_command(ENGINE_SET_NOTE.with(id.clone()), None);
+                            ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                        }),
+                )
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                    }),
+                ),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Picks the value of `slot`, as (assignment, slot), named `name`.  Values
+// already given to another slot say which.
+fn assignment_picker_builder(
+    slot: (String, String),
+    name: String,
+    choices: Vec<AssignmentChoice>,
+) -> impl Widget<DisplayState> {
+    let mut list = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    for choice in choices {
+        let text = match &choice.taken_by {
+            Some(other) => format!("{} (at {})", choice.name, other),
+            None => choice.name.clone(),
+        };
+        let assign = (slot.0.clone(), slot.1.clone(), Some(choice.id.clone()));
+        list.add_child(
+            Button::new(text).on_click(move |ctx, _data: &mut DisplayState, _env| {
+                ctx.submit_command(ENGINE_ASSIGN.with(assign.clone()), None);
+                ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+            }),
+        );
+    }
+    let clear = (slot.0, slot.1, None);
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-scout-question")
+                .with_placeholder("What is here?")
+                .with_arg("name", move |_data: &DisplayState, _env| {
+                    name.clone().into()
+                }),
+        ))
+        .with_spacer(8.0)
+        .with_child(SizedBox::new(Scroll::new(list).vertical()).height(400.))
+        .with_spacer(8.0)
+        .with_child(
+            Flex::row()
+                .with_flex_spacer(1.0)
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-assignment-unknown")
+                            .with_placeholder("Unknown"),
+                    )
+                    .on_click(move |ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(ENGINE_ASSIGN.with(clear.clone()), None);
+                        ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                    }),
+                )
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                    }),
+                ),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Picks the entrance `from` leads to.  Reached entrances are listed first as
+// they are the likeliest to have been found.
+fn entrance_picker_builder(
+    from: String,
+    mut entrances: Vec<EntranceConnection>,
+) -> impl Widget<DisplayState> {
+    let name = entrances
+        .iter()
+        .find(|e| e.id == from)
+        .map_or(from.clone(), |e| e.name.clone());
+    entrances.retain(|e| e.id != from);
+    entrances.sort_by_key(|e| !e.state.at_least(&ObjectiveState::Unlocked));
+
+    let mut list = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    for entrance in entrances {
+        let pair = (from.clone(), entrance.id.clone());
+        list.add_child(Button::new(entrance.name.clone()).on_click(
+            move |ctx, _data: &mut DisplayState, _env| {
+                ctx.submit_command(ENGINE_CONNECT_ENTRANCES.with(pair.clone()), None);
+            },
+        ));
+    }
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-connect-question")
+                .with_placeholder("Where does this lead?")
+                .with_arg("name", move |_data: &DisplayState, _env| {
+                    name.clone().into()
+                }),
+        ))
+        .with_spacer(8.0)
+        .with_child(SizedBox::new(Scroll::new(list).vertical()).height(400.))
+        .with_spacer(8.0)
+        .with_child(
+            Flex::row()
+                .with_flex_spacer(1.0)
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-entrance-unknown")
+                            .with_placeholder("Unknown"),
+                    )
+                    .on_click(move |ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(ENGINE_DISCONNECT_ENTRANCE.with(from.clone()), None);
+                    }),
+                )
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(Command::new(UI_OPEN_ENTRANCES, ()), None);
+                    }),
+                ),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Time and progress of each seed of a multi-seed session and their totals.
+fn seed_summary_builder(summaries: Vec<SeedSummary>) -> impl Widget<DisplayState> {
+    let line = |name: LocalizedString<DisplayState>, s: &SeedSummary| {
+        let mut text = format!(
+            ": {}, {} / {} checks, {} key items",
+            format_duration(&s.elapsed),
+            s.checks.0,
+            s.checks.1,
+            s.key_items
+        );
+        if s.goals.1 > 0 {
+            text.push_str(&format!(", {} / {} goals", s.goals.0, s.goals.1));
+        }
+        Flex::row()
+            .with_child(Label::new(name))
+            .with_child(Label::new(text))
+    };
+
+    let mut total = SeedSummary {
+        elapsed: Duration::from_secs(0),
+        checks: (0, 0),
+        key_items: 0,
+        goals: (0, 0),
+    };
+    let mut col = Flex::column();
+    for (i, s) in summaries.iter().enumerate() {
+        let name = LocalizedString::new("pollendina-seed")
+            .with_placeholder("Seed")
+            .with_arg("number", move |_data: &DisplayState, _env| (i + 1).into());
+        col.add_child(line(name, s));
+        total.elapsed += s.elapsed;
+        total.checks = (total.checks.0 + s.checks.0, total.checks.1 + s.checks.1);
+        total.key_items += s.key_items;
+        total.goals = (total.goals.0 + s.goals.0, total.goals.1 + s.goals.1);
+    }
+    col.with_spacer(8.0)
+        .with_child(line(
+            LocalizedString::new("pollendina-total").with_placeholder("Total"),
+            &total,
+        ))
+        .with_spacer(8.0)
+        .with_child(Flex::row().with_flex_spacer(1.0).with_child(
+            Button::new(LocalizedString::new("pollendina-ok").with_placeholder("Ok")).on_click(
+                |ctx, _data: &mut DisplayState, _env| {
+                    ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                },
+            ),
+        ))
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Saves or copies a dump of every objective's logic and state.
+fn dump_builder() -> impl Widget<DisplayState> {
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-dump-state")
+                .with_placeholder("Dump the expressions and state of every objective:"),
+        ))
+        .with_spacer(8.0)
+        .with_child(
+            Flex::row()
+                .with_child(
+                    Button::new(LocalizedString::new("pollendina-save").with_placeholder("Save…"))
+                        .on_click(|ctx, _data: &mut DisplayState, _env| {
+                            let options = FileDialogOptions::new()
+                                .allowed_types(vec![JSON_FILE_TYPE, YAML_FILE_TYPE])
+                                .default_type(JSON_FILE_TYPE);
+                            ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                            ctx.submit_command(
+                                Command::new(druid::commands::SHOW_SAVE_PANEL, options),
+                                None,
+                            );
+                        }),
+                )
+                .with_spacer(8.0)
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-copy-json").with_placeholder("Copy JSON"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(Command::new(UI_COPY_DUMP, ()), None);
+                        ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                    }),
+                )
+                .with_flex_spacer(1.0)
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                    }),
+                ),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Shows the read-only link that was copied to the clipboard.
+fn share_link_builder(link: String) -> impl Widget<DisplayState> {
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-share-link-copied")
+                .with_placeholder("Copied a read-only link to the clipboard:"),
+        ))
+        .with_child(Label::new(link))
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-share-link-network")
+                .with_placeholder("Spectators need to reach this computer on the network."),
+        ))
+        .with_spacer(8.0)
+        .with_child(Flex::row().with_flex_spacer(1.0).with_child(
+            Button::new(LocalizedString::new("pollendina-ok").with_placeholder("Ok")).on_click(
+                |ctx, _data: &mut DisplayState, _env| {
+                    ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                },
+            ),
+        ))
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Asks `question` before applying `updates`.
+// Asks whether to change `count` checks at the objective `name`.
+fn checks_question(key: &'static str, count: usize, name: String) -> LocalizedString<DisplayState> {
+    LocalizedString::new(key)
+        .with_placeholder("Update these checks?")
+        .with_arg("count", move |_data: &DisplayState, _env| count.into())
+        .with_arg("name", move |_data: &DisplayState, _env| {
+            name.clone().into()
+        })
+}
+
+fn batch_confirm_builder(
+    question: LocalizedString<DisplayState>,
+    updates: HashMap<String, ObjectiveState>,
+) -> impl Widget<DisplayState> {
+    Flex::column()
+        .with_child(Label::new(question))
+        .with_spacer(8.0)
+        .with_child(
+            Flex::row()
+                .with_flex_spacer(1.0)
+                .with_child(
+                    Button::new(LocalizedString::new("pollendina-ok").with_placeholder("Ok"))
+                        .on_click(move |ctx, _data: &mut DisplayState, _env| {
+                            ctx.submit_command(ENGINE_BATCH_UPDATE.with(updates.clone()), None);
+                            ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                        }),
+                )
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                    }),
+                ),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Offers to carry on the spectated session in this tracker.
+fn take_over_builder() -> impl Widget<DisplayState> {
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-take-over-question")
+                .with_placeholder("Take over the session?")
+                .with_arg("address", |data: &DisplayState, _env| {
+                    data.spectating.clone().into()
+                }),
+        ))
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-take-over-explanation")
+                .with_placeholder("Its state is kept here and it stops auto tracking."),
+        ))
+        .with_spacer(8.0)
+        .with_child(
+            Flex::row()
+                .with_flex_spacer(1.0)
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-take-over").with_placeholder("Take over"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(UI_TAKE_OVER.with(false), None);
+                    }),
+                )
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-take-over-auto-track")
+                            .with_placeholder("Take over and auto track"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(UI_TAKE_OVER.with(true), None);
+                    }),
+                )
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"),
+                    )
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                    }),
+                ),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
+
+// Tells the host that a spectator carries on the session.
+fn handed_over_builder() -> impl Widget<DisplayState> {
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-handed-over")
+                .with_placeholder("A spectator took over this session. Auto tracking stopped."),
+        ))
+        .with_spacer(8.0)
+        .with_child(Flex::row().with_flex_spacer(1.0).with_child(
+            Button::new(LocalizedString::new("pollendina-ok").with_placeholder("Ok")).on_click(
+                |ctx, _data: &mut DisplayState, _env| {
+                    ctx.submit_command(ModalHost::DISMISS_MODAL, None);
+                },
+            ),
+        ))
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+}
 
+// Hover tooltip listing an objective's text lines and image.
+fn tooltip_builder() -> impl Widget<DisplayState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            List::new(|| Label::new(|line: &String, _env: &_| line.clone()))
+                .lens(DisplayState::tooltip_lines),
+        )
+        .with_child(
+            List::new(|| {
+                Label::new(
+                    LocalizedString::new("pollendina-note-line")
+                        .with_placeholder("Note")
+                        .with_arg("note", |note: &String, _env| note.clone().into()),
+                )
+            })
+            .lens(DisplayState::tooltip_notes),
+        )
+        .with_child(Either::new(
+            |data: &DisplayState, _env| data.tooltip_image.is_empty(),
+            SizedBox::empty(),
+            Asset::new()
+                .lens(DisplayState::tooltip_image)
+                .fix_size(TOOLTIP_IMAGE_SIZE.0, TOOLTIP_IMAGE_SIZE.1)
+                .padding((0.0, 4.0, 0.0, 0.0)),
+        ))
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+        .rounded(4.0)
+}
+
+// Undo toast shown after a manual state change.
+fn toast_builder() -> impl Widget<DisplayState> {
+    Flex::row()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-undo-toast")
+                .with_placeholder("Marked")
+                .with_arg("changes", |data: &DisplayState, _env| {
+                    data.toast.clone().into()
+                }),
+        ))
+        .with_child(
+            Button::new(LocalizedString::new("pollendina-undo").with_placeholder("Undo")).on_click(
+                |ctx, _data, _env| {
+                    ctx.submit_command(Command::new(UI_UNDO, ()), None);
+                },
+            ),
+        )
+        .padding(8.0)
+        .background(theme::BACKGROUND_DARK)
+        .rounded(4.0)
+}
+
+// The controls of the action bar.
+fn action_bar_row() -> impl Widget<DisplayState> {
     let mut top = Flex::row();
+    top.add_child(Either::new(
+        |data: &DisplayState, _env| data.spectating.is_empty(),
+        SizedBox::empty(),
+        Flex::row()
+            .with_child(Label::new(
+                LocalizedString::new("pollendina-spectating")
+                    .with_placeholder("Spectating (read only)")
+                    .with_arg("address", |data: &DisplayState, _env| {
+                        data.spectating.clone().into()
+                    }),
+            ))
+            .with_child(
+                Button::new(
+                    LocalizedString::new("pollendina-take-over-session")
+                        .with_placeholder("Take over session"),
+                )
+                .on_click(|ctx, _data, _env| {
+                    ctx.submit_command(Command::new(UI_CONFIRM_TAKE_OVER, ()), None);
+                }),
+            )
+            .padding((0.0, 0.0, 8.0, 0.0)),
+    ));
     top.add_child(
-        Button::new(|data: &AutoTrackerState, _env: &_| {
-            if *data == AutoTrackerState::Idle {
-                "Start auto tracking".into()
-            } else {
-                "Stop auto tracking".into()
-            }
-        })
-        .on_click(|ctx, data: &mut AutoTrackerState, _env| {
-            let cmd = if *data == AutoTrackerState::Idle {
-                Command::new(ENGINE_START_AUTO_TRACKING, ())
-            } else {
-                Command::new(ENGINE_STOP_AUTO_TRACKING, ())
-            };
-            ctx.submit_command(cmd, None);
-        })
+        Either::new(
+            |data: &AutoTrackerState, _env| *data == AutoTrackerState::Idle,
+            Flex::row()
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-start-auto-tracking")
+                            .with_placeholder("Start auto tracking"),
+                    )
+                    .on_click(|ctx, _data, _env| {
+                        ctx.submit_command(Command::new(ENGINE_START_AUTO_TRACKING, ()), None);
+                    }),
+                )
+                .with_child(
+                    Button::new(
+                        LocalizedString::new("pollendina-watch-save-file")
+                            .with_placeholder("Watch save file"),
+                    )
+                    .on_click(|ctx, _data, _env| {
+                        let options = FileDialogOptions::new()
+                            .allowed_types(vec![SAVE_FILE_TYPE, SAVE_STATE_FILE_TYPE])
+                            .default_type(SAVE_FILE_TYPE);
+                        ctx.submit_command(
+                            Command::new(druid::commands::SHOW_OPEN_PANEL, options),
+                            None,
+                        );
+                    }),
+                ),
+            Button::new(
+                LocalizedString::new("pollendina-stop-auto-tracking")
+                    .with_placeholder("Stop auto tracking"),
+            )
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(ENGINE_STOP_AUTO_TRACKING, ()), None);
+            }),
+        )
         .lens(DisplayState::auto_tracker_state),
     );
     top.add_child(
         Label::new(|data: &AutoTrackerState, _env: &_| format!("{:?}", data))
             .lens(DisplayState::auto_tracker_state),
     );
+    top.add_child(
+        Label::new(|data: &String, _env: &_| {
+            if data.is_empty() {
+                String::new()
+            } else {
+                format!("ROM {}", data)
+            }
+        })
+        .lens(DisplayState::rom_version),
+    );
+    top.add_child(Either::new(
+        |data: &DisplayState, _env| data.go_mode,
+        Label::new(LocalizedString::new("pollendina-go-mode").with_placeholder("GO MODE"))
+            .with_text_color(Color::BLACK)
+            .padding((6.0, 2.0))
+            .background(GO_MODE_COLOR)
+            .rounded(4.0)
+            .padding((8.0, 0.0, 0.0, 0.0)),
+        SizedBox::empty(),
+    ));
     top.add_flex_spacer(1.0);
-    top.add_child(Button::new("Dump").on_click(|ctx, _data, _env| {
-        ctx.submit_command(Command::new(ENGINE_DUMP_STATE, ()), None);
-    }));
-    top.add_child(Button::new("Broadcast View").on_click(|ctx, _data, _env| {
-        ctx.submit_command(Command::new(UI_OPEN_BROADCAST, ()), None);
-    }));
-    top.add_child(Button::new("Config").on_click(|ctx, _data, _env| {
-        ctx.submit_command(Command::new(UI_OPEN_CONFIG, ()), None);
-    }));
-    root.add_child(Padding::new(8.0, top));
+    top.add_child(Either::new(
+        |data: &DisplayState, _env| data.update_version.is_empty(),
+        SizedBox::empty(),
+        Button::new(
+            LocalizedString::new("pollendina-update-to")
+                .with_placeholder("Update")
+                .with_arg("version", |data: &DisplayState, _env| {
+                    data.update_version.clone().into()
+                }),
+        )
+        .on_click(|ctx, _data, _env| {
+            ctx.submit_command(Command::new(UI_INSTALL_UPDATE, ()), None);
+        }),
+    ));
+    top.add_child(
+        Checkbox::new(LocalizedString::new("pollendina-compact").with_placeholder("Compact"))
+            .lens(Settings::compact)
+            .lens(DisplayState::settings),
+    );
+    top.add_child(
+        Checkbox::new(LocalizedString::new("pollendina-mute").with_placeholder("Mute"))
+            .lens(Settings::muted)
+            .lens(DisplayState::settings),
+    );
+    top.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-restream-safe").with_placeholder("Restream safe"),
+        )
+        .lens(Settings::restream_safe)
+        .lens(DisplayState::settings),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-report").with_placeholder("Report")).on_click(
+            |ctx, _data, _env| {
+                let options = FileDialogOptions::new()
+                    .allowed_types(vec![MARKDOWN_FILE_TYPE, FileSpec::HTML])
+                    .default_type(MARKDOWN_FILE_TYPE);
+                ctx.submit_command(
+                    Command::new(druid::commands::SHOW_SAVE_PANEL, options),
+                    None,
+                );
+            },
+        ),
+    );
+    top.add_child(
+        Button::new(
+            LocalizedString::new("pollendina-copy-summary").with_placeholder("Copy Summary"),
+        )
+        .on_click(|ctx, _data, _env| {
+            ctx.submit_command(Command::new(UI_COPY_SUMMARY, ()), None);
+        }),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-goals").with_placeholder("Goals")).on_click(
+            |ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_OPEN_GOALS, ()), None);
+            },
+        ),
+    );
+    top.add_child(Either::new(
+        |data: &DisplayState, _env| data.spectating.is_empty(),
+        Button::new(LocalizedString::new("pollendina-share").with_placeholder("Share")).on_click(
+            |ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_SHARE_READ_ONLY, ()), None);
+            },
+        ),
+        SizedBox::empty(),
+    ));
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-new-seed").with_placeholder("New Seed"))
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_ADD_SEED, ()), None);
+            }),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-dump").with_placeholder("Dump")).on_click(
+            |ctx, _data, _env| {
+                ctx.submit_command(Command::new(ENGINE_DUMP_STATE, ()), None);
+            },
+        ),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-entrances").with_placeholder("Entrances"))
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_OPEN_ENTRANCES, ()), None);
+            }),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-run-tests").with_placeholder("Tests"))
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(ENGINE_RUN_TESTS, ()), None);
+            }),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-open-watches").with_placeholder("Watches"))
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_OPEN_WATCHES, ()), None);
+            }),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-open-logic").with_placeholder("Logic"))
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_OPEN_LOGIC, ()), None);
+            }),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-open-graph").with_placeholder("Graph"))
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_OPEN_GRAPH, ()), None);
+            }),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-open-timeline").with_placeholder("Timeline"))
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_OPEN_TIMELINE, ()), None);
+            }),
+    );
+    top.add_child(
+        Button::new(
+            LocalizedString::new("pollendina-open-broadcast").with_placeholder("Broadcast View"),
+        )
+        .on_click(|ctx, _data, _env| {
+            ctx.submit_command(Command::new(UI_OPEN_BROADCAST, ()), None);
+        }),
+    );
+    top.add_child(
+        Button::new(LocalizedString::new("pollendina-open-config").with_placeholder("Config"))
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_OPEN_CONFIG, ()), None);
+            }),
+    );
+    top
+}
+
+// The action bar.  Compact mode shrinks its text, controls and padding.
+fn action_bar() -> impl Widget<DisplayState> {
+    EnvScope::new(
+        |env, data: &DisplayState| {
+            if data.settings.compact {
+                let mut shrink = |key: Key<f64>| {
+                    let size = env.get(&key);
+                    env.set(key, size * COMPACT_DENSITY);
+                };
+                shrink(theme::TEXT_SIZE_NORMAL);
+                shrink(theme::BASIC_WIDGET_HEIGHT);
+                shrink(theme::BORDERED_WIDGET_HEIGHT);
+            }
+        },
+        Either::new(
+            |data: &DisplayState, _env| data.settings.compact,
+            Padding::new(2.0, action_bar_row()),
+            Padding::new(8.0, action_bar_row()),
+        ),
+    )
+}
+
+fn ui_builder() -> impl Widget<DisplayState> {
+    let mut root = Flex::column();
+
+    root.add_child(action_bar());
+    root.add_child(Either::new(
+        |data: &DisplayState, _env| data.seeds.len() > 1,
+        seed_tabs(),
+        SizedBox::empty(),
+    ));
+    root.add_child(
+        Either::new(
+            |data: &GoalsState, _env| !data.active.is_empty(),
+            goals_panel(),
+            SizedBox::empty(),
+        )
+        .lens(DisplayState::goals),
+    );
 
     root.add_flex_child(display_widget().lens(DisplayState::layout), 1.0);
 
-    let root = ModalHost::new(root);
+    let root = Toast::new(
+        root,
+        toast_builder(),
+        |data: &DisplayState| data.toast.clone(),
+        |data: &mut DisplayState| data.toast.clear(),
+    );
+    let root = EnvScope::new(
+        |env, data: &DisplayState| {
+            let density = if data.settings.compact {
+                COMPACT_DENSITY
+            } else {
+                1.0
+            };
+            env.set(DENSITY, density);
+        },
+        root,
+    );
+    // The tooltip goes over the popup too, so it can show for its checks.
+    let root = Tooltip::new(
+        ModalHost::new(root),
+        tooltip_builder(),
+        |data: &DisplayState| data.tooltip_pos.map(|(x, y)| Point::new(x, y)),
+    );
+    let root = settings_scope(root);
     // root.debug_paint_layout()
     root
 }
 
-fn config_ui_builder() -> impl Widget<DisplayState> {
+// Tabs of the seeds of a multi-seed session.
+fn seed_tabs() -> impl Widget<DisplayState> {
+    Flex::row()
+        .with_child(
+            DynFlex::row(|| {
+                Button::new(
+                    LocalizedString::new("pollendina-seed-tab")
+                        .with_placeholder("Seed")
+                        .with_arg("number", |(_, tab): &(usize, SeedTab), _env| {
+                            (tab.index + 1).into()
+                        })
+                        .with_arg("active", |(active, tab): &(usize, SeedTab), _env| {
+                            if *active == tab.index { "yes" } else { "no" }.into()
+                        }),
+                )
+                .on_click(|ctx, (_, tab): &mut (usize, SeedTab), _env| {
+                    ctx.submit_command(UI_SWITCH_SEED.with(tab.index), None);
+                })
+            })
+            .lens(lens::Id.map(
+                |data: &DisplayState| (data.active_seed, data.seeds.clone()),
+                |_data: &mut DisplayState, _tabs: (usize, Arc<Vec<SeedTab>>)| {},
+            )),
+        )
+        .with_child(
+            Button::new(
+                LocalizedString::new("pollendina-seed-summary").with_placeholder("Summary"),
+            )
+            .on_click(|ctx, _data, _env| {
+                ctx.submit_command(Command::new(UI_SHOW_SEED_SUMMARY, ()), None);
+            }),
+        )
+        .padding((8.0, 0.0))
+}
+
+// Progress of the practice goals shown below the action bar.
+fn goals_panel() -> impl Widget<GoalsState> {
+    let mut progress = Flex::row();
+    progress.add_child(Label::new(|data: &GoalsState, _env: &_| {
+        let complete = data.active.iter().filter(|g| g.complete).count();
+        format!("{} / {}", complete, data.active.len())
+    }));
+    progress.add_flex_child(
+        ProgressBar::new().expand_width().lens(GoalsState::progress),
+        1.0,
+    );
+
+    let list = List::new(|| {
+        Label::new(|data: &GoalStatus, _env: &_| {
+            if data.complete {
+                format!("\u{2713} {} ({})", data.name, data.time)
+            } else {
+                data.name.clone()
+            }
+        })
+    })
+    .lens(GoalsState::active);
+
+    Flex::column()
+        .with_child(progress)
+        .with_child(SizedBox::new(Scroll::new(list).vertical()).height(60.))
+        .padding((8.0, 0.0))
+}
+
+fn goals_ui_builder() -> impl Widget<DisplayState> {
+    let mut root = Flex::column();
+
+    let list = List::new(|| {
+        Flex::row()
+            .with_child(Checkbox::new("").lens(Goal::selected))
+            .with_child(Label::new(|data: &Goal, _env: &_| data.name.clone()))
+    })
+    .lens(GoalsState::candidates)
+    .lens(DisplayState::goals);
+    root.add_flex_child(Scroll::new(list).vertical().expand_width(), 1.0);
+
+    root.add_child(
+        Flex::row()
+            .with_flex_spacer(1.0)
+            .with_child(
+                Button::new(LocalizedString::new("pollendina-ok").with_placeholder("Ok")).on_click(
+                    |ctx, _data: &mut DisplayState, _env| {
+                        let cmd = Command::new(UI_APPLY_GOALS, ());
+                        ctx.submit_command(cmd, None);
+                    },
+                ),
+            )
+            .with_child(
+                Button::new(LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"))
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        let cmd = Command::new(UI_CANCEL_GOALS, ());
+                        ctx.submit_command(cmd, None);
+                    }),
+            ),
+    );
+
+    root.padding(8.0)
+}
+
+#[cfg(target_os = "windows")]
+const MONOSPACE_FONT: &str = "Consolas";
+#[cfg(target_os = "macos")]
+const MONOSPACE_FONT: &str = "Menlo";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const MONOSPACE_FONT: &str = "monospace";
+
+// Developer window listing the auto tracker's watches with the bytes they
+// last read.
+fn watches_ui_builder() -> impl Widget<DisplayState> {
+    let list = List::new(|| {
+        Flex::column()
+            .with_child(Label::new(|data: &WatchDebug, _env: &_| data.title.clone()))
+            .with_child(EnvScope::new(
+                |env, _data| env.set(theme::FONT_NAME, MONOSPACE_FONT),
+                List::new(|| Label::new(|line: &String, _env: &_| line.clone()))
+                    .lens(WatchDebug::hex),
+            ))
+            .with_child(Label::new(|data: &WatchDebug, _env: &_| {
+                if data.set.is_empty() {
+                    String::new()
+                } else {
+                    format!("set {}", data.set)
+                }
+            }))
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .padding((0.0, 0.0, 0.0, 8.0))
+    })
+    .lens(DisplayState::watch_debug);
+
+    let empty = Label::new(
+        LocalizedString::new("pollendina-watches-empty")
+            .with_placeholder("No watches have been read yet."),
+    );
+    Either::new(
+        |data: &DisplayState, _env| data.watch_debug.is_empty(),
+        empty,
+        Scroll::new(list).vertical().expand_width(),
+    )
+    .padding(8.0)
+}
+
+// Evaluates an expression typed by a module author against the current
+// state as it's edited.
+fn logic_ui_builder() -> impl Widget<DisplayState> {
+    let result = EnvScope::new(
+        |env, _data| env.set(theme::FONT_NAME, MONOSPACE_FONT),
+        List::new(|| Label::new(|line: &String, _env: &_| line.clone())),
+    )
+    .lens(DisplayState::logic_result);
+    Flex::column()
+        .with_child(
+            TextBox::new()
+                .with_placeholder("complete(hook) || magma-key")
+                .expand_width()
+                .lens(DisplayState::logic_input)
+                .on_change(|ctx, _data, _env| {
+                    ctx.submit_command(Command::new(UI_EVAL_EXPRESSION, ()), None);
+                }),
+        )
+        .with_spacer(8.0)
+        .with_flex_child(Scroll::new(result).vertical().expand(), 1.0)
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .padding(8.0)
+}
+
+// The main layout at a point in the session picked with a slider, for
+// reviewing a race afterwards.
+fn timeline_ui_builder() -> impl Widget<DisplayState> {
+    Flex::column()
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Slider::new()
+                        .on_change(|ctx, _data, _env| {
+                            ctx.submit_command(Command::new(UI_SCRUB_TIMELINE, ()), None);
+                        })
+                        .lens(DisplayState::timeline_pos)
+                        .expand_width(),
+                    1.0,
+                )
+                .with_spacer(8.0)
+                .with_child(Label::new(|data: &DisplayState, _env: &_| {
+                    data.timeline_time.clone()
+                })),
+        )
+        .with_spacer(8.0)
+        .with_flex_child(
+            Scroll::new(settings_scope(
+                display_widget().lens(DisplayState::timeline_view),
+            ))
+            .vertical()
+            .expand(),
+            1.0,
+        )
+        .padding(8.0)
+}
+
+// Every objective and what it depends on, colored by state.
+fn graph_ui_builder() -> impl Widget<DisplayState> {
+    Scroll::new(
+        DependencyGraphView::new()
+            .padding(8.0)
+            .lens(DisplayState::graph),
+    )
+}
+
+// Broadcast preview of the config window.  `size` is the size of the
+// broadcast window.
+fn broadcast_preview(size: (f64, f64)) -> impl Widget<DisplayState> {
+    Flex::column()
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-broadcast-preview")
+                .with_placeholder("Broadcast preview:"),
+        ))
+        .with_child(
+            Scale::new(
+                BROADCAST_PREVIEW_SCALE,
+                broadcast_widget()
+                    .fix_size(size.0, size.1)
+                    .background(theme::WINDOW_BACKGROUND_COLOR),
+            )
+            .border(theme::BORDER_DARK, 1.0),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+}
+
+// `preview_size` is the size of the broadcast window to preview.  No preview
+// is shown if `None`.
+fn config_ui_builder(preview_size: Option<(f64, f64)>) -> impl Widget<DisplayState> {
     let mut root = Flex::column();
 
     root.add_child(
@@ -361,26 +2810,367 @@ fn config_ui_builder() -> impl Widget<DisplayState> {
                 .lens(ModuleParam::value),
                 1.0,
             );
-            row
+            // Clicking a section's header collapses or expands it.
+            let header = Label::new(|data: &ModuleParam, _env: &_| {
+                let arrow = if data.collapsed { "\u{25b8}" } else { "\u{25be}" };
+                format!("{} {}", arrow, data.group)
+            })
+            .padding((0.0, 8.0, 0.0, 4.0))
+            .on_click(|ctx, data: &mut ModuleParam, _env| {
+                ctx.submit_command(UI_TOGGLE_PARAM_GROUP.with(data.group.clone()), None);
+            });
+            Flex::column()
+                .with_child(Either::new(
+                    |data: &ModuleParam, _env| data.group_start,
+                    header,
+                    SizedBox::empty(),
+                ))
+                .with_child(Either::new(
+                    |data: &ModuleParam, _env| data.collapsed,
+                    SizedBox::empty(),
+                    row,
+                ))
+                .cross_axis_alignment(CrossAxisAlignment::Start)
         })
         .lens(DisplayState::params),
     );
 
+    root.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-shape-indicators")
+                .with_placeholder("Shape state indicators"),
+        )
+        .lens(Settings::shape_indicators)
+        .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-map-heatmap")
+                .with_placeholder("Tint map regions by progress"),
+        )
+        .lens(Settings::map_heatmap)
+        .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-announce-changes")
+                .with_placeholder("Announce changes (text to speech)"),
+        )
+        .lens(Settings::announce_changes)
+        .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-double-click-complete")
+                .with_placeholder("Double-click to mark complete"),
+        )
+        .lens(Settings::double_click_complete)
+        .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Flex::row()
+            .with_child(
+                Checkbox::new(
+                    LocalizedString::new("pollendina-wheel-steps-state")
+                        .with_placeholder("Mouse wheel steps state"),
+                )
+                .lens(Settings::wheel_steps_state),
+            )
+            .with_child(
+                Checkbox::new(
+                    LocalizedString::new("pollendina-wheel-reversed").with_placeholder("Reversed"),
+                )
+                .lens(Settings::wheel_reversed),
+            )
+            .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-undo-toast")
+                .with_placeholder("Offer undo for changes"),
+        )
+        .lens(Settings::undo_toast)
+        .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Flex::row()
+            .with_child(Label::new(
+                LocalizedString::new("pollendina-broadcast-update-interval")
+                    .with_placeholder("Broadcast update interval:"),
+            ))
+            .with_child(Label::new(|data: &f64, _env: &_| format!("{} ms", data)))
+            .with_child(Stepper::new().with_range(0.0, 1000.0).with_step(50.0))
+            .lens(Settings::broadcast_update_ms)
+            .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-broadcast-overlay")
+                .with_placeholder("Broadcast overlay mode (no title bar)"),
+        )
+        .lens(Settings::broadcast_overlay)
+        .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-broadcast-on-startup")
+                .with_placeholder("Open broadcast window at startup"),
+        )
+        .lens(Settings::broadcast_on_startup)
+        .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(Label::new(
+                LocalizedString::new("pollendina-broadcast-monitor")
+                    .with_placeholder("Broadcast monitor:"),
+            ))
+            .with_child(
+                List::new(|| {
+                    Button::new(|(selected, monitor): &(String, String), _env: &_| {
+                        let name = if monitor.is_empty() {
+                            "Any".to_string()
+                        } else {
+                            monitor.clone()
+                        };
+                        if selected == monitor {
+                            format!("{} \u{2713}", name)
+                        } else {
+                            name
+                        }
+                    })
+                    .on_click(
+                        |_ctx, (selected, monitor): &mut (String, String), _env| {
+                            *selected = monitor.clone();
+                        },
+                    )
+                })
+                .lens(lens::Id.map(
+                    |d: &DisplayState| {
+                        let mut monitors = vec![String::new()];
+                        monitors.extend(d.monitors.iter().cloned());
+                        (d.settings.broadcast_monitor.clone(), Arc::new(monitors))
+                    },
+                    |d: &mut DisplayState, data: (String, Arc<Vec<String>>)| {
+                        d.settings.broadcast_monitor = data.0;
+                    },
+                )),
+            )
+            .with_child(
+                Flex::row()
+                    .with_child(Label::new(|data: &Settings, _env: &_| {
+                        format!("Position: {}, {}", data.broadcast_x, data.broadcast_y)
+                    }))
+                    .with_child(
+                        Stepper::new()
+                            .with_range(0.0, 8000.0)
+                            .with_step(10.0)
+                            .lens(Settings::broadcast_x),
+                    )
+                    .with_child(
+                        Stepper::new()
+                            .with_range(0.0, 8000.0)
+                            .with_step(10.0)
+                            .lens(Settings::broadcast_y),
+                    )
+                    .lens(DisplayState::settings),
+            ),
+    );
+    root.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-broadcast-split")
+                .with_placeholder("Split broadcast view between the first two seeds"),
+        )
+        .lens(Settings::broadcast_split)
+        .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Flex::row()
+            .with_child(
+                Checkbox::new(
+                    LocalizedString::new("pollendina-broadcast-chroma-key")
+                        .with_placeholder("Broadcast chroma key color:"),
+                )
+                .lens(Settings::broadcast_chroma_key),
+            )
+            .with_flex_child(
+                TextBox::new()
+                    .with_placeholder("#00ff00")
+                    .expand_width()
+                    .lens(Settings::broadcast_chroma_color),
+                1.0,
+            )
+            .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Button::new(
+            LocalizedString::new("pollendina-reset-layout")
+                .with_placeholder("Show hidden objectives in their module order"),
+        )
+        .on_click(|ctx, _data: &mut DisplayState, _env| {
+            ctx.submit_command(UI_RESET_LAYOUT_OVERRIDES, None);
+        }),
+    );
+    root.add_child(
+        Checkbox::new(
+            LocalizedString::new("pollendina-check-for-updates")
+                .with_placeholder("Check for updates at startup"),
+        )
+        .lens(Settings::check_for_updates)
+        .lens(DisplayState::settings),
+    );
+    root.add_child(
+        Flex::row()
+            .with_child(Label::new(
+                LocalizedString::new("pollendina-notify-types")
+                    .with_placeholder("Notify for types:"),
+            ))
+            .with_flex_child(
+                TextBox::new()
+                    .expand_width()
+                    .lens(Settings::notify_types)
+                    .lens(DisplayState::settings),
+                1.0,
+            ),
+    );
+    root.add_child(
+        Flex::row()
+            .with_child(Label::new(
+                LocalizedString::new("pollendina-livesplit-server")
+                    .with_placeholder("LiveSplit server:"),
+            ))
+            .with_flex_child(
+                TextBox::new()
+                    .expand_width()
+                    .lens(Settings::livesplit_server)
+                    .lens(DisplayState::settings),
+                1.0,
+            ),
+    );
+    root.add_child(
+        Flex::row()
+            .with_child(Label::new(
+                LocalizedString::new("pollendina-share-address")
+                    .with_placeholder("Share on address:"),
+            ))
+            .with_flex_child(
+                TextBox::new()
+                    .expand_width()
+                    .lens(Settings::share_address)
+                    .lens(DisplayState::settings),
+                1.0,
+            ),
+    );
+    root.add_child(
+        Flex::row()
+            .with_child(Label::new(
+                LocalizedString::new("pollendina-language").with_placeholder("Language:"),
+            ))
+            .with_flex_child(
+                TextBox::new()
+                    .expand_width()
+                    .lens(Settings::locale)
+                    .lens(DisplayState::settings),
+                1.0,
+            ),
+    );
+    root.add_child(
+        Flex::row()
+            .with_child(Label::new(
+                LocalizedString::new("pollendina-usb2snes-device")
+                    .with_placeholder("usb2snes device:"),
+            ))
+            .with_flex_child(
+                TextBox::new()
+                    .expand_width()
+                    .lens(Settings::usb2snes_device)
+                    .lens(DisplayState::settings),
+                1.0,
+            )
+            .with_child(
+                Button::new(
+                    LocalizedString::new("pollendina-find-devices").with_placeholder("Find"),
+                )
+                .on_click(|ctx, _data: &mut DisplayState, _env| {
+                    ctx.submit_command(Command::new(ENGINE_LIST_DEVICES, ()), None);
+                }),
+            ),
+    );
+    root.add_child(
+        List::new(|| {
+            Button::new(|(_, device): &(String, String), _env: &_| device.clone()).on_click(
+                |_ctx, (selected, device): &mut (String, String), _env| {
+                    *selected = device.clone();
+                },
+            )
+        })
+        .lens(lens::Id.map(
+            // Lets the device buttons change the selected device.
+            |d: &DisplayState| (d.settings.usb2snes_device.clone(), d.devices.clone()),
+            |d: &mut DisplayState, data: (String, Arc<Vec<String>>)| {
+                d.settings.usb2snes_device = data.0;
+            },
+        )),
+    );
+
+    // Only modules with more than one script offer a choice.
+    let scripts = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new(
+            LocalizedString::new("pollendina-auto-track-script")
+                .with_placeholder("Auto tracker script:"),
+        ))
+        .with_child(
+            List::new(|| {
+                Button::new(|(selected, script): &(String, String), _env: &_| {
+                    if selected == script {
+                        format!("{} \u{2713}", script)
+                    } else {
+                        script.clone()
+                    }
+                })
+                .on_click(
+                    |_ctx, (selected, script): &mut (String, String), _env| {
+                        *selected = script.clone();
+                    },
+                )
+            })
+            .lens(lens::Id.map(
+                |d: &DisplayState| (d.auto_track_script.clone(), d.auto_track_scripts.clone()),
+                |d: &mut DisplayState, data: (String, Arc<Vec<String>>)| {
+                    d.auto_track_script = data.0;
+                },
+            )),
+        );
+    root.add_child(Either::new(
+        |data: &DisplayState, _env| data.auto_track_scripts.len() > 1,
+        scripts,
+        SizedBox::empty(),
+    ));
+
+    if let Some(size) = preview_size {
+        root.add_child(Padding::new(8.0, broadcast_preview(size)));
+    }
+
     root.add_flex_spacer(1.0);
     root.add_child(
         Flex::row()
             .with_flex_spacer(1.0)
             .with_child(
-                Button::new("Ok").on_click(|ctx, _data: &mut DisplayState, _env| {
-                    let cmd = Command::new(UI_APPLY_CONFIG, ());
-                    ctx.submit_command(cmd, None);
-                }),
+                Button::new(LocalizedString::new("pollendina-ok").with_placeholder("Ok")).on_click(
+                    |ctx, _data: &mut DisplayState, _env| {
+                        let cmd = Command::new(UI_APPLY_CONFIG, ());
+                        ctx.submit_command(cmd, None);
+                    },
+                ),
             )
             .with_child(
-                Button::new("Cancel").on_click(|ctx, _data: &mut DisplayState, _env| {
-                    let cmd = Command::new(UI_CANCEL_CONFIG, ());
-                    ctx.submit_command(cmd, None);
-                }),
+                Button::new(LocalizedString::new("pollendina-cancel").with_placeholder("Cancel"))
+                    .on_click(|ctx, _data: &mut DisplayState, _env| {
+                        let cmd = Command::new(UI_CANCEL_CONFIG, ());
+                        ctx.submit_command(cmd, None);
+                    }),
             ),
     );
 
@@ -388,8 +3178,45 @@ fn config_ui_builder() -> impl Widget<DisplayState> {
     root.padding(8.0)
 }
 
+// The broadcast layout, painted over a solid chroma key color with its
+// theme backgrounds turned off when the chroma key setting is on.  A glow
+// is painted over its edges in go mode.
+fn broadcast_widget() -> impl Widget<DisplayState> {
+    let chroma_key = Painter::new(|ctx, data: &DisplayState, _env| {
+        if data.settings.broadcast_chroma_key {
+            let rect = ctx.size().to_rect();
+            ctx.fill(rect, &data.settings.chroma_color());
+        }
+    });
+    let go_mode_glow = Painter::new(|ctx, data: &DisplayState, _env| {
+        if !data.go_mode {
+            return;
+        }
+        // One pixel wide outlines fading out towards the middle.
+        let rect = ctx.size().to_rect();
+        let width = GO_MODE_GLOW_WIDTH as usize;
+        for i in 0..width {
+            let alpha = 1.0 - i as f64 / width as f64;
+            let outline = rect.inset(-(i as f64 + 0.5));
+            ctx.stroke(outline, &GO_MODE_COLOR.with_alpha(alpha), 1.0);
+        }
+    });
+    let layout = EnvScope::new(
+        |env, data: &DisplayState| env.set(NO_BACKGROUNDS, data.settings.broadcast_chroma_key),
+        settings_scope(Either::new(
+            |data: &DisplayState, _env| data.split_broadcast(),
+            Flex::row()
+                .with_flex_child(display_widget().lens(DisplayState::broadcast), 1.0)
+                .with_flex_child(display_widget().lens(DisplayState::broadcast_split), 1.0),
+            display_widget().lens(DisplayState::broadcast),
+        )),
+    )
+    .background(chroma_key);
+    Stack::new().with_child(layout).with_child(go_mode_glow)
+}
+
 fn broadcast_ui_builder() -> impl Widget<DisplayState> {
-    display_widget().lens(DisplayState::broadcast)
+    broadcast_widget()
     /*
     ViewSwitcher::new(
         |data, _env| data.broadcast,
@@ -411,6 +3238,37 @@ fn broadcast_ui_builder() -> impl Widget<DisplayState> {
     */
 }
 
+// Hotkeys of the first main layout variants in the view menu.
+const LAYOUT_HOTKEYS: [&str; 9] = ["1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+// The main window's menu, which has a view menu to switch between the main
+// layout variants `layouts` if there is more than one.
+fn main_menu(layouts: &[String]) -> MenuDesc<DisplayState> {
+    let menu = app_menu();
+    if layouts.len() < 2 {
+        return menu;
+    }
+
+    let mut view_menu =
+        MenuDesc::new(LocalizedString::new("pollendina-menu-view").with_placeholder("View"));
+    for (i, layout) in layouts.iter().enumerate() {
+        let title = layout
+            .splitn(2, ':')
+            .nth(1)
+            .unwrap_or("default")
+            .to_string();
+        let mut item = MenuItem::new(
+            LocalizedString::new("pollendina-menu-layout").with_placeholder(title),
+            Command::new(UI_SET_MAIN_LAYOUT, layout.clone()),
+        );
+        if let Some(key) = LAYOUT_HOTKEYS.get(i) {
+            item = item.hotkey(SysMods::Cmd, *key);
+        }
+        view_menu = view_menu.append(item);
+    }
+    menu.append(view_menu)
+}
+
 #[allow(unused_mut)]
 pub(crate) fn app_menu() -> MenuDesc<DisplayState> {
     let mut menu = MenuDesc::empty();
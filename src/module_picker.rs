@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+use std::process::Command as Process;
+use std::sync::Arc;
+use std::thread;
+
+use druid::widget::{Button, CrossAxisAlignment, Flex, Label, List, Scroll, SizedBox};
+use druid::{
+    AppDelegate, AppLauncher, Application, Command, Data, DelegateCtx, Env, ExtEventSink,
+    FileDialogOptions, FileSpec, Lens, LocalizedString, Selector, Target, Widget, WidgetExt,
+    WindowDesc,
+};
+use failure::Error;
+
+use crate::cli::Args;
+use crate::settings::get_user_module_dir;
+use crate::update::{download_default_module, open};
+
+const MODULE_FILE_TYPE: FileSpec = FileSpec::new(
+    "Module manifest",
+    pollendina_core::module::MODULE_FILE_EXTENSIONS,
+);
+
+const UI_OPEN_MODULES_FOLDER: Selector<()> = Selector::new("ui:open_modules_folder");
+const UI_DOWNLOAD_MODULE: Selector<()> = Selector::new("ui:download_module");
+// Sent with the downloaded module's manifest, or why it couldn't be
+// downloaded.
+const UI_MODULE_DOWNLOADED: Selector<Result<PathBuf, String>> =
+    Selector::new("ui:module_downloaded");
+
+#[derive(Clone, Data, Lens)]
+struct PickerState {
+    module: String,
+    // Every path the module was looked for at.
+    searched: Arc<Vec<String>>,
+    // What the last action did, e.g. a failed download.
+    status: String,
+}
+
+struct PickerDelegate {
+    args: Args,
+    sink: ExtEventSink,
+}
+
+impl PickerDelegate {
+    // Starts the tracker again with the module at `path` in place of this
+    // window.
+    fn relaunch(&self, path: &Path) {
+        let mut args = self.args.clone();
+        args.module = Some(path.to_string_lossy().into_owned());
+        let result =
+            std::env::current_exe().and_then(|exe| Process::new(exe).args(&args.to_args()).spawn());
+        match result {
+            Ok(_) => Application::global().quit(),
+            Err(e) => println!("error starting tracker: {}", e),
+        }
+    }
+}
+
+impl AppDelegate<PickerState> for PickerDelegate {
+    fn command(
+        &mut self,
+        _ctx: &mut DelegateCtx,
+        _target: Target,
+        cmd: &Command,
+        data: &mut PickerState,
+        _env: &Env,
+    ) -> bool {
+        if let Some(info) = cmd.get(druid::commands::OPEN_FILE) {
+            self.relaunch(info.path());
+            false
+        } else if cmd.is(UI_OPEN_MODULES_FOLDER) {
+            let result = get_user_module_dir().and_then(|dir| {
+                let mods = dir.join("mods");
+                std::fs::create_dir_all(&mods)?;
+                open(&mods.to_string_lossy())
+            });
+            if let Err(e) = result {
+                data.status = format!("Couldn't open the modules folder: {}", e);
+            }
+            false
+        } else if cmd.is(UI_DOWNLOAD_MODULE) {
+            data.status = "Downloading the default module…".into();
+            let sink = self.sink.clone();
+            thread::spawn(move || {
+                let result = get_user_module_dir()
+                    .and_then(|dir| download_default_module(&dir))
+                    .map_err(|e| e.to_string());
+                if let Err(e) = sink.submit_command(UI_MODULE_DOWNLOADED, result, None) {
+                    println!("error reporting module download: {}", e);
+                }
+            });
+            false
+        } else if let Some(result) = cmd.get(UI_MODULE_DOWNLOADED) {
+            match result {
+                Ok(path) => self.relaunch(path),
+                Err(e) => data.status = format!("Couldn't download the default module: {}", e),
+            }
+            false
+        } else {
+            true
+        }
+    }
+}
+
+// Explains that the module couldn't be found and offers ways to get one
+// instead of exiting.  Picking one starts the tracker again with it.
+pub(crate) fn run(args: Args, searched: Vec<PathBuf>) -> Result<(), Error> {
+    let data = PickerState {
+        module: args.module.clone().unwrap_or_else(|| "ff4fe".into()),
+        searched: Arc::new(
+            searched
+                .iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect(),
+        ),
+        status: String::new(),
+    };
+    let window = WindowDesc::new(picker_builder)
+        .title(
+            LocalizedString::new("pollendina-module-picker-title")
+                .with_placeholder("Pollendina: Module Not Found"),
+        )
+        .window_size((560., 360.));
+    let app = AppLauncher::with_window(window);
+    let sink = app.get_external_handle();
+    app.delegate(PickerDelegate { args, sink })
+        .launch(data)
+        .map_err(|e| failure::format_err!("launch failed: {}", e))
+}
+
+fn picker_builder() -> impl Widget<PickerState> {
+    let searched = List::new(|| Label::new(|path: &String, _env: &_| path.clone()))
+        .lens(PickerState::searched);
+    let buttons = Flex::row()
+        .with_child(
+            Button::new(LocalizedString::new("pollendina-browse").with_placeholder("Browse…"))
+                .on_click(|ctx, _data: &mut PickerState, _env| {
+                    let options = FileDialogOptions::new().allowed_types(vec![MODULE_FILE_TYPE]);
+                    ctx.submit_command(
+                        Command::new(druid::commands::SHOW_OPEN_PANEL, options),
+                        None,
+                    );
+                }),
+        )
+        .with_spacer(4.0)
+        .with_child(
+            Button::new(
+                LocalizedString::new("pollendina-open-modules-folder")
+                    .with_placeholder("Open modules folder"),
+            )
+            .on_click(|ctx, _data: &mut PickerState, _env| {
+                ctx.submit_command(UI_OPEN_MODULES_FOLDER, None);
+            }),
+        )
+        .with_spacer(4.0)
+        .with_child(
+            Button::new(
+                LocalizedString::new("pollendina-download-module")
+                    .with_placeholder("Download default module"),
+            )
+            .on_click(|ctx, _data: &mut PickerState, _env| {
+                ctx.submit_command(UI_DOWNLOAD_MODULE, None);
+            }),
+        )
+        .with_flex_spacer(1.0)
+        .with_child(
+            Button::new(LocalizedString::new("pollendina-quit").with_placeholder("Quit")).on_click(
+                |_ctx, _data: &mut PickerState, _env| {
+                    Application::global().quit();
+                },
+            ),
+        );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new(|data: &PickerState, _env: &_| {
+            format!(
+                "Pollendina couldn't find the module {}. It looked in:",
+                data.module
+            )
+        }))
+        .with_spacer(8.0)
+        .with_flex_child(Scroll::new(searched).vertical().expand_width(), 1.0)
+        .with_spacer(8.0)
+        .with_child(Label::new(
+            "Browse for its manifest, put it in the modules folder and start \
+             Pollendina again, or download the default module.",
+        ))
+        .with_spacer(8.0)
+        .with_child(Label::new(|data: &PickerState, _env: &_| {
+            data.status.clone()
+        }))
+        .with_child(SizedBox::new(buttons).expand_width())
+        .padding(8.0)
+}
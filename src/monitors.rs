@@ -0,0 +1,134 @@
+use std::process::Command;
+use std::thread;
+
+use failure::{format_err, Error};
+
+// A display attached to the desktop, in desktop coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Monitor {
+    pub name: String,
+    pub x: i64,
+    pub y: i64,
+}
+
+// druid can't list monitors or move windows, so both are left to the
+// desktop's own tools.  Only X11 is supported so far.
+pub(crate) fn list_monitors() -> Vec<Monitor> {
+    let output = match Command::new("xrandr").arg("--listmonitors").output() {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+    parse_monitors(&String::from_utf8_lossy(&output.stdout))
+}
+
+// Parses `xrandr --listmonitors` lines like
+// " 0: +*DP-1 2560/597x1440/336+0+0  DP-1".
+fn parse_monitors(output: &str) -> Vec<Monitor> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let geometry = fields.nth(2)?;
+            let name = fields.next()?.to_string();
+            let mut origin = geometry.splitn(2, '+').nth(1)?.split('+');
+            let x = origin.next()?.parse().ok()?;
+            let y = origin.next()?.parse().ok()?;
+            Some(Monitor { name, x, y })
+        })
+        .collect()
+}
+
+// Moves the window titled `title` to (`x`, `y`) of `monitor` once it shows
+// up.
+pub(crate) fn place_window(title: &str, monitor: &str, x: f64, y: f64) {
+    let title = format!("^{}$", title);
+    let monitor = monitor.to_string();
+    thread::spawn(move || {
+        if let Err(e) = try_place_window(&title, &monitor, x, y) {
+            println!("error placing window on {}: {}", monitor, e);
+        }
+    });
+}
+
+fn try_place_window(title: &str, monitor: &str, x: f64, y: f64) -> Result<(), Error> {
+    let origin = list_monitors()
+        .into_iter()
+        .find(|m| m.name == monitor)
+        .ok_or(format_err!("no monitor named {}", monitor))?;
+    let status = Command::new("xdotool")
+        .args(&[
+            "search",
+            "--sync",
+            "--limit",
+            "1",
+            "--name",
+            title,
+            "windowmove",
+        ])
+        .arg((origin.x + x as i64).to_string())
+        .arg((origin.y + y as i64).to_string())
+        .status()?;
+    if !status.success() {
+        return Err(format_err!("xdotool could not move the window"));
+    }
+    Ok(())
+}
+
+// Moves the window titled `title` by (`dx`, `dy`).
+pub(crate) fn move_window_by(title: &str, dx: f64, dy: f64) -> Result<(), Error> {
+    let status = Command::new("xdotool")
+        .args(["search", "--limit", "1", "--name"])
+        .arg(format!("^{}$", title))
+        .args(["windowmove", "--relative", "--"])
+        .arg((dx as i64).to_string())
+        .arg((dy as i64).to_string())
+        .status()?;
+    if !status.success() {
+        return Err(format_err!("xdotool could not move the window"));
+    }
+    Ok(())
+}
+
+// Keeps the window titled `title` above other windows once it shows up.
+pub(crate) fn keep_above(title: &str) {
+    let title = format!("^{}$", title);
+    thread::spawn(move || {
+        let status = Command::new("xdotool")
+            .args(["search", "--sync", "--limit", "1", "--name"])
+            .arg(&title)
+            .args(["windowstate", "--add", "ABOVE"])
+            .status();
+        match status {
+            Ok(status) if status.success() => (),
+            _ => println!("error keeping {} above other windows", title),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_xrandr_monitors() {
+        let output = "Monitors: 2\n \
+                      0: +*DP-1 2560/597x1440/336+0+0  DP-1\n \
+                      1: +HDMI-1 1920/527x1080/296+2560+360  HDMI-1\n";
+        assert_eq!(
+            parse_monitors(output),
+            vec![
+                Monitor {
+                    name: "DP-1".into(),
+                    x: 0,
+                    y: 0
+                },
+                Monitor {
+                    name: "HDMI-1".into(),
+                    x: 2560,
+                    y: 360
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use notify_rust::Notification;
+
+use crate::engine::{
+    module::GO_MODE_ID, Engine, ObjectiveState, Settings, StateChange, StateEvent, StateObserver,
+    StateSource,
+};
+
+// Shows notifications for the changes the auto tracker makes and when go
+// mode is reached.
+pub(crate) struct Notifier;
+
+impl StateObserver for Notifier {
+    fn state_changed(&mut self, engine: &Engine, settings: &Settings, event: &StateEvent) {
+        if event.source == StateSource::AutoTracker {
+            notify_state_changes(engine, &event.changes, &settings.notify_types);
+        } else if event
+            .changes
+            .iter()
+            .any(|c| c.id == GO_MODE_ID && c.new_state == ObjectiveState::Complete)
+        {
+            show_notification("Go mode!");
+        }
+    }
+}
+
+// Show desktop notifications for `changes` to objectives that are marked
+// with `notify`, whose type is listed in the comma separated `types` or
+// that are go mode.
+fn notify_state_changes(engine: &Engine, changes: &[StateChange], types: &String) {
+    let types: Vec<&str> = types
+        .split(',')
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    for change in changes {
+        let notify = engine
+            .objective_info(&change.id)
+            .map(|info| info.notify)
+            .unwrap_or(false)
+            || types.contains(&change.ty.as_str())
+            || change.id == GO_MODE_ID;
+        if !notify {
+            continue;
+        }
+
+        let name = engine.objective_name(&change.id);
+        let body = match change.new_state {
+            ObjectiveState::Complete if change.id == GO_MODE_ID => "Go mode!".to_string(),
+            _ if change.id == GO_MODE_ID => continue,
+            ObjectiveState::Unlocked => format!("{} found!", name),
+            ObjectiveState::Complete => format!("{} complete!", name),
+            _ => continue,
+        };
+        show_notification(&body);
+    }
+}
+
+fn show_notification(body: &str) {
+    if let Err(e) = Notification::new().summary("Pollendina").body(body).show() {
+        println!("error showing notification: {}", e);
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::{format_err, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{LayoutOverrides, Settings};
+
+// Settings that only apply to a single module.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ModuleSettings {
+    #[serde(default)]
+    pub goals: Vec<String>,
+    // Values the module's auto tracker script saved with `storage.set`.
+    #[serde(default)]
+    pub script_storage: HashMap<String, serde_json::Value>,
+    // Game process the user allowed the auto tracker to read.  Empty until
+    // they agree.
+    #[serde(default)]
+    pub process_consent: String,
+    // Main layout variant last switched to.  Empty for the module's "main"
+    // layout.
+    #[serde(default)]
+    pub main_layout: String,
+    // Name of the auto tracker script picked in the config window.  Empty
+    // for the module's first script.
+    #[serde(default)]
+    pub auto_track_script: String,
+    // Objectives the user hid or reordered, keyed by main layout variant.
+    #[serde(default)]
+    pub layout_overrides: HashMap<String, LayoutOverrides>,
+}
+
+// The user's settings as stored on disk.
+#[derive(Default, Deserialize, Serialize)]
+pub struct SettingsFile {
+    #[serde(default)]
+    pub settings: Settings,
+    // Keyed by module name.
+    #[serde(default)]
+    pub modules: HashMap<String, ModuleSettings>,
+}
+
+impl SettingsFile {
+    pub fn load(path: &Path) -> Result<SettingsFile, Error> {
+        if !path.exists() {
+            return Ok(Default::default());
+        }
+        let data = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+fn env_path(var: &str) -> Result<PathBuf, Error> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .ok_or(format_err!("{} is not set", var))
+}
+
+#[cfg(target_os = "macos")]
+fn get_config_dir() -> Result<PathBuf, Error> {
+    let mut p = env_path("HOME")?;
+    p.push("Library");
+    p.push("Application Support");
+    Ok(p)
+}
+
+#[cfg(target_os = "windows")]
+fn get_config_dir() -> Result<PathBuf, Error> {
+    env_path("APPDATA")
+}
+
+#[cfg(target_os = "linux")]
+fn get_config_dir() -> Result<PathBuf, Error> {
+    env_path("XDG_CONFIG_HOME").or_else(|_| {
+        let mut p = env_path("HOME")?;
+        p.push(".config");
+        Ok(p)
+    })
+}
+
+pub fn get_settings_path() -> Result<PathBuf, Error> {
+    let mut p = get_user_module_dir()?;
+    p.push("settings.json");
+    Ok(p)
+}
+
+// Per user directory searched for modules.  Its `mods` folder is where
+// downloaded modules go.
+pub fn get_user_module_dir() -> Result<PathBuf, Error> {
+    let mut p = get_config_dir()?;
+    p.push("pollendina");
+    Ok(p)
+}
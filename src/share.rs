@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use druid::{ExtEventSink, Selector};
+use failure::{format_err, Error};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::percent_encode;
+use crate::engine::ObjectiveState;
+
+// Port trackers share their state on.
+pub(crate) const SHARE_PORT: u16 = 47101;
+
+// States are sent from the UI thread so a stalled spectator is dropped
+// rather than waited on.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+// How long a spectator has to send the session token after connecting.
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Sent to a spectating tracker with the state of the tracker it watches.
+pub(crate) const ENGINE_SPECTATE_STATE: Selector<SpectatedState> =
+    Selector::new("engine:spectate_state");
+// Sent to a host when one of its spectators took over the session.
+pub(crate) const ENGINE_HANDED_OVER: Selector<()> = Selector::new("engine:handed_over");
+
+// Line a spectator sends back to its host when it takes over the session,
+// followed by a space and the session token.
+const TAKE_OVER: &str = "take-over";
+
+// One line of the share protocol.  A spectator first sends the session
+// token from the link on a line of its own.  Hosts then send their whole
+// state, one JSON object per line, and again whenever it changes.
+#[derive(Deserialize, Serialize)]
+struct SharedState {
+    module: String,
+    // In module order so unchanged states send the same line.
+    states: IndexMap<String, ObjectiveState>,
+    // The auto tracker script's storage, so a spectator that takes over
+    // can pick up auto tracking where the host left off.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    script_storage: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Clone)]
+pub(crate) struct SpectatedState {
+    pub states: HashMap<String, ObjectiveState>,
+    pub script_storage: HashMap<String, serde_json::Value>,
+}
+
+// Sends a tracker's state to read-only spectators.
+pub(crate) struct ShareHost {
+    // Name of the module from its manifest.
+    module: String,
+    // Link spectators open to watch this tracker.  It carries the session
+    // token so only people given the link can watch.
+    link: String,
+    // Spectators connected so far and the last line sent to them.
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+    last: Arc<Mutex<String>>,
+}
+
+impl ShareHost {
+    // `module_path` is how spectators find the module, as passed to
+    // `--module`.  Only `address` is listened on, or the interface used to
+    // reach the network if it's empty.  `ENGINE_HANDED_OVER` is sent to
+    // `sink` when a spectator takes over.
+    pub fn start(
+        module: &str,
+        module_path: &str,
+        address: &str,
+        sink: ExtEventSink,
+    ) -> Result<ShareHost, Error> {
+        let ip = if address.trim().is_empty() {
+            local_ip()
+        } else {
+            address
+                .trim()
+                .parse()
+                .map_err(|_| format_err!("{} is not an IP address", address))?
+        };
+        let addr = SocketAddr::new(ip, SHARE_PORT);
+        let listener =
+            TcpListener::bind(addr).map_err(|e| format_err!("can't listen on {}: {}", addr, e))?;
+        let token = session_token();
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let last = Arc::new(Mutex::new(String::new()));
+
+        let accept_clients = clients.clone();
+        let accept_last = last.clone();
+        let accept_token = token.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let clients = accept_clients.clone();
+                        let last = accept_last.clone();
+                        let token = accept_token.clone();
+                        let sink = sink.clone();
+                        thread::spawn(move || {
+                            if let Err(e) = admit(stream, &token, &clients, &last, sink) {
+                                println!("error admitting spectator: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => println!("error accepting spectator: {}", e),
+                }
+            }
+        });
+
+        Ok(ShareHost {
+            module: module.to_string(),
+            link: format!(
+                "pollendina://spectate?module={}&address={}&token={}",
+                percent_encode(module_path),
+                percent_encode(&addr.to_string()),
+                token
+            ),
+            clients,
+            last,
+        })
+    }
+
+    pub fn link(&self) -> &String {
+        &self.link
+    }
+
+    // Sends `states` and `script_storage` to every spectator if they
+    // changed since the last call.  Spectators that went away are dropped.
+    pub fn publish(
+        &self,
+        states: &IndexMap<String, ObjectiveState>,
+        script_storage: &HashMap<String, serde_json::Value>,
+    ) -> Result<(), Error> {
+        let mut line = serde_json::to_string(&SharedState {
+            module: self.module.clone(),
+            states: states.clone(),
+            script_storage: script_storage.clone(),
+        })?;
+        line.push('\n');
+
+        let mut last = self.last.lock().unwrap();
+        if *last == line {
+            return Ok(());
+        }
+        self.clients
+            .lock()
+            .unwrap()
+            .retain(|mut client| client.write_all(line.as_bytes()).is_ok());
+        *last = line;
+        Ok(())
+    }
+}
+
+// Adds the spectator on the other end of `stream` to `clients` once it
+// sends `token`.  Spectators that don't are disconnected without seeing any
+// state.
+fn admit(
+    mut stream: TcpStream,
+    token: &str,
+    clients: &Mutex<Vec<TcpStream>>,
+    last: &Mutex<String>,
+    sink: ExtEventSink,
+) -> Result<(), Error> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    stream.set_read_timeout(Some(TOKEN_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if !tokens_match(line.trim_end(), token) {
+        stream.shutdown(Shutdown::Both)?;
+        return Err(format_err!("wrong session token"));
+    }
+    stream.set_read_timeout(None)?;
+    watch_for_take_over(reader, token.to_string(), sink);
+
+    // Catch the spectator up before it gets changes.
+    let last = last.lock().unwrap();
+    if last.is_empty() || stream.write_all(last.as_bytes()).is_ok() {
+        clients.lock().unwrap().push(stream);
+    }
+    Ok(())
+}
+
+// Tells the host when the spectator on the other end of `reader` takes over.
+// Requests without the session `token` are ignored.
+fn watch_for_take_over(reader: BufReader<TcpStream>, token: String, sink: ExtEventSink) {
+    let take_over = format!("{} {}", TAKE_OVER, token);
+    thread::spawn(move || {
+        for line in reader.lines() {
+            match line {
+                Ok(line) if tokens_match(&line, &take_over) => {
+                    if let Err(e) = sink.submit_command(ENGINE_HANDED_OVER, (), None) {
+                        println!("error handing over session: {}", e);
+                    }
+                }
+                Ok(_) => (),
+                Err(_) => return,
+            }
+        }
+    });
+}
+
+// 128 random bits as hex.
+fn session_token() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+// Compares every byte so how long a guess takes doesn't tell how much of it
+// was right.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+// Connection to the tracker a spectator watches.
+pub(crate) struct Spectator {
+    stream: TcpStream,
+    token: String,
+}
+
+impl Spectator {
+    // Tells the host this tracker carries on the session and stops
+    // watching it.
+    pub fn take_over(self) -> Result<(), Error> {
+        let mut stream = self.stream;
+        stream.write_all(format!("{} {}\n", TAKE_OVER, self.token).as_bytes())?;
+        stream.shutdown(Shutdown::Both)?;
+        Ok(())
+    }
+}
+
+// Watches the tracker at `addr` with the session `token` from its link and
+// sends its state as `ENGINE_SPECTATE_STATE` commands until it goes away.
+pub(crate) fn spectate(
+    addr: &str,
+    token: &str,
+    module: &str,
+    sink: ExtEventSink,
+) -> Result<Spectator, Error> {
+    let mut stream =
+        TcpStream::connect(addr).map_err(|e| format_err!("can't connect to {}: {}", addr, e))?;
+    stream.write_all(format!("{}\n", token).as_bytes())?;
+    let reader = stream.try_clone()?;
+    let module = module.to_string();
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let result = line
+                .map_err(Error::from)
+                .and_then(|line| Ok(serde_json::from_str::<SharedState>(&line)?))
+                .and_then(|shared| {
+                    if shared.module != module {
+                        return Err(format_err!("host is tracking {}", shared.module));
+                    }
+                    let state = SpectatedState {
+                        states: shared.states.into_iter().collect(),
+                        script_storage: shared.script_storage,
+                    };
+                    Ok(sink.submit_command(ENGINE_SPECTATE_STATE, state, None)?)
+                });
+            if let Err(e) = result {
+                println!("error spectating: {}", e);
+                return;
+            }
+        }
+        println!("spectated tracker closed the connection");
+    });
+    Ok(Spectator {
+        stream,
+        token: token.to_string(),
+    })
+}
+
+// Address other machines on the network reach this one at.  Connecting a
+// UDP socket sends nothing but has the OS pick the interface it would route
+// a private address through, without naming any outside host.
+fn local_ip() -> IpAddr {
+    UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| {
+            socket.connect("10.255.255.255:1")?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip())
+        .unwrap_or_else(|_| [127, 0, 0, 1].into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_state_line() -> Result<(), Error> {
+        let mut states = IndexMap::new();
+        states.insert("hook".to_string(), ObjectiveState::Complete);
+        states.insert("bomb".to_string(), ObjectiveState::Locked);
+        let line = serde_json::to_string(&SharedState {
+            module: "ff4fe".to_string(),
+            states,
+            script_storage: HashMap::new(),
+        })?;
+        assert_eq!(
+            line,
+            r#"{"module":"ff4fe","states":{"hook":"complete","bomb":"locked"}}"#
+        );
+
+        let shared: SharedState = serde_json::from_str(&line)?;
+        assert_eq!(shared.states["hook"], ObjectiveState::Complete);
+        assert!(shared.script_storage.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn shared_script_storage() -> Result<(), Error> {
+        let mut script_storage = HashMap::new();
+        script_storage.insert("gil".to_string(), serde_json::json!(500));
+        let line = serde_json::to_string(&SharedState {
+            module: "ff4fe".to_string(),
+            states: IndexMap::new(),
+            script_storage,
+        })?;
+        let shared: SharedState = serde_json::from_str(&line)?;
+        assert_eq!(shared.script_storage["gil"], serde_json::json!(500));
+        Ok(())
+    }
+
+    #[test]
+    fn session_tokens() {
+        let token = session_token();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, session_token());
+        assert!(tokens_match(&token, &token.clone()));
+        assert!(!tokens_match(&token, &token[1..]));
+        assert!(!tokens_match(&token, ""));
+        assert!(!tokens_match(&token, &"0".repeat(32)));
+    }
+}
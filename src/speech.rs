@@ -0,0 +1,74 @@
+use std::io;
+use std::process::{Child, Command};
+use std::thread;
+
+use crate::engine::{Engine, ObjectiveState, Settings, StateChange, StateEvent, StateObserver};
+
+// Reads state changes aloud when enabled in the settings.
+pub(crate) struct Announcer;
+
+impl StateObserver for Announcer {
+    fn state_changed(&mut self, engine: &Engine, settings: &Settings, event: &StateEvent) {
+        if settings.announce_changes {
+            announce_state_changes(engine, &event.changes);
+        }
+    }
+}
+
+// Describe `change` in words, e.g. "Magma Key complete".
+pub(crate) fn state_change_phrase(engine: &Engine, change: &StateChange) -> String {
+    let state = match change.new_state {
+        ObjectiveState::Disabled => "disabled",
+        ObjectiveState::Locked => "locked",
+        ObjectiveState::GlitchLocked => "glitch locked",
+        ObjectiveState::Unlocked => "unlocked",
+        ObjectiveState::Complete => "complete",
+    };
+    format!("{} {}", engine.objective_name(&change.id), state)
+}
+
+// Read `changes` aloud.
+fn announce_state_changes(engine: &Engine, changes: &[StateChange]) {
+    let phrases: Vec<String> = changes
+        .iter()
+        .map(|change| state_change_phrase(engine, change))
+        .collect();
+    if phrases.is_empty() {
+        return;
+    }
+
+    match speak(&phrases.join(". ")) {
+        // Reap the speech process without blocking the UI.
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => println!("error announcing changes: {}", e),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn speak(text: &str) -> io::Result<Child> {
+    Command::new("say").arg(text).spawn()
+}
+
+#[cfg(target_os = "windows")]
+fn speak(text: &str) -> io::Result<Child> {
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; \
+         (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+        text.replace('\'', "''")
+    );
+    Command::new("powershell")
+        .args(&["-NoProfile", "-Command", &script])
+        .spawn()
+}
+
+#[cfg(target_os = "linux")]
+fn speak(text: &str) -> io::Result<Child> {
+    // speech-dispatcher is the desktop default.  Fall back to espeak where
+    // it's not installed.
+    Command::new("spd-say")
+        .arg(text)
+        .spawn()
+        .or_else(|_| Command::new("espeak").arg(text).spawn())
+}
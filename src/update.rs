@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use druid::{ExtEventSink, Selector};
+use failure::{format_err, Error};
+use flate2::read::GzDecoder;
+use pollendina_core::checksums::{self, CHECKSUMS_FILE};
+use serde::Deserialize;
+use tar::Archive;
+
+// Latest release of the tracker on GitHub.
+const RELEASES_URL: &str = "https://api.github.com/repos/konkers/pollendina/releases/latest";
+// Release pages opened when there's no installer.  Anything else the feed
+// points at is refused.
+const RELEASE_PAGE_PREFIX: &str = "https://github.com/konkers/pollendina/releases/";
+const DEFAULT_MODULE: &str = "ff4fe";
+// Largest download accepted, well above the size of an installer.
+const MAX_DOWNLOAD_SIZE: u64 = 256 * 1024 * 1024;
+
+// Sent when a release newer than this build is found.
+pub(crate) const UI_UPDATE_AVAILABLE: Selector<UpdateInfo> = Selector::new("ui:update_available");
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct UpdateInfo {
+    pub version: String,
+    // Release page to send users to when there's no installer for their
+    // platform.
+    pub page_url: String,
+    pub installer: Option<ReleaseFile>,
+}
+
+// A release asset and the SHA-256 published for it in the release's
+// checksums file.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ReleaseFile {
+    pub name: String,
+    pub url: String,
+    pub sha256: String,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn latest() -> Result<Release, Error> {
+        let response = ureq::get(RELEASES_URL)
+            .set("Accept", "application/vnd.github.v3+json")
+            .call()?;
+        Ok(serde_json::from_reader(response.into_reader())?)
+    }
+
+    // The assets listed in the release's checksums file.  Assets without a
+    // checksum are never downloaded.
+    fn files(&self) -> Result<Vec<ReleaseFile>, Error> {
+        let sums = match self.assets.iter().find(|a| a.name == CHECKSUMS_FILE) {
+            Some(asset) => get(&asset.browser_download_url)?,
+            None => return Ok(Vec::new()),
+        };
+        let sums = checksums::parse(&String::from_utf8_lossy(&sums))?;
+        Ok(release_files(&self.assets, &sums))
+    }
+}
+
+fn release_files(assets: &[ReleaseAsset], sums: &HashMap<String, String>) -> Vec<ReleaseFile> {
+    assets
+        .iter()
+        .filter_map(|a| {
+            sums.get(&a.name).map(|sha256| ReleaseFile {
+                name: a.name.clone(),
+                url: a.browser_download_url.clone(),
+                sha256: sha256.clone(),
+            })
+        })
+        .collect()
+}
+
+// Check the release feed in the background and send `UI_UPDATE_AVAILABLE`
+// if there's a newer version.
+pub(crate) fn check_for_update(sink: ExtEventSink) {
+    thread::spawn(move || match latest_release() {
+        Ok(Some(info)) => {
+            if let Err(e) = sink.submit_command(UI_UPDATE_AVAILABLE, info, None) {
+                println!("error reporting update: {}", e);
+            }
+        }
+        Ok(None) => (),
+        Err(e) => println!("error checking for updates: {}", e),
+    });
+}
+
+// Download, verify and launch the installer of `info` in the background.
+// Without an installer for this platform the release page is opened
+// instead.
+pub(crate) fn install_update(info: UpdateInfo) {
+    thread::spawn(move || {
+        let result = match &info.installer {
+            Some(file) => download(file).and_then(|data| launch_installer(&file.name, &data)),
+            None if info.page_url.starts_with(RELEASE_PAGE_PREFIX) => open(&info.page_url),
+            None => Err(format_err!("unexpected release page {}", info.page_url)),
+        };
+        if let Err(e) = result {
+            println!("error installing update: {}", e);
+        }
+    });
+}
+
+fn latest_release() -> Result<Option<UpdateInfo>, Error> {
+    let release = Release::latest()?;
+    if !is_newer(&release.tag_name, env!("CARGO_PKG_VERSION")) {
+        return Ok(None);
+    }
+    let installer = release
+        .files()?
+        .into_iter()
+        .find(|f| INSTALLER_EXTENSIONS.iter().any(|e| f.name.ends_with(e)));
+    Ok(Some(UpdateInfo {
+        version: release.tag_name.trim_start_matches('v').to_string(),
+        page_url: release.html_url,
+        installer,
+    }))
+}
+
+#[cfg(target_os = "windows")]
+const INSTALLER_EXTENSIONS: &[&str] = &[".msi"];
+#[cfg(target_os = "macos")]
+const INSTALLER_EXTENSIONS: &[&str] = &[".dmg"];
+// Linux users update through their package manager.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const INSTALLER_EXTENSIONS: &[&str] = &[];
+
+fn get(url: &str) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    ureq::get(url)
+        .call()?
+        .into_reader()
+        .take(MAX_DOWNLOAD_SIZE)
+        .read_to_end(&mut data)?;
+    Ok(data)
+}
+
+// Fetches `file` and checks it against its published checksum.
+fn download(file: &ReleaseFile) -> Result<Vec<u8>, Error> {
+    let data = get(&file.url)?;
+    if checksums::sha256_hex(&data) != file.sha256 {
+        return Err(format_err!("{} does not match its checksum", file.name));
+    }
+    Ok(data)
+}
+
+// Saves the installer under a name of our choosing, so the feed can't
+// pick where it's written, and runs it.
+fn launch_installer(name: &str, data: &[u8]) -> Result<(), Error> {
+    let extension = INSTALLER_EXTENSIONS
+        .iter()
+        .find(|e| name.ends_with(*e))
+        .ok_or_else(|| format_err!("{} is not an installer", name))?;
+    let path = env::temp_dir().join(format!("pollendina-update{}", extension));
+    fs::write(&path, data)?;
+    run_installer(&path)
+}
+
+#[cfg(target_os = "windows")]
+fn run_installer(path: &Path) -> Result<(), Error> {
+    Command::new("msiexec").arg("/i").arg(path).spawn()?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn run_installer(path: &Path) -> Result<(), Error> {
+    // Mounts the disk image in Finder.
+    Command::new("open").arg(path).spawn()?.wait()?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn run_installer(path: &Path) -> Result<(), Error> {
+    Err(format_err!("can't run installer {}", path.display()))
+}
+
+// Fetches the default module from the latest release into `dir`'s `mods`
+// folder, returning the path of its manifest.
+pub(crate) fn download_default_module(dir: &Path) -> Result<PathBuf, Error> {
+    let name = format!("{}.tar.gz", DEFAULT_MODULE);
+    let file = Release::latest()?
+        .files()?
+        .into_iter()
+        .find(|f| f.name == name)
+        .ok_or_else(|| format_err!("the latest release has no checksummed {}", name))?;
+    let archive = download(&file)?;
+    let mods = dir.join("mods");
+    fs::create_dir_all(&mods)?;
+    extract_module(&archive, &mods)?;
+    Ok(mods.join(DEFAULT_MODULE).join("manifest.json"))
+}
+
+// Unpacks the default module's folder from `archive` into `mods`.  Other
+// entries are skipped and `unpack_in` refuses paths that leave `mods`.
+fn extract_module(archive: &[u8], mods: &Path) -> Result<(), Error> {
+    let mut archive = Archive::new(GzDecoder::new(archive));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.path()?.starts_with(DEFAULT_MODULE) {
+            continue;
+        }
+        if !entry.unpack_in(mods)? {
+            return Err(format_err!("bad path in module archive"));
+        }
+    }
+    Ok(())
+}
+
+// Open a file or URL with its default application.
+pub(crate) fn open(target: &str) -> Result<(), Error> {
+    // Run directly rather than through `cmd /C start` so `target` is never
+    // parsed by a shell.
+    #[cfg(target_os = "windows")]
+    let mut command = Command::new("explorer");
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(target).spawn()?.wait()?;
+    Ok(())
+}
+
+// Whether the release tagged `tag` (e.g. "v0.2.0") is newer than `current`.
+fn is_newer(tag: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Vec<u64> {
+        version
+            .trim_start_matches('v')
+            .split('.')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    }
+    parse(tag) > parse(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_versions() {
+        assert!(is_newer("v0.1.2", "0.1.1"));
+        assert!(is_newer("v0.2.0", "0.1.10"));
+        assert!(is_newer("1.0.0", "0.9.9"));
+        assert!(!is_newer("v0.1.1", "0.1.1"));
+        assert!(!is_newer("v0.1.0", "0.1.1"));
+        assert!(!is_newer("v0.1.9", "0.1.10"));
+    }
+
+    #[test]
+    fn only_checksummed_assets() {
+        let asset = |name: &str| ReleaseAsset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.com/{}", name),
+        };
+        let assets = vec![asset("a.msi"), asset("b.dmg"), asset(CHECKSUMS_FILE)];
+        let sums = checksums::parse(&format!("{}  a.msi\n", "0".repeat(64))).unwrap();
+        let files = release_files(&assets, &sums);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "a.msi");
+        assert_eq!(files[0].sha256, "0".repeat(64));
+    }
+}
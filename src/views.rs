@@ -1,31 +1,192 @@
 use std::sync::Arc;
 
-use druid::widget::{Button, Flex, Label, Padding, ViewSwitcher};
-use druid::{lens, Command, LensExt, MouseEvent, Widget, WidgetExt};
+use druid::widget::{Button, Either, EnvScope, Flex, Label, List, Padding, SizedBox, ViewSwitcher};
+use druid::{
+    lens, theme, Command, ContextMenu, Key, LensExt, LocalizedString, MenuDesc, MenuItem,
+    MouseEvent, Point, Widget, WidgetExt,
+};
 
 use match_macro::match_widget;
 
 use crate::{
     engine::{
-        self, DisplayChild, DisplayView, DisplayViewCount, DisplayViewData, DisplayViewFlex,
-        DisplayViewGrid, DisplayViewMap, DisplayViewTabChild, DisplayViewTabs, MapInfo,
+        self, AssignmentSlot, CheckMark, ClickAction, DisplayChild, DisplayState, DisplayView,
+        DisplayViewAssignment, DisplayViewChecksInLogic, DisplayViewCount, DisplayViewData,
+        DisplayViewFlex, DisplayViewGrid, DisplayViewMap, DisplayViewSplits, DisplayViewTabChild,
+        DisplayViewTabs, DisplayViewText, MapInfo, MapLayer, ObjectiveState, SplitRow,
+        ThemeColorKey,
     },
     widget::{
         dyn_flex::CrossAxisAlignment, Asset, ClickExt, Constellation, Container, DynFlex, Grid,
-        MapObjective, Objective, Stack,
+        MapObjective, Objective, RegionOverlay, Stack, DENSITY,
     },
-    ENGINE_TOGGLE_STATE, UI_OPEN_POPUP,
+    BatchAction, ENGINE_MARK_CHECK, ENGINE_SHOW_HINTS, ENGINE_STEP_STATE_BACK,
+    ENGINE_TOGGLE_GLITCH_LOCKED, ENGINE_TOGGLE_LOCK, ENGINE_TOGGLE_STATE, UI_CONFIRM_BATCH,
+    UI_EDIT_NOTE, UI_HIDE_OBJECTIVE, UI_HIDE_TOOLTIP, UI_MOVE_OBJECTIVE, UI_OPEN_POPUP,
+    UI_PICK_ASSIGNMENT, UI_PICK_CHECK_ITEM, UI_SHOW_TOOLTIP,
 };
 
+// When set, an unlocked objective is only marked complete by a double click.
+pub(crate) const DOUBLE_CLICK_COMPLETE: Key<bool> = Key::new("pollendina.double-click-complete");
+// Notches scrolling up step an objective's state by, 0 to leave the wheel
+// to scrolling.
+pub(crate) const WHEEL_STEP: Key<f64> = Key::new("pollendina.wheel-step");
+
+// Bulk changes to the checks of the location `id`.
+fn location_menu_items(id: &String) -> Vec<MenuItem<DisplayState>> {
+    vec![
+        MenuItem::new(
+            LocalizedString::new("pollendina-complete-checks")
+                .with_placeholder("Complete all checks here\u{2026}"),
+            UI_CONFIRM_BATCH.with(BatchAction::CompleteChecks(id.clone())),
+        ),
+        MenuItem::new(
+            LocalizedString::new("pollendina-reset-location")
+                .with_placeholder("Reset location\u{2026}"),
+            UI_CONFIRM_BATCH.with(BatchAction::ResetChecks(id.clone())),
+        ),
+    ]
+}
+
+fn note_menu_item(id: &String) -> MenuItem<DisplayState> {
+    MenuItem::new(
+        LocalizedString::new("pollendina-edit-note").with_placeholder("Edit note\u{2026}"),
+        UI_EDIT_NOTE.with(id.clone()),
+    )
+}
+
+// Shows the tooltip of `id` at the window position `pos`, or hides it when
+// there's none.
+fn tooltip_command(pos: Option<Point>, id: &String) -> Command {
+    match pos {
+        Some(pos) => UI_SHOW_TOOLTIP.with(((pos.x, pos.y), id.clone())),
+        None => UI_HIDE_TOOLTIP.into(),
+    }
+}
+
 fn grid_widget() -> impl Widget<DisplayViewGrid> {
     Grid::new(|| {
-        Padding::new(
-            2.0,
-            Objective::new().on_click(|ctx, data: &mut DisplayChild, _env| {
+        Objective::new()
+            .on_left_click(|ctx, event: &MouseEvent, data: &mut DisplayChild, env| {
+                // Shift+click does the same as a middle click.
+                if event.mods.shift {
+                    ctx.submit_command(ENGINE_TOGGLE_GLITCH_LOCKED.with(data.id.clone()), None);
+                    return;
+                }
+                match data.click_action {
+                    ClickAction::None => return,
+                    ClickAction::OpenPopup => {
+                        let pos = event.window_pos;
+                        let cmd = UI_OPEN_POPUP.with(((pos.x, pos.y), data.id.clone()));
+                        ctx.submit_command(cmd, None);
+                        return;
+                    }
+                    _ => (),
+                }
+                if data.click_action != ClickAction::ToggleComplete
+                    && env.try_get(DOUBLE_CLICK_COMPLETE).unwrap_or(false)
+                    && data.state == ObjectiveState::Unlocked
+                    && event.count < 2
+                {
+                    return;
+                }
                 let cmd = Command::new(ENGINE_TOGGLE_STATE, data.id.clone());
                 ctx.submit_command(cmd, None);
-            }),
-        )
+            })
+            .on_hover(|ctx, pos, data: &mut DisplayChild, _env| {
+                ctx.submit_command(tooltip_command(pos, &data.id), None);
+            })
+            .on_middle_click(|ctx, _event, data: &mut DisplayChild, _env| {
+                ctx.submit_command(ENGINE_TOGGLE_GLITCH_LOCKED.with(data.id.clone()), None);
+            })
+            .on_right_click(|ctx, event, data: &mut DisplayChild, _env| {
+                let lock = if data.locked {
+                    "Unlock state"
+                } else {
+                    "Lock state"
+                };
+                let mut menu = MenuDesc::<DisplayState>::empty()
+                    .append(MenuItem::new(
+                        LocalizedString::new("pollendina-show-locations")
+                            .with_placeholder("Show possible locations"),
+                        ENGINE_SHOW_HINTS.with(data.id.clone()),
+                    ))
+                    .append(MenuItem::new(
+                        LocalizedString::new("pollendina-toggle-lock").with_placeholder(lock),
+                        ENGINE_TOGGLE_LOCK.with(data.id.clone()),
+                    ));
+                if data.checks_total > 0 {
+                    menu = menu
+                        .append_separator()
+                        .append_iter(|| location_menu_items(&data.id).into_iter());
+                }
+                if !data.check {
+                    menu = menu.append(MenuItem::new(
+                        LocalizedString::new("pollendina-complete-grid")
+                            .with_placeholder("Complete all in this grid\u{2026}"),
+                        UI_CONFIRM_BATCH.with(BatchAction::CompleteGrid(data.id.clone())),
+                    ));
+                }
+                if data.check {
+                    menu = menu
+                        .append_separator()
+                        .append(MenuItem::new(
+                            LocalizedString::new("pollendina-mark-junk")
+                                .with_placeholder("Mark junk"),
+                            ENGINE_MARK_CHECK.with((data.id.clone(), Some(CheckMark::Junk))),
+                        ))
+                        .append(MenuItem::new(
+                            LocalizedString::new("pollendina-record-item")
+                                .with_placeholder("Record item found\u{2026}"),
+                            UI_PICK_CHECK_ITEM.with(data.id.clone()),
+                        ));
+                    if data.junk || !data.found_item.is_empty() {
+                        menu = menu.append(MenuItem::new(
+                            LocalizedString::new("pollendina-clear-mark")
+                                .with_placeholder("Clear mark"),
+                            ENGINE_MARK_CHECK.with((data.id.clone(), None)),
+                        ));
+                    }
+                }
+                let menu = menu
+                    .append_separator()
+                    .append(note_menu_item(&data.id))
+                    .append(MenuItem::new(
+                        LocalizedString::new("pollendina-move-earlier")
+                            .with_placeholder("Move earlier"),
+                        UI_MOVE_OBJECTIVE.with((data.id.clone(), -1)),
+                    ))
+                    .append(MenuItem::new(
+                        LocalizedString::new("pollendina-move-later")
+                            .with_placeholder("Move later"),
+                        UI_MOVE_OBJECTIVE.with((data.id.clone(), 1)),
+                    ))
+                    .append(MenuItem::new(
+                        LocalizedString::new("pollendina-hide-objective").with_placeholder("Hide"),
+                        UI_HIDE_OBJECTIVE.with(data.id.clone()),
+                    ));
+                ctx.show_context_menu(ContextMenu::new(menu, event.window_pos));
+            })
+            .on_wheel(|ctx, notches, data: &mut DisplayChild, env| {
+                let step = env.try_get(WHEEL_STEP).unwrap_or(0.0) as i32;
+                if step == 0 || data.locked {
+                    return;
+                }
+                let steps = notches * step;
+                let selector = if steps > 0 {
+                    ENGINE_TOGGLE_STATE
+                } else {
+                    ENGINE_STEP_STATE_BACK
+                };
+                for _ in 0..steps.abs() {
+                    ctx.submit_command(selector.with(data.id.clone()), None);
+                }
+                ctx.set_handled();
+            })
+    })
+    .on_activate(|ctx, data: &mut DisplayChild, _env| {
+        let cmd = Command::new(ENGINE_TOGGLE_STATE, data.id.clone());
+        ctx.submit_command(cmd, None);
     })
 }
 
@@ -33,20 +194,120 @@ fn count_widget() -> impl Widget<DisplayViewCount> {
     Label::new(|data: &DisplayViewCount, _env: &_| format!("{} / {}", data.found, data.total))
 }
 
+fn checks_in_logic_widget() -> impl Widget<DisplayViewChecksInLogic> {
+    Label::new(
+        |data: &DisplayViewChecksInLogic, _env: &_| match data.count {
+            1 => "1 check in logic".to_string(),
+            n => format!("{} checks in logic", n),
+        },
+    )
+}
+
+// Each slot of an assignment with its value.  Clicking the value picks
+// another.
+fn assignment_widget() -> impl Widget<DisplayViewAssignment> {
+    List::new(|| {
+        Flex::row()
+            .with_child(Label::new(|data: &AssignmentSlot, _env: &_| {
+                data.name.clone()
+            }))
+            .with_flex_spacer(1.0)
+            .with_child(
+                Button::new(|data: &AssignmentSlot, _env: &_| {
+                    if data.value.is_empty() {
+                        "?".to_string()
+                    } else {
+                        data.value.clone()
+                    }
+                })
+                .on_click(|ctx, data: &mut AssignmentSlot, _env| {
+                    let slot = (data.assignment.clone(), data.id.clone());
+                    ctx.submit_command(UI_PICK_ASSIGNMENT.with(slot), None);
+                }),
+            )
+    })
+    .lens(DisplayViewAssignment::slots)
+}
+
+fn text_widget() -> impl Widget<DisplayViewText> {
+    EnvScope::new(
+        |env, data: &DisplayViewText| {
+            if data.font_size > 0. {
+                env.set(theme::TEXT_SIZE_NORMAL, data.font_size);
+            }
+            if let Some(key) = data.color.color_key() {
+                let color = env.get(key);
+                env.set(theme::LABEL_COLOR, color);
+            }
+        },
+        Label::new(|data: &DisplayViewText, _env: &_| data.text.clone()),
+    )
+}
+
+// Each split with the time it was reached, or a dash before that.
+fn splits_widget() -> impl Widget<DisplayViewSplits> {
+    List::new(|| {
+        Flex::row()
+            .with_child(Label::new(|data: &SplitRow, _env: &_| data.name.clone()))
+            .with_flex_spacer(1.0)
+            .with_child(Label::new(|data: &SplitRow, _env: &_| {
+                if data.time.is_empty() {
+                    "-".to_string()
+                } else {
+                    data.time.clone()
+                }
+            }))
+    })
+    .lens(DisplayViewSplits::splits)
+}
+
+// Buttons switching between the image layers of a map.
+fn map_layers_widget() -> impl Widget<MapInfo> {
+    DynFlex::row(|| {
+        Button::new(|(current, layer): &(usize, MapLayer), _env: &_| {
+            if *current == layer.index {
+                format!("\u{25b8} {}", layer.name)
+            } else {
+                layer.name.clone()
+            }
+        })
+        .on_click(|_ctx, (current, layer): &mut (usize, MapLayer), _env| {
+            *current = layer.index;
+        })
+    })
+    .lens(lens::Id.map(
+        // Like the tab buttons, lets the layer buttons change `layer`.
+        |m: &MapInfo| (m.layer, m.layers.clone()),
+        |m: &mut MapInfo, data: (usize, Arc<Vec<MapLayer>>)| {
+            m.layer = data.0;
+        },
+    ))
+}
+
 fn map_widget() -> impl Widget<DisplayViewMap> {
     DynFlex::column(|| {
-        Padding::new(
-            8.0,
+        let mut w = Flex::column();
+        w.add_child(Either::new(
+            |data: &MapInfo, _env| data.layers.len() > 1,
+            map_layers_widget(),
+            SizedBox::empty(),
+        ));
+        w.add_flex_child(
             Stack::new()
-                .with_child(
-                    Asset::new()
-                        .lens(MapInfo::id.map(|id| format!("map:{}", id), |_id, _new_id| {})),
-                )
+                .with_child(Asset::new().lens(lens::Id.map(
+                    |m: &MapInfo| m.current_image().clone(),
+                    |_m: &mut MapInfo, _image: String| {},
+                )))
+                .with_child(RegionOverlay::new())
                 .with_child(Constellation::new(|| {
                     MapObjective::new()
-                        .lens(engine::MapObjective::state)
                         .on_left_click(
                             |ctx, event: &MouseEvent, data: &mut engine::MapObjective, _env| {
+                                if event.mods.shift {
+                                    let cmd = ENGINE_TOGGLE_GLITCH_LOCKED.with(data.id.clone());
+                                    ctx.submit_command(cmd, None);
+                                    return;
+                                }
                                 // We're sending window based position here and the
                                 // modal host uses widget local coordinates.  This
                                 // works out only because it's placed at the window
@@ -61,8 +322,24 @@ fn map_widget() -> impl Widget<DisplayViewMap> {
                                 ctx.submit_command(cmd, None);
                             },
                         )
+                        .on_hover(|ctx, pos, data: &mut engine::MapObjective, _env| {
+                            ctx.submit_command(tooltip_command(pos, &data.id), None);
+                        })
+                        .on_right_click(|ctx, event, data: &mut engine::MapObjective, _env| {
+                            let menu = MenuDesc::<DisplayState>::empty()
+                                .append_iter(|| location_menu_items(&data.id).into_iter())
+                                .append_separator()
+                                .append(note_menu_item(&data.id));
+                            ctx.show_context_menu(ContextMenu::new(menu, event.window_pos));
+                        })
+                        .on_middle_click(|ctx, _event, data: &mut engine::MapObjective, _env| {
+                            let cmd = ENGINE_TOGGLE_GLITCH_LOCKED.with(data.id.clone());
+                            ctx.submit_command(cmd, None);
+                        })
                 })),
-        )
+            1.0,
+        );
+        Padding::new(8.0, w)
     })
     .cross_axis_alignment(CrossAxisAlignment::Center)
     .must_fill_minor_axis(true)
@@ -115,17 +392,43 @@ fn tabs_widget() -> impl Widget<DisplayViewTabs> {
 }
 
 pub fn display_widget() -> impl Widget<DisplayView> {
-    Container::new(
-        (match_widget! { DisplayViewData,
-            DisplayViewData::Grid(_) => grid_widget(),
-            DisplayViewData::Count(_) => count_widget(),
-            DisplayViewData::Map(_) => map_widget(),
-            DisplayViewData::FlexRow(_) => flex_row_widget(),
-            DisplayViewData::FlexCol(_) => flex_col_widget(),
-            DisplayViewData::Spacer(_) => Label::new(""),
-            DisplayViewData::None => Label::new(""),
-            DisplayViewData::Tabs(_) => tabs_widget(),
-        })
-        .lens(DisplayView::data),
+    Either::new(
+        |data: &DisplayView, env| {
+            // Compact mode makes room by leaving maps out.
+            let hidden_map = match data.data {
+                DisplayViewData::Map(_) => env.try_get(DENSITY).unwrap_or(1.0) < 1.0,
+                _ => false,
+            };
+            data.visible && !hidden_map
+        },
+        EnvScope::new(
+            |env, data: &DisplayView| {
+                let params = &data.layout_params;
+                if !params.font.is_empty() {
+                    env.set(theme::FONT_NAME, params.font.clone());
+                }
+                if params.font_size > 0. {
+                    env.set(theme::TEXT_SIZE_NORMAL, params.font_size);
+                }
+            },
+            Container::new(
+                (match_widget! { DisplayViewData,
+                    DisplayViewData::Grid(_) => grid_widget(),
+                    DisplayViewData::Count(_) => count_widget(),
+                    DisplayViewData::Map(_) => map_widget(),
+                    DisplayViewData::FlexRow(_) => flex_row_widget(),
+                    DisplayViewData::FlexCol(_) => flex_col_widget(),
+                    DisplayViewData::Spacer(_) => Label::new(""),
+                    DisplayViewData::Text(_) => text_widget(),
+                    DisplayViewData::Splits(_) => splits_widget(),
+                    DisplayViewData::ChecksInLogic(_) => checks_in_logic_widget(),
+                    DisplayViewData::Assignment(_) => assignment_widget(),
+                    DisplayViewData::None => Label::new(""),
+                    DisplayViewData::Tabs(_) => tabs_widget(),
+                })
+                .lens(DisplayView::data),
+            ),
+        ),
+        SizedBox::empty(),
     )
 }
@@ -5,21 +5,29 @@ use druid::{
     LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Rect, RenderContext, Size, UpdateCtx, Widget,
 };
 
-use crate::assets::{image::ImageData, IMAGES};
+use crate::assets::{
+    image::{ImageData, PietImageCache},
+    IMAGES,
+};
 
 pub struct Asset {
     image: Option<Arc<ImageData>>,
+    piet_image: PietImageCache,
 }
 
 impl Asset {
     pub fn new() -> Self {
-        Asset { image: None }
+        Asset {
+            image: None,
+            piet_image: Default::default(),
+        }
     }
 
     fn update_image(&mut self, id: &String) {
         IMAGES.with(|images| {
             self.image = images.borrow().get(&id);
         });
+        self.piet_image.clear();
     }
 }
 
@@ -71,7 +79,12 @@ impl Widget<String> for Asset {
                 let clip_rect = Rect::ZERO.with_size(ctx.size());
                 ctx.clip(clip_rect);
             }
-            i.to_piet(offset_matrix, ctx, InterpolationMode::Bilinear);
+            i.to_piet(
+                offset_matrix,
+                ctx,
+                InterpolationMode::Bilinear,
+                &mut self.piet_image,
+            );
         }
     }
 }
@@ -1,16 +1,30 @@
+use std::time::Duration;
+
 use druid::widget::{Controller, ControllerHost};
-use druid::{Data, Env, Event, EventCtx, LifeCycle, LifeCycleCtx, MouseEvent, Widget};
+use druid::{
+    Data, Env, Event, EventCtx, LifeCycle, LifeCycleCtx, MouseButton, MouseEvent, Point,
+    TimerToken, Widget,
+};
 
 pub struct Click<T> {
+    /// The mouse button that triggers the action.
+    button: MouseButton,
     /// A closure that will be invoked when the child widget is clicked.
     action: Box<dyn Fn(&mut EventCtx, &MouseEvent, &mut T, &Env)>,
+    /// Click count of the last mouse down.
+    count: u8,
 }
 
 impl<T: Data> Click<T> {
     /// Create a new clickable [`Controller`] widget.
-    pub fn new(action: impl Fn(&mut EventCtx, &MouseEvent, &mut T, &Env) + 'static) -> Self {
+    pub fn new(
+        button: MouseButton,
+        action: impl Fn(&mut EventCtx, &MouseEvent, &mut T, &Env) + 'static,
+    ) -> Self {
         Click {
+            button,
             action: Box::new(action),
+            count: 0,
         }
     }
 }
@@ -18,15 +32,20 @@ impl<T: Data> Click<T> {
 impl<T: Data, W: Widget<T>> Controller<T, W> for Click<T> {
     fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
         match event {
-            Event::MouseDown(_) => {
+            Event::MouseDown(m) if m.button == self.button => {
+                self.count = m.count;
                 ctx.set_active(true);
                 ctx.request_paint();
             }
-            Event::MouseUp(m) => {
+            Event::MouseUp(m) if m.button == self.button => {
                 if ctx.is_active() {
                     ctx.set_active(false);
                     if ctx.is_hot() {
-                        (self.action)(ctx, m, data, env);
+                        // Not every platform reports the click count on mouse
+                        // up so pass along the one from the mouse down.
+                        let mut m = m.clone();
+                        m.count = self.count;
+                        (self.action)(ctx, &m, data, env);
                     }
                     ctx.request_paint();
                 }
@@ -53,13 +72,133 @@ impl<T: Data, W: Widget<T>> Controller<T, W> for Click<T> {
     }
 }
 
+/// Wheel movement of one notch of a mouse wheel.
+const WHEEL_NOTCH: f64 = 120.0;
+
+pub struct Wheel<T> {
+    /// Called with the notches scrolled, positive for up.
+    action: Box<dyn Fn(&mut EventCtx, i32, &mut T, &Env)>,
+    /// Scrolling not yet adding up to a notch, as from touchpads.
+    delta: f64,
+}
+
+impl<T: Data, W: Widget<T>> Controller<T, W> for Wheel<T> {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::Wheel(m) = event {
+            self.delta -= m.wheel_delta.y;
+            let notches = (self.delta / WHEEL_NOTCH).trunc();
+            if notches != 0.0 {
+                self.delta -= notches * WHEEL_NOTCH;
+                (self.action)(ctx, notches as i32, data, env);
+            }
+        }
+
+        child.event(ctx, event, data, env);
+    }
+}
+
+/// How long the mouse has to rest on a widget before it's hovered.
+const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+pub struct Hover<T> {
+    /// Called with the mouse's window position once the widget is hovered
+    /// and with `None` once it no longer is.
+    action: Box<dyn Fn(&mut EventCtx, Option<Point>, &mut T, &Env)>,
+    timer: TimerToken,
+    pos: Point,
+    hovered: bool,
+}
+
+impl<T> Hover<T> {
+    fn leave(&mut self, ctx: &mut EventCtx, data: &mut T, env: &Env) {
+        self.timer = TimerToken::INVALID;
+        if self.hovered {
+            self.hovered = false;
+            (self.action)(ctx, None, data, env);
+        }
+    }
+}
+
+impl<T: Data, W: Widget<T>> Controller<T, W> for Hover<T> {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseMove(m) if ctx.is_hot() => {
+                // Waiting starts over each time the mouse moves.
+                self.pos = m.window_pos;
+                if !self.hovered {
+                    self.timer = ctx.request_timer(HOVER_DELAY);
+                }
+            }
+            Event::MouseMove(_) | Event::MouseDown(_) | Event::Wheel(_) => {
+                self.leave(ctx, data, env)
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.timer = TimerToken::INVALID;
+                if ctx.is_hot() {
+                    self.hovered = true;
+                    (self.action)(ctx, Some(self.pos), data, env);
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+
+        child.event(ctx, event, data, env);
+    }
+}
+
 /// A trait that provides extra methods for combining `Widget`s.
 pub trait ClickExt<T: Data>: Widget<T> + Sized + 'static {
     fn on_left_click(
         self,
         f: impl Fn(&mut EventCtx, &MouseEvent, &mut T, &Env) + 'static,
     ) -> ControllerHost<Self, Click<T>> {
-        ControllerHost::new(self, Click::new(f))
+        ControllerHost::new(self, Click::new(MouseButton::Left, f))
+    }
+
+    fn on_right_click(
+        self,
+        f: impl Fn(&mut EventCtx, &MouseEvent, &mut T, &Env) + 'static,
+    ) -> ControllerHost<Self, Click<T>> {
+        ControllerHost::new(self, Click::new(MouseButton::Right, f))
+    }
+
+    fn on_middle_click(
+        self,
+        f: impl Fn(&mut EventCtx, &MouseEvent, &mut T, &Env) + 'static,
+    ) -> ControllerHost<Self, Click<T>> {
+        ControllerHost::new(self, Click::new(MouseButton::Middle, f))
+    }
+
+    fn on_hover(
+        self,
+        f: impl Fn(&mut EventCtx, Option<Point>, &mut T, &Env) + 'static,
+    ) -> ControllerHost<Self, Hover<T>> {
+        ControllerHost::new(
+            self,
+            Hover {
+                action: Box::new(f),
+                timer: TimerToken::INVALID,
+                pos: Point::ORIGIN,
+                hovered: false,
+            },
+        )
+    }
+
+    /// `f` should mark the event handled if it used the scroll so it doesn't
+    /// also scroll the view.
+    fn on_wheel(
+        self,
+        f: impl Fn(&mut EventCtx, i32, &mut T, &Env) + 'static,
+    ) -> ControllerHost<Self, Wheel<T>> {
+        ControllerHost::new(
+            self,
+            Wheel {
+                action: Box::new(f),
+                delta: 0.0,
+            },
+        )
     }
 }
 
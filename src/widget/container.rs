@@ -16,10 +16,18 @@
 
 use druid::widget::BackgroundBrush;
 use druid::{
-    BoxConstraints, Color, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
+    BoxConstraints, Color, Data, Env, Event, EventCtx, Key, KeyOrValue, LayoutCtx, LifeCycle,
     LifeCycleCtx, PaintCtx, Point, Rect, RenderContext, Size, UpdateCtx, Widget, WidgetPod,
 };
 
+/// Set to skip the backgrounds of every container below, such as when
+/// something else paints a chroma key behind them.
+pub const NO_BACKGROUNDS: Key<bool> = Key::new("pollendina.no-backgrounds");
+
+/// Scales the insets, grid spacing and objective icons below.  Below 1 for
+/// compact mode.
+pub const DENSITY: Key<f64> = Key::new("pollendina.density");
+
 struct BorderStyle {
     width: KeyOrValue<f64>,
     color: KeyOrValue<Color>,
@@ -84,7 +92,7 @@ impl<T: Data + ContainerParams> Widget<T> for Container<T> {
             Some(border) => border.width.resolve(env),
             None => 0.0,
         };
-        let padding = border_width + self.inset;
+        let padding = border_width + self.inset * env.try_get(DENSITY).unwrap_or(1.0);
 
         let child_bc = bc.shrink((2. * padding, 2. * padding));
         let size = self.inner.layout(ctx, &child_bc, data, env);
@@ -100,7 +108,8 @@ impl<T: Data + ContainerParams> Widget<T> for Container<T> {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
-        if let Some(background) = self.background.as_mut() {
+        let skip_background = env.try_get(NO_BACKGROUNDS).unwrap_or(false);
+        if let Some(background) = self.background.as_mut().filter(|_| !skip_background) {
             let panel = ctx.size().to_rounded_rect(self.corner_radius);
 
             ctx.with_save(|ctx| {
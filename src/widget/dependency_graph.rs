@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use druid::kurbo::{BezPath, Point, Rect, Size};
+use druid::{
+    piet::{FontBuilder, Text, TextLayoutBuilder},
+    theme, BoxConstraints, Color, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, RenderContext, UpdateCtx, Widget,
+};
+
+use crate::engine::{DependencyGraph, GraphNode, ObjectiveState};
+
+const NODE_WIDTH: f64 = 140.;
+const NODE_HEIGHT: f64 = 22.;
+const LAYER_SPACING: f64 = 60.;
+const ROW_SPACING: f64 = 8.;
+const FONT_SIZE: f64 = 11.;
+
+/// Draws the objective dependency graph in columns, one per layer, with
+/// edges from each objective to the ones it depends on.  Clicking a node
+/// highlights it and everything it depends on, directly or not.
+pub struct DependencyGraphView;
+
+impl DependencyGraphView {
+    pub fn new() -> Self {
+        DependencyGraphView
+    }
+
+    fn node_rect(node: &GraphNode) -> Rect {
+        let origin = Point::new(
+            node.layer as f64 * (NODE_WIDTH + LAYER_SPACING),
+            node.row as f64 * (NODE_HEIGHT + ROW_SPACING),
+        );
+        Rect::from_origin_size(origin, Size::new(NODE_WIDTH, NODE_HEIGHT))
+    }
+
+    fn state_color(state: &ObjectiveState) -> Color {
+        match state {
+            ObjectiveState::Disabled => Color::grey8(0x40),
+            ObjectiveState::Locked => Color::rgb8(0x80, 0x30, 0x30),
+            ObjectiveState::GlitchLocked => Color::rgb8(0x70, 0x30, 0x80),
+            ObjectiveState::Unlocked => Color::rgb8(0x90, 0x80, 0x20),
+            ObjectiveState::Complete => Color::rgb8(0x30, 0x80, 0x30),
+        }
+    }
+
+    // `focus` and every node it depends on.
+    fn ancestors(nodes: &[GraphNode], focus: usize) -> HashSet<usize> {
+        let mut found = HashSet::new();
+        let mut pending = vec![focus];
+        while let Some(i) = pending.pop() {
+            if i < nodes.len() && found.insert(i) {
+                pending.extend(nodes[i].deps.iter());
+            }
+        }
+        found
+    }
+}
+
+impl Widget<DependencyGraph> for DependencyGraphView {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut DependencyGraph, _env: &Env) {
+        if let Event::MouseDown(mouse) = event {
+            let clicked = data
+                .nodes
+                .iter()
+                .position(|node| Self::node_rect(node).contains(mouse.pos));
+            // Clicking the focused node or empty space clears the focus.
+            data.focus = if clicked == data.focus { None } else { clicked };
+            ctx.request_paint();
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &DependencyGraph,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        _old_data: &DependencyGraph,
+        _data: &DependencyGraph,
+        _env: &Env,
+    ) {
+        ctx.request_layout();
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &DependencyGraph,
+        _env: &Env,
+    ) -> Size {
+        let size = data
+            .nodes
+            .iter()
+            .map(Self::node_rect)
+            .fold(Rect::ZERO, |bounds, rect| bounds.union(rect))
+            .size();
+        bc.constrain(size)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &DependencyGraph, env: &Env) {
+        let highlighted = data.focus.map(|focus| Self::ancestors(&data.nodes, focus));
+        let dimmed = |i: usize| highlighted.as_ref().map_or(false, |h| !h.contains(&i));
+
+        let edge_color = env.get(theme::BORDER_LIGHT);
+        for (i, node) in data.nodes.iter().enumerate() {
+            let to = Self::node_rect(node);
+            for dep in node.deps.iter() {
+                let from = Self::node_rect(&data.nodes[*dep]);
+                let start = Point::new(from.x1, from.center().y);
+                let end = Point::new(to.x0, to.center().y);
+                let mut path = BezPath::new();
+                path.move_to(start);
+                path.curve_to(
+                    Point::new(start.x + LAYER_SPACING / 2., start.y),
+                    Point::new(end.x - LAYER_SPACING / 2., end.y),
+                    end,
+                );
+                let alpha = if dimmed(i) || dimmed(*dep) { 0.15 } else { 0.8 };
+                ctx.stroke(path, &edge_color.clone().with_alpha(alpha), 1.);
+            }
+        }
+
+        let font = match ctx
+            .text()
+            .new_font_by_name(env.get(theme::FONT_NAME), FONT_SIZE)
+            .build()
+        {
+            Ok(font) => font,
+            Err(_) => return,
+        };
+        for (i, node) in data.nodes.iter().enumerate() {
+            let rect = Self::node_rect(node);
+            let alpha = if dimmed(i) { 0.3 } else { 1.0 };
+            let rounded = rect.to_rounded_rect(3.);
+            ctx.fill(rounded, &Self::state_color(&node.state).with_alpha(alpha));
+            if data.focus == Some(i) {
+                ctx.stroke(rounded, &env.get(theme::PRIMARY_LIGHT), 2.);
+            }
+
+            let layout = match ctx.text().new_text_layout(&font, &node.name, None).build() {
+                Ok(layout) => layout,
+                Err(_) => continue,
+            };
+            ctx.with_save(|ctx| {
+                ctx.clip(rect);
+                let x = rect.x0 + 4.;
+                let y = rect.center().y + FONT_SIZE * 0.35;
+                ctx.draw_text(&layout, Point::new(x, y), &Color::WHITE.with_alpha(alpha));
+            });
+        }
+    }
+}
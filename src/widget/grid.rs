@@ -14,19 +14,79 @@
 
 use std::cmp::Ordering;
 
-use druid::kurbo::{Point, Rect, Size};
+use druid::kurbo::{Point, Rect, Size, Vec2};
 use druid::{
-    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, UpdateCtx,
-    Widget, WidgetPod,
+    theme, BoxConstraints, Env, Event, EventCtx, KeyCode, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, RenderContext, UpdateCtx, Widget, WidgetPod,
 };
 
 use super::list_iter::ListIter;
-use crate::engine::{DisplayChild, DisplayViewGrid, ObjectiveState};
+use super::DENSITY;
+use crate::engine::{DisplayChild, DisplayViewGrid};
+
+// Space around each child, scaled by `DENSITY`.
+const CELL_PADDING: f64 = 2.0;
+
+// Seconds children take to move to their new place when others are hidden
+// or shown.
+const REFLOW_DURATION: f64 = 0.15;
+
+// Grids with at least this many children only lay out the children near the
+// part of the grid that was last painted.  Their cells are all assumed to be
+// the size of the first shown child.
+const VIRTUALIZE_MIN_CHILDREN: usize = 100;
+
+// Rows beyond the painted part of a virtualized grid that are laid out so
+// scrolling doesn't show children that weren't.
+const VIRTUALIZE_MARGIN_ROWS: f64 = 2.0;
+
+// Where a child is drawn while it moves from `from` to `to`.
+#[derive(Clone, Copy)]
+struct Placement {
+    from: Point,
+    to: Point,
+    // 0 to 1 as the move progresses.
+    progress: f64,
+}
+
+impl Placement {
+    fn at(origin: Point) -> Self {
+        Placement {
+            from: origin,
+            to: origin,
+            progress: 1.0,
+        }
+    }
+
+    fn origin(&self) -> Point {
+        // Ease out so children settle into place.
+        let t = 1.0 - (1.0 - self.progress).powi(2);
+        self.from.lerp(self.to, t)
+    }
+}
 
 /// A list widget for a variable-size collection of items.
+///
+/// The grid can take keyboard focus.  Arrow keys move a focus ring between
+/// the shown children, Home and End jump to the ends of the row and Space or
+/// Enter activates the focused child.
 pub struct Grid {
     closure: Box<dyn Fn() -> Box<dyn Widget<DisplayChild>>>,
     children: Vec<WidgetPod<DisplayChild, Box<dyn Widget<DisplayChild>>>>,
+    on_activate: Option<Box<dyn Fn(&mut EventCtx, &mut DisplayChild, &Env)>>,
+    // Position of the focused child among the shown (non-hidden) children.
+    focus: usize,
+    // Column count used by the last layout.
+    cols: usize,
+    // Placement of each child.  `None` for hidden children so they appear
+    // in place when shown.
+    placements: Vec<Option<Placement>>,
+    // Set when the hidden children changed and the next layout should move
+    // children to their new place smoothly.
+    reflow_pending: bool,
+    // Part of the grid painted last, in its own coordinates.  Used to pick
+    // the children virtualized grids lay out.
+    visible: Option<Rect>,
 }
 
 impl Grid {
@@ -36,9 +96,62 @@ impl Grid {
         Grid {
             closure: Box::new(move || Box::new(closure())),
             children: Vec::new(),
+            on_activate: None,
+            focus: 0,
+            cols: 1,
+            placements: Vec::new(),
+            reflow_pending: false,
+            visible: None,
         }
     }
 
+    /// Builder-style method to set the handler for activating a child from
+    /// the keyboard.
+    pub fn on_activate(
+        mut self,
+        f: impl Fn(&mut EventCtx, &mut DisplayChild, &Env) + 'static,
+    ) -> Self {
+        self.on_activate = Some(Box::new(f));
+        self
+    }
+
+    // Indices of the children that are shown, in layout order.
+    fn shown(data: &DisplayViewGrid) -> Vec<usize> {
+        let mut shown = Vec::new();
+        data.children.for_each(|child_data, i| {
+            if !child_data.is_hidden() {
+                shown.push(i);
+            }
+        });
+        shown
+    }
+
+    // Returns the new focus position for `key` or `None` if the key doesn't
+    // move focus.
+    fn move_focus(&self, key: KeyCode, len: usize) -> Option<usize> {
+        let cols = self.cols.max(1);
+        let focus = self.focus.min(len - 1);
+        let row_start = focus - focus % cols;
+        let new_focus = match key {
+            KeyCode::ArrowLeft => focus.saturating_sub(1),
+            KeyCode::ArrowRight => (focus + 1).min(len - 1),
+            KeyCode::ArrowUp => focus.checked_sub(cols).unwrap_or(focus),
+            KeyCode::ArrowDown if focus + cols < len => focus + cols,
+            KeyCode::ArrowDown => focus,
+            KeyCode::Home => row_start,
+            KeyCode::End => (row_start + cols - 1).min(len - 1),
+            _ => return None,
+        };
+        Some(new_focus)
+    }
+
+    fn moving(&self) -> bool {
+        self.placements
+            .iter()
+            .flatten()
+            .any(|placement| placement.progress < 1.0)
+    }
+
     /// When the widget is created or the data changes, create or remove children as needed
     ///
     /// Returns `true` if children were added or removed.
@@ -60,6 +173,55 @@ impl Grid {
 
 impl Widget<DisplayViewGrid> for Grid {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut DisplayViewGrid, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) => {
+                // Clicking a child moves focus to it.
+                let shown = Self::shown(data);
+                if let Some(pos) = shown.iter().position(|i| {
+                    self.children
+                        .get(*i)
+                        .map_or(false, |c| c.layout_rect().contains(mouse.pos))
+                }) {
+                    self.focus = pos;
+                    ctx.request_focus();
+                    ctx.request_paint();
+                }
+            }
+            Event::KeyDown(key) if ctx.is_focused() => {
+                let shown = Self::shown(data);
+                if shown.is_empty() {
+                    return;
+                }
+                match key.key_code {
+                    KeyCode::Space | KeyCode::Return | KeyCode::NumpadEnter => {
+                        let focused = shown[self.focus.min(shown.len() - 1)];
+                        if let Some(on_activate) = &self.on_activate {
+                            data.children.for_each_mut(|child_data, i| {
+                                if i == focused {
+                                    on_activate(ctx, child_data, env);
+                                }
+                            });
+                        }
+                        ctx.set_handled();
+                    }
+                    key_code => {
+                        if let Some(focus) = self.move_focus(key_code, shown.len()) {
+                            self.focus = focus;
+                            ctx.request_paint();
+                            ctx.set_handled();
+                        }
+                    }
+                }
+                return;
+            }
+            // The grid may be scrolled, which moves the children that need
+            // laying out.
+            Event::Wheel(_) if data.children.len() >= VIRTUALIZE_MIN_CHILDREN => {
+                ctx.request_layout();
+            }
+            _ => (),
+        }
+
         let mut children = self.children.iter_mut();
         data.children.for_each_mut(|child_data, _| {
             if let Some(child) = children.next() {
@@ -75,10 +237,26 @@ impl Widget<DisplayViewGrid> for Grid {
         data: &DisplayViewGrid,
         env: &Env,
     ) {
-        if let LifeCycle::WidgetAdded = event {
-            if self.update_child_count(&data.children, env) {
-                ctx.children_changed();
+        match event {
+            LifeCycle::WidgetAdded => {
+                ctx.register_for_focus();
+                if self.update_child_count(&data.children, env) {
+                    ctx.children_changed();
+                }
+            }
+            LifeCycle::FocusChanged(_) => ctx.request_paint(),
+            LifeCycle::AnimFrame(interval) if self.reflow_pending || self.moving() => {
+                let step = *interval as f64 / 1e9 / REFLOW_DURATION;
+                for placement in self.placements.iter_mut().flatten() {
+                    placement.progress = (placement.progress + step).min(1.0);
+                }
+                // Frames can come before the layout that starts the move.
+                if self.reflow_pending || self.moving() {
+                    ctx.request_anim_frame();
+                }
+                ctx.request_layout();
             }
+            _ => (),
         }
 
         let mut children = self.children.iter_mut();
@@ -92,10 +270,16 @@ impl Widget<DisplayViewGrid> for Grid {
     fn update(
         &mut self,
         ctx: &mut UpdateCtx,
-        _old_data: &DisplayViewGrid,
+        old_data: &DisplayViewGrid,
         data: &DisplayViewGrid,
         env: &Env,
     ) {
+        // Animate the children that move when others are hidden or shown.
+        if Self::shown(old_data) != Self::shown(data) {
+            self.reflow_pending = true;
+            ctx.request_anim_frame();
+        }
+
         // we send update to children first, before adding or removing children;
         // this way we avoid sending update to newly added children, at the cost
         // of potentially updating children that are going to be removed.
@@ -109,6 +293,7 @@ impl Widget<DisplayViewGrid> for Grid {
         if self.update_child_count(&data.children, env) {
             ctx.children_changed();
         }
+        self.placements.resize(self.children.len(), None);
     }
 
     fn layout(
@@ -123,10 +308,26 @@ impl Widget<DisplayViewGrid> for Grid {
         let mut y = 0.0;
         let mut x = 0.0;
 
+        let virtualize = data.children.len() >= VIRTUALIZE_MIN_CHILDREN;
+        // Size of every cell of a virtualized grid, once known.
+        let mut cell: Option<Size> = None;
+        let visible = self.visible;
+
+        // Children only move smoothly after a change of the hidden children,
+        // not when the grid is resized.  Virtualized grids don't animate as
+        // most of their children aren't laid out.
+        let reflowing = !virtualize && (self.reflow_pending || self.moving());
+        self.reflow_pending = false;
+
+        let pad = CELL_PADDING * env.try_get(DENSITY).unwrap_or(1.0);
+        let pad_size = Size::new(2. * pad, 2. * pad);
+
         let mut paint_rect = Rect::ZERO;
         let mut children = self.children.iter_mut();
-        let cols = data.columns;
+        let mut cols = data.columns;
         let mut skipped_children = 0;
+        let placements = &mut self.placements;
+        placements.resize(data.children.len(), None);
         data.children.for_each(|child_data, i| {
             let child = match children.next() {
                 Some(child) => child,
@@ -135,36 +336,94 @@ impl Widget<DisplayViewGrid> for Grid {
                 }
             };
 
-            // Skip disabled children.
-            if child_data.state == ObjectiveState::Disabled {
+            // Skip hidden children.
+            if child_data.is_hidden() {
                 skipped_children += 1;
+                if let Some(placement) = placements.get_mut(i) {
+                    *placement = None;
+                }
                 return;
             }
 
+            let placement = &mut placements[i];
+
             // Adjust index for children that get skipped.
             let i = i - skipped_children;
 
+            let child_bc = BoxConstraints::new(
+                Size::new((bc.min().width - pad_size.width).max(0.0), 0.0),
+                Size::new(
+                    (bc.max().width - pad_size.width).max(0.0),
+                    std::f64::INFINITY,
+                ),
+            );
+            let mut child_size = None;
+
+            // For auto columns, fit as many cells the size of the first one
+            // as the available width allows.
+            if cols == 0 {
+                let size = child.layout(ctx, &child_bc, child_data, env);
+                let width = size.width + pad_size.width;
+                cols = if bc.max().width.is_finite() && width > 0.0 {
+                    ((bc.max().width / width).floor() as usize).max(1)
+                } else {
+                    data.children.len()
+                };
+                child_size = Some(size);
+            }
+
             if i % cols == 0 {
                 y += row_height;
                 row_height = 0.0;
                 x = 0.0;
             }
 
-            let child_bc = BoxConstraints::new(
-                Size::new(bc.min().width, 0.0),
-                Size::new(bc.max().width, std::f64::INFINITY),
-            );
-            let child_size = child.layout(ctx, &child_bc, child_data, env);
+            let target = Point::new(x, y);
+            let child_size = match (child_size, cell, visible) {
+                (Some(size), _, _) => size,
+                // Off screen children of virtualized grids keep their last
+                // layout and are only moved into place.
+                (None, Some(cell), Some(visible))
+                    if visible
+                        .inset(cell.height * VIRTUALIZE_MARGIN_ROWS)
+                        .intersect(Rect::from_origin_size(target, cell))
+                        .area()
+                        == 0.0 =>
+                {
+                    cell
+                }
+                _ => child.layout(ctx, &child_bc, child_data, env),
+            };
+            if virtualize && cell.is_none() {
+                cell = Some(child_size);
+            }
 
-            let rect = Rect::from_origin_size(Point::new(x, y), child_size);
+            let origin = match placement {
+                Some(p) if reflowing && p.to != target => {
+                    *p = Placement {
+                        from: p.origin(),
+                        to: target,
+                        progress: 0.0,
+                    };
+                    p.origin()
+                }
+                Some(p) if reflowing => p.origin(),
+                _ => {
+                    *placement = Some(Placement::at(target));
+                    target
+                }
+            };
+            let rect = Rect::from_origin_size(origin + Vec2::new(pad, pad), child_size);
             child.set_layout_rect(ctx, child_data, env, rect);
             paint_rect = paint_rect.union(child.paint_rect());
 
-            x += child_size.width;
+            x += child_size.width + pad_size.width;
             width = width.max(x);
-            row_height = row_height.max(child_size.height);
+            row_height = row_height.max(child_size.height + pad_size.height);
         });
 
+        self.cols = cols;
+
         if row_height > bc.min().height {
             y += row_height;
         }
@@ -178,11 +437,23 @@ impl Widget<DisplayViewGrid> for Grid {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &DisplayViewGrid, env: &Env) {
+        self.visible = Some(ctx.region().to_rect());
         let mut children = self.children.iter_mut();
         data.children.for_each(|child_data, _| {
             if let Some(child) = children.next() {
                 child.paint(ctx, child_data, env);
             }
         });
+
+        if ctx.is_focused() {
+            let shown = Self::shown(data);
+            if let Some(child) = shown
+                .get(self.focus.min(shown.len().saturating_sub(1)))
+                .and_then(|i| self.children.get(*i))
+            {
+                let rect = child.layout_rect().inset(-1.);
+                ctx.stroke(rect, &env.get(theme::PRIMARY_LIGHT), 2.);
+            }
+        }
     }
 }
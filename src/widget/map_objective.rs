@@ -1,57 +1,142 @@
 use druid::{
-    kurbo::{Circle, Size},
-    BoxConstraints, Color, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    kurbo::{BezPath, Circle, Point, Rect, Shape, Size},
+    BoxConstraints, Color, Data, Env, Event, EventCtx, Key, LayoutCtx, LifeCycle, LifeCycleCtx,
     PaintCtx, RenderContext, UpdateCtx, Widget,
 };
 
-use crate::engine::ObjectiveState;
+use super::objective::GOAL_COLOR;
+use super::state_overlay::{paint_state_overlay, shape_indicators_enabled};
+use crate::engine::{self, module::MarkerShape, ObjectiveState};
+
+const HINT_COLOR: Color = Color::rgb8(0xff, 0x44, 0xff);
+const NOTE_COLOR: Color = Color::rgb8(0xff, 0xcc, 0x44);
+
+// Length of one hint pulse in nanoseconds.
+const PULSE_PERIOD: f64 = 1_000_000_000.;
+
+// Set while restream safe mode is on.  Note dots are hidden then.
+pub const RESTREAM_SAFE: Key<bool> = Key::new("pollendina.restream-safe");
+
+fn restream_safe(env: &Env) -> bool {
+    env.try_get(RESTREAM_SAFE).unwrap_or(false)
+}
 
 pub struct MapObjective {
     radius: f64,
+    shape_indicators: bool,
+    restream_safe: bool,
+    // Position in the hint pulse animation from 0 to 1.
+    pulse: f64,
 }
 
 impl MapObjective {
     pub fn new() -> MapObjective {
-        MapObjective { radius: 0. }
+        MapObjective {
+            radius: 0.,
+            shape_indicators: false,
+            restream_safe: false,
+            pulse: 0.,
+        }
     }
 }
 
-impl Widget<ObjectiveState> for MapObjective {
+// Outline of a marker of `shape` around `center` that fits in a circle of
+// `radius`.
+fn marker_path(shape: MarkerShape, center: Point, radius: f64) -> BezPath {
+    let corners = |angles: &[f64]| {
+        let mut path = BezPath::new();
+        for (i, angle) in angles.iter().enumerate() {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let p = Point::new(center.x + radius * cos, center.y + radius * sin);
+            if i == 0 {
+                path.move_to(p);
+            } else {
+                path.line_to(p);
+            }
+        }
+        path.close_path();
+        path
+    };
+    match shape {
+        MarkerShape::Circle => Circle::new(center, radius).into_bez_path(0.1),
+        MarkerShape::Square => {
+            let side = radius * std::f64::consts::SQRT_2;
+            Rect::from_center_size(center, (side, side)).into_bez_path(0.1)
+        }
+        MarkerShape::Diamond => corners(&[-90., 0., 90., 180.]),
+        MarkerShape::Triangle => corners(&[-90., 30., 150.]),
+    }
+}
+
+impl Widget<engine::MapObjective> for MapObjective {
     fn event(
         &mut self,
         _ctx: &mut EventCtx,
         _event: &Event,
-        _data: &mut ObjectiveState,
+        _data: &mut engine::MapObjective,
         _env: &Env,
     ) {
     }
 
     fn lifecycle(
         &mut self,
-        _ctx: &mut LifeCycleCtx,
-        _event: &LifeCycle,
-        _data: &ObjectiveState,
-        _env: &Env,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &engine::MapObjective,
+        env: &Env,
     ) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                self.shape_indicators = shape_indicators_enabled(env);
+                self.restream_safe = restream_safe(env);
+                if data.hinted {
+                    ctx.request_anim_frame();
+                }
+            }
+            LifeCycle::AnimFrame(interval) => {
+                if data.hinted {
+                    self.pulse = (self.pulse + *interval as f64 / PULSE_PERIOD) % 1.;
+                    ctx.request_anim_frame();
+                } else {
+                    self.pulse = 0.;
+                }
+                ctx.request_paint();
+            }
+            _ => (),
+        }
     }
 
     fn update(
         &mut self,
         ctx: &mut UpdateCtx,
-        old_data: &ObjectiveState,
-        data: &ObjectiveState,
-        _env: &Env,
+        old_data: &engine::MapObjective,
+        data: &engine::MapObjective,
+        env: &Env,
     ) {
         if !old_data.same(data) {
             ctx.children_changed();
         }
+        if data.hinted && !old_data.hinted {
+            ctx.request_anim_frame();
+        }
+
+        let shape_indicators = shape_indicators_enabled(env);
+        if shape_indicators != self.shape_indicators {
+            self.shape_indicators = shape_indicators;
+            ctx.request_paint();
+        }
+        let restream_safe = restream_safe(env);
+        if restream_safe != self.restream_safe {
+            self.restream_safe = restream_safe;
+            ctx.request_paint();
+        }
     }
 
     fn layout(
         &mut self,
         _ctx: &mut LayoutCtx,
         bc: &BoxConstraints,
-        _data: &ObjectiveState,
+        _data: &engine::MapObjective,
         _env: &Env,
     ) -> Size {
         // Set our radius to the maximum circle that will fit in our constraints.
@@ -64,9 +149,13 @@ impl Widget<ObjectiveState> for MapObjective {
         bc.constrain((d, d))
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, data: &ObjectiveState, _env: &Env) {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &engine::MapObjective, _env: &Env) {
         let bg_color = Color::rgb8(0x00, 0x00, 0x00);
-        let outline_color = Color::rgb8(0xff, 0xff, 0xff);
+        let outline_color = if data.goal {
+            GOAL_COLOR
+        } else {
+            Color::rgb8(0xff, 0xff, 0xff)
+        };
 
         let unlocked_color = Color::rgb8(0x00, 0xff, 0x00);
         let complete_color = Color::rgb8(0x00, 0x88, 0xcc);
@@ -80,7 +169,7 @@ impl Widget<ObjectiveState> for MapObjective {
         let outline_width = r * 0.2;
         let inner_radius = r * 0.6;
 
-        let inner_color = match data {
+        let inner_color = match data.state {
             ObjectiveState::Disabled => return,
             ObjectiveState::Complete => &complete_color,
             ObjectiveState::Locked => &locked_color,
@@ -89,12 +178,34 @@ impl Widget<ObjectiveState> for MapObjective {
         };
 
         let pos = (r, r);
+        let center = Point::new(r, r);
         let inner_circle = Circle::new(pos, inner_radius);
-        let bg_circle = Circle::new(pos, bg_radius);
-        let outline_circle = Circle::new(pos, outline_radius);
 
-        ctx.fill(bg_circle, &bg_color);
-        ctx.fill(inner_circle, inner_color);
-        ctx.stroke(outline_circle, &outline_color, outline_width);
+        ctx.fill(marker_path(data.shape, center, bg_radius), &bg_color);
+        ctx.fill(marker_path(data.shape, center, inner_radius), inner_color);
+        ctx.stroke(
+            marker_path(data.shape, center, outline_radius),
+            &outline_color,
+            outline_width,
+        );
+
+        if self.shape_indicators {
+            let rect = inner_circle.bounding_box();
+            paint_state_overlay(ctx, &data.state, rect);
+        }
+
+        if data.has_note && !self.restream_safe {
+            // A dot in the top right corner.
+            let note_dot = Circle::new((r * 1.7, r * 0.3), r * 0.3);
+            ctx.fill(note_dot, &NOTE_COLOR);
+            ctx.stroke(note_dot, &bg_color, r * 0.1);
+        }
+
+        if data.hinted {
+            // A ring that shrinks from the edge of the marker while fading in.
+            let pulse_radius = r * (1. - 0.4 * self.pulse);
+            let pulse_color = HINT_COLOR.with_alpha(self.pulse);
+            ctx.stroke(Circle::new(pos, pulse_radius), &pulse_color, r * 0.2);
+        }
     }
 }
@@ -2,21 +2,37 @@ pub mod asset;
 pub mod click_ext;
 pub mod constellation;
 pub mod container;
+pub mod dependency_graph;
 pub mod dyn_flex;
 pub mod grid;
 pub mod list_iter;
 pub mod map_objective;
 pub mod modal_host;
 pub mod objective;
+pub mod on_change;
+pub mod overlay_frame;
+pub mod region_overlay;
+pub mod scale;
 pub mod stack;
+pub mod state_overlay;
+pub mod toast;
+pub mod tooltip;
 
 pub use asset::Asset;
 pub use click_ext::ClickExt;
 pub use constellation::{Constellation, Star};
-pub use container::{Container, ContainerParams};
+pub use container::{Container, ContainerParams, DENSITY, NO_BACKGROUNDS};
+pub use dependency_graph::DependencyGraphView;
 pub use dyn_flex::{DynFlex, DynFlexParams};
 pub use grid::Grid;
-pub use map_objective::MapObjective;
+pub use map_objective::{MapObjective, RESTREAM_SAFE};
 pub use modal_host::ModalHost;
 pub use objective::Objective;
+pub use on_change::OnChangeExt;
+pub use overlay_frame::OverlayFrame;
+pub use region_overlay::{RegionOverlay, MAP_HEATMAP};
+pub use scale::Scale;
 pub use stack::Stack;
+pub use state_overlay::SHAPE_INDICATORS;
+pub use toast::Toast;
+pub use tooltip::Tooltip;
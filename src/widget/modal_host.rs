@@ -30,10 +30,15 @@ pub struct ModalHost<T> {
 impl ModalHost<()> {
     /// Command to dismiss the modal.
     pub const DISMISS_MODAL: Selector = Selector::new("pollendina.dismiss-modal-widget");
+
+    /// Notification sent when the modal is closed, either by `DISMISS_MODAL`
+    /// or by clicking outside of it.  Not sent when a modal is replaced.
+    pub const MODAL_DISMISSED: Selector = Selector::new("pollendina.modal-widget-dismissed");
 }
 
 impl<T: Data> ModalHost<T> {
-    /// Command to display a modal in this host.
+    /// Command to display a modal in this host.  Replaces the modal already
+    /// shown, if any.
     ///
     /// The argument **must** be a `ModalBuilder`.
     pub const SHOW_MODAL: Selector<(Point, SingleUse<ModalBuilder<T>>)> =
@@ -64,18 +69,15 @@ impl<T: Data> Widget<T> for ModalHost<T> {
         match event {
             Event::Command(cmd) => {
                 if let Some(payload) = cmd.get(Self::SHOW_MODAL) {
-                    if self.modal.is_none() {
-                        self.modal = Some(WidgetPod::new(payload.1.take().unwrap().build()));
-                        self.modal_pos = payload.0;
-                        ctx.children_changed();
-                    } else {
-                        log::warn!("cannot show modal; already showing modal");
-                    }
+                    self.modal = Some(WidgetPod::new(payload.1.take().unwrap().build()));
+                    self.modal_pos = payload.0;
+                    ctx.children_changed();
                     ctx.set_handled();
                 } else if cmd.is(ModalHost::DISMISS_MODAL) {
                     if self.modal.is_some() {
                         self.modal = None;
                         ctx.children_changed();
+                        ctx.submit_command(ModalHost::MODAL_DISMISSED, None);
                     } else {
                         log::warn!("cannot dismiss modal; no modal shown");
                     }
@@ -91,6 +93,7 @@ impl<T: Data> Widget<T> for ModalHost<T> {
                         } else {
                             self.modal = None;
                             ctx.children_changed();
+                            ctx.submit_command(ModalHost::MODAL_DISMISSED, None);
                         }
                     } else {
                         modal.event(ctx, event, data, env)
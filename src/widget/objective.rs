@@ -15,16 +15,43 @@
 use std::sync::Arc;
 
 use druid::{
-    piet::InterpolationMode, widget::FillStrat, BoxConstraints, Data, Env, Event, EventCtx,
-    LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Rect, RenderContext, Size, UpdateCtx, Widget,
+    kurbo::{BezPath, Circle},
+    piet::{FontBuilder, InterpolationMode, Text, TextLayout, TextLayoutBuilder},
+    theme,
+    widget::FillStrat,
+    BoxConstraints, Color, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Rect, RenderContext, Size, UpdateCtx, Widget,
 };
 
-use crate::assets::{image::ImageData, IMAGES};
-use crate::engine::{DisplayChild, ObjectiveState};
+use super::state_overlay::{paint_state_overlay, shape_indicators_enabled};
+use super::DENSITY;
+use crate::assets::{
+    image::{ImageData, PietImageCache},
+    thumbnail_id, IMAGES, THUMBNAIL_SIZES,
+};
+use crate::engine::{DisabledDisplay, DisplayChild, ObjectiveState};
+
+pub const GOAL_COLOR: Color = Color::rgb8(0xff, 0xcc, 0x00);
+// Dots of checks marked junk or with the item found there.
+const JUNK_COLOR: Color = Color::rgb8(0x88, 0x88, 0x88);
+const FOUND_ITEM_COLOR: Color = Color::rgb8(0x33, 0xaa, 0xff);
+
+// Size of the tile drawn for objectives without an image.
+const PLACEHOLDER_SIZE: f64 = 32.;
 
 /// A widget that renders an Image
 pub struct Objective {
-    image: Option<Arc<ImageData>>,
+    // The image and its id in the image store.  Its size is the size the
+    // objective is laid out at.
+    image: Option<(String, Arc<ImageData>)>,
+    // What's painted: the smallest thumbnail of `image` that fits the laid
+    // out size, or `image` itself.
+    drawn: Option<Arc<ImageData>>,
+    piet_image: PietImageCache,
+    shape_indicators: bool,
+    // Set when the objective should be shown but the module has no image
+    // for it.
+    placeholder: bool,
 }
 
 impl Objective {
@@ -32,31 +59,172 @@ impl Objective {
     ///
     /// The Image will scale to fit its box constraints.
     pub fn new() -> Self {
-        Objective { image: None }
+        Objective {
+            image: None,
+            drawn: None,
+            piet_image: Default::default(),
+            shape_indicators: false,
+            placeholder: false,
+        }
     }
 
     fn update_image(&mut self, data: &DisplayChild) {
+        let image = Self::find_image(data);
+        // Only the image, not the rest of the data, decides what's painted
+        // so keep the piet image unless it changed.
+        let old = self.image.as_ref().map(|(id, _)| id);
+        if image.as_ref().map(|(id, _)| id) != old {
+            self.drawn = None;
+            self.piet_image.clear();
+        }
+        self.placeholder = image.is_none() && !data.is_hidden();
+        self.image = image;
+    }
+
+    // Picks what to paint at `size`.
+    fn select_thumbnail(&mut self, size: Size) {
+        let (id, image) = match &self.image {
+            Some(image) => image,
+            None => return,
+        };
+        let needed = size.width.max(size.height).ceil() as u32;
+        let thumbnail = THUMBNAIL_SIZES
+            .iter()
+            .filter(|s| **s >= needed)
+            .find_map(|s| IMAGES.with(|images| images.borrow().get(&thumbnail_id(id, *s))));
+        let drawn = thumbnail.unwrap_or_else(|| image.clone());
+        if !Some(drawn.clone()).same(&self.drawn) {
+            self.piet_image.clear();
+            self.drawn = Some(drawn);
+        }
+    }
+
+    fn find_image(data: &DisplayChild) -> Option<(String, Arc<ImageData>)> {
         let postfix = match data.state {
             ObjectiveState::Unlocked => "",
             ObjectiveState::Complete => ":completed",
             ObjectiveState::Locked => ":locked",
-            _ => {
-                self.image = None;
-                return;
-            }
+            ObjectiveState::GlitchLocked => ":glitchlocked",
+            // Shown objectives look locked while disabled.
+            ObjectiveState::Disabled if !data.is_hidden() => ":locked",
+            ObjectiveState::Disabled => return None,
         };
 
+        let stage_id = format!("objective:{}:stage{}", &data.id, data.stage);
         let obj_id = format!("objective:{}{}", &data.id, &postfix);
         let ty_id = format!("type:{}{}", &data.ty, &postfix);
 
         IMAGES.with(|images| {
-            self.image = images.borrow().get(&obj_id);
-            // If there is no objective specific image, fall back on a type
-            // specific one.
-            if self.image.is_none() {
-                self.image = images.borrow().get(&ty_id);
-            }
-        });
+            // Staged objectives can have an image for each stage.  If there
+            // is no objective specific image, fall back on a type specific
+            // one.
+            let images = images.borrow();
+            let get = |id: String| images.get(&id).map(|image| (id, image));
+            let stage = if data.stage > 0 { get(stage_id) } else { None };
+            stage.or_else(|| get(obj_id)).or_else(|| get(ty_id))
+        })
+    }
+
+    // Draws a tile with the objective's initials in place of a missing
+    // image.  Its color is picked from the id so neighbouring tiles differ
+    // and it is darkened like the generated locked and completed images.
+    fn paint_placeholder(ctx: &mut PaintCtx, data: &DisplayChild, env: &Env) {
+        let hue = data
+            .id
+            .bytes()
+            .fold(0u32, |h, b| h.wrapping_mul(31) + b as u32)
+            % 360;
+        let color = match data.state {
+            ObjectiveState::Locked | ObjectiveState::Disabled => Color::hlc(0., 10., 0.),
+            ObjectiveState::GlitchLocked => Color::hlc(95., 55., 50.),
+            ObjectiveState::Complete => Color::hlc(135., 30., 40.),
+            _ => Color::hlc(hue as f64, 55., 40.),
+        };
+        let rect = ctx.size().to_rect().inset(-1.);
+        ctx.fill(rect.to_rounded_rect(3.), &color);
+
+        let text = initials(&data.id);
+        let font_size = rect.height() * 0.45;
+        let font = match ctx
+            .text()
+            .new_font_by_name(env.get(theme::FONT_NAME), font_size)
+            .build()
+        {
+            Ok(font) => font,
+            Err(_) => return,
+        };
+        let layout = match ctx.text().new_text_layout(&font, &text, None).build() {
+            Ok(layout) => layout,
+            Err(_) => return,
+        };
+        let origin = Point::new(
+            rect.center().x - layout.width() / 2.,
+            rect.center().y + font_size * 0.35,
+        );
+        ctx.draw_text(&layout, origin, &Color::WHITE);
+    }
+
+    // Draws a padlock in the top right corner for objectives whose state is
+    // pinned.
+    fn paint_lock(ctx: &mut PaintCtx) {
+        let size = ctx.size();
+        let s = size.width.min(size.height) * 0.35;
+        let x = size.width - s;
+        let body = Rect::new(x, s * 0.45, x + s, s);
+        let mut shackle = BezPath::new();
+        shackle.move_to((x + s * 0.25, s * 0.45));
+        shackle.line_to((x + s * 0.25, s * 0.25));
+        shackle.quad_to((x + s * 0.5, -s * 0.05), (x + s * 0.75, s * 0.25));
+        shackle.line_to((x + s * 0.75, s * 0.45));
+
+        ctx.stroke(&shackle, &Color::BLACK, s * 0.25);
+        ctx.stroke(&shackle, &GOAL_COLOR, s * 0.12);
+        ctx.fill(body.inset(s * 0.06).to_rounded_rect(s * 0.1), &Color::BLACK);
+        ctx.fill(body.to_rounded_rect(s * 0.1), &GOAL_COLOR);
+    }
+
+    // Draws the dot of a marked check in the bottom left corner.
+    fn paint_mark(ctx: &mut PaintCtx, color: &Color) {
+        let size = ctx.size();
+        let r = size.width.min(size.height) * 0.12;
+        let center = Point::new(r * 1.5, size.height - r * 1.5);
+        ctx.fill(Circle::new(center, r * 1.3), &Color::BLACK);
+        ctx.fill(Circle::new(center, r), color);
+    }
+
+    // Draws `text` in a badge in the bottom right corner, or the top left
+    // one when `top` is set.
+    fn paint_badge(ctx: &mut PaintCtx, text: &str, top: bool, env: &Env) {
+        let size = ctx.size();
+        let font_size = (size.height / 3.).min(env.get(theme::TEXT_SIZE_NORMAL));
+        let font = match ctx
+            .text()
+            .new_font_by_name(env.get(theme::FONT_NAME), font_size)
+            .build()
+        {
+            Ok(font) => font,
+            Err(_) => return,
+        };
+        let layout = match ctx.text().new_text_layout(&font, text, None).build() {
+            Ok(layout) => layout,
+            Err(_) => return,
+        };
+
+        let pad = 1.;
+        let width = layout.width() + pad * 2.;
+        let height = font_size + pad * 2.;
+        let origin = if top {
+            Point::ORIGIN
+        } else {
+            Point::new(size.width - width, size.height - height)
+        };
+        let badge = Rect::from_origin_size(origin, Size::new(width, height));
+        ctx.fill(badge.to_rounded_rect(2.), &Color::BLACK.with_alpha(0.7));
+        ctx.draw_text(
+            &layout,
+            Point::new(badge.x0 + pad, badge.y1 - pad - font_size * 0.2),
+            &Color::WHITE,
+        );
     }
 }
 
@@ -69,10 +237,11 @@ impl Widget<DisplayChild> for Objective {
         _ctx: &mut LifeCycleCtx,
         event: &LifeCycle,
         data: &DisplayChild,
-        _env: &Env,
+        env: &Env,
     ) {
         if let LifeCycle::WidgetAdded = event {
             self.update_image(data);
+            self.shape_indicators = shape_indicators_enabled(env);
         }
     }
 
@@ -81,12 +250,18 @@ impl Widget<DisplayChild> for Objective {
         ctx: &mut UpdateCtx,
         old_data: &DisplayChild,
         data: &DisplayChild,
-        _env: &Env,
+        env: &Env,
     ) {
         if !old_data.same(data) {
             self.update_image(data);
             ctx.request_layout();
         }
+
+        let shape_indicators = shape_indicators_enabled(env);
+        if shape_indicators != self.shape_indicators {
+            self.shape_indicators = shape_indicators;
+            ctx.request_paint();
+        }
     }
 
     fn layout(
@@ -94,19 +269,33 @@ impl Widget<DisplayChild> for Objective {
         _layout_ctx: &mut LayoutCtx,
         bc: &BoxConstraints,
         _data: &DisplayChild,
-        _env: &Env,
+        env: &Env,
     ) -> Size {
         bc.debug_check("Image");
 
-        if let Some(i) = &self.image {
-            bc.constrain(i.get_size())
+        let density = env.try_get(DENSITY).unwrap_or(1.0);
+        if let Some((_, i)) = &self.image {
+            let size = bc.constrain(i.get_size() * density);
+            self.select_thumbnail(size);
+            size
+        } else if self.placeholder {
+            let size = PLACEHOLDER_SIZE * density;
+            bc.constrain(Size::new(size, size))
         } else {
             Size::ZERO
         }
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, _data: &DisplayChild, _env: &Env) {
-        if let Some(i) = &self.image {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &DisplayChild, env: &Env) {
+        let disabled = data.state == ObjectiveState::Disabled;
+        if disabled && data.disabled == DisabledDisplay::Placeholder {
+            // Only an outline marks the space kept for the objective.
+            let rect = ctx.size().to_rect().inset(-1.);
+            ctx.stroke(rect.to_rounded_rect(3.), &env.get(theme::BORDER_DARK), 1.);
+            return;
+        }
+
+        if let Some(i) = self.drawn.as_ref().or(self.image.as_ref().map(|(_, i)| i)) {
             let fill = FillStrat::default();
             let offset_matrix = fill.affine_to_fill(ctx.size(), i.get_size());
 
@@ -116,7 +305,61 @@ impl Widget<DisplayChild> for Objective {
                 let clip_rect = Rect::ZERO.with_size(ctx.size());
                 ctx.clip(clip_rect);
             }
-            i.to_piet(offset_matrix, ctx, InterpolationMode::Bilinear);
+            i.to_piet(
+                offset_matrix,
+                ctx,
+                InterpolationMode::Bilinear,
+                &mut self.piet_image,
+            );
+        } else if self.placeholder {
+            Self::paint_placeholder(ctx, data, env);
+        } else {
+            return;
+        }
+
+        if disabled {
+            let rect = ctx.size().to_rect();
+            let background = env.get(theme::WINDOW_BACKGROUND_COLOR);
+            ctx.fill(rect, &background.with_alpha(0.6));
+        }
+
+        if self.shape_indicators {
+            let rect = ctx.size().to_rect();
+            paint_state_overlay(ctx, &data.state, rect);
+        }
+
+        if data.checks_total > 0 {
+            let text = format!("{}/{}", data.checks_complete, data.checks_total);
+            Self::paint_badge(ctx, &text, false, env);
+        }
+
+        if !data.timer.is_empty() {
+            Self::paint_badge(ctx, &data.timer, true, env);
+        }
+
+        if data.goal {
+            let rect = ctx.size().to_rect().inset(-1.);
+            ctx.stroke(rect.to_rounded_rect(3.), &GOAL_COLOR, 2.);
+        }
+
+        if data.locked {
+            Self::paint_lock(ctx);
+        }
+
+        if data.junk {
+            Self::paint_mark(ctx, &JUNK_COLOR);
+        } else if !data.found_item.is_empty() {
+            Self::paint_mark(ctx, &FOUND_ITEM_COLOR);
         }
     }
 }
+
+// Up to two letters standing for an objective id, like "MK" for
+// "magma-key".
+fn initials(id: &str) -> String {
+    id.split(|c: char| c == '-' || c == '_' || c.is_whitespace())
+        .filter_map(|word| word.chars().next())
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect()
+}
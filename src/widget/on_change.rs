@@ -0,0 +1,34 @@
+use druid::widget::{Controller, ControllerHost};
+use druid::{Data, Env, UpdateCtx, Widget};
+
+pub struct OnChange<T> {
+    /// A closure that will be invoked when the child's data changes.
+    action: Box<dyn Fn(&mut UpdateCtx, &T, &Env)>,
+}
+
+impl<T: Data, W: Widget<T>> Controller<T, W> for OnChange<T> {
+    fn update(&mut self, child: &mut W, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        child.update(ctx, old_data, data, env);
+        if !old_data.same(data) {
+            (self.action)(ctx, data, env);
+        }
+    }
+}
+
+/// A trait that provides a method for reacting to a widget's data changing,
+/// such as the text of a `TextBox` being edited.
+pub trait OnChangeExt<T: Data>: Widget<T> + Sized + 'static {
+    fn on_change(
+        self,
+        f: impl Fn(&mut UpdateCtx, &T, &Env) + 'static,
+    ) -> ControllerHost<Self, OnChange<T>> {
+        ControllerHost::new(
+            self,
+            OnChange {
+                action: Box::new(f),
+            },
+        )
+    }
+}
+
+impl<T: Data, W: Widget<T> + 'static> OnChangeExt<T> for W {}
@@ -0,0 +1,143 @@
+//! A frame for windows without a title bar that lets them be moved and
+//! closed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use druid::kurbo::Line;
+use druid::widget::prelude::*;
+use druid::{Color, Command, Cursor, Data, Point, Rect, WidgetExt, WidgetPod};
+
+use crate::monitors;
+
+// Height of the bar shown along the top while the pointer is over the
+// window.
+const BAR_HEIGHT: f64 = 18.0;
+const BAR_COLOR: Color = Color::rgba8(0, 0, 0, 0xa0);
+// Size of the cross drawn in the close button.
+const CLOSE_CROSS: f64 = 8.0;
+
+/// Wraps the contents of a window without a title bar.  While the pointer
+/// is over the window a bar is painted along the top: dragging it moves
+/// the window and its cross closes it.  druid can't move windows so they
+/// are moved with `monitors::move_window_by`.
+pub struct OverlayFrame<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    // Title the window is found by when moving it.
+    title: String,
+    // Where the drag started in window coordinates.
+    drag_start: Option<Point>,
+    // Set while a move is in flight so moves don't pile up behind a slow
+    // window manager.
+    moving: Arc<AtomicBool>,
+}
+
+impl<T: Data> OverlayFrame<T> {
+    pub fn new(child: impl Widget<T> + 'static, title: &str) -> Self {
+        OverlayFrame {
+            child: WidgetPod::new(child.boxed()),
+            title: title.to_string(),
+            drag_start: None,
+            moving: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn close_rect(size: Size) -> Rect {
+        Rect::new(size.width - BAR_HEIGHT, 0.0, size.width, BAR_HEIGHT)
+    }
+
+    fn move_by(&self, dx: f64, dy: f64) {
+        if self.moving.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let title = self.title.clone();
+        let moving = self.moving.clone();
+        thread::spawn(move || {
+            if let Err(e) = monitors::move_window_by(&title, dx, dy) {
+                println!("error moving window: {}", e);
+            }
+            moving.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+impl<T: Data> Widget<T> for OverlayFrame<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(m) if m.button.is_left() && m.pos.y < BAR_HEIGHT => {
+                if Self::close_rect(ctx.size()).contains(m.pos) {
+                    let command = Command::new(druid::commands::CLOSE_WINDOW, ());
+                    ctx.submit_command(command, ctx.window_id());
+                } else {
+                    self.drag_start = Some(m.window_pos);
+                    ctx.set_active(true);
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseMove(m) if ctx.is_active() => {
+                // The window moves under the pointer, so the distance from
+                // where the drag started is what's left to move.
+                if let Some(start) = self.drag_start {
+                    let delta = m.window_pos - start;
+                    if delta.x != 0.0 || delta.y != 0.0 {
+                        self.move_by(delta.x, delta.y);
+                    }
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseUp(_) if ctx.is_active() => {
+                self.drag_start = None;
+                ctx.set_active(false);
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseMove(m)
+                if m.pos.y < BAR_HEIGHT && !Self::close_rect(ctx.size()).contains(m.pos) =>
+            {
+                ctx.set_cursor(&Cursor::OpenHand);
+            }
+            _ => (),
+        }
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::HotChanged(_) = event {
+            ctx.request_paint();
+        }
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("OverlayFrame");
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child
+            .set_layout_rect(ctx, data, env, Rect::from_origin_size(Point::ORIGIN, size));
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+        if !ctx.is_hot() && !ctx.is_active() {
+            return;
+        }
+        let size = ctx.size();
+        ctx.fill(Rect::new(0.0, 0.0, size.width, BAR_HEIGHT), &BAR_COLOR);
+        let center = Self::close_rect(size).center();
+        let r = CLOSE_CROSS / 2.0;
+        for (dx, dy) in &[(r, r), (r, -r)] {
+            let line = Line::new(
+                (center.x - dx, center.y - dy),
+                (center.x + dx, center.y + dy),
+            );
+            ctx.stroke(line, &Color::WHITE, 1.5);
+        }
+    }
+}
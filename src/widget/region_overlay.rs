@@ -0,0 +1,81 @@
+//! Tints the regions of a map by how many of their checks are done.
+
+use druid::{
+    kurbo::BezPath, BoxConstraints, Color, Data, Env, Event, EventCtx, Key, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, RenderContext, Size, UpdateCtx, Widget,
+};
+
+use crate::engine::MapInfo;
+
+/// When set, maps tint their regions by progress.
+pub const MAP_HEATMAP: Key<bool> = Key::new("pollendina.map-heatmap");
+
+// Opacity of region tints so the map shows through.
+const TINT_ALPHA: f64 = 0.35;
+
+/// Paints a map's regions from red, with nothing done, to green, with
+/// everything done.  Sized like `Constellation` so the two line up.
+pub struct RegionOverlay;
+
+impl RegionOverlay {
+    pub fn new() -> Self {
+        RegionOverlay
+    }
+}
+
+/// Tint of a region with `complete` of `total` checks done.
+pub fn progress_color(complete: u32, total: u32) -> Color {
+    let done = complete as f64 / total as f64;
+    Color::hlc(30. + 110. * done, 55., 70.).with_alpha(TINT_ALPHA)
+}
+
+impl Widget<MapInfo> for RegionOverlay {
+    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut MapInfo, _env: &Env) {}
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &MapInfo,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &MapInfo, data: &MapInfo, _env: &Env) {
+        if !old_data.regions.same(&data.regions) {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &MapInfo,
+        _env: &Env,
+    ) -> Size {
+        let scale = (bc.max().width / data.width).min(bc.max().height / data.height);
+        bc.constrain(Size::new(data.width * scale, data.height * scale))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &MapInfo, env: &Env) {
+        if !env.try_get(MAP_HEATMAP).unwrap_or(false) {
+            return;
+        }
+        let size = ctx.size();
+        let scale = (size.width / data.width).min(size.height / data.height);
+        for region in data.regions.iter().filter(|r| r.total > 0) {
+            let mut path = BezPath::new();
+            for (i, (x, y)) in region.points.iter().enumerate() {
+                let point = Point::new(x * scale, y * scale);
+                if i == 0 {
+                    path.move_to(point);
+                } else {
+                    path.line_to(point);
+                }
+            }
+            path.close_path();
+            ctx.fill(path, &progress_color(region.complete, region.total));
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! A widget that draws its child scaled.
+
+use druid::widget::prelude::*;
+use druid::{Affine, Data, WidgetPod};
+
+/// A widget that lays out and paints its child scaled by a constant
+/// factor.  The child is display only and doesn't receive user input.
+pub struct Scale<T> {
+    factor: f64,
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+}
+
+impl<T: Data> Scale<T> {
+    pub fn new(factor: f64, child: impl Widget<T> + 'static) -> Self {
+        Scale {
+            factor,
+            child: WidgetPod::new(Box::new(child)),
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for Scale<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        // Positions of user input would have to be scaled.  Since the child
+        // is display only, only forward the rest.
+        match event {
+            Event::MouseDown(_)
+            | Event::MouseUp(_)
+            | Event::MouseMove(_)
+            | Event::Wheel(_)
+            | Event::KeyDown(_)
+            | Event::KeyUp(_) => (),
+            _ => self.child.event(ctx, event, data, env),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let child_bc = BoxConstraints::new(
+            bc.min() * (1.0 / self.factor),
+            bc.max() * (1.0 / self.factor),
+        );
+        let size = self.child.layout(ctx, &child_bc, data, env);
+        self.child.set_layout_rect(ctx, data, env, size.to_rect());
+        bc.constrain(size * self.factor)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let factor = self.factor;
+        let clip = ctx.size().to_rect();
+        let child = &mut self.child;
+        ctx.with_save(|ctx| {
+            ctx.clip(clip);
+            ctx.transform(Affine::scale(factor));
+            // The paint region is in our unscaled coordinates.  Widen it to
+            // the whole child so nothing gets culled.
+            let region = (clip.size() * (1.0 / factor)).to_rect();
+            ctx.with_child_ctx(region, |ctx| child.paint(ctx, data, env));
+        });
+    }
+}
@@ -0,0 +1,54 @@
+//! Shape overlays that signal objective state without relying on color.
+
+use druid::{
+    kurbo::{BezPath, Circle, Line},
+    Color, Env, Key, PaintCtx, Point, Rect, RenderContext,
+};
+
+use crate::engine::ObjectiveState;
+
+/// When set, objective widgets draw shape overlays on top of their normal
+/// color based state indication.
+pub const SHAPE_INDICATORS: Key<bool> = Key::new("pollendina.shape-indicators");
+
+pub fn shape_indicators_enabled(env: &Env) -> bool {
+    env.try_get(SHAPE_INDICATORS).unwrap_or(false)
+}
+
+fn point_in(rect: &Rect, x: f64, y: f64) -> Point {
+    Point::new(rect.x0 + rect.width() * x, rect.y0 + rect.height() * y)
+}
+
+/// Paint the overlay for `state` inside of `rect`.
+///
+/// * Locked: a slash.
+/// * GlitchLocked: a dot.
+/// * Complete: a check mark.
+pub fn paint_state_overlay(ctx: &mut PaintCtx, state: &ObjectiveState, rect: Rect) {
+    let width = rect.width().min(rect.height()) * 0.12;
+    let shadow = Color::BLACK;
+    let fg = Color::WHITE;
+
+    match state {
+        ObjectiveState::Locked => {
+            let line = Line::new(point_in(&rect, 0.2, 0.8), point_in(&rect, 0.8, 0.2));
+            ctx.stroke(line, &shadow, width * 1.8);
+            ctx.stroke(line, &fg, width);
+        }
+        ObjectiveState::GlitchLocked => {
+            let r = rect.width().min(rect.height()) * 0.15;
+            let dot = Circle::new(rect.center(), r);
+            ctx.fill(Circle::new(rect.center(), r + width * 0.4), &shadow);
+            ctx.fill(dot, &fg);
+        }
+        ObjectiveState::Complete => {
+            let mut path = BezPath::new();
+            path.move_to(point_in(&rect, 0.2, 0.55));
+            path.line_to(point_in(&rect, 0.42, 0.75));
+            path.line_to(point_in(&rect, 0.8, 0.28));
+            ctx.stroke(&path, &shadow, width * 1.8);
+            ctx.stroke(&path, &fg, width);
+        }
+        ObjectiveState::Disabled | ObjectiveState::Unlocked => (),
+    }
+}
@@ -0,0 +1,113 @@
+//! A widget that shows a transient message over its child.
+
+use std::time::Duration;
+
+use druid::widget::prelude::*;
+use druid::{Data, Point, Rect, TimerToken, WidgetExt, WidgetPod};
+
+// How long a toast stays up before dismissing itself.
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+// Distance between the toast and the bottom of the child.
+const TOAST_MARGIN: f64 = 16.0;
+
+/// A widget that overlays `toast` at the bottom of its child whenever
+/// `message` returns a non empty string.  The toast is dismissed with
+/// `dismiss` after a few seconds.
+pub struct Toast<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    toast: WidgetPod<T, Box<dyn Widget<T>>>,
+    toast_rect: Rect,
+    message: Box<dyn Fn(&T) -> String>,
+    dismiss: Box<dyn Fn(&mut T)>,
+    timer: TimerToken,
+}
+
+impl<T: Data> Toast<T> {
+    pub fn new(
+        child: impl Widget<T> + 'static,
+        toast: impl Widget<T> + 'static,
+        message: impl Fn(&T) -> String + 'static,
+        dismiss: impl Fn(&mut T) + 'static,
+    ) -> Self {
+        Toast {
+            child: WidgetPod::new(child.boxed()),
+            toast: WidgetPod::new(toast.boxed()),
+            toast_rect: Rect::ZERO,
+            message: Box::new(message),
+            dismiss: Box::new(dismiss),
+            timer: TimerToken::INVALID,
+        }
+    }
+
+    fn is_shown(&self, data: &T) -> bool {
+        !(self.message)(data).is_empty()
+    }
+}
+
+impl<T: Data> Widget<T> for Toast<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::Timer(token) = event {
+            if *token == self.timer {
+                self.timer = TimerToken::INVALID;
+                (self.dismiss)(data);
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        if self.is_shown(data) {
+            // Mouse input over the toast doesn't reach the child.
+            let over_toast = match event {
+                Event::MouseDown(m) | Event::MouseUp(m) | Event::MouseMove(m) => {
+                    self.toast_rect.contains(m.pos)
+                }
+                _ => false,
+            };
+            self.toast.event(ctx, event, data, env);
+            if over_toast {
+                return;
+            }
+        }
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.toast.lifecycle(ctx, event, data, env);
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        let message = (self.message)(data);
+        if message != (self.message)(old_data) {
+            if !message.is_empty() {
+                self.timer = ctx.request_timer(TOAST_DURATION);
+            }
+            ctx.request_layout();
+        }
+        self.toast.update(ctx, data, env);
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_layout_rect(ctx, data, env, size.to_rect());
+
+        let toast_size = self
+            .toast
+            .layout(ctx, &BoxConstraints::new(Size::ZERO, size), data, env);
+        let origin = Point::new(
+            (size.width - toast_size.width) / 2.0,
+            (size.height - toast_size.height - TOAST_MARGIN).max(0.0),
+        );
+        self.toast_rect = Rect::from_origin_size(origin, toast_size);
+        self.toast.set_layout_rect(ctx, data, env, self.toast_rect);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+        if self.is_shown(data) {
+            self.toast.paint(ctx, data, env);
+        }
+    }
+}
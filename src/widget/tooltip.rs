@@ -0,0 +1,80 @@
+//! A widget that shows a tooltip over its child.
+
+use druid::widget::prelude::*;
+use druid::{Data, Point, Rect, Vec2, WidgetExt, WidgetPod};
+
+// Distance between the mouse and the tooltip's corner.
+const TOOLTIP_OFFSET: f64 = 16.0;
+
+/// A widget that overlays `tooltip` on its child next to the window
+/// position `pos` returns.  The tooltip is hidden while it returns `None`
+/// and never takes input, so it doesn't get in the way of the mouse.
+pub struct Tooltip<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    tooltip: WidgetPod<T, Box<dyn Widget<T>>>,
+    pos: Box<dyn Fn(&T) -> Option<Point>>,
+}
+
+impl<T: Data> Tooltip<T> {
+    pub fn new(
+        child: impl Widget<T> + 'static,
+        tooltip: impl Widget<T> + 'static,
+        pos: impl Fn(&T) -> Option<Point> + 'static,
+    ) -> Self {
+        Tooltip {
+            child: WidgetPod::new(child.boxed()),
+            tooltip: WidgetPod::new(tooltip.boxed()),
+            pos: Box::new(pos),
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for Tooltip<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.tooltip.lifecycle(ctx, event, data, env);
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        if (self.pos)(data) != (self.pos)(old_data) {
+            ctx.request_layout();
+        }
+        self.tooltip.update(ctx, data, env);
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_layout_rect(ctx, data, env, size.to_rect());
+
+        let tooltip_size =
+            self.tooltip
+                .layout(ctx, &BoxConstraints::new(Size::ZERO, size), data, env);
+        // Keep the tooltip inside the window, moving it to the other side of
+        // the mouse when there's no room below or to the right.
+        let pos = (self.pos)(data).unwrap_or_default();
+        let mut origin = pos + Vec2::new(TOOLTIP_OFFSET, TOOLTIP_OFFSET);
+        if origin.x + tooltip_size.width > size.width {
+            origin.x = pos.x - TOOLTIP_OFFSET - tooltip_size.width;
+        }
+        if origin.y + tooltip_size.height > size.height {
+            origin.y = pos.y - TOOLTIP_OFFSET - tooltip_size.height;
+        }
+        origin.x = origin.x.max(0.0);
+        origin.y = origin.y.max(0.0);
+        let rect = Rect::from_origin_size(origin, tooltip_size);
+        self.tooltip.set_layout_rect(ctx, data, env, rect);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+        if (self.pos)(data).is_some() {
+            self.tooltip.paint(ctx, data, env);
+        }
+    }
+}